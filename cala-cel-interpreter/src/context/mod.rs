@@ -10,7 +10,7 @@ use package::CelPackage;
 
 const SELF_PACKAGE_NAME: Cow<'static, str> = Cow::Borrowed("self");
 
-type CelFunction = Box<dyn Fn(Vec<CelValue>) -> Result<CelValue, CelError> + Sync>;
+type CelFunction = Box<dyn Fn(Vec<CelValue>) -> Result<CelValue, CelError> + Send + Sync>;
 pub(crate) type CelMemberFunction =
     Box<dyn Fn(&CelValue, Vec<CelValue>) -> Result<CelValue, CelError> + Sync>;
 
@@ -25,6 +25,17 @@ impl CelContext {
             .insert(name.into(), ContextItem::Value(value.into()));
     }
 
+    /// Registers a function callable from expressions by `name`, replacing any
+    /// identifier previously registered under the same name.
+    pub fn add_function(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        function: impl Fn(Vec<CelValue>) -> Result<CelValue, CelError> + Send + Sync + 'static,
+    ) {
+        self.idents
+            .insert(name.into(), ContextItem::Function(Box::new(function)));
+    }
+
     pub fn new() -> Self {
         let mut idents = HashMap::new();
         idents.insert(
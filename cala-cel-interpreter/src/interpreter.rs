@@ -38,6 +38,78 @@ impl CelExpression {
     }
 }
 
+impl CelExpression {
+    /// Evaluates the arguments of every call to the global function `name` in the
+    /// expression, including calls in branches that evaluation may not reach. Calls whose
+    /// arguments can't be evaluated are skipped as they would fail evaluation anyway.
+    pub fn evaluate_call_args(&self, name: &str, ctx: &CelContext) -> Vec<Vec<CelValue>> {
+        let mut calls = Vec::new();
+        collect_call_args(&self.expr, name, ctx, &mut calls);
+        calls
+    }
+}
+
+fn collect_call_args(
+    expr: &Expression,
+    name: &str,
+    ctx: &CelContext,
+    calls: &mut Vec<Vec<CelValue>>,
+) {
+    use Expression::*;
+    match expr {
+        Ternary(cond, left, right) => {
+            collect_call_args(cond, name, ctx, calls);
+            collect_call_args(left, name, ctx, calls);
+            collect_call_args(right, name, ctx, calls);
+        }
+        Relation(_, left, right) | Arithmetic(_, left, right) => {
+            collect_call_args(left, name, ctx, calls);
+            collect_call_args(right, name, ctx, calls);
+        }
+        Unary(_, expr) | Has(expr) => collect_call_args(expr, name, ctx, calls),
+        Member(target, member) => {
+            match member.as_ref() {
+                ast::Member::FunctionCall(args) if matches!(target.as_ref(), Ident(ident) if ident.as_str() == name) =>
+                {
+                    let values = args
+                        .iter()
+                        .map(|arg| evaluate_expression(arg, ctx)?.try_into_value())
+                        .collect::<Result<Vec<_>, _>>();
+                    if let Ok(values) = values {
+                        calls.push(values);
+                    }
+                    return;
+                }
+                ast::Member::FunctionCall(args) => {
+                    for arg in args {
+                        collect_call_args(arg, name, ctx, calls);
+                    }
+                }
+                ast::Member::Index(index) => collect_call_args(index, name, ctx, calls),
+                ast::Member::Attribute(_) => (),
+            }
+            collect_call_args(target, name, ctx, calls);
+        }
+        List(items) => {
+            for item in items {
+                collect_call_args(item, name, ctx, calls);
+            }
+        }
+        Map(entries) => {
+            for (k, v) in entries {
+                collect_call_args(k, name, ctx, calls);
+                collect_call_args(v, name, ctx, calls);
+            }
+        }
+        Struct(_, fields) => {
+            for (_, v) in fields {
+                collect_call_args(v, name, ctx, calls);
+            }
+        }
+        Literal(_) | Ident(_) => (),
+    }
+}
+
 impl std::fmt::Display for CelExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.source)
@@ -455,6 +527,33 @@ mod tests {
         assert_eq!(expression.evaluate(&context).unwrap(), CelValue::Bool(true))
     }
 
+    #[test]
+    fn call_args() {
+        let expression = "params.flag ? account('ns', params.code) : account('ns', 'other')"
+            .parse::<CelExpression>()
+            .unwrap();
+        let mut params = CelMap::new();
+        params.insert("flag", true);
+        params.insert("code", "code");
+        let mut context = CelContext::new();
+        context.add_variable("params", params);
+        assert_eq!(
+            expression.evaluate_call_args("account", &context),
+            vec![
+                vec![CelValue::from("ns"), CelValue::from("code")],
+                vec![CelValue::from("ns"), CelValue::from("other")],
+            ]
+        );
+        assert!(expression.evaluate_call_args("other", &context).is_empty());
+
+        let expression = "account('ns', unknown.code)"
+            .parse::<CelExpression>()
+            .unwrap();
+        assert!(expression
+            .evaluate_call_args("account", &context)
+            .is_empty());
+    }
+
     #[test]
     fn lookup() {
         let expression = "params.hello.world".parse::<CelExpression>().unwrap();
@@ -574,4 +673,18 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn custom_function() -> anyhow::Result<()> {
+        let mut context = CelContext::new();
+        context.add_function("greet", |args| match args.first() {
+            Some(CelValue::String(name)) => Ok(CelValue::from(format!("hello {name}").as_str())),
+            _ => Err(CelError::MissingArgument),
+        });
+
+        let expression = "greet('cala')".parse::<CelExpression>().unwrap();
+        assert_eq!(expression.evaluate(&context)?, CelValue::from("hello cala"));
+
+        Ok(())
+    }
 }
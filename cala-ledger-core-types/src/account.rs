@@ -7,9 +7,15 @@ pub struct AccountValues {
     pub id: AccountId,
     pub version: u32,
     pub code: String,
+    /// Accounts can only be posted to by templates and journals of the same tenant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<TenantId>,
     pub name: String,
+    /// Selects the registered metadata schema the metadata is validated against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<String>,
     pub normal_balance_type: DebitOrCredit,
-    pub status: Status,
+    pub status: AccountStatus,
     pub external_id: Option<String>,
     pub description: Option<String>,
     pub metadata: Option<serde_json::Value>,
@@ -20,4 +26,23 @@ pub struct AccountValues {
 pub struct AccountConfig {
     pub is_account_set: bool,
     pub eventually_consistent: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_shards: Option<u16>,
+}
+
+/// An additional external identifier of an account (eg. an IBAN). The `value` is
+/// unique within its `namespace`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AccountAlias {
+    pub namespace: String,
+    pub value: String,
+}
+
+impl AccountAlias {
+    pub fn new(namespace: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+            value: value.into(),
+        }
+    }
 }
@@ -0,0 +1,24 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::primitives::*;
+
+/// Exchange rate for converting units of `base_currency` into `quote_currency`
+/// that applies from its `effective` date onwards.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FxRateValues {
+    pub base_currency: Currency,
+    pub quote_currency: Currency,
+    pub effective: NaiveDate,
+    pub rate: Decimal,
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub modified_at: DateTime<Utc>,
+}
+
+impl FxRateValues {
+    pub fn convert(&self, units: Decimal) -> Decimal {
+        units * self.rate
+    }
+}
@@ -8,6 +8,9 @@ pub struct JournalValues {
     pub version: u32,
     pub name: String,
     pub code: Option<String>,
+    /// Codes are unique per tenant. `None` is the default (untenanted) scope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<TenantId>,
     pub status: Status,
     pub description: Option<String>,
     pub config: JournalConfig,
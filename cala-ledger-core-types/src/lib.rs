@@ -5,6 +5,7 @@ pub mod account;
 pub mod account_set;
 pub mod balance;
 pub mod entry;
+pub mod fx_rate;
 pub mod journal;
 pub mod outbox;
 pub mod param;
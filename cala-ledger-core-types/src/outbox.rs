@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    account::*, account_set::*, balance::*, entry::*, journal::*, primitives::*, transaction::*,
-    tx_template::*,
+    account::*, account_set::*, balance::*, entry::*, fx_rate::*, journal::*, primitives::*,
+    transaction::*, tx_template::*, velocity::VelocityBreach,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,6 +38,16 @@ pub enum OutboxEventPayload {
         account: AccountValues,
         fields: Vec<String>,
     },
+    AccountAliasAdded {
+        source: DataSource,
+        account_id: AccountId,
+        alias: AccountAlias,
+    },
+    AccountAliasRemoved {
+        source: DataSource,
+        account_id: AccountId,
+        alias: AccountAlias,
+    },
     AccountSetCreated {
         source: DataSource,
         account_set: AccountSetValues,
@@ -91,6 +101,14 @@ pub enum OutboxEventPayload {
         source: DataSource,
         balance: BalanceSnapshot,
     },
+    FxRateUpserted {
+        source: DataSource,
+        fx_rate: FxRateValues,
+    },
+    VelocityBreachRecorded {
+        source: DataSource,
+        breach: VelocityBreach,
+    },
 }
 
 #[derive(
@@ -24,6 +24,7 @@ impl From<JournalId> for cel_interpreter::CelValue {
     }
 }
 es_entity::entity_id! { DataSourceId }
+es_entity::entity_id! { TenantId }
 es_entity::entity_id! { TxTemplateId }
 impl From<TxTemplateId> for cel_interpreter::CelValue {
     fn from(id: TxTemplateId) -> Self {
@@ -44,6 +45,7 @@ impl From<EntryId> for cel_interpreter::CelValue {
 }
 es_entity::entity_id! { VelocityLimitId }
 es_entity::entity_id! { VelocityControlId }
+es_entity::entity_id! { VelocityBreachId }
 
 pub type BalanceId = (JournalId, AccountId, Currency);
 impl From<&AccountSetId> for AccountId {
@@ -63,8 +65,10 @@ impl From<AccountSetId> for AccountId {
     Deserialize,
     Clone,
     Copy,
+    Default,
     PartialEq,
     Eq,
+    Hash,
     sqlx::Type,
     strum::Display,
     strum::EnumString,
@@ -75,15 +79,10 @@ impl From<AccountSetId> for AccountId {
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum DebitOrCredit {
     Debit,
+    #[default]
     Credit,
 }
 
-impl Default for DebitOrCredit {
-    fn default() -> Self {
-        Self::Credit
-    }
-}
-
 impl TryFrom<CelResult<'_>> for DebitOrCredit {
     type Error = ResultCoercionError;
 
@@ -109,26 +108,39 @@ impl From<DebitOrCredit> for CelValue {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, sqlx::Type)]
 #[sqlx(type_name = "Status", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
 pub enum Status {
+    #[default]
     Active,
     Locked,
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Self::Active
-    }
+/// Lifecycle status of an account, enforced when posting transactions.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "AccountStatus", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+pub enum AccountStatus {
+    #[default]
+    Active,
+    /// Credits can still be posted but debits are rejected.
+    Frozen,
+    /// No entries can be posted.
+    Locked,
+    /// No entries can be posted and the status can't be changed anymore.
+    /// Only accounts with a zero balance can be closed.
+    Closed,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash, sqlx::Type)]
 #[sqlx(type_name = "Layer", rename_all = "snake_case")]
 #[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum Layer {
+    #[default]
     Settled,
     Pending,
     Encumbrance,
@@ -157,12 +169,6 @@ impl TryFrom<CelResult<'_>> for Layer {
     }
 }
 
-impl Default for Layer {
-    fn default() -> Self {
-        Self::Settled
-    }
-}
-
 impl From<Layer> for CelValue {
     fn from(l: Layer) -> Self {
         match l {
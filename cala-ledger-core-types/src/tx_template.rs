@@ -9,6 +9,8 @@ pub struct TxTemplateValues {
     pub id: TxTemplateId,
     pub version: u32,
     pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<TenantId>,
     pub params: Option<Vec<ParamDefinition>>,
     pub transaction: TxTemplateTransaction,
     pub entries: Vec<TxTemplateEntry>,
@@ -24,8 +26,14 @@ pub struct TxTemplateEntry {
     pub direction: CelExpression,
     pub units: CelExpression,
     pub currency: CelExpression,
+    /// Journal of the entry. Defaults to the journal of the transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal_id: Option<CelExpression>,
     pub description: Option<CelExpression>,
     pub metadata: Option<CelExpression>,
+    /// JSON schema the evaluated metadata of the entry has to conform to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_schema: Option<serde_json::Value>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -36,4 +44,6 @@ pub struct TxTemplateTransaction {
     pub external_id: Option<CelExpression>,
     pub description: Option<CelExpression>,
     pub metadata: Option<CelExpression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_schema: Option<serde_json::Value>,
 }
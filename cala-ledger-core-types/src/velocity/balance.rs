@@ -24,11 +24,18 @@ impl From<serde_json::Value> for Window {
     }
 }
 
+/// Usage of a balance limit within the current window of a velocity limit.
+#[derive(Debug, Clone)]
 pub struct VelocityBalance {
     pub control_id: VelocityControlId,
     pub limit_id: VelocityLimitId,
+    pub window: Window,
+    pub currency: Currency,
+    pub layer: Layer,
+    pub direction: DebitOrCredit,
+    pub limit: Decimal,
     pub spent: Decimal,
     pub remaining: Decimal,
-    pub currency: Currency,
-    pub balance: BalanceSnapshot,
+    /// The balance accumulated within the window, `None` if nothing was posted yet.
+    pub balance: Option<BalanceSnapshot>,
 }
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::VelocityEnforcementAction;
+use crate::primitives::*;
+
+/// A velocity limit that was exceeded by a posting that was allowed to go through
+/// because its control only warns or records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VelocityBreach {
+    pub id: VelocityBreachId,
+    pub control_id: VelocityControlId,
+    pub limit_id: VelocityLimitId,
+    pub account_id: AccountId,
+    pub transaction_id: TransactionId,
+    pub action: VelocityEnforcementAction,
+    pub currency: Currency,
+    pub layer: Layer,
+    pub direction: DebitOrCredit,
+    pub limit: Decimal,
+    pub requested: Decimal,
+    pub created_at: DateTime<Utc>,
+}
@@ -8,6 +8,8 @@ pub struct VelocityControlValues {
     pub id: VelocityControlId,
     pub name: String,
     pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<TenantId>,
     pub enforcement: VelocityEnforcement,
     pub condition: Option<CelExpression>,
 }
@@ -17,11 +19,15 @@ pub struct VelocityEnforcement {
     pub action: VelocityEnforcementAction,
 }
 
-#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum VelocityEnforcementAction {
     #[default]
     Reject,
+    /// Allows the posting, records a breach and logs a warning.
+    Warn,
+    /// Allows the posting and records a breach.
+    Record,
 }
 
 impl From<VelocityEnforcementAction> for VelocityEnforcement {
@@ -1,9 +1,11 @@
 mod balance;
+mod breach;
 mod context_values;
 mod control;
 mod limit;
 
 pub use balance::*;
+pub use breach::*;
 pub use context_values::*;
 pub use control::*;
 pub use limit::*;
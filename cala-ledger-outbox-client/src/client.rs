@@ -15,6 +15,8 @@ pub struct CalaLedgerOutboxClient {
     _config: CalaLedgerOutboxClientConfig,
     proto_client: ProtoClient,
 }
+// The error wraps tonic::Status
+#[allow(clippy::result_large_err)]
 impl CalaLedgerOutboxClient {
     pub async fn connect(
         config: CalaLedgerOutboxClientConfig,
@@ -1,6 +1,6 @@
 use cala_types::{
-    account::*, account_set::*, balance::*, entry::*, journal::*, outbox::*, primitives::*,
-    transaction::*, tx_template::*,
+    account::*, account_set::*, balance::*, entry::*, fx_rate::*, journal::*, outbox::*,
+    primitives::*, transaction::*, tx_template::*, velocity::*,
 };
 use cel_interpreter::CelExpression;
 
@@ -73,6 +73,41 @@ impl TryFrom<proto::cala_ledger_event::Payload> for OutboxEventPayload {
                 )?,
                 fields,
             },
+            proto::cala_ledger_event::Payload::AccountAliasAdded(proto::AccountAliasAdded {
+                data_source_id,
+                alias,
+            }) => {
+                let alias = alias.ok_or(CalaLedgerOutboxClientError::MissingField)?;
+                AccountAliasAdded {
+                    source: data_source_id.parse()?,
+                    account_id: alias.account_id.parse()?,
+                    alias: AccountAlias::new(alias.namespace, alias.value),
+                }
+            }
+            proto::cala_ledger_event::Payload::VelocityBreachRecorded(
+                proto::VelocityBreachRecorded {
+                    data_source_id,
+                    breach,
+                },
+            ) => VelocityBreachRecorded {
+                source: data_source_id.parse()?,
+                breach: VelocityBreach::try_from(
+                    breach.ok_or(CalaLedgerOutboxClientError::MissingField)?,
+                )?,
+            },
+            proto::cala_ledger_event::Payload::AccountAliasRemoved(
+                proto::AccountAliasRemoved {
+                    data_source_id,
+                    alias,
+                },
+            ) => {
+                let alias = alias.ok_or(CalaLedgerOutboxClientError::MissingField)?;
+                AccountAliasRemoved {
+                    source: data_source_id.parse()?,
+                    account_id: alias.account_id.parse()?,
+                    alias: AccountAlias::new(alias.namespace, alias.value),
+                }
+            }
             proto::cala_ledger_event::Payload::AccountSetMemberCreated(
                 proto::AccountSetMemberCreated {
                     data_source_id,
@@ -203,6 +238,15 @@ impl TryFrom<proto::cala_ledger_event::Payload> for OutboxEventPayload {
                     balance.ok_or(CalaLedgerOutboxClientError::MissingField)?,
                 )?,
             },
+            proto::cala_ledger_event::Payload::FxRateUpserted(proto::FxRateUpserted {
+                data_source_id,
+                fx_rate,
+            }) => FxRateUpserted {
+                source: data_source_id.parse()?,
+                fx_rate: FxRateValues::try_from(
+                    fx_rate.ok_or(CalaLedgerOutboxClientError::MissingField)?,
+                )?,
+            },
 
             proto::cala_ledger_event::Payload::Empty(_) => Empty,
         };
@@ -217,12 +261,14 @@ impl TryFrom<proto::Account> for AccountValues {
         let metadata = account.metadata.map(serde_json::to_value).transpose()?;
         let normal_balance_type =
             proto::DebitOrCredit::try_from(account.normal_balance_type).map(DebitOrCredit::from)?;
-        let status = proto::Status::try_from(account.status).map(Status::from)?;
+        let status = proto::AccountStatus::try_from(account.status).map(AccountStatus::from)?;
         let res = Self {
             id: account.id.parse()?,
             version: account.version,
             code: account.code,
+            tenant_id: account.tenant_id.map(|id| id.parse()).transpose()?,
             name: account.name,
+            account_type: account.account_type,
             external_id: account.external_id,
             normal_balance_type,
             status,
@@ -243,6 +289,7 @@ impl From<proto::AccountConfig> for AccountConfig {
         Self {
             is_account_set: config.is_account_set,
             eventually_consistent: config.eventually_consistent,
+            balance_shards: config.balance_shards.map(|shards| shards as u16),
         }
     }
 }
@@ -278,6 +325,7 @@ impl TryFrom<proto::Journal> for JournalValues {
             version: journal.version,
             name: journal.name,
             code: journal.code,
+            tenant_id: journal.tenant_id.map(|id| id.parse()).transpose()?,
             status,
             description: journal.description,
             config: JournalConfig::from(
@@ -316,6 +364,17 @@ impl From<proto::Status> for Status {
     }
 }
 
+impl From<proto::AccountStatus> for AccountStatus {
+    fn from(status: proto::AccountStatus) -> Self {
+        match status {
+            proto::AccountStatus::Active => AccountStatus::Active,
+            proto::AccountStatus::Frozen => AccountStatus::Frozen,
+            proto::AccountStatus::Locked => AccountStatus::Locked,
+            proto::AccountStatus::Closed => AccountStatus::Closed,
+        }
+    }
+}
+
 impl TryFrom<proto::TxTemplate> for TxTemplateValues {
     type Error = CalaLedgerOutboxClientError;
 
@@ -329,6 +388,7 @@ impl TryFrom<proto::TxTemplate> for TxTemplateValues {
             entries,
             description,
             metadata,
+            tenant_id,
         }: proto::TxTemplate,
     ) -> Result<Self, Self::Error> {
         let params = params
@@ -347,6 +407,7 @@ impl TryFrom<proto::TxTemplate> for TxTemplateValues {
             id: id.parse()?,
             version,
             code,
+            tenant_id: tenant_id.map(|id| id.parse()).transpose()?,
             params: Some(params),
             transaction,
             entries,
@@ -387,6 +448,7 @@ impl TryFrom<proto::TxTemplateTransaction> for TxTemplateTransaction {
             external_id,
             description,
             metadata,
+            metadata_schema,
         }: proto::TxTemplateTransaction,
     ) -> Result<Self, Self::Error> {
         let res = Self {
@@ -396,6 +458,7 @@ impl TryFrom<proto::TxTemplateTransaction> for TxTemplateTransaction {
             external_id: external_id.map(CelExpression::try_from).transpose()?,
             description: description.map(CelExpression::try_from).transpose()?,
             metadata: metadata.map(CelExpression::try_from).transpose()?,
+            metadata_schema: metadata_schema.map(serde_json::to_value).transpose()?,
         };
         Ok(res)
     }
@@ -413,6 +476,8 @@ impl TryFrom<proto::TxTemplateEntry> for TxTemplateEntry {
             currency,
             description,
             metadata,
+            journal_id,
+            metadata_schema,
         }: proto::TxTemplateEntry,
     ) -> Result<Self, Self::Error> {
         let res = Self {
@@ -422,8 +487,10 @@ impl TryFrom<proto::TxTemplateEntry> for TxTemplateEntry {
             direction: CelExpression::try_from(direction)?,
             units: CelExpression::try_from(units)?,
             currency: CelExpression::try_from(currency)?,
+            journal_id: journal_id.map(CelExpression::try_from).transpose()?,
             description: description.map(CelExpression::try_from).transpose()?,
             metadata: metadata.map(CelExpression::try_from).transpose()?,
+            metadata_schema: metadata_schema.map(serde_json::to_value).transpose()?,
         };
         Ok(res)
     }
@@ -588,6 +655,85 @@ impl TryFrom<proto::BalanceAmount> for BalanceAmount {
     }
 }
 
+impl TryFrom<proto::FxRate> for FxRateValues {
+    type Error = CalaLedgerOutboxClientError;
+    fn try_from(
+        proto::FxRate {
+            base_currency,
+            quote_currency,
+            effective,
+            rate,
+            version,
+            created_at,
+            modified_at,
+        }: proto::FxRate,
+    ) -> Result<Self, Self::Error> {
+        let res = Self {
+            base_currency: base_currency.parse()?,
+            quote_currency: quote_currency.parse()?,
+            effective: effective.parse()?,
+            rate: rate.parse()?,
+            version,
+            created_at: created_at
+                .ok_or(CalaLedgerOutboxClientError::MissingField)?
+                .into(),
+            modified_at: modified_at
+                .ok_or(CalaLedgerOutboxClientError::MissingField)?
+                .into(),
+        };
+        Ok(res)
+    }
+}
+
+impl TryFrom<proto::VelocityBreach> for VelocityBreach {
+    type Error = CalaLedgerOutboxClientError;
+    fn try_from(
+        proto::VelocityBreach {
+            id,
+            velocity_control_id,
+            velocity_limit_id,
+            account_id,
+            transaction_id,
+            action,
+            currency,
+            layer,
+            direction,
+            limit,
+            requested,
+            created_at,
+        }: proto::VelocityBreach,
+    ) -> Result<Self, Self::Error> {
+        let res = Self {
+            id: id.parse()?,
+            control_id: velocity_control_id.parse()?,
+            limit_id: velocity_limit_id.parse()?,
+            account_id: account_id.parse()?,
+            transaction_id: transaction_id.parse()?,
+            action: proto::VelocityEnforcementAction::try_from(action)
+                .map(VelocityEnforcementAction::from)?,
+            currency: currency.parse()?,
+            layer: proto::Layer::try_from(layer).map(Layer::from)?,
+            direction: proto::DebitOrCredit::try_from(direction).map(DebitOrCredit::from)?,
+            limit: limit.parse()?,
+            requested: requested.parse()?,
+            created_at: created_at
+                .ok_or(CalaLedgerOutboxClientError::MissingField)?
+                .into(),
+        };
+        Ok(res)
+    }
+}
+
+impl From<proto::VelocityEnforcementAction> for VelocityEnforcementAction {
+    fn from(action: proto::VelocityEnforcementAction) -> Self {
+        match action {
+            proto::VelocityEnforcementAction::Reject => VelocityEnforcementAction::Reject,
+            proto::VelocityEnforcementAction::Warn => VelocityEnforcementAction::Warn,
+            proto::VelocityEnforcementAction::Record => VelocityEnforcementAction::Record,
+        }
+    }
+}
+
 impl From<proto::Layer> for Layer {
     fn from(layer: proto::Layer) -> Self {
         match layer {
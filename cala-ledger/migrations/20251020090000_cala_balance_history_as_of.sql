@@ -0,0 +1 @@
+CREATE INDEX idx_cala_balance_history_as_of ON cala_balance_history (journal_id, account_id, currency, recorded_at, version);
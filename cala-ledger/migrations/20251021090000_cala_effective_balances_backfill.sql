@@ -0,0 +1,12 @@
+CREATE TABLE cala_effective_balances_backfills (
+  journal_id UUID PRIMARY KEY REFERENCES cala_journals(id),
+  last_account_id UUID DEFAULT NULL,
+  last_currency VARCHAR DEFAULT NULL,
+  balances_processed INT NOT NULL DEFAULT 0,
+  balances_total INT NOT NULL,
+  started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  completed_at TIMESTAMPTZ DEFAULT NULL
+);
+
+CREATE INDEX idx_cala_entries_journal_id_account_id ON cala_entries (journal_id, account_id);
@@ -0,0 +1,21 @@
+CREATE TABLE cala_balance_queue (
+  sequence BIGSERIAL PRIMARY KEY,
+  journal_id UUID NOT NULL REFERENCES cala_journals(id),
+  account_id UUID NOT NULL REFERENCES cala_accounts(id),
+  currency VARCHAR NOT NULL,
+  entry_id UUID NOT NULL,
+  values JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_cala_balance_queue_journal_id_account_id_currency ON cala_balance_queue (journal_id, account_id, currency, sequence);
+
+CREATE TABLE cala_balance_queue_progress (
+  journal_id UUID NOT NULL,
+  account_id UUID NOT NULL,
+  currency VARCHAR NOT NULL,
+  last_sequence BIGINT NOT NULL,
+  last_entry_id UUID NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE(journal_id, account_id, currency),
+  FOREIGN KEY (account_id, journal_id, currency) REFERENCES cala_current_balances(account_id, journal_id, currency)
+);
@@ -0,0 +1,13 @@
+ALTER TABLE cala_accounts ADD COLUMN balance_shards INT DEFAULT NULL;
+
+CREATE TABLE cala_balance_shards (
+  journal_id UUID NOT NULL REFERENCES cala_journals(id),
+  account_id UUID NOT NULL REFERENCES cala_accounts(id),
+  currency VARCHAR NOT NULL,
+  shard INT NOT NULL,
+  version INT NOT NULL,
+  values JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  modified_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (journal_id, account_id, currency, shard)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE cala_fx_rates (
+  base_currency VARCHAR NOT NULL,
+  quote_currency VARCHAR NOT NULL,
+  effective DATE NOT NULL,
+  rate NUMERIC NOT NULL,
+  version INT NOT NULL,
+  data_source_id UUID NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  modified_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (base_currency, quote_currency, effective)
+);
@@ -0,0 +1,23 @@
+CREATE TYPE BalanceHistoryRetentionMode AS ENUM ('end_of_day', 'archive');
+
+CREATE TABLE cala_balance_history_retention_policies (
+  journal_id UUID PRIMARY KEY REFERENCES cala_journals(id),
+  retain_days INT NOT NULL,
+  mode BalanceHistoryRetentionMode NOT NULL,
+  pruned_before TIMESTAMPTZ DEFAULT NULL,
+  last_compacted_at TIMESTAMPTZ DEFAULT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  modified_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE cala_balance_history_archive (
+  LIKE cala_balance_history INCLUDING DEFAULTS,
+  archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_cala_balance_history_archive_balance ON cala_balance_history_archive (journal_id, account_id, currency, version);
+
+CREATE TABLE cala_velocity_balance_history_archive (
+  LIKE cala_velocity_balance_history INCLUDING DEFAULTS,
+  archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_cala_velocity_balance_history_archive_balance ON cala_velocity_balance_history_archive (journal_id, account_id, currency, velocity_control_id, velocity_limit_id, version);
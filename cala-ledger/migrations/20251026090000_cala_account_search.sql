@@ -0,0 +1,22 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE TYPE AccountStatus AS ENUM ('active', 'frozen', 'locked', 'closed');
+
+ALTER TABLE cala_accounts
+  ADD COLUMN status AccountStatus NOT NULL DEFAULT 'active', -- For filtering accounts
+  ADD COLUMN metadata JSONB DEFAULT NULL; -- For filtering accounts
+
+UPDATE cala_accounts a
+SET status = (latest.event->'values'->>'status')::AccountStatus,
+    metadata = NULLIF(latest.event->'values'->'metadata', 'null'::jsonb)
+FROM (
+  SELECT DISTINCT ON (id) id, event
+  FROM cala_account_events
+  ORDER BY id, sequence DESC
+) latest
+WHERE latest.id = a.id;
+
+CREATE INDEX idx_cala_accounts_code_prefix ON cala_accounts (code text_pattern_ops);
+CREATE INDEX idx_cala_accounts_name_trgm ON cala_accounts USING GIN (name gin_trgm_ops);
+CREATE INDEX idx_cala_accounts_status ON cala_accounts (status, name, id);
+CREATE INDEX idx_cala_accounts_metadata ON cala_accounts USING GIN (metadata jsonb_path_ops);
@@ -0,0 +1,29 @@
+ALTER TABLE cala_entries
+  ADD COLUMN entry_type VARCHAR,
+  ADD COLUMN layer Layer,
+  ADD COLUMN direction DebitOrCredit,
+  ADD COLUMN currency VARCHAR,
+  ADD COLUMN units NUMERIC,
+  ADD COLUMN metadata JSONB DEFAULT NULL;
+
+UPDATE cala_entries e
+SET entry_type = ev.event->'values'->>'entry_type',
+    layer = LOWER(ev.event->'values'->>'layer')::Layer,
+    direction = (ev.event->'values'->>'direction')::DebitOrCredit,
+    currency = ev.event->'values'->>'currency',
+    units = (ev.event->'values'->>'units')::NUMERIC,
+    metadata = NULLIF(ev.event->'values'->'metadata', 'null'::jsonb)
+FROM cala_entry_events ev
+WHERE ev.id = e.id AND ev.sequence = 1;
+
+ALTER TABLE cala_entries
+  ALTER COLUMN entry_type SET NOT NULL,
+  ALTER COLUMN layer SET NOT NULL,
+  ALTER COLUMN direction SET NOT NULL,
+  ALTER COLUMN currency SET NOT NULL,
+  ALTER COLUMN units SET NOT NULL;
+
+CREATE INDEX idx_cala_entries_created_at ON cala_entries (created_at, id);
+CREATE INDEX idx_cala_entries_entry_type ON cala_entries (entry_type, created_at, id);
+CREATE INDEX idx_cala_entries_metadata ON cala_entries USING GIN (metadata jsonb_path_ops);
+CREATE INDEX idx_cala_transactions_effective ON cala_transactions (effective, created_at, id);
@@ -0,0 +1,4 @@
+CREATE INDEX idx_cala_transactions_journal_id ON cala_transactions (journal_id, created_at, id);
+CREATE INDEX idx_cala_transactions_journal_id_effective ON cala_transactions (journal_id, effective, created_at, id);
+CREATE INDEX idx_cala_transactions_correlation_id_created_at ON cala_transactions (correlation_id, created_at, id);
+DROP INDEX idx_cala_transactions_correlation_id;
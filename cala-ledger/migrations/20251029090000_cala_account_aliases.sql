@@ -0,0 +1,9 @@
+CREATE TABLE cala_account_aliases (
+  account_id UUID NOT NULL REFERENCES cala_accounts(id),
+  namespace VARCHAR NOT NULL,
+  value VARCHAR NOT NULL,
+  data_source_id UUID NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE(namespace, value)
+);
+CREATE INDEX idx_cala_account_aliases_account_id ON cala_account_aliases (account_id);
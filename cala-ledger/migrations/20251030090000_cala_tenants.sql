@@ -0,0 +1,16 @@
+ALTER TABLE cala_journals ADD COLUMN tenant_id UUID;
+ALTER TABLE cala_journals DROP CONSTRAINT cala_journals_code_key;
+CREATE UNIQUE INDEX cala_journals_tenant_id_code_key
+  ON cala_journals (COALESCE(tenant_id, '00000000-0000-0000-0000-000000000000'), code);
+
+ALTER TABLE cala_accounts ADD COLUMN tenant_id UUID;
+ALTER TABLE cala_accounts DROP CONSTRAINT cala_accounts_code_key;
+CREATE UNIQUE INDEX cala_accounts_tenant_id_code_key
+  ON cala_accounts (COALESCE(tenant_id, '00000000-0000-0000-0000-000000000000'), code);
+
+ALTER TABLE cala_tx_templates ADD COLUMN tenant_id UUID;
+ALTER TABLE cala_tx_templates DROP CONSTRAINT cala_tx_templates_code_key;
+CREATE UNIQUE INDEX cala_tx_templates_tenant_id_code_key
+  ON cala_tx_templates (COALESCE(tenant_id, '00000000-0000-0000-0000-000000000000'), code);
+
+ALTER TABLE cala_velocity_controls ADD COLUMN tenant_id UUID;
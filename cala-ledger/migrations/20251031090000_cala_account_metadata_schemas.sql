@@ -0,0 +1,6 @@
+CREATE TABLE cala_account_metadata_schemas (
+  account_type VARCHAR PRIMARY KEY,
+  schema JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  modified_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,13 @@
+CREATE TABLE cala_velocity_breaches (
+  id UUID PRIMARY KEY,
+  data_source_id UUID NOT NULL,
+  velocity_control_id UUID NOT NULL,
+  velocity_limit_id UUID NOT NULL,
+  account_id UUID NOT NULL,
+  transaction_id UUID NOT NULL,
+  values JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_cala_velocity_breaches_account_id ON cala_velocity_breaches (account_id, created_at, id);
+CREATE INDEX idx_cala_velocity_breaches_velocity_control_id ON cala_velocity_breaches (velocity_control_id, created_at, id);
+CREATE INDEX idx_cala_velocity_breaches_transaction_id ON cala_velocity_breaches (transaction_id);
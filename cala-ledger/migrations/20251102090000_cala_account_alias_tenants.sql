@@ -0,0 +1,6 @@
+ALTER TABLE cala_account_aliases ADD COLUMN tenant_id UUID;
+UPDATE cala_account_aliases al SET tenant_id = a.tenant_id
+  FROM cala_accounts a WHERE a.id = al.account_id;
+ALTER TABLE cala_account_aliases DROP CONSTRAINT cala_account_aliases_namespace_value_key;
+CREATE UNIQUE INDEX cala_account_aliases_tenant_id_namespace_value_key
+  ON cala_account_aliases (COALESCE(tenant_id, '00000000-0000-0000-0000-000000000000'), namespace, value);
@@ -0,0 +1,4 @@
+ALTER TABLE cala_account_metadata_schemas ADD COLUMN tenant_id UUID;
+ALTER TABLE cala_account_metadata_schemas DROP CONSTRAINT cala_account_metadata_schemas_pkey;
+CREATE UNIQUE INDEX cala_account_metadata_schemas_tenant_id_account_type_key
+  ON cala_account_metadata_schemas (COALESCE(tenant_id, '00000000-0000-0000-0000-000000000000'), account_type);
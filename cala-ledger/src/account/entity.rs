@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 pub use cala_types::{account::*, primitives::AccountId, velocity::VelocityContextAccountValues};
 
+use super::error::AccountError;
 use crate::primitives::*;
 
 #[derive(EsEvent, Debug, Serialize, Deserialize)]
@@ -21,6 +22,8 @@ pub enum AccountEvent {
     Updated {
         values: AccountValues,
         fields: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
     },
 }
 
@@ -30,6 +33,8 @@ pub struct Account {
     pub id: AccountId,
     values: AccountValues,
     events: EntityEvents<AccountEvent>,
+    #[builder(default)]
+    pending_status: Option<AccountStatus>,
 }
 
 impl Account {
@@ -53,6 +58,16 @@ impl Account {
         self.values.config.is_account_set
     }
 
+    /// Whether an update of the metadata is waiting to be persisted.
+    pub(super) fn has_pending_metadata_update(&self) -> bool {
+        self.events
+            .iter_all()
+            .skip(self.events.len_persisted())
+            .any(|event| {
+                matches!(event, AccountEvent::Updated { fields, .. } if fields.iter().any(|f| f == "metadata"))
+            })
+    }
+
     pub fn values(&self) -> &AccountValues {
         &self.values
     }
@@ -73,6 +88,7 @@ impl Account {
             normal_balance_type,
             description,
             status,
+            requested_status,
             metadata,
         } = builder
             .into()
@@ -107,6 +123,9 @@ impl Account {
                 updated_fields.push("status".to_string());
             }
         }
+        if let Some(status) = requested_status {
+            self.pending_status = (status != self.values().status).then_some(status);
+        }
         if external_id.is_some() && external_id != self.values().external_id {
             self.values.external_id.clone_from(&external_id);
             updated_fields.push("external_id".to_string());
@@ -128,10 +147,53 @@ impl Account {
             self.events.push(AccountEvent::Updated {
                 values: self.values.clone(),
                 fields: updated_fields,
+                reason: None,
             });
         }
     }
 
+    pub fn status(&self) -> AccountStatus {
+        self.values.status
+    }
+
+    pub(super) fn take_pending_status(&mut self) -> Option<AccountStatus> {
+        self.pending_status.take()
+    }
+
+    /// Moves the account to a new lifecycle status, recording the reason with the update.
+    /// Returns `false` if the account already has the status. Closed accounts can't change
+    /// their status anymore.
+    pub(super) fn transition_status(
+        &mut self,
+        status: AccountStatus,
+        reason: impl Into<String>,
+    ) -> Result<bool, AccountError> {
+        if self.values.status == status {
+            return Ok(false);
+        }
+        if self.values.status == AccountStatus::Closed {
+            return Err(AccountError::AccountClosed(self.id()));
+        }
+        self.values.status = status;
+        self.events.push(AccountEvent::Updated {
+            values: self.values.clone(),
+            fields: vec!["status".to_string()],
+            reason: Some(reason.into()),
+        });
+        Ok(true)
+    }
+
+    /// Checks whether an entry in the given direction may be posted to the account.
+    pub(super) fn check_postable(&self, direction: DebitOrCredit) -> Result<(), AccountError> {
+        match self.values.status {
+            AccountStatus::Active => Ok(()),
+            AccountStatus::Frozen if direction == DebitOrCredit::Credit => Ok(()),
+            AccountStatus::Frozen => Err(AccountError::AccountFrozen(self.id())),
+            AccountStatus::Locked => Err(AccountError::AccountLocked(self.id())),
+            AccountStatus::Closed => Err(AccountError::AccountClosed(self.id())),
+        }
+    }
+
     pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
         self.events
             .entity_first_persisted_at()
@@ -186,13 +248,25 @@ pub struct AccountUpdateValues {
     pub normal_balance_type: Option<DebitOrCredit>,
     #[builder(setter(strip_option, into))]
     pub description: Option<String>,
-    #[builder(setter(strip_option, into))]
-    pub status: Option<Status>,
+    /// Only set when syncing imported accounts, local status changes go through
+    /// `Accounts::update_status`.
+    #[builder(setter(strip_option, into, name = "synced_status"), private)]
+    pub status: Option<AccountStatus>,
+    #[builder(setter(custom))]
+    pub(super) requested_status: Option<AccountStatus>,
     #[builder(setter(custom))]
     pub metadata: Option<serde_json::Value>,
 }
 
 impl AccountUpdate {
+    /// The status is applied by `Accounts::persist` with the same checks as
+    /// `Accounts::update_status`.
+    #[deprecated(note = "use `Accounts::update_status` to record a reason for the change")]
+    pub fn status(&mut self, status: AccountStatus) -> &mut Self {
+        self.requested_status = Some(Some(status));
+        self
+    }
+
     pub fn metadata<T: serde::Serialize>(
         &mut self,
         metadata: T,
@@ -227,7 +301,7 @@ impl From<(AccountValues, Vec<String>)> for AccountUpdate {
                     }
                 }
                 "status" => {
-                    builder.status(values.status);
+                    builder.synced_status(values.status);
                 }
                 "metadata" => {
                     if let Some(metadata) = values.metadata.clone() {
@@ -245,21 +319,37 @@ impl From<(AccountValues, Vec<String>)> for AccountUpdate {
 
 /// Representation of a ***new*** ledger account entity with required/optional properties and a builder.
 #[derive(Builder, Debug, Clone)]
+#[builder(build_fn(validate = "Self::validate"))]
 pub struct NewAccount {
     #[builder(setter(into))]
     pub id: AccountId,
     #[builder(setter(into))]
     pub(super) code: String,
+    #[builder(setter(strip_option, into), default)]
+    pub(super) tenant_id: Option<TenantId>,
     #[builder(setter(into))]
     pub(super) name: String,
+    /// The metadata of typed accounts is validated against the schema registered for the
+    /// tenant and type via `Accounts::set_metadata_schema`.
+    #[builder(setter(strip_option, into), default)]
+    pub(super) account_type: Option<String>,
     #[builder(setter(strip_option, into), default)]
     pub(super) external_id: Option<String>,
     #[builder(default)]
     pub(super) normal_balance_type: DebitOrCredit,
     #[builder(default)]
-    pub(super) status: Status,
-    #[builder(setter(custom), default)]
+    pub(super) status: AccountStatus,
+    /// Balances of eventually consistent accounts are not updated while posting but
+    /// folded in asynchronously by `Balances::fold_queued_entries`. This avoids lock
+    /// contention on accounts with a high volume of postings.
+    #[builder(default)]
     pub(super) eventually_consistent: bool,
+    /// Splits the balance of the account into the given number of shards that are
+    /// updated independently so that concurrent postings don't contend on a single lock.
+    /// Reads merge the shards. Velocity controls can't be attached to sharded accounts
+    /// and no effective balances or balance history are recorded for them.
+    #[builder(setter(strip_option), default)]
+    pub(super) balance_shards: Option<u16>,
     #[builder(setter(custom), default)]
     pub(super) is_account_set: bool,
     #[builder(setter(custom), default)]
@@ -267,7 +357,7 @@ pub struct NewAccount {
     #[builder(setter(strip_option, into), default)]
     description: Option<String>,
     #[builder(setter(custom), default)]
-    metadata: Option<serde_json::Value>,
+    pub(super) metadata: Option<serde_json::Value>,
 }
 
 impl NewAccount {
@@ -290,7 +380,9 @@ impl NewAccount {
             id: self.id,
             version: 1,
             code: self.code,
+            tenant_id: self.tenant_id,
             name: self.name,
+            account_type: self.account_type,
             external_id: self.external_id,
             normal_balance_type: self.normal_balance_type,
             status: self.status,
@@ -298,7 +390,8 @@ impl NewAccount {
             metadata: self.metadata,
             config: AccountConfig {
                 is_account_set: self.is_account_set,
-                eventually_consistent: false,
+                eventually_consistent: self.eventually_consistent,
+                balance_shards: self.balance_shards,
             },
         }
     }
@@ -330,13 +423,21 @@ impl NewAccountBuilder {
 
     pub(crate) fn is_account_set(&mut self, is_account_set: bool) -> &mut Self {
         self.is_account_set = Some(is_account_set);
-        self.eventually_consistent(is_account_set)
+        self
     }
 
-    // dummy fn since its not fully supported yet
-    fn eventually_consistent(&mut self, eventually_consistent: bool) -> &mut Self {
-        self.is_account_set = Some(eventually_consistent);
-        self
+    fn validate(&self) -> Result<(), String> {
+        if let Some(Some(shards)) = self.balance_shards {
+            if shards < 2 {
+                return Err("balance_shards must be at least 2".to_string());
+            }
+            if self.eventually_consistent == Some(true) {
+                return Err(
+                    "balance_shards can't be combined with eventually_consistent".to_string(),
+                );
+            }
+        }
+        Ok(())
     }
 }
 
@@ -356,7 +457,7 @@ mod tests {
         assert_eq!(new_account.name, "name");
         assert_eq!(new_account.normal_balance_type, DebitOrCredit::Credit);
         assert_eq!(new_account.description, None);
-        assert_eq!(new_account.status, Status::Active);
+        assert_eq!(new_account.status, AccountStatus::Active);
         assert_eq!(new_account.metadata, None);
     }
 
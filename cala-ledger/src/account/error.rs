@@ -16,19 +16,41 @@ pub enum AccountError {
     CouldNotFindByExternalId(String),
     #[error("AccountError - NotFound: code '{0}' not found")]
     CouldNotFindByCode(String),
+    #[error("AccountError - NotFound: alias '{0}:{1}' not found")]
+    CouldNotFindByAlias(String, String),
     #[error("AccountError - external_id already exists")]
     ExternalIdAlreadyExists,
     #[error("AccountError - code already exists")]
     CodeAlreadyExists,
+    #[error("AccountError - alias already exists in its namespace")]
+    AliasAlreadyExists,
     #[error("AccountError - cannot update accounts backing an AccountSet")]
     CannotUpdateAccountSetAccounts,
+    #[error("AccountError - AccountFrozen: account '{0}' is frozen and can't be debited")]
+    AccountFrozen(AccountId),
+    #[error("AccountError - AccountLocked: account '{0}' is locked")]
+    AccountLocked(AccountId),
+    #[error("AccountError - AccountClosed: account '{0}' is closed")]
+    AccountClosed(AccountId),
+    #[error("AccountError - TenantMismatch: account '{0}' belongs to a different tenant")]
+    TenantMismatch(AccountId),
+    #[error("AccountError - MetadataSchemaNotFound: no schema registered for account type '{0}'")]
+    MetadataSchemaNotFound(String),
+    #[error("AccountError - MetadataSchemaError: {0}")]
+    MetadataSchemaError(#[from] crate::metadata_schema::error::MetadataSchemaError),
+    #[error(
+        "AccountError - NonZeroBalance: account '{0}' can't be closed with a non-zero balance"
+    )]
+    NonZeroBalance(AccountId),
 }
 
 impl From<sqlx::Error> for AccountError {
     fn from(error: sqlx::Error) -> Self {
         if let Some(err) = error.as_database_error() {
             if let Some(constraint) = err.constraint() {
-                if constraint.contains("external_id") {
+                if constraint.contains("aliases") && err.is_unique_violation() {
+                    return Self::AliasAlreadyExists;
+                } else if constraint.contains("external_id") {
                     return Self::ExternalIdAlreadyExists;
                 } else if constraint.contains("code") {
                     return Self::CodeAlreadyExists;
@@ -0,0 +1,75 @@
+use serde_json::Value;
+
+use crate::{
+    metadata_filter::{add_path_eq, escape_like},
+    primitives::{AccountSetId, AccountStatus, DebitOrCredit, TenantId},
+};
+
+/// Criteria for [`Accounts::search`](super::Accounts::search). All criteria that are set
+/// must match. Account set accounts are never returned and only the accounts of the
+/// tenant are searched, or the accounts without a tenant if none is set.
+#[derive(Debug, Default, Clone)]
+pub struct AccountFilter {
+    pub(super) tenant_id: Option<TenantId>,
+    pub(super) code_prefix: Option<String>,
+    pub(super) name_contains: Option<String>,
+    pub(super) normal_balance_type: Option<DebitOrCredit>,
+    pub(super) status: Option<AccountStatus>,
+    pub(super) metadata: Option<Value>,
+    pub(super) member_of: Option<AccountSetId>,
+}
+
+impl AccountFilter {
+    pub fn tenant_id(mut self, tenant_id: impl Into<TenantId>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    pub fn code_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.code_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Case insensitive substring match on the name.
+    pub fn name_contains(mut self, name: impl Into<String>) -> Self {
+        self.name_contains = Some(name.into());
+        self
+    }
+
+    pub fn normal_balance_type(mut self, normal_balance_type: DebitOrCredit) -> Self {
+        self.normal_balance_type = Some(normal_balance_type);
+        self
+    }
+
+    pub fn status(mut self, status: AccountStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Requires the metadata value at the dot separated `path` (eg. `region.country`)
+    /// to contain `value` in the sense of the jsonb `@>` operator. Scalars have to be
+    /// equal while arrays and objects only need to include the elements and keys of
+    /// `value`. Can be called multiple times to match several paths.
+    pub fn metadata_eq(mut self, path: &str, value: impl Into<Value>) -> Self {
+        self.metadata = Some(add_path_eq(self.metadata.take(), path, value.into()));
+        self
+    }
+
+    /// Only accounts that are members of the account set, directly or through nested sets.
+    pub fn member_of(mut self, account_set_id: impl Into<AccountSetId>) -> Self {
+        self.member_of = Some(account_set_id.into());
+        self
+    }
+
+    pub(super) fn code_pattern(&self) -> Option<String> {
+        self.code_prefix
+            .as_ref()
+            .map(|prefix| format!("{}%", escape_like(prefix)))
+    }
+
+    pub(super) fn name_pattern(&self) -> Option<String> {
+        self.name_contains
+            .as_ref()
+            .map(|name| format!("%{}%", escape_like(name)))
+    }
+}
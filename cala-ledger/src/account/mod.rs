@@ -1,6 +1,7 @@
 //! [Account] holds a balance in a [Journal](crate::journal::Journal)
 mod entity;
 pub mod error;
+mod filter;
 mod repo;
 
 use es_entity::EsEntity;
@@ -11,10 +12,18 @@ use std::collections::HashMap;
 
 #[cfg(feature = "import")]
 use crate::primitives::DataSourceId;
-use crate::{ledger_operation::*, outbox::*, primitives::DataSource};
+use cala_types::entry::EntryValues;
+
+use crate::{
+    ledger_operation::*,
+    metadata_schema,
+    outbox::*,
+    primitives::{AccountStatus, DataSource, TenantId},
+};
 
 pub use entity::*;
 use error::*;
+pub use filter::*;
 pub use repo::account_cursor::*;
 use repo::*;
 
@@ -48,6 +57,17 @@ impl Accounts {
         db: &mut LedgerOperation<'_>,
         new_account: NewAccount,
     ) -> Result<Account, AccountError> {
+        self.check_metadata_in_op(
+            db,
+            new_account.account_type.as_deref().map(|account_type| {
+                (
+                    new_account.tenant_id,
+                    account_type,
+                    new_account.metadata.as_ref(),
+                )
+            }),
+        )
+        .await?;
         let account = self.repo.create_in_op(db, new_account).await?;
         db.accumulate(account.last_persisted(1).map(|p| &p.event));
         Ok(account)
@@ -69,6 +89,19 @@ impl Accounts {
         db: &mut LedgerOperation<'_>,
         new_accounts: Vec<NewAccount>,
     ) -> Result<Vec<Account>, AccountError> {
+        self.check_metadata_in_op(
+            db,
+            new_accounts.iter().filter_map(|new_account| {
+                new_account.account_type.as_deref().map(|account_type| {
+                    (
+                        new_account.tenant_id,
+                        account_type,
+                        new_account.metadata.as_ref(),
+                    )
+                })
+            }),
+        )
+        .await?;
         let accounts = self.repo.create_all_in_op(db, new_accounts).await?;
         db.accumulate(
             accounts
@@ -104,9 +137,180 @@ impl Accounts {
         self.repo.find_by_external_id(Some(external_id)).await
     }
 
+    /// Codes are unique per tenant, pass `None` to look up accounts of the default scope.
     #[instrument(name = "cala_ledger.accounts.find_by_code", skip(self))]
-    pub async fn find_by_code(&self, code: String) -> Result<Account, AccountError> {
-        self.repo.find_by_code(code).await
+    pub async fn find_by_code(
+        &self,
+        tenant_id: Option<TenantId>,
+        code: String,
+    ) -> Result<Account, AccountError> {
+        self.repo.find_by_tenant_and_code(tenant_id, code).await
+    }
+
+    /// Finds the account identified by `value` within the alias `namespace`. Aliases are
+    /// unique per tenant, pass `None` to look up accounts of the default scope.
+    #[instrument(name = "cala_ledger.accounts.find_by_alias", skip(self))]
+    pub async fn find_by_alias(
+        &self,
+        tenant_id: Option<TenantId>,
+        namespace: impl AsRef<str> + std::fmt::Debug,
+        value: impl AsRef<str> + std::fmt::Debug,
+    ) -> Result<Account, AccountError> {
+        let account_id = self
+            .repo
+            .find_id_by_alias(tenant_id, namespace.as_ref(), value.as_ref())
+            .await?;
+        self.repo.find_by_id(account_id).await
+    }
+
+    /// Resolves the aliases of the tenant to the ids of their accounts. Aliases that don't
+    /// exist are missing from the result.
+    pub(crate) async fn find_ids_by_aliases_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        aliases: &[AccountAlias],
+    ) -> Result<HashMap<AccountAlias, AccountId>, AccountError> {
+        self.repo
+            .find_ids_by_aliases_in_op(db, tenant_id, aliases)
+            .await
+    }
+
+    #[instrument(name = "cala_ledger.accounts.list_aliases", skip(self))]
+    pub async fn list_aliases(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Vec<AccountAlias>, AccountError> {
+        self.repo.list_aliases(account_id).await
+    }
+
+    pub async fn add_alias(
+        &self,
+        account_id: AccountId,
+        alias: AccountAlias,
+    ) -> Result<Account, AccountError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        let account = self.add_alias_in_op(&mut op, account_id, alias).await?;
+        op.commit().await?;
+        Ok(account)
+    }
+
+    /// Adds an external identifier to the account. Fails with
+    /// [`AccountError::AliasAlreadyExists`] if the value is taken in its namespace by
+    /// another account of the same tenant.
+    #[instrument(name = "cala_ledger.accounts.add_alias", skip(self, db))]
+    pub async fn add_alias_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        account_id: AccountId,
+        alias: AccountAlias,
+    ) -> Result<Account, AccountError> {
+        let account = self.repo.find_by_id_in_op(&mut *db, account_id).await?;
+        self.repo
+            .insert_alias_in_op(db, DataSource::Local.into(), account_id, &alias)
+            .await?;
+        db.accumulate(std::iter::once(OutboxEventPayload::AccountAliasAdded {
+            source: DataSource::Local,
+            account_id,
+            alias,
+        }));
+        Ok(account)
+    }
+
+    pub async fn remove_alias(
+        &self,
+        account_id: AccountId,
+        alias: AccountAlias,
+    ) -> Result<Account, AccountError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        let account = self.remove_alias_in_op(&mut op, account_id, alias).await?;
+        op.commit().await?;
+        Ok(account)
+    }
+
+    #[instrument(name = "cala_ledger.accounts.remove_alias", skip(self, db))]
+    pub async fn remove_alias_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        account_id: AccountId,
+        alias: AccountAlias,
+    ) -> Result<Account, AccountError> {
+        let account = self.repo.find_by_id_in_op(&mut *db, account_id).await?;
+        self.repo.delete_alias_in_op(db, account_id, &alias).await?;
+        db.accumulate(std::iter::once(OutboxEventPayload::AccountAliasRemoved {
+            source: DataSource::Local,
+            account_id,
+            alias,
+        }));
+        Ok(account)
+    }
+
+    pub async fn set_metadata_schema(
+        &self,
+        tenant_id: Option<TenantId>,
+        account_type: impl Into<String> + std::fmt::Debug,
+        schema: serde_json::Value,
+    ) -> Result<(), AccountError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        self.set_metadata_schema_in_op(&mut op, tenant_id, account_type, schema)
+            .await?;
+        op.commit().await?;
+        Ok(())
+    }
+
+    /// Registers (or replaces) the JSON schema for the metadata of the tenant's accounts of
+    /// the type. Existing accounts are only validated against it once their metadata is updated.
+    #[instrument(
+        name = "cala_ledger.accounts.set_metadata_schema",
+        skip(self, db, schema)
+    )]
+    pub async fn set_metadata_schema_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        account_type: impl Into<String> + std::fmt::Debug,
+        schema: serde_json::Value,
+    ) -> Result<(), AccountError> {
+        metadata_schema::check_schema(&schema)?;
+        self.repo
+            .upsert_metadata_schema_in_op(db, tenant_id, &account_type.into(), &schema)
+            .await
+    }
+
+    #[instrument(name = "cala_ledger.accounts.find_metadata_schema", skip(self))]
+    pub async fn find_metadata_schema(
+        &self,
+        tenant_id: Option<TenantId>,
+        account_type: impl AsRef<str> + std::fmt::Debug,
+    ) -> Result<Option<serde_json::Value>, AccountError> {
+        self.repo
+            .find_metadata_schema(tenant_id, account_type.as_ref())
+            .await
+    }
+
+    async fn check_metadata_in_op<'a>(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        typed_metadata: impl IntoIterator<
+            Item = (Option<TenantId>, &'a str, Option<&'a serde_json::Value>),
+        >,
+    ) -> Result<(), AccountError> {
+        let typed_metadata: Vec<_> = typed_metadata.into_iter().collect();
+        if typed_metadata.is_empty() {
+            return Ok(());
+        }
+        let keys: Vec<_> = typed_metadata
+            .iter()
+            .map(|(tenant_id, account_type, _)| (*tenant_id, *account_type))
+            .collect();
+        let schemas = self.repo.find_metadata_schemas_in_op(db, &keys).await?;
+        for (tenant_id, account_type, metadata) in typed_metadata {
+            let schema = schemas
+                .get(&(tenant_id, account_type.to_string()))
+                .ok_or_else(|| AccountError::MetadataSchemaNotFound(account_type.to_string()))?;
+            metadata_schema::validate(schema, metadata)?;
+        }
+        Ok(())
     }
 
     #[instrument(name = "cala_ledger.accounts.list", skip(self))]
@@ -117,6 +321,16 @@ impl Accounts {
         self.repo.list_by_name(query, Default::default()).await
     }
 
+    /// Lists the accounts matching the filter ordered by name.
+    #[instrument(name = "cala_ledger.accounts.search", skip(self))]
+    pub async fn search(
+        &self,
+        filter: AccountFilter,
+        query: es_entity::PaginatedQueryArgs<AccountsByNameCursor>,
+    ) -> Result<es_entity::PaginatedQueryRet<Account, AccountsByNameCursor>, AccountError> {
+        self.repo.search(&filter, query).await
+    }
+
     #[instrument(name = "cala_ledger.accounts.persist", skip(self, account))]
     pub async fn persist(&self, account: &mut Account) -> Result<(), AccountError> {
         let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
@@ -133,12 +347,121 @@ impl Accounts {
         if account.is_account_set() {
             return Err(AccountError::CannotUpdateAccountSetAccounts);
         }
+        if account.has_pending_metadata_update() {
+            let values = account.values();
+            self.check_metadata_in_op(
+                db,
+                values
+                    .account_type
+                    .as_deref()
+                    .map(|account_type| (values.tenant_id, account_type, values.metadata.as_ref())),
+            )
+            .await?;
+        }
+        if let Some(status) = account.take_pending_status() {
+            self.repo.lock_for_status_change(db, account.id()).await?;
+            self.transition_status_in_op(db, account, status, "account update")
+                .await?;
+        }
 
         let n_events = self.repo.update_in_op(db, account).await?;
         db.accumulate(account.last_persisted(n_events).map(|p| &p.event));
         Ok(())
     }
 
+    pub async fn update_status(
+        &self,
+        account_id: AccountId,
+        status: AccountStatus,
+        reason: impl Into<String> + std::fmt::Debug,
+    ) -> Result<Account, AccountError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        let account = self
+            .update_status_in_op(&mut op, account_id, status, reason)
+            .await?;
+        op.commit().await?;
+        Ok(account)
+    }
+
+    /// Moves the account through its lifecycle (active, frozen, locked, closed). The
+    /// reason is recorded on the update event. Closing requires the account to have a
+    /// zero balance in every journal and closed accounts can't be reopened.
+    #[instrument(name = "cala_ledger.accounts.update_status", skip(self, db))]
+    pub async fn update_status_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        account_id: AccountId,
+        status: AccountStatus,
+        reason: impl Into<String> + std::fmt::Debug,
+    ) -> Result<Account, AccountError> {
+        self.repo.lock_for_status_change(db, account_id).await?;
+        let mut account = self.repo.find_by_id_in_op(&mut *db, account_id).await?;
+        if account.is_account_set() {
+            return Err(AccountError::CannotUpdateAccountSetAccounts);
+        }
+        if self
+            .transition_status_in_op(db, &mut account, status, reason)
+            .await?
+        {
+            self.persist_in_op(db, &mut account).await?;
+        }
+        Ok(account)
+    }
+
+    async fn transition_status_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        account: &mut Account,
+        status: AccountStatus,
+        reason: impl Into<String>,
+    ) -> Result<bool, AccountError> {
+        if status == AccountStatus::Closed
+            && account.status() != AccountStatus::Closed
+            && !self.repo.has_zero_balance(db, account.id()).await?
+        {
+            return Err(AccountError::NonZeroBalance(account.id()));
+        }
+        account.transition_status(status, reason)
+    }
+
+    /// Rejects the entries if any of their accounts doesn't accept postings in the
+    /// direction of the entry.
+    pub(crate) async fn check_postable_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        entries: &[EntryValues],
+    ) -> Result<(), AccountError> {
+        let account_ids: Vec<_> = entries.iter().map(|entry| entry.account_id).collect();
+        self.repo.lock_for_posting(db, &account_ids).await?;
+        let accounts = self
+            .repo
+            .find_all_in_op::<Account>(&mut *db, &account_ids)
+            .await?;
+        for entry in entries {
+            if let Some(account) = accounts.get(&entry.account_id) {
+                account.check_postable(entry.direction)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects references to accounts that don't belong to the tenant.
+    pub(crate) async fn check_tenant_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        account_ids: &[AccountId],
+    ) -> Result<(), AccountError> {
+        match self
+            .repo
+            .find_first_outside_tenant(db, tenant_id, account_ids)
+            .await?
+        {
+            Some(account_id) => Err(AccountError::TenantMismatch(account_id)),
+            None => Ok(()),
+        }
+    }
+
     pub(crate) async fn update_velocity_context_values_in_op(
         &self,
         db: &mut LedgerOperation<'_>,
@@ -191,6 +514,58 @@ impl Accounts {
             .await?;
         Ok(())
     }
+
+    #[cfg(feature = "import")]
+    pub async fn sync_alias_addition(
+        &self,
+        mut db: es_entity::DbOpWithTime<'_>,
+        origin: DataSourceId,
+        account_id: AccountId,
+        alias: AccountAlias,
+    ) -> Result<(), AccountError> {
+        self.repo
+            .insert_alias_in_op(&mut db, origin, account_id, &alias)
+            .await?;
+        let time = db.now();
+        self.outbox
+            .persist_events_at(
+                db,
+                std::iter::once(OutboxEventPayload::AccountAliasAdded {
+                    source: DataSource::Remote { id: origin },
+                    account_id,
+                    alias,
+                }),
+                time,
+            )
+            .await?;
+        Ok(())
+    }
+
+    #[cfg(feature = "import")]
+    pub async fn sync_alias_removal(
+        &self,
+        mut db: es_entity::DbOpWithTime<'_>,
+        origin: DataSourceId,
+        account_id: AccountId,
+        alias: AccountAlias,
+    ) -> Result<(), AccountError> {
+        self.repo
+            .delete_alias_in_op(&mut db, account_id, &alias)
+            .await?;
+        let time = db.now();
+        self.outbox
+            .persist_events_at(
+                db,
+                std::iter::once(OutboxEventPayload::AccountAliasRemoved {
+                    source: DataSource::Remote { id: origin },
+                    account_id,
+                    alias,
+                }),
+                time,
+            )
+            .await?;
+        Ok(())
+    }
 }
 
 impl From<&AccountEvent> for OutboxEventPayload {
@@ -211,6 +586,7 @@ impl From<&AccountEvent> for OutboxEventPayload {
             AccountEvent::Updated {
                 values: account,
                 fields,
+                ..
             } => OutboxEventPayload::AccountUpdated {
                 source: DataSource::Local,
                 account: account.clone(),
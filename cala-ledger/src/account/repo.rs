@@ -1,9 +1,13 @@
 use es_entity::*;
 use sqlx::PgPool;
 
-use crate::primitives::{AccountId, DataSourceId, DebitOrCredit};
+use std::collections::HashMap;
 
-use super::{entity::*, error::AccountError};
+use crate::primitives::{
+    AccountId, AccountSetId, AccountStatus, DataSourceId, DebitOrCredit, TenantId,
+};
+
+use super::{entity::*, error::AccountError, filter::AccountFilter};
 
 #[derive(EsRepo, Debug, Clone)]
 #[es_repo(
@@ -12,6 +16,7 @@ use super::{entity::*, error::AccountError};
     columns(
         name(ty = "String", update(accessor = "values().name"), list_by),
         code(ty = "String", update(accessor = "values().code"), list_by),
+        tenant_id(ty = "Option<TenantId>", update(persist = false)),
         external_id(
             ty = "Option<String>",
             update(accessor = "values().external_id"),
@@ -21,7 +26,17 @@ use super::{entity::*, error::AccountError};
             ty = "DebitOrCredit",
             update(accessor = "values().normal_balance_type")
         ),
+        status(ty = "AccountStatus", update(accessor = "values().status")),
+        metadata(
+            ty = "Option<serde_json::Value>",
+            update(accessor = "values().metadata")
+        ),
         eventually_consistent(ty = "bool", update(persist = false)),
+        balance_shards(
+            ty = "Option<i32>",
+            create(accessor = "balance_shards.map(i32::from)"),
+            update(persist = false)
+        ),
         velocity_context_values(
             ty = "VelocityContextAccountValues",
             create(accessor = "context_values()"),
@@ -54,17 +69,21 @@ impl AccountRepo {
     ) -> Result<(), AccountError> {
         let recorded_at = op.now();
         sqlx::query!(
-            r#"INSERT INTO cala_accounts (data_source_id, id, code, name, external_id, normal_balance_type, eventually_consistent, created_at, velocity_context_values)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+            r#"INSERT INTO cala_accounts (data_source_id, id, code, name, external_id, normal_balance_type, status, metadata, eventually_consistent, created_at, velocity_context_values, balance_shards, tenant_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)"#,
             origin as DataSourceId,
             account.values().id as AccountId,
             account.values().code,
             account.values().name,
             account.values().external_id,
             account.values().normal_balance_type as DebitOrCredit,
+            account.values().status as AccountStatus,
+            account.values().metadata,
             account.values().config.eventually_consistent,
             recorded_at,
             account.context_values() as VelocityContextAccountValues,
+            account.values().config.balance_shards.map(i32::from),
+            account.values().tenant_id as Option<TenantId>,
         )
         .execute(op.as_executor())
         .await?;
@@ -72,6 +91,77 @@ impl AccountRepo {
         Ok(())
     }
 
+    pub async fn find_by_tenant_and_code(
+        &self,
+        tenant_id: Option<TenantId>,
+        code: String,
+    ) -> Result<Account, AccountError> {
+        es_query!(
+            tbl_prefix = "cala",
+            r#"SELECT id FROM cala_accounts
+            WHERE tenant_id IS NOT DISTINCT FROM $1 AND code = $2"#,
+            tenant_id as Option<TenantId>,
+            &code,
+        )
+        .fetch_optional(self.pool())
+        .await?
+        .ok_or(AccountError::CouldNotFindByCode(code))
+    }
+
+    pub async fn search(
+        &self,
+        filter: &AccountFilter,
+        query: es_entity::PaginatedQueryArgs<account_cursor::AccountsByNameCursor>,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<Account, account_cursor::AccountsByNameCursor>,
+        AccountError,
+    > {
+        let (entities, has_next_page) = es_entity::es_query!(
+            tbl_prefix = "cala",
+            r#"SELECT a.id, a.name, a.created_at
+              FROM cala_accounts a
+              WHERE NOT EXISTS (SELECT 1 FROM cala_account_sets s WHERE s.id = a.id)
+              AND a.tenant_id IS NOT DISTINCT FROM $10
+              AND ($1::text IS NULL OR a.code LIKE $1)
+              AND ($2::text IS NULL OR a.name ILIKE $2)
+              AND ($3::DebitOrCredit IS NULL OR a.normal_balance_type = $3)
+              AND ($4::AccountStatus IS NULL OR a.status = $4)
+              AND ($5::jsonb IS NULL OR a.metadata @> $5)
+              AND ($6::uuid IS NULL OR EXISTS (
+                SELECT 1 FROM cala_account_set_member_accounts m
+                WHERE m.account_set_id = $6 AND m.member_account_id = a.id
+              ))
+              AND ((a.name, a.id) > ($8, $7) OR ($8 IS NULL AND $7 IS NULL))
+              ORDER BY a.name, a.id
+              LIMIT $9"#,
+            filter.code_pattern(),
+            filter.name_pattern(),
+            filter.normal_balance_type as Option<DebitOrCredit>,
+            filter.status as Option<AccountStatus>,
+            filter.metadata,
+            filter.member_of as Option<AccountSetId>,
+            query.after.as_ref().map(|c| c.id) as Option<AccountId>,
+            query.after.map(|c| c.name),
+            query.first as i64 + 1,
+            filter.tenant_id as Option<TenantId>
+        )
+        .fetch_n(&self.pool, query.first)
+        .await?;
+
+        let mut end_cursor = None;
+        if let Some(last) = entities.last() {
+            end_cursor = Some(account_cursor::AccountsByNameCursor {
+                id: last.values().id,
+                name: last.values().name.clone(),
+            });
+        }
+        Ok(es_entity::PaginatedQueryRet {
+            entities,
+            has_next_page,
+            end_cursor,
+        })
+    }
+
     pub async fn update_velocity_context_values_in_op(
         &self,
         op: &mut impl es_entity::AtomicOperation,
@@ -90,4 +180,281 @@ impl AccountRepo {
         .await?;
         Ok(())
     }
+
+    /// Serialises status changes of an account with postings to it. Postings hold the
+    /// lock shared so that closing an account waits for in-flight postings to commit.
+    pub async fn lock_for_status_change(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_id: AccountId,
+    ) -> Result<(), AccountError> {
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock(hashtext(concat('account_status', $1::text)))",
+            account_id as AccountId,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_first_outside_tenant(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        tenant_id: Option<TenantId>,
+        account_ids: &[AccountId],
+    ) -> Result<Option<AccountId>, AccountError> {
+        let row = sqlx::query!(
+            r#"SELECT id AS "id: AccountId" FROM cala_accounts
+            WHERE id = ANY($1) AND tenant_id IS DISTINCT FROM $2
+            LIMIT 1"#,
+            account_ids as &[AccountId],
+            tenant_id as Option<TenantId>,
+        )
+        .fetch_optional(op.as_executor())
+        .await?;
+        Ok(row.map(|row| row.id))
+    }
+
+    pub async fn lock_for_posting(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_ids: &[AccountId],
+    ) -> Result<(), AccountError> {
+        sqlx::query!(
+            r#"
+            SELECT pg_advisory_xact_lock_shared(hashtext(concat('account_status', id::text)))
+            FROM (SELECT DISTINCT UNNEST($1::uuid[]) AS id ORDER BY id) ids"#,
+            account_ids as &[AccountId],
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    /// Whether the balances of the account net out to zero in every journal, currency
+    /// and layer. Reads the current balances merged with the shards and adds the entries
+    /// of eventually consistent accounts that are still queued for folding.
+    pub async fn has_zero_balance(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_id: AccountId,
+    ) -> Result<bool, AccountError> {
+        let row = sqlx::query!(
+            r#"
+            WITH balances AS (
+                SELECT journal_id, currency, latest_values AS values
+                FROM cala_current_balances
+                WHERE account_id = $1
+                UNION ALL
+                SELECT journal_id, currency, values
+                FROM cala_balance_shards
+                WHERE account_id = $1
+            ),
+            amounts AS (
+                SELECT b.journal_id, b.currency, l.layer,
+                    (b.values->l.layer->>'dr_balance')::numeric
+                    - (b.values->l.layer->>'cr_balance')::numeric AS amount
+                FROM balances b
+                CROSS JOIN (VALUES ('settled'), ('pending'), ('encumbrance')) AS l(layer)
+                UNION ALL
+                SELECT q.journal_id, q.currency, LOWER(q.values->>'layer'),
+                    CASE WHEN q.values->>'direction' = 'debit'
+                    THEN (q.values->>'units')::numeric
+                    ELSE -(q.values->>'units')::numeric
+                    END
+                FROM cala_balance_queue q
+                WHERE q.account_id = $1
+            )
+            SELECT NOT EXISTS (
+                SELECT 1
+                FROM amounts
+                GROUP BY journal_id, currency, layer
+                HAVING SUM(amount) <> 0
+            ) AS "is_zero!""#,
+            account_id as AccountId,
+        )
+        .fetch_one(op.as_executor())
+        .await?;
+        Ok(row.is_zero)
+    }
+
+    pub async fn insert_alias_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        origin: DataSourceId,
+        account_id: AccountId,
+        alias: &AccountAlias,
+    ) -> Result<(), AccountError> {
+        let recorded_at = op.now();
+        sqlx::query!(
+            r#"INSERT INTO cala_account_aliases (account_id, tenant_id, namespace, value, data_source_id, created_at)
+            SELECT id, tenant_id, $2, $3, $4, COALESCE($5, NOW())
+            FROM cala_accounts WHERE id = $1"#,
+            account_id as AccountId,
+            alias.namespace,
+            alias.value,
+            origin as DataSourceId,
+            recorded_at,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_alias_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_id: AccountId,
+        alias: &AccountAlias,
+    ) -> Result<(), AccountError> {
+        let result = sqlx::query!(
+            r#"DELETE FROM cala_account_aliases
+            WHERE account_id = $1 AND namespace = $2 AND value = $3"#,
+            account_id as AccountId,
+            alias.namespace,
+            alias.value,
+        )
+        .execute(op.as_executor())
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AccountError::CouldNotFindByAlias(
+                alias.namespace.clone(),
+                alias.value.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn find_id_by_alias(
+        &self,
+        tenant_id: Option<TenantId>,
+        namespace: &str,
+        value: &str,
+    ) -> Result<AccountId, AccountError> {
+        let row = sqlx::query!(
+            r#"SELECT account_id AS "account_id: AccountId"
+            FROM cala_account_aliases
+            WHERE tenant_id IS NOT DISTINCT FROM $1 AND namespace = $2 AND value = $3"#,
+            tenant_id as Option<TenantId>,
+            namespace,
+            value,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|row| row.account_id).ok_or_else(|| {
+            AccountError::CouldNotFindByAlias(namespace.to_string(), value.to_string())
+        })
+    }
+
+    pub async fn find_ids_by_aliases_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        tenant_id: Option<TenantId>,
+        aliases: &[AccountAlias],
+    ) -> Result<HashMap<AccountAlias, AccountId>, AccountError> {
+        let (namespaces, values): (Vec<_>, Vec<_>) = aliases
+            .iter()
+            .map(|alias| (alias.namespace.as_str(), alias.value.as_str()))
+            .unzip();
+        let rows = sqlx::query!(
+            r#"SELECT a.account_id AS "account_id: AccountId", a.namespace, a.value
+            FROM cala_account_aliases a
+            JOIN UNNEST($1::text[], $2::text[]) AS requested(namespace, value)
+                ON a.namespace = requested.namespace AND a.value = requested.value
+            WHERE a.tenant_id IS NOT DISTINCT FROM $3"#,
+            &namespaces as &[&str],
+            &values as &[&str],
+            tenant_id as Option<TenantId>,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (AccountAlias::new(row.namespace, row.value), row.account_id))
+            .collect())
+    }
+
+    pub async fn upsert_metadata_schema_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        tenant_id: Option<TenantId>,
+        account_type: &str,
+        schema: &serde_json::Value,
+    ) -> Result<(), AccountError> {
+        let recorded_at = op.now();
+        sqlx::query!(
+            r#"INSERT INTO cala_account_metadata_schemas (tenant_id, account_type, schema, created_at, modified_at)
+            VALUES ($1, $2, $3, COALESCE($4, NOW()), COALESCE($4, NOW()))
+            ON CONFLICT ((COALESCE(tenant_id, '00000000-0000-0000-0000-000000000000')), account_type) DO UPDATE
+            SET schema = EXCLUDED.schema, modified_at = EXCLUDED.modified_at"#,
+            tenant_id as Option<TenantId>,
+            account_type,
+            schema,
+            recorded_at,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_metadata_schema(
+        &self,
+        tenant_id: Option<TenantId>,
+        account_type: &str,
+    ) -> Result<Option<serde_json::Value>, AccountError> {
+        let row = sqlx::query!(
+            r#"SELECT schema FROM cala_account_metadata_schemas
+            WHERE tenant_id IS NOT DISTINCT FROM $1 AND account_type = $2"#,
+            tenant_id as Option<TenantId>,
+            account_type,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| row.schema))
+    }
+
+    pub async fn find_metadata_schemas_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        keys: &[(Option<TenantId>, &str)],
+    ) -> Result<HashMap<(Option<TenantId>, String), serde_json::Value>, AccountError> {
+        let (tenant_ids, account_types): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .map(|(tenant_id, account_type)| (tenant_id.map(uuid::Uuid::from), *account_type))
+            .unzip();
+        let rows = sqlx::query!(
+            r#"SELECT DISTINCT s.tenant_id AS "tenant_id: TenantId", s.account_type, s.schema
+            FROM cala_account_metadata_schemas s
+            JOIN UNNEST($1::uuid[], $2::text[]) AS requested(tenant_id, account_type)
+                ON s.tenant_id IS NOT DISTINCT FROM requested.tenant_id
+                AND s.account_type = requested.account_type"#,
+            &tenant_ids as &[Option<uuid::Uuid>],
+            &account_types as &[&str],
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| ((row.tenant_id, row.account_type), row.schema))
+            .collect())
+    }
+
+    pub async fn list_aliases(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Vec<AccountAlias>, AccountError> {
+        let rows = sqlx::query!(
+            r#"SELECT namespace, value
+            FROM cala_account_aliases
+            WHERE account_id = $1
+            ORDER BY namespace, value"#,
+            account_id as AccountId,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| AccountAlias::new(row.namespace, row.value))
+            .collect())
+    }
 }
@@ -124,6 +124,18 @@ impl AccountSet {
     }
 }
 
+/// How the history of the affected account sets is treated when a member is moved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MemberMoveMode {
+    /// The member's balance leaves the old parent and enters the new one as of now,
+    /// the effective balances before today are left untouched.
+    #[default]
+    AsOfNow,
+    /// The effective balances of the account sets gaining or losing the member are
+    /// rebuilt as if the member had always belonged to the new parent.
+    Retroactive,
+}
+
 #[derive(Debug, Builder, Default)]
 #[builder(name = "AccountSetUpdate", default)]
 pub struct AccountSetUpdateValues {
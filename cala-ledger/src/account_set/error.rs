@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::primitives::AccountSetId;
+use crate::primitives::{AccountId, AccountSetId};
 
 #[derive(Error, Debug)]
 pub enum AccountSetError {
@@ -24,8 +24,20 @@ pub enum AccountSetError {
     ExternalIdAlreadyExists,
     #[error("AccountSetError - JournalIdMismatch")]
     JournalIdMismatch,
+    #[error(
+        "AccountSetError - TenantMismatch: account {1} and account set {0} belong to different tenants"
+    )]
+    TenantMismatch(AccountSetId, AccountId),
     #[error("AccountSetError - Member already added to account set")]
     MemberAlreadyAdded,
+    #[error("AccountSetError - MemberNotFound: member is not part of account set {0}")]
+    MemberNotFound(AccountSetId),
+    #[error("AccountSetError - MoveToSameAccountSet: member already belongs to account set {0}")]
+    MoveToSameAccountSet(AccountSetId),
+    #[error("AccountSetError - CycleDetected: adding account set {1} to {0} would create a cycle")]
+    CycleDetected(AccountSetId, AccountSetId),
+    #[error("AccountSetError - MaxDepthExceeded: account sets cannot be nested more than {0} levels deep")]
+    MaxDepthExceeded(u32),
 }
 
 es_entity::from_es_entity_error!(AccountSetError);
@@ -2,10 +2,11 @@ mod cursor;
 mod entity;
 pub mod error;
 mod repo;
+mod tree;
 
 use es_entity::EsEntity;
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tracing::instrument;
 
 #[cfg(feature = "import")]
@@ -16,7 +17,7 @@ use crate::{
     entry::*,
     ledger_operation::*,
     outbox::*,
-    primitives::{DataSource, DebitOrCredit, JournalId, Layer},
+    primitives::{Currency, DataSource, DebitOrCredit, JournalId, Layer},
 };
 
 pub use cursor::*;
@@ -24,6 +25,7 @@ pub use entity::*;
 use error::*;
 use repo::*;
 pub use repo::{account_set_cursor::*, members_cursor::*};
+pub use tree::*;
 
 const UNASSIGNED_TRANSACTION_ID: uuid::Uuid = uuid::Uuid::nil();
 
@@ -34,6 +36,7 @@ pub struct AccountSets {
     entries: Entries,
     balances: Balances,
     outbox: Outbox,
+    max_depth: Option<u32>,
     pool: PgPool,
 }
 
@@ -44,10 +47,12 @@ impl AccountSets {
         accounts: &Accounts,
         entries: &Entries,
         balances: &Balances,
+        max_depth: Option<u32>,
     ) -> Self {
         Self {
             repo: AccountSetRepo::new(pool),
             outbox,
+            max_depth,
             accounts: accounts.clone(),
             entries: entries.clone(),
             balances: balances.clone(),
@@ -179,6 +184,13 @@ impl AccountSets {
         let (time, parents, account_set, member_id) = match member {
             AccountSetMemberId::Account(id) => {
                 let set = self.repo.find_by_id_in_op(&mut *op, account_set_id).await?;
+                if !self
+                    .repo
+                    .is_same_tenant(&mut *op, account_set_id, id)
+                    .await?
+                {
+                    return Err(AccountSetError::TenantMismatch(account_set_id, id));
+                }
                 let (time, parents) = self
                     .repo
                     .add_member_account_and_return_parents(&mut *op, account_set_id, id)
@@ -186,6 +198,9 @@ impl AccountSets {
                 (time, parents, set, id)
             }
             AccountSetMemberId::AccountSet(id) => {
+                if id == account_set_id {
+                    return Err(AccountSetError::CycleDetected(account_set_id, id));
+                }
                 let mut accounts = self
                     .repo
                     .find_all_in_op::<AccountSet>(&mut *op, &[account_set_id, id])
@@ -203,7 +218,7 @@ impl AccountSets {
 
                 let (time, parents) = self
                     .repo
-                    .add_member_set_and_return_parents(op, account_set_id, id)
+                    .add_member_set_and_return_parents(op, account_set_id, id, self.max_depth)
                     .await?;
                 (time, parents, target, AccountId::from(id))
             }
@@ -338,6 +353,123 @@ impl AccountSets {
         Ok(account_set)
     }
 
+    #[instrument(name = "cala_ledger.account_sets.move_member", skip(self, member))]
+    pub async fn move_member(
+        &self,
+        from: AccountSetId,
+        to: AccountSetId,
+        member: impl Into<AccountSetMemberId>,
+        mode: MemberMoveMode,
+    ) -> Result<AccountSet, AccountSetError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        let account_set = self
+            .move_member_in_op(&mut op, from, to, member, mode)
+            .await?;
+        op.commit().await?;
+        Ok(account_set)
+    }
+
+    /// Moves a member from one account set to another of the same journal and returns the
+    /// new parent. With [`MemberMoveMode::Retroactive`] the entries of the member are
+    /// added to the cumulative effective balances of every account set gaining the member
+    /// at their effective dates and removed from every account set losing it.
+    #[instrument(
+        name = "cala_ledger.account_sets.move_member_in_op",
+        skip(self, op, member)
+    )]
+    pub async fn move_member_in_op(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        from: AccountSetId,
+        to: AccountSetId,
+        member: impl Into<AccountSetMemberId>,
+        mode: MemberMoveMode,
+    ) -> Result<AccountSet, AccountSetError> {
+        let member = member.into();
+        if from == to {
+            return Err(AccountSetError::MoveToSameAccountSet(to));
+        }
+        let mut sets = self
+            .repo
+            .find_all_in_op::<AccountSet>(&mut *op, &[from, to])
+            .await?;
+        let source = sets
+            .remove(&from)
+            .ok_or(AccountSetError::CouldNotFindById(from))?;
+        let target = sets
+            .remove(&to)
+            .ok_or(AccountSetError::CouldNotFindById(to))?;
+        let journal_id = source.values().journal_id;
+        if journal_id != target.values().journal_id {
+            return Err(AccountSetError::JournalIdMismatch);
+        }
+        let member_id = match member {
+            AccountSetMemberId::Account(id) => id,
+            AccountSetMemberId::AccountSet(id) => AccountId::from(id),
+        };
+        let Some(joined_at) = self
+            .repo
+            .find_direct_member_since(&mut *op, from, member_id.into())
+            .await?
+        else {
+            return Err(AccountSetError::MemberNotFound(from));
+        };
+
+        self.remove_member_in_op(op, from, member).await?;
+        let account_set = self.add_member_in_op(op, to, member).await?;
+
+        if mode == MemberMoveMode::Retroactive {
+            let old_parents: HashSet<_> = self
+                .repo
+                .find_ancestors_in_op(&mut *op, uuid::Uuid::from(from))
+                .await?
+                .into_iter()
+                .map(|ancestor| ancestor.account_set_id)
+                .chain(std::iter::once(from))
+                .collect();
+            let new_parents: HashSet<_> = self
+                .repo
+                .find_ancestors_in_op(&mut *op, uuid::Uuid::from(to))
+                .await?
+                .into_iter()
+                .map(|ancestor| ancestor.account_set_id)
+                .chain(std::iter::once(to))
+                .collect();
+            let currencies: Vec<_> = self
+                .balances
+                .find_balances_for_update(op, journal_id, member_id)
+                .await?
+                .into_keys()
+                .collect();
+            let mut gained: Vec<_> = new_parents
+                .difference(&old_parents)
+                .map(AccountId::from)
+                .collect();
+            gained.sort();
+            let mut lost: Vec<_> = old_parents
+                .difference(&new_parents)
+                .map(AccountId::from)
+                .collect();
+            lost.sort();
+            let now = op.now();
+            self.balances
+                .effective()
+                .restate_moved_member_in_op(
+                    op,
+                    journal_id,
+                    member_id,
+                    &currencies,
+                    &gained,
+                    &lost,
+                    joined_at,
+                    now,
+                )
+                .await?;
+        }
+
+        Ok(account_set)
+    }
+
     #[instrument(name = "cala_ledger.account_sets.find_all", skip(self))]
     pub async fn find_all<T: From<AccountSet>>(
         &self,
@@ -508,6 +640,72 @@ impl AccountSets {
             .await
     }
 
+    /// Returns the members of the account set and of all its nested sets, down to
+    /// `max_depth` levels below it. With a `currency` the balance of every node is loaded
+    /// as well.
+    #[instrument(name = "cala_ledger.account_sets.tree", skip(self))]
+    pub async fn tree(
+        &self,
+        root_id: AccountSetId,
+        max_depth: Option<u32>,
+        currency: Option<Currency>,
+    ) -> Result<AccountSetTree, AccountSetError> {
+        let root = self.repo.find_by_id(root_id).await?;
+        let journal_id = root.values().journal_id;
+        let rows = self.repo.find_tree_rows(root_id, max_depth).await?;
+
+        let balances = match currency {
+            Some(currency) => {
+                let ids: Vec<_> = rows
+                    .iter()
+                    .map(|row| {
+                        let account_id = match row.id {
+                            AccountSetMemberId::Account(id) => id,
+                            AccountSetMemberId::AccountSet(id) => id.into(),
+                        };
+                        (journal_id, account_id, currency)
+                    })
+                    .collect();
+                self.balances
+                    .find_all(&ids)
+                    .await?
+                    .into_iter()
+                    .map(|((_, account_id, _), balance)| (account_id, balance))
+                    .collect()
+            }
+            None => HashMap::new(),
+        };
+
+        AccountSetTree::from_rows(journal_id, currency, rows, balances)
+            .ok_or(AccountSetError::CouldNotFindById(root_id))
+    }
+
+    /// Returns every account set that (transitively) contains the member, closest first.
+    #[instrument(name = "cala_ledger.account_sets.find_ancestors", skip(self))]
+    pub async fn find_ancestors(
+        &self,
+        member: impl Into<AccountSetMemberId> + std::fmt::Debug,
+    ) -> Result<Vec<AccountSetAncestor>, AccountSetError> {
+        let member_id = match member.into() {
+            AccountSetMemberId::Account(id) => uuid::Uuid::from(id),
+            AccountSetMemberId::AccountSet(id) => uuid::Uuid::from(id),
+        };
+        self.repo.find_ancestors(member_id).await
+    }
+
+    #[instrument(name = "cala_ledger.account_sets.find_ancestors_in_op", skip(self, op))]
+    pub async fn find_ancestors_in_op(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        member: impl Into<AccountSetMemberId> + std::fmt::Debug,
+    ) -> Result<Vec<AccountSetAncestor>, AccountSetError> {
+        let member_id = match member.into() {
+            AccountSetMemberId::Account(id) => uuid::Uuid::from(id),
+            AccountSetMemberId::AccountSet(id) => uuid::Uuid::from(id),
+        };
+        self.repo.find_ancestors_in_op(op, member_id).await
+    }
+
     pub(crate) async fn fetch_mappings_in_op(
         &self,
         op: &mut LedgerOperation<'_>,
@@ -6,9 +6,11 @@ use std::collections::HashMap;
 
 use crate::primitives::{AccountId, DataSourceId, JournalId};
 
-use super::{entity::*, error::*};
+use super::{entity::*, error::*, tree::*};
 
 const ADDVISORY_LOCK_ID: i64 = 123456;
+// Bounds the hierarchy traversal when no maximum depth is configured.
+const MAX_HIERARCHY_TRAVERSAL_DEPTH: u32 = 1000;
 
 pub mod members_cursor {
     use cala_types::account_set::{
@@ -383,6 +385,27 @@ impl AccountSetRepo {
         })
     }
 
+    /// Whether the account belongs to the tenant of the journal of the account set.
+    pub async fn is_same_tenant(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        account_id: AccountId,
+    ) -> Result<bool, AccountSetError> {
+        let row = sqlx::query!(
+            r#"SELECT j.tenant_id IS NOT DISTINCT FROM a.tenant_id AS "same_tenant!"
+            FROM cala_account_sets s
+            JOIN cala_journals j ON j.id = s.journal_id
+            CROSS JOIN cala_accounts a
+            WHERE s.id = $1 AND a.id = $2"#,
+            account_set_id as AccountSetId,
+            account_id as AccountId,
+        )
+        .fetch_optional(op.as_executor())
+        .await?;
+        Ok(row.is_none_or(|row| row.same_tenant))
+    }
+
     pub async fn add_member_account_and_return_parents(
         &self,
         db: &mut impl es_entity::AtomicOperation,
@@ -498,15 +521,204 @@ impl AccountSetRepo {
         Ok((time.expect("time not set"), ret))
     }
 
+    /// Returns the members of the account set and of its nested sets down to `max_depth`
+    /// levels below it, parents always preceding their children.
+    pub async fn find_tree_rows(
+        &self,
+        account_set_id: AccountSetId,
+        max_depth: Option<u32>,
+    ) -> Result<Vec<AccountSetTreeRow>, AccountSetError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH RECURSIVE edges AS (
+                SELECT account_set_id AS parent_id, member_account_set_id AS id, TRUE AS is_set
+                FROM cala_account_set_member_account_sets
+                UNION ALL
+                SELECT account_set_id, member_account_id, FALSE
+                FROM cala_account_set_member_accounts
+                WHERE transitive IS FALSE
+            ),
+            tree AS (
+                SELECT $1::uuid AS id, ARRAY[]::uuid[] AS path, TRUE AS is_set, 0 AS depth
+                UNION ALL
+                SELECT e.id, t.path || t.id, e.is_set, t.depth + 1
+                FROM tree t
+                JOIN edges e ON e.parent_id = t.id
+                WHERE t.is_set AND t.depth < $2
+            )
+            SELECT
+                t.id AS "id!",
+                t.path AS "path!",
+                t.is_set AS "is_set!",
+                t.depth AS "depth!",
+                COALESCE(s.name, a.name) AS "name!",
+                a.code
+            FROM tree t
+            JOIN cala_accounts a ON a.id = t.id
+            LEFT JOIN cala_account_sets s ON s.id = t.id
+            WHERE NOT t.is_set OR s.id IS NOT NULL
+            ORDER BY t.depth, COALESCE(s.name, a.name), t.path"#,
+            account_set_id as AccountSetId,
+            max_depth.unwrap_or(MAX_HIERARCHY_TRAVERSAL_DEPTH) as i32,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| AccountSetTreeRow {
+                id: if row.is_set {
+                    AccountSetMemberId::AccountSet(row.id.into())
+                } else {
+                    AccountSetMemberId::Account(row.id.into())
+                },
+                path: row.path,
+                name: row.name,
+                code: if row.is_set { None } else { Some(row.code) },
+                depth: row.depth as u32,
+            })
+            .collect())
+    }
+
+    /// Returns when the member was added to the account set if it is a direct member.
+    pub async fn find_direct_member_since(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        member_id: uuid::Uuid,
+    ) -> Result<Option<DateTime<Utc>>, AccountSetError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT created_at AS "created_at!" FROM cala_account_set_member_accounts
+            WHERE account_set_id = $1 AND member_account_id = $2 AND transitive IS FALSE
+            UNION ALL
+            SELECT created_at FROM cala_account_set_member_account_sets
+            WHERE account_set_id = $1 AND member_account_set_id = $2"#,
+            account_set_id as AccountSetId,
+            member_id,
+        )
+        .fetch_optional(op.as_executor())
+        .await?;
+        Ok(row.map(|row| row.created_at))
+    }
+
+    pub async fn find_ancestors(
+        &self,
+        member_id: uuid::Uuid,
+    ) -> Result<Vec<AccountSetAncestor>, AccountSetError> {
+        self.find_ancestors_in_op(&self.pool, member_id).await
+    }
+
+    pub async fn find_ancestors_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        member_id: uuid::Uuid,
+    ) -> Result<Vec<AccountSetAncestor>, AccountSetError> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+                r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT account_set_id, 1 AS depth
+                FROM cala_account_set_member_accounts
+                WHERE member_account_id = $1 AND transitive IS FALSE
+                UNION ALL
+                SELECT account_set_id, 1
+                FROM cala_account_set_member_account_sets
+                WHERE member_account_set_id = $1
+                UNION ALL
+                SELECT m.account_set_id, a.depth + 1
+                FROM ancestors a
+                JOIN cala_account_set_member_account_sets m
+                    ON m.member_account_set_id = a.account_set_id
+                WHERE a.depth < $2
+            )
+            SELECT account_set_id AS "account_set_id!: AccountSetId", MIN(depth) AS "depth!"
+            FROM ancestors
+            GROUP BY account_set_id
+            ORDER BY 2, 1"#,
+                member_id,
+                MAX_HIERARCHY_TRAVERSAL_DEPTH as i32,
+            ))
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| AccountSetAncestor {
+                account_set_id: row.account_set_id,
+                depth: row.depth as u32,
+            })
+            .collect())
+    }
+
+    /// Rejects adding the member set if it already (transitively) contains the account set
+    /// or if the longest chain of nested sets through the new membership would exceed
+    /// `max_depth`.
+    async fn check_member_set_hierarchy(
+        &self,
+        db: &mut impl es_entity::AtomicOperation,
+        account_set_id: AccountSetId,
+        member_account_set_id: AccountSetId,
+        max_depth: Option<u32>,
+    ) -> Result<(), AccountSetError> {
+        let traversal_depth = max_depth.unwrap_or(MAX_HIERARCHY_TRAVERSAL_DEPTH);
+        let row = sqlx::query!(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT $1::uuid AS id, 0 AS depth
+                UNION ALL
+                SELECT m.account_set_id, a.depth + 1
+                FROM ancestors a
+                JOIN cala_account_set_member_account_sets m
+                    ON m.member_account_set_id = a.id
+                WHERE a.depth < $3
+            ),
+            descendants AS (
+                SELECT $2::uuid AS id, 0 AS depth
+                UNION ALL
+                SELECT m.member_account_set_id, d.depth + 1
+                FROM descendants d
+                JOIN cala_account_set_member_account_sets m
+                    ON m.account_set_id = d.id
+                WHERE d.depth < $3
+            )
+            SELECT
+                EXISTS (SELECT 1 FROM ancestors WHERE id = $2) AS "cycle!",
+                (SELECT MAX(depth) FROM ancestors) AS "ancestor_depth!",
+                (SELECT MAX(depth) FROM descendants) AS "descendant_depth!"
+            "#,
+            account_set_id as AccountSetId,
+            member_account_set_id as AccountSetId,
+            traversal_depth as i32,
+        )
+        .fetch_one(db.as_executor())
+        .await?;
+
+        if row.cycle {
+            return Err(AccountSetError::CycleDetected(
+                account_set_id,
+                member_account_set_id,
+            ));
+        }
+        if let Some(max_depth) = max_depth {
+            let depth = row.ancestor_depth + 1 + row.descendant_depth;
+            if depth as u32 > max_depth {
+                return Err(AccountSetError::MaxDepthExceeded(max_depth));
+            }
+        }
+        Ok(())
+    }
+
     pub async fn add_member_set_and_return_parents(
         &self,
         db: &mut impl es_entity::AtomicOperation,
         account_set_id: AccountSetId,
         member_account_set_id: AccountSetId,
+        max_depth: Option<u32>,
     ) -> Result<(DateTime<Utc>, Vec<AccountSetId>), AccountSetError> {
         sqlx::query!("SELECT pg_advisory_xact_lock($1)", ADDVISORY_LOCK_ID)
             .execute(db.as_executor())
             .await?;
+        self.check_member_set_hierarchy(db, account_set_id, member_account_set_id, max_depth)
+            .await?;
         let rows = sqlx::query!(r#"
           WITH RECURSIVE parents AS (
             SELECT m.member_account_set_id, m.account_set_id
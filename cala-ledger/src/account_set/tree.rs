@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use cala_types::{account_set::AccountSetMemberId, primitives::*};
+
+use crate::balance::AccountBalance;
+
+/// A member of an account set hierarchy together with its (nested) members.
+#[derive(Debug, Clone)]
+pub struct AccountSetTreeNode {
+    pub id: AccountSetMemberId,
+    pub name: String,
+    /// Code of the account. `None` for account sets.
+    pub code: Option<String>,
+    /// Distance from the root of the tree (the root itself has depth 0).
+    pub depth: u32,
+    /// Balance in the requested currency, if one was requested and has been recorded.
+    pub balance: Option<AccountBalance>,
+    pub children: Vec<AccountSetTreeNode>,
+}
+
+impl AccountSetTreeNode {
+    /// Iterates over this node and all of its descendants depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &AccountSetTreeNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    pub fn find(&self, id: impl Into<AccountSetMemberId>) -> Option<&AccountSetTreeNode> {
+        let id = id.into();
+        self.iter().find(|node| node.id == id)
+    }
+}
+
+/// The descendants of an account set.
+#[derive(Debug, Clone)]
+pub struct AccountSetTree {
+    pub journal_id: JournalId,
+    pub currency: Option<Currency>,
+    pub root: AccountSetTreeNode,
+}
+
+/// An account set that (transitively) contains a given account or account set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountSetAncestor {
+    pub account_set_id: AccountSetId,
+    /// 1 for direct parents, 2 for their parents and so on.
+    pub depth: u32,
+}
+
+pub(super) struct AccountSetTreeRow {
+    pub id: AccountSetMemberId,
+    /// Ids of the ancestors from the root down to the parent (empty for the root).
+    pub path: Vec<uuid::Uuid>,
+    pub name: String,
+    pub code: Option<String>,
+    pub depth: u32,
+}
+
+impl AccountSetTree {
+    /// Assembles the tree from rows in which every parent precedes its children. Members
+    /// reachable through several nested sets have one row (and one node) per path.
+    pub(super) fn from_rows(
+        journal_id: JournalId,
+        currency: Option<Currency>,
+        rows: Vec<AccountSetTreeRow>,
+        balances: HashMap<AccountId, AccountBalance>,
+    ) -> Option<Self> {
+        let mut children: HashMap<Vec<uuid::Uuid>, Vec<AccountSetTreeRow>> = HashMap::new();
+        let mut root = None;
+        for row in rows {
+            if row.path.is_empty() {
+                root = Some(row);
+            } else {
+                children.entry(row.path.clone()).or_default().push(row);
+            }
+        }
+
+        fn build(
+            row: AccountSetTreeRow,
+            children: &mut HashMap<Vec<uuid::Uuid>, Vec<AccountSetTreeRow>>,
+            balances: &HashMap<AccountId, AccountBalance>,
+        ) -> AccountSetTreeNode {
+            let account_id = match row.id {
+                AccountSetMemberId::Account(id) => id,
+                AccountSetMemberId::AccountSet(id) => id.into(),
+            };
+            let nested = match row.id {
+                AccountSetMemberId::AccountSet(id) => {
+                    let mut path = row.path;
+                    path.push(id.into());
+                    children.remove(&path).unwrap_or_default()
+                }
+                AccountSetMemberId::Account(_) => Vec::new(),
+            };
+            AccountSetTreeNode {
+                id: row.id,
+                name: row.name,
+                code: row.code,
+                depth: row.depth,
+                balance: balances.get(&account_id).cloned(),
+                children: nested
+                    .into_iter()
+                    .map(|child| build(child, children, balances))
+                    .collect(),
+            }
+        }
+
+        root.map(|root| Self {
+            journal_id,
+            currency,
+            root: build(root, &mut children, &balances),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: AccountSetMemberId, path: &[AccountSetId], name: &str) -> AccountSetTreeRow {
+        AccountSetTreeRow {
+            id,
+            path: path.iter().map(|id| uuid::Uuid::from(*id)).collect(),
+            name: name.to_string(),
+            code: None,
+            depth: path.len() as u32,
+        }
+    }
+
+    #[test]
+    fn builds_nested_tree() {
+        let root = AccountSetId::new();
+        let child = AccountSetId::new();
+        let account = AccountId::new();
+        let rows = vec![
+            row(root.into(), &[], "root"),
+            row(child.into(), &[root], "child"),
+            row(account.into(), &[root, child], "account"),
+        ];
+
+        let tree = AccountSetTree::from_rows(JournalId::new(), None, rows, HashMap::new()).unwrap();
+        assert_eq!(tree.root.id, AccountSetMemberId::AccountSet(root));
+        assert_eq!(tree.root.children.len(), 1);
+        assert_eq!(tree.root.iter().count(), 3);
+        let leaf = tree.root.find(account).unwrap();
+        assert_eq!(leaf.depth, 2);
+        assert!(leaf.children.is_empty());
+    }
+
+    #[test]
+    fn repeats_members_reachable_through_several_sets() {
+        let root = AccountSetId::new();
+        let left = AccountSetId::new();
+        let right = AccountSetId::new();
+        let shared = AccountSetId::new();
+        let account = AccountId::new();
+        let rows = vec![
+            row(root.into(), &[], "root"),
+            row(left.into(), &[root], "left"),
+            row(right.into(), &[root], "right"),
+            row(shared.into(), &[root, left], "shared"),
+            row(shared.into(), &[root, right], "shared"),
+            row(account.into(), &[root, left, shared], "account"),
+            row(account.into(), &[root, right, shared], "account"),
+        ];
+        let entry_id = EntryId::new();
+        let amount = cala_types::balance::BalanceAmount {
+            dr_balance: rust_decimal::Decimal::ZERO,
+            cr_balance: rust_decimal::Decimal::ONE,
+            entry_id,
+            modified_at: chrono::Utc::now(),
+        };
+        let balance = AccountBalance::new(
+            DebitOrCredit::Credit,
+            cala_types::balance::BalanceSnapshot {
+                journal_id: JournalId::new(),
+                account_id: account,
+                currency: Currency::USD,
+                version: 1,
+                created_at: amount.modified_at,
+                modified_at: amount.modified_at,
+                entry_id,
+                settled: amount.clone(),
+                pending: amount.clone(),
+                encumbrance: amount,
+            },
+        );
+        let balances = HashMap::from([(account, balance)]);
+
+        let tree = AccountSetTree::from_rows(JournalId::new(), None, rows, balances).unwrap();
+        assert_eq!(tree.root.iter().count(), 7);
+        for parent in [left, right] {
+            let parent = tree.root.find(parent).unwrap();
+            assert_eq!(parent.children.len(), 1);
+            let nested = &parent.children[0];
+            assert_eq!(nested.id, AccountSetMemberId::AccountSet(shared));
+            assert_eq!(nested.children.len(), 1);
+            assert!(nested.children[0].balance.is_some());
+        }
+    }
+
+    #[test]
+    fn missing_root() {
+        assert!(
+            AccountSetTree::from_rows(JournalId::new(), None, vec![], HashMap::new()).is_none()
+        );
+    }
+}
@@ -1,7 +1,8 @@
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
 use crate::primitives::*;
-use cala_types::balance::*;
+use cala_types::{balance::*, entry::EntryValues};
 
 /// Representation of account's balance tracked in 3 distinct layers.
 #[derive(Debug, Clone)]
@@ -113,6 +114,14 @@ impl<'a> BalanceWithDirection<'a> {
     }
 }
 
+/// A single historical version of an account's balance together with the entry that produced it.
+#[derive(Debug, Clone)]
+pub struct BalanceVersion {
+    pub balance: AccountBalance,
+    pub entry: Option<EntryValues>,
+    pub recorded_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub struct BalanceRange {
     pub open: AccountBalance,
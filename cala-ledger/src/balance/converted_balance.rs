@@ -0,0 +1,181 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use std::collections::HashMap;
+
+use cala_types::fx_rate::FxRateValues;
+
+use crate::primitives::*;
+
+use super::account_balance::AccountBalance;
+
+/// A single currency balance of an account and the rate used to convert it
+/// into the reporting currency. `fx_rate` is `None` for the balance that is
+/// already held in the reporting currency.
+#[derive(Debug, Clone)]
+pub struct ConvertedBalanceLine {
+    pub balance: AccountBalance,
+    pub fx_rate: Option<FxRateValues>,
+}
+
+impl ConvertedBalanceLine {
+    fn convert(&self, units: Decimal) -> Decimal {
+        match &self.fx_rate {
+            Some(rate) => rate.convert(units),
+            None => units,
+        }
+    }
+
+    pub fn settled(&self) -> Decimal {
+        self.convert(self.balance.settled())
+    }
+
+    pub fn pending(&self) -> Decimal {
+        self.convert(self.balance.pending())
+    }
+
+    pub fn encumbrance(&self) -> Decimal {
+        self.convert(self.balance.encumbrance())
+    }
+
+    pub fn available(&self, layer: Layer) -> Decimal {
+        self.convert(self.balance.available(layer))
+    }
+}
+
+/// The balances an account holds in all currencies summed up in a single reporting currency.
+#[derive(Debug, Clone)]
+pub struct ConvertedBalance {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub as_of: DateTime<Utc>,
+    pub lines: Vec<ConvertedBalanceLine>,
+}
+
+impl ConvertedBalance {
+    pub(super) fn new(
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+        balances: Vec<AccountBalance>,
+        mut rates: HashMap<Currency, FxRateValues>,
+    ) -> Self {
+        let lines = balances
+            .into_iter()
+            .map(|balance| ConvertedBalanceLine {
+                fx_rate: rates.remove(&balance.details.currency),
+                balance,
+            })
+            .collect();
+        Self {
+            journal_id,
+            account_id,
+            currency,
+            as_of,
+            lines,
+        }
+    }
+
+    pub fn settled(&self) -> Decimal {
+        self.lines.iter().map(|l| l.settled()).sum()
+    }
+
+    pub fn pending(&self) -> Decimal {
+        self.lines.iter().map(|l| l.pending()).sum()
+    }
+
+    pub fn encumbrance(&self) -> Decimal {
+        self.lines.iter().map(|l| l.encumbrance()).sum()
+    }
+
+    pub fn available(&self, layer: Layer) -> Decimal {
+        self.lines.iter().map(|l| l.available(layer)).sum()
+    }
+
+    /// The rates that were applied in the conversion.
+    pub fn fx_rates(&self) -> impl Iterator<Item = &FxRateValues> {
+        self.lines.iter().filter_map(|l| l.fx_rate.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+    use rust_decimal_macros::dec;
+
+    use cala_types::balance::{BalanceAmount, BalanceSnapshot};
+
+    use super::*;
+
+    fn balance(currency: &str, settled_dr: Decimal, pending_dr: Decimal) -> AccountBalance {
+        let time = Utc::now();
+        let entry_id = EntryId::new();
+        let amount = |dr_balance| BalanceAmount {
+            dr_balance,
+            cr_balance: Decimal::ZERO,
+            entry_id,
+            modified_at: time,
+        };
+        AccountBalance::new(
+            DebitOrCredit::Debit,
+            BalanceSnapshot {
+                journal_id: JournalId::new(),
+                account_id: AccountId::new(),
+                currency: currency.parse().unwrap(),
+                version: 1,
+                created_at: time,
+                modified_at: time,
+                entry_id,
+                settled: amount(settled_dr),
+                pending: amount(pending_dr),
+                encumbrance: amount(Decimal::ZERO),
+            },
+        )
+    }
+
+    fn rate(base: &str, quote: &str, rate: Decimal) -> FxRateValues {
+        FxRateValues {
+            base_currency: base.parse().unwrap(),
+            quote_currency: quote.parse().unwrap(),
+            effective: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            rate,
+            version: 1,
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn sums_balances_converted_at_their_rates() {
+        let usd: Currency = "USD".parse().unwrap();
+        let eur: Currency = "EUR".parse().unwrap();
+        let btc: Currency = "BTC".parse().unwrap();
+        let rates = [
+            (eur, rate("EUR", "USD", dec!(1.1))),
+            (btc, rate("BTC", "USD", dec!(50000))),
+        ]
+        .into_iter()
+        .collect();
+
+        let converted = ConvertedBalance::new(
+            JournalId::new(),
+            AccountId::new(),
+            usd,
+            Utc::now(),
+            vec![
+                balance("USD", dec!(100), dec!(0)),
+                balance("EUR", dec!(100), dec!(10)),
+                balance("BTC", dec!(0.5), dec!(0)),
+            ],
+            rates,
+        );
+
+        assert_eq!(converted.settled(), dec!(25210));
+        assert_eq!(converted.pending(), dec!(11));
+        assert_eq!(converted.available(Layer::Pending), dec!(25221));
+        assert_eq!(converted.fx_rates().count(), 2);
+        assert!(converted.lines[0].fx_rate.is_none());
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+
+use cala_types::primitives::{AccountId, Currency, JournalId};
+
+/// Progress of replaying the historical entries of a journal into its
+/// cumulative effective balances.
+#[derive(Debug, Clone)]
+pub struct EffectiveBalancesBackfill {
+    pub journal_id: JournalId,
+    pub balances_processed: u32,
+    pub balances_total: u32,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub(super) last_balance: Option<(AccountId, Currency)>,
+}
+
+impl EffectiveBalancesBackfill {
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+}
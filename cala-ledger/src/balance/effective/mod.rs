@@ -1,29 +1,38 @@
+mod backfill;
 mod data;
 mod repo;
+mod statement;
 
 use chrono::{DateTime, NaiveDate, Utc};
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tracing::instrument;
 
 use cala_types::{entry::EntryValues, primitives::*};
 
-use crate::primitives::JournalId;
+use crate::{journal::Journals, primitives::JournalId};
 
-use super::{account_balance::*, error::BalanceError};
+use super::{account_balance::*, error::BalanceError, shard::BalanceShards};
 
+pub use backfill::*;
+use data::EffectiveBalanceData;
 use repo::*;
+pub use statement::*;
 
 #[derive(Clone)]
 pub struct EffectiveBalances {
     repo: EffectiveBalanceRepo,
-    _pool: PgPool,
+    journals: Journals,
+    shards: BalanceShards,
+    pool: PgPool,
 }
 impl EffectiveBalances {
-    pub(crate) fn new(pool: &PgPool) -> Self {
+    pub(super) fn new(pool: &PgPool, journals: &Journals, shards: &BalanceShards) -> Self {
         Self {
             repo: EffectiveBalanceRepo::new(pool),
-            _pool: pool.clone(),
+            journals: journals.clone(),
+            shards: shards.clone(),
+            pool: pool.clone(),
         }
     }
 
@@ -35,9 +44,9 @@ impl EffectiveBalances {
         currency: Currency,
         date: NaiveDate,
     ) -> Result<AccountBalance, BalanceError> {
-        self.repo
-            .find(journal_id, account_id.into(), currency, date)
-            .await
+        let account_id = account_id.into();
+        self.shards.ensure_unsharded(&[account_id]).await?;
+        self.repo.find(journal_id, account_id, currency, date).await
     }
 
     #[instrument(name = "cala_ledger.balance.effective.find_in_range", skip(self))]
@@ -49,6 +58,7 @@ impl EffectiveBalances {
         from: NaiveDate,
         until: Option<NaiveDate>,
     ) -> Result<BalanceRange, BalanceError> {
+        self.shards.ensure_unsharded(&[account_id]).await?;
         match self
             .repo
             .find_range(journal_id, account_id, currency, from, until)
@@ -80,6 +90,66 @@ impl EffectiveBalances {
         Ok(res)
     }
 
+    /// Summarises the activity of an account between `from` and `until` (inclusive) based
+    /// on its cumulative effective balances. Without a grouping the statement covers the
+    /// whole range in a single period.
+    #[instrument(name = "cala_ledger.balance.effective.statement", skip(self))]
+    pub async fn statement(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        currency: Currency,
+        from: NaiveDate,
+        until: NaiveDate,
+        grouping: Option<StatementGrouping>,
+    ) -> Result<BalanceStatement, BalanceError> {
+        if until < from {
+            return Err(BalanceError::InvalidDateRange(from, until));
+        }
+        let journal = self.journals.find(journal_id).await?;
+        if !journal.insert_effective_balances() {
+            return Err(BalanceError::EffectiveBalancesNotEnabled(journal_id));
+        }
+
+        let account_id = account_id.into();
+        self.shards.ensure_unsharded(&[account_id]).await?;
+        let bounds = statement_periods(from, until, grouping);
+        let dates: Vec<_> = std::iter::once(from.pred_opt().unwrap_or(NaiveDate::MIN))
+            .chain(bounds.iter().map(|(_, until)| *until))
+            .collect();
+        let balances = self
+            .repo
+            .find_at_dates(journal_id, account_id, currency, &dates)
+            .await?;
+
+        let periods = bounds
+            .into_iter()
+            .zip(balances.windows(2))
+            .filter_map(|((from, until), window)| {
+                let (end, end_version) = window[1].clone()?;
+                let (start, start_version) = match window[0].clone() {
+                    Some((start, version)) => (Some(start), version),
+                    None => (None, 0),
+                };
+                Some(StatementPeriod {
+                    from,
+                    until,
+                    range: BalanceRange::new(start, end, end_version - start_version),
+                })
+            })
+            .collect();
+
+        Ok(BalanceStatement {
+            journal_id,
+            account_id,
+            currency,
+            from,
+            until,
+            grouping,
+            periods,
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) async fn update_cumulative_balances_in_op(
         &self,
@@ -119,4 +189,214 @@ impl EffectiveBalances {
 
         Ok(())
     }
+
+    /// Restates the cumulative effective balances of the account sets gaining or losing
+    /// `member_id` in a move as if the member had always belonged to its new parents.
+    /// The entries of the member are applied to the `gained` sets at their effective dates
+    /// and removed from the `lost` sets, where entries posted before the member joined its
+    /// old parent (`joined_at`) are only removed from that day on. The membership entries
+    /// recorded by the move itself on `moved_at` are cancelled out. Does nothing if the
+    /// journal does not maintain effective balances.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn restate_moved_member_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        member_id: AccountId,
+        currencies: &[Currency],
+        gained: &[AccountId],
+        lost: &[AccountId],
+        joined_at: DateTime<Utc>,
+        moved_at: DateTime<Utc>,
+    ) -> Result<(), BalanceError> {
+        let journal = self.journals.find(journal_id).await?;
+        if !journal.insert_effective_balances() {
+            return Ok(());
+        }
+        let moved_on = moved_at.date_naive();
+        for currency in currencies {
+            let entries = self
+                .repo
+                .find_entries_in_op(&mut *op, journal_id, member_id, *currency)
+                .await?;
+            let reversed: Vec<_> = entries
+                .iter()
+                .map(|(_, _, entry)| EntryValues {
+                    units: -entry.units,
+                    ..entry.clone()
+                })
+                .collect();
+            let mut gained_entries: BTreeMap<_, Vec<_>> = BTreeMap::new();
+            let mut lost_entries: BTreeMap<_, Vec<_>> = BTreeMap::new();
+            for ((effective, created_at, entry), reversed) in entries.iter().zip(reversed.iter()) {
+                gained_entries.entry(*effective).or_default().push(entry);
+                gained_entries.entry(moved_on).or_default().push(reversed);
+                lost_entries.entry(moved_on).or_default().push(entry);
+                let removed_on = if *created_at < joined_at {
+                    joined_at.date_naive()
+                } else {
+                    *effective
+                };
+                lost_entries.entry(removed_on).or_default().push(reversed);
+            }
+            self.apply_entries_in_op(
+                &mut *op,
+                journal_id,
+                gained,
+                *currency,
+                gained_entries,
+                moved_at,
+            )
+            .await?;
+            self.apply_entries_in_op(
+                &mut *op,
+                journal_id,
+                lost,
+                *currency,
+                lost_entries,
+                moved_at,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Adds the entries to the cumulative effective balances of the accounts, one
+    /// effective date at a time so that later balances are carried forward.
+    async fn apply_entries_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_ids: &[AccountId],
+        currency: Currency,
+        entries: BTreeMap<NaiveDate, Vec<&EntryValues>>,
+        created_at: DateTime<Utc>,
+    ) -> Result<(), BalanceError> {
+        if account_ids.is_empty() {
+            return Ok(());
+        }
+        for (effective, entries) in entries {
+            let balance_ids = (
+                account_ids.to_vec(),
+                vec![currency.code(); account_ids.len()],
+            );
+            let mut all_data = self
+                .repo
+                .find_for_update(&mut *op, journal_id, balance_ids, effective)
+                .await?;
+            for data in all_data.values_mut() {
+                for entry in entries.iter() {
+                    data.push(effective, entry);
+                }
+                data.re_calculate_snapshots(created_at);
+            }
+            self.repo
+                .insert_new_snapshots(&mut *op, journal_id, all_data)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the cumulative effective balances of a balance by replaying the entries
+    /// of the account (or those recorded in the balance history of an account set)
+    /// from scratch.
+    async fn rebuild_balance_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        created_at: DateTime<Utc>,
+    ) -> Result<(), BalanceError> {
+        let entries = self
+            .repo
+            .lock_and_clear_for_rebuild(&mut *op, journal_id, account_id, currency)
+            .await?;
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut data = EffectiveBalanceData::new(account_id, currency, None, 0, vec![]);
+        for (effective, entry) in entries.iter() {
+            data.push(*effective, entry);
+        }
+        data.re_calculate_snapshots(created_at);
+        self.repo
+            .insert_new_snapshots(
+                op,
+                journal_id,
+                HashMap::from([((account_id, currency), data)]),
+            )
+            .await
+    }
+
+    /// (Re)starts populating the cumulative effective balances of a journal from its
+    /// existing entries. Any previous progress is discarded.
+    #[instrument(name = "cala_ledger.balance.effective.start_backfill", skip(self))]
+    pub async fn start_backfill(
+        &self,
+        journal_id: JournalId,
+    ) -> Result<EffectiveBalancesBackfill, BalanceError> {
+        let journal = self.journals.find(journal_id).await?;
+        if !journal.insert_effective_balances() {
+            return Err(BalanceError::EffectiveBalancesNotEnabled(journal_id));
+        }
+        self.repo.start_backfill(journal_id).await
+    }
+
+    #[instrument(name = "cala_ledger.balance.effective.find_backfill", skip(self))]
+    pub async fn find_backfill(
+        &self,
+        journal_id: JournalId,
+    ) -> Result<Option<EffectiveBalancesBackfill>, BalanceError> {
+        self.repo.find_backfill(journal_id).await
+    }
+
+    /// Rebuilds the cumulative effective balances of up to `batch_size` balances of the
+    /// journal, replaying their entries ordered by effective date and creation.
+    /// Starts a backfill if none exists and resumes from the last processed balance
+    /// otherwise. Each balance is rebuilt in its own transaction while holding the lock
+    /// used by postings so it is safe to run while the journal is live. Account sets are
+    /// rebuilt from the entries recorded in their balance history, so members only count
+    /// while they belonged to the set (restatements of retroactive moves are not replayed).
+    #[instrument(name = "cala_ledger.balance.effective.backfill_batch", skip(self))]
+    pub async fn backfill_batch(
+        &self,
+        journal_id: JournalId,
+        batch_size: usize,
+    ) -> Result<EffectiveBalancesBackfill, BalanceError> {
+        let backfill = match self.repo.find_backfill(journal_id).await? {
+            Some(backfill) => backfill,
+            None => self.start_backfill(journal_id).await?,
+        };
+        if backfill.is_completed() {
+            return Ok(backfill);
+        }
+
+        let balances = self
+            .repo
+            .next_backfill_balances(&backfill, batch_size)
+            .await?;
+        for (account_id, currency) in balances.iter() {
+            let mut op = es_entity::DbOp::init(&self.pool)
+                .await?
+                .with_db_time()
+                .await?;
+            let now = op.now();
+            self.rebuild_balance_in_op(&mut op, journal_id, *account_id, *currency, now)
+                .await?;
+            self.repo
+                .record_backfill_progress(&mut op, journal_id, *account_id, *currency)
+                .await?;
+            op.commit().await?;
+        }
+        if balances.len() < batch_size {
+            self.repo.complete_backfill(journal_id).await?;
+        }
+
+        Ok(self
+            .repo
+            .find_backfill(journal_id)
+            .await?
+            .expect("backfill exists"))
+    }
 }
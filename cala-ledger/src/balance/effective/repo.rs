@@ -1,4 +1,4 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use sqlx::PgPool;
 use std::collections::HashMap;
 use tracing::instrument;
@@ -6,10 +6,11 @@ use tracing::instrument;
 use crate::balance::{account_balance::AccountBalance, error::BalanceError};
 use cala_types::{
     balance::BalanceSnapshot,
+    entry::EntryValues,
     primitives::{AccountId, BalanceId, Currency, DebitOrCredit, EntryId, JournalId},
 };
 
-use super::data::*;
+use super::{backfill::EffectiveBalancesBackfill, data::*};
 
 #[derive(Debug, Clone)]
 pub(super) struct EffectiveBalanceRepo {
@@ -144,6 +145,59 @@ impl EffectiveBalanceRepo {
         Ok((first, last, last_version - first_version))
     }
 
+    /// Returns the cumulative balance (and its all time version) as of each of the given
+    /// dates, `None` where the account had no effective balance yet.
+    pub(super) async fn find_at_dates(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        dates: &[NaiveDate],
+    ) -> Result<Vec<Option<(AccountBalance, u32)>>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                b.idx AS "idx!",
+                h.values AS "values?",
+                h.all_time_version AS "all_time_version?",
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+            FROM UNNEST($4::date[]) WITH ORDINALITY AS b(effective, idx)
+            JOIN cala_accounts a
+                ON a.id = $2
+            LEFT JOIN LATERAL (
+                SELECT values, all_time_version
+                FROM cala_cumulative_effective_balances
+                WHERE journal_id = $1
+                  AND account_id = $2
+                  AND currency = $3
+                  AND effective <= b.effective
+                ORDER BY effective DESC, version DESC
+                LIMIT 1
+            ) h ON TRUE
+            ORDER BY b.idx"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+            dates,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                row.values.map(|values| {
+                    let details: BalanceSnapshot = serde_json::from_value(values)
+                        .expect("Failed to deserialize balance snapshot");
+                    (
+                        AccountBalance::new(row.normal_balance_type, details),
+                        row.all_time_version.expect("all_time_version") as u32,
+                    )
+                })
+            })
+            .collect())
+    }
+
     pub(super) async fn find_range_all(
         &self,
         ids: &[BalanceId],
@@ -276,6 +330,7 @@ impl EffectiveBalanceRepo {
             JOIN cala_accounts a
             ON account_id = a.id
             WHERE eventually_consistent = FALSE
+            AND balance_shards IS NULL
           ),
           delete_balances AS (
             DELETE FROM cala_cumulative_effective_balances
@@ -434,4 +489,297 @@ impl EffectiveBalanceRepo {
 
         Ok(())
     }
+
+    pub(super) async fn start_backfill(
+        &self,
+        journal_id: JournalId,
+    ) -> Result<EffectiveBalancesBackfill, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO cala_effective_balances_backfills (journal_id, balances_total)
+            SELECT $1, COUNT(*)
+            FROM cala_current_balances b
+            JOIN cala_accounts a ON a.id = b.account_id
+            WHERE b.journal_id = $1 AND a.eventually_consistent = FALSE
+            ON CONFLICT (journal_id) DO UPDATE SET
+                last_account_id = NULL,
+                last_currency = NULL,
+                balances_processed = 0,
+                balances_total = EXCLUDED.balances_total,
+                started_at = NOW(),
+                updated_at = NOW(),
+                completed_at = NULL
+            RETURNING
+                journal_id AS "journal_id: JournalId",
+                last_account_id AS "last_account_id: AccountId",
+                last_currency,
+                balances_processed,
+                balances_total,
+                started_at,
+                updated_at,
+                completed_at"#,
+            journal_id as JournalId,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(backfill_from_row(
+            row.journal_id,
+            row.last_account_id,
+            row.last_currency,
+            row.balances_processed,
+            row.balances_total,
+            (row.started_at, row.updated_at, row.completed_at),
+        ))
+    }
+
+    pub(super) async fn find_backfill(
+        &self,
+        journal_id: JournalId,
+    ) -> Result<Option<EffectiveBalancesBackfill>, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                journal_id AS "journal_id: JournalId",
+                last_account_id AS "last_account_id: AccountId",
+                last_currency,
+                balances_processed,
+                balances_total,
+                started_at,
+                updated_at,
+                completed_at
+            FROM cala_effective_balances_backfills
+            WHERE journal_id = $1"#,
+            journal_id as JournalId,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| {
+            backfill_from_row(
+                row.journal_id,
+                row.last_account_id,
+                row.last_currency,
+                row.balances_processed,
+                row.balances_total,
+                (row.started_at, row.updated_at, row.completed_at),
+            )
+        }))
+    }
+
+    pub(super) async fn next_backfill_balances(
+        &self,
+        backfill: &EffectiveBalancesBackfill,
+        batch_size: usize,
+    ) -> Result<Vec<(AccountId, Currency)>, BalanceError> {
+        let (last_account_id, last_currency) = match backfill.last_balance {
+            Some((account_id, currency)) => (Some(account_id), Some(currency.code().to_string())),
+            None => (None, None),
+        };
+        let rows = sqlx::query!(
+            r#"
+            SELECT b.account_id AS "account_id: AccountId", b.currency
+            FROM cala_current_balances b
+            JOIN cala_accounts a ON a.id = b.account_id
+            WHERE b.journal_id = $1
+            AND a.eventually_consistent = FALSE
+            AND ($2::uuid IS NULL OR (b.account_id, b.currency) > ($2, $3))
+            ORDER BY b.account_id, b.currency
+            LIMIT $4"#,
+            backfill.journal_id as JournalId,
+            last_account_id as Option<AccountId>,
+            last_currency,
+            batch_size as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.account_id,
+                    row.currency.parse().expect("Failed to parse currency"),
+                )
+            })
+            .collect())
+    }
+
+    /// Takes the same lock that is held while postings update the balance, removes the
+    /// cumulative balances and returns all entries affecting the balance ordered by
+    /// effective date and creation.
+    pub(super) async fn lock_and_clear_for_rebuild(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<Vec<(NaiveDate, EntryValues)>, BalanceError> {
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock(hashtext(concat($1::text, $2::text, $3::text)))",
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+        )
+        .execute(op.as_executor())
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM cala_cumulative_effective_balances
+            WHERE journal_id = $1 AND account_id = $2 AND currency = $3"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(self
+            .find_entries_in_op(op, journal_id, account_id, currency)
+            .await?
+            .into_iter()
+            .map(|(effective, _, entry)| (effective, entry))
+            .collect())
+    }
+
+    /// Returns the entries that make up a balance with their effective date and creation
+    /// time, ordered by effective date and creation.
+    /// For accounts these are the entries posted to the account. For account sets they are
+    /// the entries recorded in the balance history of the set, i.e. the entries of members
+    /// while they belonged to the set and the entries adding or removing members, which
+    /// take effect on the day they were created. Fails if the history has been thinned.
+    pub(super) async fn find_entries_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<Vec<(NaiveDate, DateTime<Utc>, EntryValues)>, BalanceError> {
+        let pruned = sqlx::query!(
+            r#"
+            SELECT r.pruned_before AS "pruned_before!"
+            FROM cala_current_balances b
+            JOIN cala_account_sets s
+                ON s.id = b.account_id
+            JOIN cala_balance_history_retention_policies r
+                ON r.journal_id = b.journal_id
+                AND r.pruned_before IS NOT NULL
+            WHERE b.journal_id = $1 AND b.account_id = $2 AND b.currency = $3
+            AND b.latest_version > (
+                SELECT COUNT(*) FROM cala_balance_history h
+                WHERE h.journal_id = $1 AND h.account_id = $2 AND h.currency = $3
+            ) + (
+                SELECT COUNT(*) FROM cala_balance_history_archive h
+                WHERE h.journal_id = $1 AND h.account_id = $2 AND h.currency = $3
+            )"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+        )
+        .fetch_optional(op.as_executor())
+        .await?;
+        if let Some(row) = pruned {
+            return Err(BalanceError::HistoryPruned(journal_id, row.pruned_before));
+        }
+        let rows = sqlx::query!(
+            r#"
+            WITH history AS (
+                SELECT latest_entry_id
+                FROM cala_balance_history
+                WHERE journal_id = $1 AND account_id = $2 AND currency = $3
+                UNION ALL
+                SELECT latest_entry_id
+                FROM cala_balance_history_archive
+                WHERE journal_id = $1 AND account_id = $2 AND currency = $3
+            )
+            SELECT
+                ev.event->'values' AS "values!",
+                COALESCE(t.effective, (e.created_at AT TIME ZONE 'UTC')::DATE) AS "effective!",
+                e.created_at
+            FROM cala_entries e
+            JOIN cala_entry_events ev
+                ON ev.id = e.id
+                AND ev.sequence = 1
+            LEFT JOIN cala_transactions t
+                ON t.id = e.transaction_id
+            WHERE e.journal_id = $1
+            AND ev.event->'values'->>'currency' = $3
+            AND CASE
+                WHEN EXISTS (SELECT 1 FROM cala_account_sets WHERE id = $2)
+                THEN e.id IN (SELECT latest_entry_id FROM history)
+                ELSE e.account_id = $2
+            END
+            ORDER BY 2, e.created_at, e.id"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let entry: EntryValues =
+                    serde_json::from_value(row.values).expect("Failed to deserialize entry values");
+                (row.effective, row.created_at, entry)
+            })
+            .collect())
+    }
+
+    pub(super) async fn record_backfill_progress(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<(), BalanceError> {
+        sqlx::query!(
+            r#"
+            UPDATE cala_effective_balances_backfills
+            SET last_account_id = $2,
+                last_currency = $3,
+                balances_processed = balances_processed + 1,
+                balances_total = GREATEST(balances_total, balances_processed + 1),
+                updated_at = NOW()
+            WHERE journal_id = $1"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    pub(super) async fn complete_backfill(
+        &self,
+        journal_id: JournalId,
+    ) -> Result<(), BalanceError> {
+        sqlx::query!(
+            r#"
+            UPDATE cala_effective_balances_backfills
+            SET completed_at = NOW(), updated_at = NOW()
+            WHERE journal_id = $1"#,
+            journal_id as JournalId,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+fn backfill_from_row(
+    journal_id: JournalId,
+    last_account_id: Option<AccountId>,
+    last_currency: Option<String>,
+    balances_processed: i32,
+    balances_total: i32,
+    (started_at, updated_at, completed_at): (DateTime<Utc>, DateTime<Utc>, Option<DateTime<Utc>>),
+) -> EffectiveBalancesBackfill {
+    EffectiveBalancesBackfill {
+        journal_id,
+        balances_processed: balances_processed as u32,
+        balances_total: balances_total as u32,
+        started_at,
+        updated_at,
+        completed_at,
+        last_balance: last_account_id
+            .zip(last_currency.map(|c| c.parse().expect("Failed to parse currency"))),
+    }
 }
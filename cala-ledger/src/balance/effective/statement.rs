@@ -0,0 +1,175 @@
+use chrono::{Datelike, Months, NaiveDate};
+use rust_decimal::Decimal;
+
+use cala_types::primitives::{AccountId, Currency, DebitOrCredit, JournalId, Layer};
+
+use super::super::account_balance::{AccountBalance, BalanceRange};
+
+/// How the periods of a `BalanceStatement` are bucketed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementGrouping {
+    Daily,
+    Monthly,
+    Quarterly,
+}
+
+impl StatementGrouping {
+    fn bucket_until(&self, date: NaiveDate) -> NaiveDate {
+        let months = match self {
+            StatementGrouping::Daily => return date,
+            StatementGrouping::Monthly => 1,
+            StatementGrouping::Quarterly => 3 - (date.month0() % 3),
+        };
+        let first_of_month = date.with_day(1).expect("first day of month");
+        (first_of_month + Months::new(months))
+            .pred_opt()
+            .expect("date before end of time")
+    }
+}
+
+/// Splits `[from, until]` into consecutive periods according to the grouping,
+/// clipping the first and last bucket to the requested range.
+pub(super) fn statement_periods(
+    from: NaiveDate,
+    until: NaiveDate,
+    grouping: Option<StatementGrouping>,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let Some(grouping) = grouping else {
+        return vec![(from, until)];
+    };
+    let mut periods = Vec::new();
+    let mut start = from;
+    while start <= until {
+        let end = grouping.bucket_until(start).min(until);
+        periods.push((start, end));
+        match end.succ_opt() {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+    periods
+}
+
+/// Opening and closing balance of a single layer together with the debits and
+/// credits posted to it within the period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerActivity {
+    pub layer: Layer,
+    pub opening: Decimal,
+    pub debits: Decimal,
+    pub credits: Decimal,
+    pub closing: Decimal,
+}
+
+/// Activity of an account within one period of a `BalanceStatement`.
+#[derive(Debug, Clone)]
+pub struct StatementPeriod {
+    pub from: NaiveDate,
+    pub until: NaiveDate,
+    pub range: BalanceRange,
+}
+
+impl StatementPeriod {
+    pub fn activity(&self, layer: Layer) -> LayerActivity {
+        let (open, close) = (&self.range.open, &self.range.close);
+        let period = match layer {
+            Layer::Settled => &self.range.period.details.settled,
+            Layer::Pending => &self.range.period.details.pending,
+            Layer::Encumbrance => &self.range.period.details.encumbrance,
+        };
+        LayerActivity {
+            layer,
+            opening: layer_balance(open, layer),
+            debits: period.dr_balance,
+            credits: period.cr_balance,
+            closing: layer_balance(close, layer),
+        }
+    }
+
+    pub fn balance_type(&self) -> DebitOrCredit {
+        self.range.close.balance_type
+    }
+}
+
+fn layer_balance(balance: &AccountBalance, layer: Layer) -> Decimal {
+    match layer {
+        Layer::Settled => balance.settled(),
+        Layer::Pending => balance.pending(),
+        Layer::Encumbrance => balance.encumbrance(),
+    }
+}
+
+/// Period summaries of an account's effective balance. Periods before the first
+/// effective balance of the account are omitted.
+#[derive(Debug, Clone)]
+pub struct BalanceStatement {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub from: NaiveDate,
+    pub until: NaiveDate,
+    pub grouping: Option<StatementGrouping>,
+    pub periods: Vec<StatementPeriod>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn ungrouped_statement_has_single_period() {
+        let periods = statement_periods(date(2025, 1, 15), date(2025, 3, 2), None);
+        assert_eq!(periods, vec![(date(2025, 1, 15), date(2025, 3, 2))]);
+    }
+
+    #[test]
+    fn monthly_periods_are_clipped_to_range() {
+        let periods = statement_periods(
+            date(2025, 1, 15),
+            date(2025, 3, 2),
+            Some(StatementGrouping::Monthly),
+        );
+        assert_eq!(
+            periods,
+            vec![
+                (date(2025, 1, 15), date(2025, 1, 31)),
+                (date(2025, 2, 1), date(2025, 2, 28)),
+                (date(2025, 3, 1), date(2025, 3, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn quarterly_periods_follow_calendar_quarters() {
+        let periods = statement_periods(
+            date(2024, 11, 20),
+            date(2025, 12, 31),
+            Some(StatementGrouping::Quarterly),
+        );
+        assert_eq!(
+            periods,
+            vec![
+                (date(2024, 11, 20), date(2024, 12, 31)),
+                (date(2025, 1, 1), date(2025, 3, 31)),
+                (date(2025, 4, 1), date(2025, 6, 30)),
+                (date(2025, 7, 1), date(2025, 9, 30)),
+                (date(2025, 10, 1), date(2025, 12, 31)),
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_periods_cover_every_day() {
+        let periods = statement_periods(
+            date(2025, 2, 27),
+            date(2025, 3, 1),
+            Some(StatementGrouping::Daily),
+        );
+        assert_eq!(periods.len(), 3);
+        assert!(periods.iter().all(|(from, until)| from == until));
+    }
+}
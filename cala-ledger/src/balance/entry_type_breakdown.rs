@@ -0,0 +1,49 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::primitives::*;
+
+/// Debits and credits posted with a single entry type in one currency and layer.
+#[derive(Debug, Clone)]
+pub struct EntryTypeTotal {
+    pub entry_type: String,
+    pub currency: Currency,
+    pub layer: Layer,
+    pub dr_balance: Decimal,
+    pub cr_balance: Decimal,
+    pub entry_count: u64,
+}
+
+impl EntryTypeTotal {
+    /// The net amount in the direction of the given normal balance type.
+    pub fn net(&self, balance_type: DebitOrCredit) -> Decimal {
+        match balance_type {
+            DebitOrCredit::Debit => self.dr_balance - self.cr_balance,
+            DebitOrCredit::Credit => self.cr_balance - self.dr_balance,
+        }
+    }
+}
+
+/// Totals of the entries posted to an account (or to the members of an account set)
+/// within an effective date range, grouped by entry type.
+#[derive(Debug, Clone)]
+pub struct EntryTypeBreakdown {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub from: NaiveDate,
+    pub until: Option<NaiveDate>,
+    pub totals: Vec<EntryTypeTotal>,
+}
+
+impl EntryTypeBreakdown {
+    pub fn find(
+        &self,
+        entry_type: &str,
+        currency: Currency,
+        layer: Layer,
+    ) -> Option<&EntryTypeTotal> {
+        self.totals
+            .iter()
+            .find(|t| t.entry_type == entry_type && t.currency == currency && t.layer == layer)
+    }
+}
@@ -10,6 +10,18 @@ pub enum BalanceError {
     NotFound(JournalId, AccountId, Currency),
     #[error("LedgerError - JournalError: {0}")]
     JournalError(#[from] crate::journal::error::JournalError),
+    #[error("BalanceError - FxRateError: {0}")]
+    FxRateError(#[from] crate::fx_rate::error::FxRateError),
     #[error("BalanceError - JournalLocked: - Cannot update balances. The journal {0} is locked")]
     JournalLocked(JournalId),
+    #[error("BalanceError - InvalidDateRange: {1} is before {0}")]
+    InvalidDateRange(chrono::NaiveDate, chrono::NaiveDate),
+    #[error("BalanceError - HistoryPruned: the balance history of journal {0} before {1} has been compacted")]
+    HistoryPruned(JournalId, chrono::DateTime<chrono::Utc>),
+    #[error("BalanceError - EffectiveBalancesNotEnabled: effective balances are not enabled for journal {0}")]
+    EffectiveBalancesNotEnabled(JournalId),
+    #[error(
+        "BalanceError - BalanceSharded: the balance of account {0} is sharded and keeps no history"
+    )]
+    BalanceSharded(AccountId),
 }
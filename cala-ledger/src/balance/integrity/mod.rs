@@ -0,0 +1,573 @@
+mod repo;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use es_entity::{AtomicOperation, DbOp};
+use futures::TryStreamExt;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use cala_types::{
+    balance::{BalanceAmount, BalanceSnapshot},
+    entry::EntryValues,
+    primitives::*,
+};
+
+use crate::{
+    journal::Journals,
+    ledger_operation::LedgerOperation,
+    outbox::*,
+    primitives::{DataSource, JournalId},
+};
+
+use super::{
+    error::BalanceError,
+    repo::BalanceRepo,
+    shard::merge,
+    snapshot::{Snapshots, UNASSIGNED_ENTRY_ID},
+};
+use repo::*;
+
+const LAYERS: [Layer; 3] = [Layer::Settled, Layer::Pending, Layer::Encumbrance];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayerTotals {
+    pub dr_balance: Decimal,
+    pub cr_balance: Decimal,
+}
+
+impl LayerTotals {
+    fn of(snapshot: &BalanceSnapshot, layer: Layer) -> Self {
+        let amount = amount(snapshot, layer);
+        Self {
+            dr_balance: amount.dr_balance,
+            cr_balance: amount.cr_balance,
+        }
+    }
+
+    fn net(&self) -> Decimal {
+        self.dr_balance - self.cr_balance
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceDiscrepancySource {
+    /// The snapshot in `cala_current_balances`.
+    Current,
+    /// The latest cumulative effective balance recorded for the given date.
+    Effective(NaiveDate),
+}
+
+/// A layer of a stored balance that does not match the value recomputed from the entries.
+/// `stored` is `None` when no snapshot was found at all.
+#[derive(Debug, Clone)]
+pub struct BalanceDiscrepancy {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub is_account_set: bool,
+    pub source: BalanceDiscrepancySource,
+    pub layer: Layer,
+    pub expected: LayerTotals,
+    pub stored: Option<LayerTotals>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BalanceIntegrityReport {
+    pub journals_checked: usize,
+    pub balances_checked: usize,
+    pub effective_balances_checked: usize,
+    pub discrepancies: Vec<BalanceDiscrepancy>,
+    pub repaired_balances: usize,
+    pub repaired_effective_balances: usize,
+}
+
+impl BalanceIntegrityReport {
+    pub fn is_consistent(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct BalanceIntegrity {
+    repo: IntegrityRepo,
+    balances: BalanceRepo,
+    journals: Journals,
+    outbox: Outbox,
+    pool: PgPool,
+}
+
+impl BalanceIntegrity {
+    pub fn new(
+        pool: &PgPool,
+        balances: &BalanceRepo,
+        journals: &Journals,
+        outbox: &Outbox,
+    ) -> Self {
+        Self {
+            repo: IntegrityRepo::new(pool),
+            balances: balances.clone(),
+            journals: journals.clone(),
+            outbox: outbox.clone(),
+            pool: pool.clone(),
+        }
+    }
+
+    pub async fn verify(
+        &self,
+        journal_id: Option<JournalId>,
+        repair: bool,
+    ) -> Result<BalanceIntegrityReport, BalanceError> {
+        let journal_ids = match journal_id {
+            Some(journal_id) => vec![journal_id],
+            None => self.repo.list_journal_ids().await?,
+        };
+        let mut report = BalanceIntegrityReport::default();
+        for journal_id in journal_ids {
+            self.verify_journal(journal_id, repair, &mut report).await?;
+            report.journals_checked += 1;
+        }
+        Ok(report)
+    }
+
+    async fn verify_journal(
+        &self,
+        journal_id: JournalId,
+        repair: bool,
+        report: &mut BalanceIntegrityReport,
+    ) -> Result<(), BalanceError> {
+        let journal = self.journals.find(journal_id).await?;
+
+        // All reads happen in the same snapshot so that postings committed while
+        // the check is running are not reported as discrepancies. Corrections written
+        // against a balance that changed in the meantime fail with a serialization error.
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+            .execute(&mut *tx)
+            .await?;
+        let mut op = LedgerOperation::new(DbOp::from(tx).with_db_time().await?, &self.outbox);
+        let now = op.now();
+
+        let mut set_members: HashMap<AccountId, Vec<AccountId>> = HashMap::new();
+        for (set_id, member) in self
+            .repo
+            .account_set_members(op.as_executor(), journal_id)
+            .await?
+        {
+            let members = set_members.entry(AccountId::from(&set_id)).or_default();
+            members.extend(member);
+        }
+
+        let track_effective = journal.insert_effective_balances();
+        let mut recomputed: HashMap<(AccountId, Currency), BalanceSnapshot> = HashMap::new();
+        let mut effective_deltas: HashMap<
+            (AccountId, Currency),
+            BTreeMap<NaiveDate, BalanceSnapshot>,
+        > = HashMap::new();
+        {
+            let mut entries = self.repo.stream_entries(op.as_executor(), journal_id);
+            while let Some((entry, effective)) = entries.try_next().await? {
+                // Entries on account sets only carry over the balance of added or removed members
+                if set_members.contains_key(&entry.account_id) {
+                    continue;
+                }
+                let key = (entry.account_id, entry.currency);
+                let balance = apply_entry(recomputed.remove(&key), now, &entry);
+                recomputed.insert(key, balance);
+                if let (true, Some(effective)) = (track_effective, effective) {
+                    let deltas = effective_deltas.entry(key).or_default();
+                    let balance = apply_entry(deltas.remove(&effective), now, &entry);
+                    deltas.insert(effective, balance);
+                }
+            }
+        }
+
+        let mut stored: HashMap<_, _> = self
+            .repo
+            .current_balances(op.as_executor(), journal_id)
+            .await?
+            .into_iter()
+            .map(|balance| ((balance.account_id, balance.currency), balance))
+            .collect();
+
+        // Sharded balances are compared after merging their shards but can't be repaired
+        // as it is not known which shard is off.
+        let mut shards: HashMap<_, Vec<_>> = HashMap::new();
+        for shard in self
+            .repo
+            .balance_shards(op.as_executor(), journal_id)
+            .await?
+        {
+            shards
+                .entry((shard.account_id, shard.currency))
+                .or_default()
+                .push(shard);
+        }
+        let sharded: HashSet<_> = shards.keys().copied().collect();
+        stored.extend(shards.into_iter().map(|(key, shards)| (key, merge(shards))));
+
+        let expected_current =
+            expected_current_balances(journal_id, now, &set_members, &recomputed, &stored);
+
+        // Balances of eventually consistent accounts are only compared once all their
+        // queued entries have been folded in.
+        let queued = self
+            .repo
+            .queued_balances(op.as_executor(), journal_id)
+            .await?;
+
+        let mut current_corrections = Vec::new();
+        for (key, expected) in expected_current.iter() {
+            if queued.contains(key) {
+                continue;
+            }
+            report.balances_checked += 1;
+            let stored = stored.get(key);
+            let discrepancies = compare(
+                set_members.contains_key(&key.0),
+                BalanceDiscrepancySource::Current,
+                expected,
+                stored,
+            );
+            if repair && !discrepancies.is_empty() && !sharded.contains(key) {
+                current_corrections.push(correction(now, expected, stored));
+            }
+            report.discrepancies.extend(discrepancies);
+        }
+
+        let mut effective_corrections = Vec::new();
+        if track_effective {
+            let without_effective = self
+                .repo
+                .accounts_without_effective_balances(op.as_executor(), journal_id)
+                .await?;
+            let mut stored_effective: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+            for balance in self
+                .repo
+                .effective_balances(op.as_executor(), journal_id)
+                .await?
+            {
+                stored_effective
+                    .entry((balance.account_id, balance.currency))
+                    .or_default()
+                    .insert(balance.effective, balance);
+            }
+
+            let keys: BTreeSet<_> = stored_effective
+                .keys()
+                .chain(effective_deltas.keys())
+                .chain(expected_current.keys())
+                .copied()
+                .collect();
+            for key in keys {
+                if without_effective.contains(&key.0) {
+                    continue;
+                }
+                let stored = stored_effective.remove(&key).unwrap_or_default();
+                let mut all_time_version = stored
+                    .values()
+                    .map(|b| b.all_time_version)
+                    .max()
+                    .unwrap_or(0);
+                let expected = if set_members.contains_key(&key.0) {
+                    // For account sets only the latest cumulative balance can be derived
+                    // (it must match the current balance).
+                    let Some(expected) = expected_current.get(&key) else {
+                        continue;
+                    };
+                    let effective = stored
+                        .keys()
+                        .next_back()
+                        .copied()
+                        .unwrap_or(now.date_naive());
+                    vec![(effective, expected.clone())]
+                } else {
+                    expected_effective_balances(
+                        effective_deltas.remove(&key).unwrap_or_default(),
+                        &stored,
+                    )
+                };
+
+                let mut repair_from = None;
+                for (effective, expected) in expected {
+                    report.effective_balances_checked += 1;
+                    let stored = stored.get(&effective);
+                    let discrepancies = compare(
+                        set_members.contains_key(&key.0),
+                        BalanceDiscrepancySource::Effective(effective),
+                        &expected,
+                        stored.map(|b| &b.snapshot),
+                    );
+                    if !discrepancies.is_empty() && repair_from.is_none() {
+                        repair_from = Some(effective);
+                    }
+                    report.discrepancies.extend(discrepancies);
+
+                    // Cumulative balances after the first discrepancy are rewritten as well
+                    // so that the all_time_version ordering follows the effective dates.
+                    if repair && repair_from.is_some() {
+                        all_time_version += 1;
+                        let snapshot = correction(now, &expected, stored.map(|b| &b.snapshot));
+                        effective_corrections.push(EffectiveBalanceCorrection {
+                            effective,
+                            version: snapshot.version,
+                            all_time_version,
+                            snapshot,
+                        });
+                    }
+                }
+            }
+        }
+
+        if current_corrections.is_empty() && effective_corrections.is_empty() {
+            return Ok(());
+        }
+
+        self.balances
+            .insert_new_snapshots(&mut op, journal_id, &current_corrections)
+            .await?;
+        self.repo
+            .insert_effective_corrections(op.as_executor(), journal_id, &effective_corrections)
+            .await?;
+        report.repaired_balances += current_corrections.len();
+        report.repaired_effective_balances += effective_corrections.len();
+
+        op.accumulate(current_corrections.into_iter().map(|balance| {
+            if balance.version == 1 {
+                OutboxEventPayload::BalanceCreated {
+                    source: DataSource::Local,
+                    balance,
+                }
+            } else {
+                OutboxEventPayload::BalanceUpdated {
+                    source: DataSource::Local,
+                    balance,
+                }
+            }
+        }));
+        op.commit().await?;
+
+        Ok(())
+    }
+}
+
+fn expected_current_balances(
+    journal_id: JournalId,
+    time: DateTime<Utc>,
+    set_members: &HashMap<AccountId, Vec<AccountId>>,
+    recomputed: &HashMap<(AccountId, Currency), BalanceSnapshot>,
+    stored: &HashMap<(AccountId, Currency), BalanceSnapshot>,
+) -> BTreeMap<(AccountId, Currency), BalanceSnapshot> {
+    let mut expected = BTreeMap::new();
+
+    let mut by_account: HashMap<AccountId, Vec<&BalanceSnapshot>> = HashMap::new();
+    for balance in recomputed.values() {
+        by_account
+            .entry(balance.account_id)
+            .or_default()
+            .push(balance);
+    }
+
+    for (key, balance) in stored.iter() {
+        if !set_members.contains_key(&key.0) && !recomputed.contains_key(key) {
+            expected.insert(*key, zeroed(balance, time));
+        }
+    }
+    for (key, balance) in recomputed.iter() {
+        expected.insert(*key, balance.clone());
+    }
+
+    // The net balance of an account set per layer is the sum of the net balances of
+    // all its (transitive) member accounts. Only the net can be derived because the
+    // debit/credit split depends on when members were added or removed.
+    for (set_id, members) in set_members.iter() {
+        let mut nets: BTreeMap<Currency, [Decimal; 3]> = BTreeMap::new();
+        for balance in members
+            .iter()
+            .filter_map(|member| by_account.get(member))
+            .flatten()
+        {
+            let net = nets.entry(balance.currency).or_default();
+            for (net, layer) in net.iter_mut().zip(LAYERS) {
+                *net += LayerTotals::of(balance, layer).net();
+            }
+        }
+        for (key, _) in stored.iter().filter(|(key, _)| key.0 == *set_id) {
+            nets.entry(key.1).or_default();
+        }
+
+        for (currency, nets) in nets {
+            let key = (*set_id, currency);
+            let mut balance = stored
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| empty_snapshot(journal_id, *set_id, currency, time));
+            for (net, layer) in nets.into_iter().zip(LAYERS) {
+                let amount = amount_mut(&mut balance, layer);
+                let diff = net - (amount.dr_balance - amount.cr_balance);
+                if diff > Decimal::ZERO {
+                    amount.dr_balance += diff;
+                } else {
+                    amount.cr_balance -= diff;
+                }
+            }
+            expected.insert(key, balance);
+        }
+    }
+
+    expected
+}
+
+fn expected_effective_balances(
+    deltas: BTreeMap<NaiveDate, BalanceSnapshot>,
+    stored: &BTreeMap<NaiveDate, StoredEffectiveBalance>,
+) -> Vec<(NaiveDate, BalanceSnapshot)> {
+    let dates: BTreeSet<_> = deltas.keys().chain(stored.keys()).copied().collect();
+    let mut cumulative: Option<BalanceSnapshot> = None;
+    let mut ret = Vec::with_capacity(dates.len());
+    for effective in dates {
+        if let Some(delta) = deltas.get(&effective) {
+            cumulative = Some(match cumulative.take() {
+                Some(mut balance) => {
+                    for layer in LAYERS {
+                        let delta = amount(delta, layer);
+                        let amount = amount_mut(&mut balance, layer);
+                        amount.dr_balance += delta.dr_balance;
+                        amount.cr_balance += delta.cr_balance;
+                    }
+                    balance.entry_id = delta.entry_id;
+                    balance
+                }
+                None => delta.clone(),
+            });
+        }
+        let expected = match (&cumulative, stored.get(&effective)) {
+            (Some(balance), _) => balance.clone(),
+            (None, Some(stored)) => zeroed(&stored.snapshot, stored.snapshot.modified_at),
+            (None, None) => unreachable!("date is either stored or has entries"),
+        };
+        ret.push((effective, expected));
+    }
+    ret
+}
+
+fn compare(
+    is_account_set: bool,
+    source: BalanceDiscrepancySource,
+    expected: &BalanceSnapshot,
+    stored: Option<&BalanceSnapshot>,
+) -> Vec<BalanceDiscrepancy> {
+    LAYERS
+        .into_iter()
+        .filter_map(|layer| {
+            let expected_totals = LayerTotals::of(expected, layer);
+            let stored_totals = stored.map(|s| LayerTotals::of(s, layer));
+            if stored_totals.unwrap_or_default() == expected_totals {
+                return None;
+            }
+            Some(BalanceDiscrepancy {
+                journal_id: expected.journal_id,
+                account_id: expected.account_id,
+                currency: expected.currency,
+                is_account_set,
+                source,
+                layer,
+                expected: expected_totals,
+                stored: stored_totals,
+            })
+        })
+        .collect()
+}
+
+/// Builds the next version of the stored snapshot carrying the expected amounts.
+fn correction(
+    time: DateTime<Utc>,
+    expected: &BalanceSnapshot,
+    stored: Option<&BalanceSnapshot>,
+) -> BalanceSnapshot {
+    let Some(stored) = stored else {
+        let mut balance = expected.clone();
+        balance.version = 1;
+        balance.created_at = time;
+        balance.modified_at = time;
+        return balance;
+    };
+    let mut balance = stored.clone();
+    balance.version += 1;
+    balance.modified_at = time;
+    for layer in LAYERS {
+        let expected = amount(expected, layer);
+        let amount = amount_mut(&mut balance, layer);
+        if amount.dr_balance != expected.dr_balance || amount.cr_balance != expected.cr_balance {
+            amount.dr_balance = expected.dr_balance;
+            amount.cr_balance = expected.cr_balance;
+            amount.modified_at = time;
+        }
+    }
+    balance
+}
+
+fn apply_entry(
+    balance: Option<BalanceSnapshot>,
+    time: DateTime<Utc>,
+    entry: &EntryValues,
+) -> BalanceSnapshot {
+    match balance {
+        Some(balance) => Snapshots::update_snapshot(time, balance, entry),
+        None => Snapshots::new_snapshot(time, entry.account_id, entry),
+    }
+}
+
+fn zeroed(balance: &BalanceSnapshot, time: DateTime<Utc>) -> BalanceSnapshot {
+    let mut balance = balance.clone();
+    for layer in LAYERS {
+        let amount = amount_mut(&mut balance, layer);
+        amount.dr_balance = Decimal::ZERO;
+        amount.cr_balance = Decimal::ZERO;
+        amount.modified_at = time;
+    }
+    balance
+}
+
+fn empty_snapshot(
+    journal_id: JournalId,
+    account_id: AccountId,
+    currency: Currency,
+    time: DateTime<Utc>,
+) -> BalanceSnapshot {
+    let entry_id = EntryId::from(UNASSIGNED_ENTRY_ID);
+    let amount = BalanceAmount {
+        dr_balance: Decimal::ZERO,
+        cr_balance: Decimal::ZERO,
+        entry_id,
+        modified_at: time,
+    };
+    BalanceSnapshot {
+        journal_id,
+        account_id,
+        entry_id,
+        currency,
+        settled: amount.clone(),
+        pending: amount.clone(),
+        encumbrance: amount,
+        version: 0,
+        modified_at: time,
+        created_at: time,
+    }
+}
+
+fn amount(balance: &BalanceSnapshot, layer: Layer) -> &BalanceAmount {
+    match layer {
+        Layer::Settled => &balance.settled,
+        Layer::Pending => &balance.pending,
+        Layer::Encumbrance => &balance.encumbrance,
+    }
+}
+
+fn amount_mut(balance: &mut BalanceSnapshot, layer: Layer) -> &mut BalanceAmount {
+    match layer {
+        Layer::Settled => &mut balance.settled,
+        Layer::Pending => &mut balance.pending,
+        Layer::Encumbrance => &mut balance.encumbrance,
+    }
+}
@@ -0,0 +1,296 @@
+use chrono::NaiveDate;
+use futures::{stream::BoxStream, StreamExt};
+use std::collections::HashSet;
+
+use cala_types::{
+    balance::BalanceSnapshot,
+    entry::EntryValues,
+    primitives::{AccountId, AccountSetId, Currency, EntryId, JournalId},
+};
+
+use crate::balance::error::BalanceError;
+
+pub(super) struct StoredEffectiveBalance {
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub effective: NaiveDate,
+    pub all_time_version: u32,
+    pub snapshot: BalanceSnapshot,
+}
+
+pub(super) struct EffectiveBalanceCorrection {
+    pub effective: NaiveDate,
+    pub version: u32,
+    pub all_time_version: u32,
+    pub snapshot: BalanceSnapshot,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct IntegrityRepo {
+    pool: sqlx::PgPool,
+}
+
+impl IntegrityRepo {
+    pub fn new(pool: &sqlx::PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    pub async fn list_journal_ids(&self) -> Result<Vec<JournalId>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"SELECT id AS "id: JournalId" FROM cala_journals ORDER BY created_at, id"#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    pub fn stream_entries<'a>(
+        &self,
+        db: &'a mut sqlx::PgConnection,
+        journal_id: JournalId,
+    ) -> BoxStream<'a, Result<(EntryValues, Option<NaiveDate>), BalanceError>> {
+        sqlx::query!(
+            r#"
+            SELECT ev.event->'values' AS "values!", t.effective AS "effective?"
+            FROM cala_entries e
+            JOIN cala_entry_events ev
+                ON ev.id = e.id
+                AND ev.sequence = 1
+            LEFT JOIN cala_transactions t
+                ON t.id = e.transaction_id
+            WHERE e.journal_id = $1
+            ORDER BY e.created_at, e.id"#,
+            journal_id as JournalId,
+        )
+        .fetch(db)
+        .map(|row| {
+            let row = row?;
+            let entry: EntryValues =
+                serde_json::from_value(row.values).expect("Failed to deserialize entry values");
+            Ok((entry, row.effective))
+        })
+        .boxed()
+    }
+
+    pub async fn account_set_members(
+        &self,
+        db: &mut sqlx::PgConnection,
+        journal_id: JournalId,
+    ) -> Result<Vec<(AccountSetId, Option<AccountId>)>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT s.id AS "account_set_id: AccountSetId", m.member_account_id AS "member_account_id?: AccountId"
+            FROM cala_account_sets s
+            LEFT JOIN cala_account_set_member_accounts m
+                ON m.account_set_id = s.id
+            WHERE s.journal_id = $1"#,
+            journal_id as JournalId,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.account_set_id, row.member_account_id))
+            .collect())
+    }
+
+    /// Balances of eventually consistent accounts with entries that have not been
+    /// folded in yet.
+    pub async fn queued_balances(
+        &self,
+        db: &mut sqlx::PgConnection,
+        journal_id: JournalId,
+    ) -> Result<HashSet<(AccountId, Currency)>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT account_id AS "account_id: AccountId", currency
+            FROM cala_balance_queue
+            WHERE journal_id = $1"#,
+            journal_id as JournalId,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.account_id,
+                    row.currency.parse().expect("Failed to parse currency"),
+                )
+            })
+            .collect())
+    }
+
+    /// Accounts whose postings don't update cumulative effective balances.
+    pub async fn accounts_without_effective_balances(
+        &self,
+        db: &mut sqlx::PgConnection,
+        journal_id: JournalId,
+    ) -> Result<HashSet<AccountId>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT a.id AS "id: AccountId"
+            FROM cala_accounts a
+            WHERE (a.eventually_consistent = TRUE OR a.balance_shards IS NOT NULL)
+            AND EXISTS (
+                SELECT 1 FROM cala_entries e
+                WHERE e.journal_id = $1 AND e.account_id = a.id
+            )"#,
+            journal_id as JournalId,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    pub async fn current_balances(
+        &self,
+        db: &mut sqlx::PgConnection,
+        journal_id: JournalId,
+    ) -> Result<Vec<BalanceSnapshot>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT h.values
+            FROM cala_balance_history h
+            JOIN cala_current_balances c
+                ON h.journal_id = c.journal_id
+                AND h.account_id = c.account_id
+                AND h.currency = c.currency
+                AND h.version = c.latest_version
+            WHERE c.journal_id = $1"#,
+            journal_id as JournalId,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot")
+            })
+            .collect())
+    }
+
+    pub async fn balance_shards(
+        &self,
+        db: &mut sqlx::PgConnection,
+        journal_id: JournalId,
+    ) -> Result<Vec<BalanceSnapshot>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"SELECT values FROM cala_balance_shards WHERE journal_id = $1"#,
+            journal_id as JournalId,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot")
+            })
+            .collect())
+    }
+
+    pub async fn effective_balances(
+        &self,
+        db: &mut sqlx::PgConnection,
+        journal_id: JournalId,
+    ) -> Result<Vec<StoredEffectiveBalance>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT ON (account_id, currency, effective)
+                account_id AS "account_id: AccountId",
+                currency,
+                effective,
+                all_time_version,
+                values
+            FROM cala_cumulative_effective_balances
+            WHERE journal_id = $1
+            ORDER BY account_id, currency, effective, version DESC"#,
+            journal_id as JournalId,
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| StoredEffectiveBalance {
+                account_id: row.account_id,
+                currency: row.currency.parse().expect("Failed to parse currency"),
+                effective: row.effective,
+                all_time_version: row.all_time_version as u32,
+                snapshot: serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot"),
+            })
+            .collect())
+    }
+
+    pub async fn insert_effective_corrections(
+        &self,
+        db: &mut sqlx::PgConnection,
+        journal_id: JournalId,
+        corrections: &[EffectiveBalanceCorrection],
+    ) -> Result<(), BalanceError> {
+        if corrections.is_empty() {
+            return Ok(());
+        }
+
+        let mut journal_ids = Vec::with_capacity(corrections.len());
+        let mut account_ids = Vec::with_capacity(corrections.len());
+        let mut currencies = Vec::with_capacity(corrections.len());
+        let mut effectives = Vec::with_capacity(corrections.len());
+        let mut versions = Vec::with_capacity(corrections.len());
+        let mut all_time_versions = Vec::with_capacity(corrections.len());
+        let mut entry_ids = Vec::with_capacity(corrections.len());
+        let mut modified_timestamps = Vec::with_capacity(corrections.len());
+        let mut created_timestamps = Vec::with_capacity(corrections.len());
+        let mut values = Vec::with_capacity(corrections.len());
+
+        for correction in corrections {
+            journal_ids.push(journal_id);
+            account_ids.push(correction.snapshot.account_id);
+            currencies.push(correction.snapshot.currency.code());
+            effectives.push(correction.effective);
+            versions.push(correction.version as i32);
+            all_time_versions.push(correction.all_time_version as i32);
+            entry_ids.push(correction.snapshot.entry_id);
+            modified_timestamps.push(correction.snapshot.modified_at);
+            created_timestamps.push(correction.snapshot.created_at);
+            values.push(
+                serde_json::to_value(&correction.snapshot)
+                    .expect("Failed to serialize balance snapshot"),
+            );
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_cumulative_effective_balances (
+              journal_id, account_id, currency, effective, version, all_time_version, latest_entry_id, updated_at, created_at, values
+            )
+            SELECT * FROM UNNEST(
+                $1::uuid[],
+                $2::uuid[],
+                $3::text[],
+                $4::date[],
+                $5::integer[],
+                $6::integer[],
+                $7::uuid[],
+                $8::timestamptz[],
+                $9::timestamptz[],
+                $10::jsonb[]
+            )
+            "#,
+            &journal_ids as &[JournalId],
+            &account_ids as &[AccountId],
+            &currencies[..] as &[&str],
+            &effectives[..],
+            &versions[..],
+            &all_time_versions[..],
+            &entry_ids as &[EntryId],
+            &modified_timestamps[..],
+            &created_timestamps[..],
+            &values[..]
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
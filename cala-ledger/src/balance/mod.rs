@@ -1,10 +1,18 @@
 mod account_balance;
+mod converted_balance;
 mod effective;
+mod entry_type_breakdown;
 pub mod error;
+mod integrity;
+mod queue;
 mod repo;
+mod retention;
+mod shard;
 mod snapshot;
+mod trial_balance;
 
 use chrono::{DateTime, NaiveDate, Utc};
+use futures::TryStreamExt;
 use sqlx::PgPool;
 use std::collections::{BTreeSet, HashMap};
 use tracing::instrument;
@@ -16,6 +24,7 @@ pub use cala_types::{
 use cala_types::{entry::EntryValues, primitives::*};
 
 use crate::{
+    fx_rate::{error::FxRateError, FxRates},
     journal::Journals,
     ledger_operation::*,
     outbox::*,
@@ -23,10 +32,28 @@ use crate::{
 };
 
 pub use account_balance::*;
-use effective::*;
+pub use converted_balance::*;
+pub use effective::{
+    BalanceStatement, EffectiveBalances, EffectiveBalancesBackfill, LayerActivity,
+    StatementGrouping, StatementPeriod,
+};
+pub use entry_type_breakdown::*;
 use error::BalanceError;
+use integrity::BalanceIntegrity;
+pub use integrity::{
+    BalanceDiscrepancy, BalanceDiscrepancySource, BalanceIntegrityReport, LayerTotals,
+};
+pub use queue::BalanceLag;
+use queue::BalanceQueue;
+pub use repo::balance_history_cursor::*;
 use repo::*;
+use retention::BalanceHistoryRetentions;
+pub use retention::{
+    BalanceHistoryCompaction, BalanceHistoryRetention, BalanceHistoryRetentionMode,
+};
+use shard::BalanceShards;
 pub(crate) use snapshot::*;
+pub use trial_balance::*;
 
 #[derive(Clone)]
 pub struct Balances {
@@ -35,18 +62,35 @@ pub struct Balances {
     #[allow(dead_code)]
     outbox: Outbox,
     journals: Journals,
+    fx_rates: FxRates,
     effective: EffectiveBalances,
-    _pool: PgPool,
+    integrity: BalanceIntegrity,
+    queue: BalanceQueue,
+    shards: BalanceShards,
+    retentions: BalanceHistoryRetentions,
+    pool: PgPool,
 }
 
 impl Balances {
-    pub(crate) fn new(pool: &PgPool, outbox: Outbox, journals: &Journals) -> Self {
+    pub(crate) fn new(
+        pool: &PgPool,
+        outbox: Outbox,
+        journals: &Journals,
+        fx_rates: &FxRates,
+    ) -> Self {
+        let repo = BalanceRepo::new(pool);
+        let shards = BalanceShards::new(pool);
         Self {
-            repo: BalanceRepo::new(pool),
-            effective: EffectiveBalances::new(pool),
+            integrity: BalanceIntegrity::new(pool, &repo, journals, &outbox),
+            queue: BalanceQueue::new(pool, &repo, &outbox),
+            shards: shards.clone(),
+            retentions: BalanceHistoryRetentions::new(pool),
+            repo,
+            effective: EffectiveBalances::new(pool, journals, &shards),
             outbox,
             journals: journals.clone(),
-            _pool: pool.clone(),
+            fx_rates: fx_rates.clone(),
+            pool: pool.clone(),
         }
     }
 
@@ -61,9 +105,15 @@ impl Balances {
         account_id: impl Into<AccountId> + std::fmt::Debug,
         currency: Currency,
     ) -> Result<AccountBalance, BalanceError> {
-        self.repo
-            .find(journal_id, account_id.into(), currency)
-            .await
+        let account_id = account_id.into();
+        match self.repo.find(journal_id, account_id, currency).await {
+            Err(BalanceError::NotFound(..)) => {
+                self.shards
+                    .find_in_op(&self.pool, journal_id, account_id, currency)
+                    .await
+            }
+            res => res,
+        }
     }
 
     #[instrument(name = "cala_ledger.balance.find_in_op", skip(self, op))]
@@ -74,9 +124,19 @@ impl Balances {
         account_id: impl Into<AccountId> + std::fmt::Debug,
         currency: Currency,
     ) -> Result<AccountBalance, BalanceError> {
-        self.repo
-            .find_in_op(op, journal_id, account_id.into(), currency)
+        let account_id = account_id.into();
+        match self
+            .repo
+            .find_in_op(&mut *op, journal_id, account_id, currency)
             .await
+        {
+            Err(BalanceError::NotFound(..)) => {
+                self.shards
+                    .find_in_op(op, journal_id, account_id, currency)
+                    .await
+            }
+            res => res,
+        }
     }
 
     #[instrument(name = "cala_ledger.balance.find_all", skip(self))]
@@ -84,7 +144,284 @@ impl Balances {
         &self,
         ids: &[BalanceId],
     ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
-        self.repo.find_all(ids).await
+        let mut balances = self.repo.find_all(ids).await?;
+        let missing: Vec<_> = ids
+            .iter()
+            .filter(|id| !balances.contains_key(id))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            balances.extend(self.shards.find_all(&missing).await?);
+        }
+        Ok(balances)
+    }
+
+    /// Returns the balance as it was recorded at or before `as_of`.
+    /// Fails if the history of the journal has been compacted past `as_of` or if the
+    /// balance of the account is sharded.
+    #[instrument(name = "cala_ledger.balance.find_as_of", skip(self))]
+    pub async fn find_as_of(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<AccountBalance, BalanceError> {
+        let account_id = account_id.into();
+        self.shards.ensure_unsharded(&[account_id]).await?;
+        self.retentions
+            .ensure_retained(&[journal_id], as_of)
+            .await?;
+        self.repo
+            .find_as_of(journal_id, account_id, currency, as_of)
+            .await
+    }
+
+    /// Returns the balances as they were recorded at or before `as_of`.
+    /// Fails if the history of any of the journals has been compacted past `as_of` or if
+    /// the balance of any of the accounts is sharded.
+    #[instrument(name = "cala_ledger.balance.find_all_as_of", skip(self))]
+    pub async fn find_all_as_of(
+        &self,
+        ids: &[BalanceId],
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        let account_ids: Vec<_> = ids
+            .iter()
+            .map(|(_, account_id, _)| *account_id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        self.shards.ensure_unsharded(&account_ids).await?;
+        let journal_ids: Vec<_> = ids
+            .iter()
+            .map(|(journal_id, _, _)| *journal_id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        self.retentions.ensure_retained(&journal_ids, as_of).await?;
+        self.repo.find_all_as_of(ids, as_of).await
+    }
+
+    /// Sums the balances the account held in every currency at `as_of`, converted into
+    /// `reporting_currency` at the latest rates effective on that day. Fails if a rate
+    /// is missing for any of the currencies involved.
+    #[instrument(name = "cala_ledger.balance.find_converted", skip(self))]
+    pub async fn find_converted(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        reporting_currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<ConvertedBalance, BalanceError> {
+        let account_id = account_id.into();
+        self.shards.ensure_unsharded(&[account_id]).await?;
+        self.retentions
+            .ensure_retained(&[journal_id], as_of)
+            .await?;
+        let balances = self
+            .repo
+            .find_all_currencies_as_of(journal_id, account_id, as_of)
+            .await?;
+
+        let effective = as_of.date_naive();
+        let currencies: Vec<_> = balances
+            .iter()
+            .map(|b| b.details.currency)
+            .filter(|c| *c != reporting_currency)
+            .collect();
+        let rates = self
+            .fx_rates
+            .find_all_effective(&currencies, reporting_currency, effective)
+            .await?;
+        if let Some(missing) = currencies.iter().find(|c| !rates.contains_key(c)) {
+            return Err(FxRateError::NotFound(*missing, reporting_currency, effective).into());
+        }
+
+        Ok(ConvertedBalance::new(
+            journal_id,
+            account_id,
+            reporting_currency,
+            as_of,
+            balances,
+            rates,
+        ))
+    }
+
+    /// Sums the debits and credits of every account (excluding account sets) in the journal
+    /// per currency and layer. Uses the current balances unless an effective date is given,
+    /// in which case the cumulative effective balances of the journal are used.
+    #[instrument(name = "cala_ledger.balance.trial_balance", skip(self))]
+    pub async fn trial_balance(
+        &self,
+        journal_id: JournalId,
+        currency: Option<Currency>,
+        as_of: Option<NaiveDate>,
+    ) -> Result<TrialBalance, BalanceError> {
+        if as_of.is_some() {
+            let journal = self.journals.find(journal_id).await?;
+            if !journal.insert_effective_balances() {
+                return Err(BalanceError::EffectiveBalancesNotEnabled(journal_id));
+            }
+        }
+
+        let mut trial_balance = TrialBalance::new(journal_id, as_of);
+        let mut lines = self
+            .repo
+            .stream_trial_balance_lines(journal_id, currency, as_of);
+        while let Some(line) = lines.try_next().await? {
+            trial_balance.push(line);
+        }
+        if as_of.is_none() {
+            let mut lines = self.shards.stream_trial_balance_lines(journal_id, currency);
+            while let Some(line) = lines.try_next().await? {
+                trial_balance.push(line);
+            }
+        }
+        trial_balance.calculate_totals();
+        Ok(trial_balance)
+    }
+
+    /// Aggregates the debits and credits posted to an account (or the members of an account
+    /// set) per entry type within the effective date range, optionally for a single currency.
+    #[instrument(name = "cala_ledger.balance.entry_type_breakdown", skip(self))]
+    pub async fn entry_type_breakdown(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        from: NaiveDate,
+        until: Option<NaiveDate>,
+        currency: Option<Currency>,
+    ) -> Result<EntryTypeBreakdown, BalanceError> {
+        if let Some(until) = until.filter(|until| *until < from) {
+            return Err(BalanceError::InvalidDateRange(from, until));
+        }
+        let account_id = account_id.into();
+        let totals = self
+            .repo
+            .entry_type_totals(journal_id, account_id, from, until, currency)
+            .await?;
+        Ok(EntryTypeBreakdown {
+            journal_id,
+            account_id,
+            from,
+            until,
+            totals,
+        })
+    }
+
+    /// Lists the recorded versions of a balance. Once the history of the journal has been
+    /// compacted `recorded_from` must not reach back before the compaction watermark.
+    /// Sharded balances keep no history and are rejected.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(name = "cala_ledger.balance.list_history", skip(self))]
+    pub async fn list_history(
+        &self,
+        journal_id: JournalId,
+        account_id: impl Into<AccountId> + std::fmt::Debug,
+        currency: Currency,
+        args: es_entity::PaginatedQueryArgs<BalanceHistoryByVersionCursor>,
+        direction: es_entity::ListDirection,
+        recorded_from: Option<DateTime<Utc>>,
+        recorded_until: Option<DateTime<Utc>>,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<BalanceVersion, BalanceHistoryByVersionCursor>,
+        BalanceError,
+    > {
+        let account_id = account_id.into();
+        self.shards.ensure_unsharded(&[account_id]).await?;
+        self.retentions
+            .ensure_listable(journal_id, recorded_from)
+            .await?;
+        self.repo
+            .list_history(
+                journal_id,
+                account_id,
+                currency,
+                args,
+                direction,
+                recorded_from,
+                recorded_until,
+            )
+            .await
+    }
+
+    /// Recomputes the balances of the journal (or of all journals if `None` is passed) from
+    /// the recorded entries and compares them with the stored snapshots. Account set
+    /// balances are derived from their member accounts. With `repair` set, corrective
+    /// versions are written for every balance that doesn't match.
+    #[instrument(name = "cala_ledger.balance.verify_integrity", skip(self))]
+    pub async fn verify_integrity(
+        &self,
+        journal_id: Option<JournalId>,
+        repair: bool,
+    ) -> Result<BalanceIntegrityReport, BalanceError> {
+        self.integrity.verify(journal_id, repair).await
+    }
+
+    /// Sets how the balance history of the journal is compacted once it is older than
+    /// `retain_days`. Compaction itself happens in [`Balances::compact_history`].
+    /// The history of account sets is never compacted and keeps growing.
+    #[instrument(name = "cala_ledger.balance.set_history_retention", skip(self))]
+    pub async fn set_history_retention(
+        &self,
+        journal_id: JournalId,
+        retain_days: u32,
+        mode: BalanceHistoryRetentionMode,
+    ) -> Result<BalanceHistoryRetention, BalanceError> {
+        self.journals.find(journal_id).await?;
+        self.retentions.set(journal_id, retain_days, mode).await
+    }
+
+    #[instrument(name = "cala_ledger.balance.find_history_retention", skip(self))]
+    pub async fn find_history_retention(
+        &self,
+        journal_id: JournalId,
+    ) -> Result<Option<BalanceHistoryRetention>, BalanceError> {
+        self.retentions.find(journal_id).await
+    }
+
+    /// Compacts the balance and velocity balance history of every journal that has a
+    /// retention policy, removing at most `batch_size` versions per statement.
+    /// Account sets are skipped since their entries are looked up through the history.
+    #[instrument(name = "cala_ledger.balance.compact_history", skip(self))]
+    pub async fn compact_history(
+        &self,
+        batch_size: usize,
+    ) -> Result<Vec<BalanceHistoryCompaction>, BalanceError> {
+        self.retentions.compact_all(batch_size).await
+    }
+
+    /// Compacts the history of a single journal. Returns `None` if it has no retention policy.
+    #[instrument(name = "cala_ledger.balance.compact_journal_history", skip(self))]
+    pub async fn compact_journal_history(
+        &self,
+        journal_id: JournalId,
+        batch_size: usize,
+    ) -> Result<Option<BalanceHistoryCompaction>, BalanceError> {
+        self.retentions
+            .compact_journal(journal_id, batch_size)
+            .await
+    }
+
+    /// Folds up to `batch_size` queued entries into the balances of eventually consistent
+    /// accounts in the order they were posted. Returns the number of entries folded
+    /// (0 if the queue is empty or another process is currently folding it).
+    #[instrument(name = "cala_ledger.balance.fold_queued_entries", skip(self))]
+    pub async fn fold_queued_entries(&self, batch_size: usize) -> Result<usize, BalanceError> {
+        self.queue.fold(batch_size).await
+    }
+
+    /// Reports how far the balance of an eventually consistent account lags behind
+    /// the entries posted to it.
+    #[instrument(name = "cala_ledger.balance.find_lag", skip(self))]
+    pub async fn find_lag(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<BalanceLag, BalanceError> {
+        self.queue.find_lag(journal_id, account_id, currency).await
     }
 
     pub(crate) async fn update_balances_in_op(
@@ -142,6 +479,12 @@ impl Balances {
             self.repo
                 .insert_new_snapshots(&mut db, journal.id, &new_balances)
                 .await?;
+            self.queue
+                .enqueue_in_op(&mut db, journal.id, &entries)
+                .await?;
+            self.shards
+                .update_in_op(&mut db, journal.id, created_at, &entries)
+                .await?;
 
             if journal.insert_effective_balances() {
                 self.effective
@@ -184,9 +527,16 @@ impl Balances {
         journal_id: JournalId,
         account_id: AccountId,
     ) -> Result<HashMap<Currency, BalanceSnapshot>, BalanceError> {
-        self.repo
-            .load_all_for_update(db, journal_id, account_id)
-            .await
+        let mut balances = self
+            .repo
+            .load_all_for_update(&mut *db, journal_id, account_id)
+            .await?;
+        balances.extend(
+            self.shards
+                .load_all_for_update(db, journal_id, account_id)
+                .await?,
+        );
+        Ok(balances)
     }
 
     fn new_snapshots(
@@ -0,0 +1,159 @@
+mod repo;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use cala_types::{entry::EntryValues, primitives::*};
+
+use crate::{
+    ledger_operation::LedgerOperation,
+    outbox::*,
+    primitives::{DataSource, JournalId},
+};
+
+use super::{error::BalanceError, repo::BalanceRepo, Balances};
+use repo::*;
+
+/// How far the stored balance of an eventually consistent account lags behind
+/// the entries that have been posted to it.
+#[derive(Debug, Clone)]
+pub struct BalanceLag {
+    pub journal_id: JournalId,
+    pub account_id: AccountId,
+    pub currency: Currency,
+    /// Queue sequence of the last entry folded into the balance.
+    pub last_folded_sequence: Option<u64>,
+    pub last_folded_entry_id: Option<EntryId>,
+    pub last_folded_at: Option<DateTime<Utc>>,
+    /// Number of posted entries not yet reflected in the balance.
+    pub pending_entries: u64,
+    pub oldest_pending_at: Option<DateTime<Utc>>,
+}
+
+impl BalanceLag {
+    pub fn is_caught_up(&self) -> bool {
+        self.pending_entries == 0
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct BalanceQueue {
+    repo: BalanceQueueRepo,
+    balances: BalanceRepo,
+    outbox: Outbox,
+    pool: PgPool,
+}
+
+impl BalanceQueue {
+    pub fn new(pool: &PgPool, balances: &BalanceRepo, outbox: &Outbox) -> Self {
+        Self {
+            repo: BalanceQueueRepo::new(pool),
+            balances: balances.clone(),
+            outbox: outbox.clone(),
+            pool: pool.clone(),
+        }
+    }
+
+    pub async fn enqueue_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        entries: &[EntryValues],
+    ) -> Result<(), BalanceError> {
+        self.repo.enqueue_in_op(op, journal_id, entries).await
+    }
+
+    pub async fn fold(&self, batch_size: usize) -> Result<usize, BalanceError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        if !self.repo.try_lock_queue(&mut op).await? {
+            return Ok(0);
+        }
+        let queued = self.repo.dequeue(&mut op, batch_size).await?;
+        let n_folded = queued.len();
+        if n_folded == 0 {
+            return Ok(0);
+        }
+
+        let mut by_journal: BTreeMap<JournalId, Vec<QueuedEntry>> = BTreeMap::new();
+        for entry in queued {
+            by_journal.entry(entry.journal_id).or_default().push(entry);
+        }
+
+        let now = op.now();
+        let mut all_new_balances = Vec::new();
+        for (journal_id, queued) in by_journal {
+            let keys: BTreeSet<_> = queued
+                .iter()
+                .map(|q| (q.entry.account_id, q.entry.currency))
+                .collect();
+            let current_balances = self
+                .repo
+                .find_for_update(&mut op, journal_id, &keys)
+                .await?;
+
+            let mut folded: HashMap<(AccountId, Currency), FoldedBalance> = HashMap::new();
+            for q in queued.iter() {
+                folded.insert(
+                    (q.entry.account_id, q.entry.currency),
+                    FoldedBalance {
+                        account_id: q.entry.account_id,
+                        currency: q.entry.currency,
+                        last_sequence: q.sequence,
+                        last_entry_id: q.entry.id,
+                    },
+                );
+            }
+            let entries: Vec<_> = queued.into_iter().map(|q| q.entry).collect();
+            let new_balances =
+                Balances::new_snapshots(now, current_balances, &entries, &HashMap::new());
+            self.balances
+                .insert_new_snapshots(&mut op, journal_id, &new_balances)
+                .await?;
+            self.repo
+                .record_progress(
+                    &mut op,
+                    journal_id,
+                    &folded.into_values().collect::<Vec<_>>(),
+                )
+                .await?;
+            all_new_balances.extend(new_balances);
+        }
+
+        op.accumulate(all_new_balances.into_iter().map(|balance| {
+            if balance.version == 1 {
+                OutboxEventPayload::BalanceCreated {
+                    source: DataSource::Local,
+                    balance,
+                }
+            } else {
+                OutboxEventPayload::BalanceUpdated {
+                    source: DataSource::Local,
+                    balance,
+                }
+            }
+        }));
+        op.commit().await?;
+
+        Ok(n_folded)
+    }
+
+    pub async fn find_lag(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<BalanceLag, BalanceError> {
+        let row = self.repo.find_lag(journal_id, account_id, currency).await?;
+        Ok(BalanceLag {
+            journal_id,
+            account_id,
+            currency,
+            last_folded_sequence: row.last_sequence.map(|s| s as u64),
+            last_folded_entry_id: row.last_entry_id,
+            last_folded_at: row.last_folded_at,
+            pending_entries: row.pending_entries as u64,
+            oldest_pending_at: row.oldest_pending_at,
+        })
+    }
+}
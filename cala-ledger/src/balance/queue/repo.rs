@@ -0,0 +1,257 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap};
+
+use cala_types::{
+    balance::BalanceSnapshot,
+    entry::EntryValues,
+    primitives::{AccountId, Currency, EntryId, JournalId},
+};
+
+use crate::balance::error::BalanceError;
+
+pub(super) struct QueuedEntry {
+    pub sequence: i64,
+    pub journal_id: JournalId,
+    pub entry: EntryValues,
+}
+
+pub(super) struct FoldedBalance {
+    pub account_id: AccountId,
+    pub currency: Currency,
+    pub last_sequence: i64,
+    pub last_entry_id: EntryId,
+}
+
+pub(super) struct BalanceLagRow {
+    pub last_sequence: Option<i64>,
+    pub last_entry_id: Option<EntryId>,
+    pub last_folded_at: Option<DateTime<Utc>>,
+    pub pending_entries: i64,
+    pub oldest_pending_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct BalanceQueueRepo {
+    pool: PgPool,
+}
+
+impl BalanceQueueRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Records the entries that post to eventually consistent accounts.
+    /// Entries on all other accounts are ignored.
+    pub async fn enqueue_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        entries: &[EntryValues],
+    ) -> Result<(), BalanceError> {
+        let mut account_ids = Vec::with_capacity(entries.len());
+        let mut currencies = Vec::with_capacity(entries.len());
+        let mut entry_ids = Vec::with_capacity(entries.len());
+        let mut values = Vec::with_capacity(entries.len());
+        for entry in entries {
+            account_ids.push(entry.account_id);
+            currencies.push(entry.currency.code());
+            entry_ids.push(entry.id);
+            values.push(serde_json::to_value(entry).expect("Failed to serialize entry values"));
+        }
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_balance_queue (journal_id, account_id, currency, entry_id, values)
+            SELECT $1, v.account_id, v.currency, v.entry_id, v.values
+            FROM UNNEST($2::uuid[], $3::text[], $4::uuid[], $5::jsonb[])
+                WITH ORDINALITY AS v(account_id, currency, entry_id, values, idx)
+            JOIN cala_accounts a
+                ON a.id = v.account_id
+            WHERE a.eventually_consistent = TRUE
+            ORDER BY v.idx"#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str],
+            &entry_ids as &[EntryId],
+            &values
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    /// Only one aggregator folds the queue at a time so that entries are applied
+    /// in the order they were enqueued.
+    pub async fn try_lock_queue(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+    ) -> Result<bool, BalanceError> {
+        let row = sqlx::query!(
+            r#"SELECT pg_try_advisory_xact_lock(hashtext('cala_balance_queue')) AS "locked!""#
+        )
+        .fetch_one(op.as_executor())
+        .await?;
+        Ok(row.locked)
+    }
+
+    pub async fn dequeue(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        batch_size: usize,
+    ) -> Result<Vec<QueuedEntry>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            DELETE FROM cala_balance_queue
+            WHERE sequence IN (
+                SELECT sequence FROM cala_balance_queue
+                ORDER BY sequence
+                LIMIT $1
+            )
+            RETURNING sequence, journal_id AS "journal_id: JournalId", values"#,
+            batch_size as i64,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        let mut entries: Vec<_> = rows
+            .into_iter()
+            .map(|row| QueuedEntry {
+                sequence: row.sequence,
+                journal_id: row.journal_id,
+                entry: serde_json::from_value(row.values)
+                    .expect("Failed to deserialize entry values"),
+            })
+            .collect();
+        entries.sort_by_key(|e| e.sequence);
+        Ok(entries)
+    }
+
+    /// Takes the same per balance lock as `BalanceRepo::find_for_update` (in key order
+    /// to avoid deadlocks) and returns the current snapshots.
+    pub async fn find_for_update(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        keys: &BTreeSet<(AccountId, Currency)>,
+    ) -> Result<HashMap<(AccountId, Currency), Option<BalanceSnapshot>>, BalanceError> {
+        let (account_ids, currencies): (Vec<_>, Vec<_>) =
+            keys.iter().map(|(a, c)| (*a, c.code())).unzip();
+        sqlx::query!(
+            r#"
+            SELECT pg_advisory_xact_lock(hashtext(concat($1::text, account_id::text, currency)))
+            FROM UNNEST($2::uuid[], $3::text[]) WITH ORDINALITY AS v(account_id, currency, idx)
+            ORDER BY idx"#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str],
+        )
+        .execute(op.as_executor())
+        .await?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                v.account_id AS "account_id!: AccountId",
+                v.currency AS "currency!",
+                b.latest_values
+            FROM UNNEST($2::uuid[], $3::text[]) AS v(account_id, currency)
+            LEFT JOIN cala_current_balances b
+                ON b.journal_id = $1
+                AND b.account_id = v.account_id
+                AND b.currency = v.currency"#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str]
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    (
+                        row.account_id,
+                        row.currency.parse().expect("Could not parse currency"),
+                    ),
+                    row.latest_values.map(|v| {
+                        serde_json::from_value(v).expect("Failed to deserialize balance snapshot")
+                    }),
+                )
+            })
+            .collect())
+    }
+
+    pub async fn record_progress(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        folded: &[FoldedBalance],
+    ) -> Result<(), BalanceError> {
+        let mut account_ids = Vec::with_capacity(folded.len());
+        let mut currencies = Vec::with_capacity(folded.len());
+        let mut sequences = Vec::with_capacity(folded.len());
+        let mut entry_ids = Vec::with_capacity(folded.len());
+        for balance in folded {
+            account_ids.push(balance.account_id);
+            currencies.push(balance.currency.code());
+            sequences.push(balance.last_sequence);
+            entry_ids.push(balance.last_entry_id);
+        }
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_balance_queue_progress AS p (
+                journal_id, account_id, currency, last_sequence, last_entry_id
+            )
+            SELECT $1, * FROM UNNEST($2::uuid[], $3::text[], $4::bigint[], $5::uuid[])
+            ON CONFLICT (journal_id, account_id, currency)
+            DO UPDATE SET
+                last_sequence = EXCLUDED.last_sequence,
+                last_entry_id = EXCLUDED.last_entry_id,
+                updated_at = NOW()"#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str],
+            &sequences,
+            &entry_ids as &[EntryId],
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_lag(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<BalanceLagRow, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                p.last_sequence AS "last_sequence?",
+                p.last_entry_id AS "last_entry_id?: EntryId",
+                p.updated_at AS "last_folded_at?",
+                q.pending_entries AS "pending_entries!",
+                q.oldest_pending_at
+            FROM (
+                SELECT COUNT(*) AS pending_entries, MIN(created_at) AS oldest_pending_at
+                FROM cala_balance_queue
+                WHERE journal_id = $1 AND account_id = $2 AND currency = $3
+            ) q
+            LEFT JOIN cala_balance_queue_progress p
+                ON p.journal_id = $1
+                AND p.account_id = $2
+                AND p.currency = $3"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(BalanceLagRow {
+            last_sequence: row.last_sequence,
+            last_entry_id: row.last_entry_id,
+            last_folded_at: row.last_folded_at,
+            pending_entries: row.pending_entries,
+            oldest_pending_at: row.oldest_pending_at,
+        })
+    }
+}
@@ -1,13 +1,62 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::{stream::BoxStream, StreamExt};
 use sqlx::PgPool;
 use tracing::instrument;
 
-use super::{account_balance::AccountBalance, error::BalanceError};
+use super::{
+    account_balance::{AccountBalance, BalanceVersion},
+    entry_type_breakdown::EntryTypeTotal,
+    error::BalanceError,
+    trial_balance::TrialBalanceLine,
+};
 use cala_types::{
     balance::BalanceSnapshot,
-    primitives::{AccountId, BalanceId, Currency, DebitOrCredit, EntryId, JournalId},
+    entry::EntryValues,
+    primitives::{AccountId, BalanceId, Currency, DebitOrCredit, EntryId, JournalId, Layer},
 };
 use std::collections::HashMap;
 
+pub mod balance_history_cursor {
+    use serde::{Deserialize, Serialize};
+
+    use crate::balance::account_balance::BalanceVersion;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct BalanceHistoryByVersionCursor {
+        pub version: u32,
+    }
+
+    impl From<&BalanceVersion> for BalanceHistoryByVersionCursor {
+        fn from(version: &BalanceVersion) -> Self {
+            Self {
+                version: version.balance.details.version,
+            }
+        }
+    }
+
+    #[cfg(feature = "graphql")]
+    impl async_graphql::connection::CursorType for BalanceHistoryByVersionCursor {
+        type Error = String;
+
+        fn encode_cursor(&self) -> String {
+            use base64::{engine::general_purpose, Engine as _};
+            let json = serde_json::to_string(&self).expect("could not serialize token");
+            general_purpose::STANDARD_NO_PAD.encode(json.as_bytes())
+        }
+
+        fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+            use base64::{engine::general_purpose, Engine as _};
+            let bytes = general_purpose::STANDARD_NO_PAD
+                .decode(s.as_bytes())
+                .map_err(|e| e.to_string())?;
+            let json = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+            serde_json::from_str(&json).map_err(|e| e.to_string())
+        }
+    }
+}
+
+use balance_history_cursor::*;
+
 #[derive(Debug, Clone)]
 pub(super) struct BalanceRepo {
     pool: PgPool,
@@ -120,6 +169,373 @@ impl BalanceRepo {
         Ok(ret)
     }
 
+    pub(super) async fn find_as_of(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        as_of: DateTime<Utc>,
+    ) -> Result<AccountBalance, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT h.values, a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+            FROM cala_balance_history h
+            JOIN cala_accounts a
+            ON h.account_id = a.id
+            WHERE h.journal_id = $1
+            AND h.account_id = $2
+            AND h.currency = $3
+            AND h.recorded_at <= $4
+            ORDER BY h.recorded_at DESC, h.version DESC
+            LIMIT 1
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+            as_of,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            let details: BalanceSnapshot =
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot");
+            Ok(AccountBalance::new(row.normal_balance_type, details))
+        } else {
+            Err(BalanceError::NotFound(journal_id, account_id, currency))
+        }
+    }
+
+    pub(super) async fn find_all_as_of(
+        &self,
+        ids: &[BalanceId],
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        let mut journal_ids = Vec::with_capacity(ids.len());
+        let mut account_ids = Vec::with_capacity(ids.len());
+        let mut currencies = Vec::with_capacity(ids.len());
+        for (journal_id, account_id, currency) in ids {
+            journal_ids.push(uuid::Uuid::from(journal_id));
+            account_ids.push(uuid::Uuid::from(account_id));
+            currencies.push(currency.code().to_string());
+        }
+
+        let rows = sqlx::query!(
+            r#"
+            WITH balance_ids AS (
+                SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::text[])
+                AS v(journal_id, account_id, currency)
+            )
+            SELECT
+                h.values,
+                a.normal_balance_type as "normal_balance_type!: DebitOrCredit"
+            FROM balance_ids b
+            JOIN cala_accounts a
+                ON b.account_id = a.id
+            JOIN LATERAL (
+                SELECT values
+                FROM cala_balance_history
+                WHERE journal_id = b.journal_id
+                  AND account_id = b.account_id
+                  AND currency = b.currency
+                  AND recorded_at <= $4
+                ORDER BY recorded_at DESC, version DESC
+                LIMIT 1
+            ) h ON TRUE"#,
+            &journal_ids[..],
+            &account_ids[..],
+            &currencies[..],
+            as_of,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut ret = HashMap::new();
+        for row in rows {
+            let details: BalanceSnapshot =
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot");
+            ret.insert(
+                (details.journal_id, details.account_id, details.currency),
+                AccountBalance::new(row.normal_balance_type, details),
+            );
+        }
+        Ok(ret)
+    }
+
+    /// Returns the balance of every currency the account had recorded at or before `as_of`.
+    pub(super) async fn find_all_currencies_as_of(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<AccountBalance>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT ON (h.currency)
+                h.values,
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+            FROM cala_balance_history h
+            JOIN cala_accounts a
+            ON h.account_id = a.id
+            WHERE h.journal_id = $1
+            AND h.account_id = $2
+            AND h.recorded_at <= $3
+            ORDER BY h.currency, h.recorded_at DESC, h.version DESC"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            as_of,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let details: BalanceSnapshot = serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot");
+                AccountBalance::new(row.normal_balance_type, details)
+            })
+            .collect())
+    }
+
+    /// Sums the entries posted to the account within the effective date range per entry
+    /// type, currency and layer. For account sets the entries of all (transitive) member
+    /// accounts are aggregated instead.
+    pub(super) async fn entry_type_totals(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        from: NaiveDate,
+        until: Option<NaiveDate>,
+        currency: Option<Currency>,
+    ) -> Result<Vec<EntryTypeTotal>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH accounts AS (
+                SELECT $2::uuid AS id
+                WHERE NOT EXISTS (SELECT 1 FROM cala_account_sets WHERE id = $2)
+                UNION
+                SELECT member_account_id
+                FROM cala_account_set_member_accounts
+                WHERE account_set_id = $2
+            ),
+            entries AS (
+                SELECT ev.event->'values' AS values
+                FROM cala_entries en
+                JOIN accounts a
+                    ON en.account_id = a.id
+                JOIN cala_transactions t
+                    ON en.transaction_id = t.id
+                JOIN cala_entry_events ev
+                    ON ev.id = en.id
+                    AND ev.sequence = 1
+                WHERE en.journal_id = $1
+                AND t.effective >= $3
+                AND ($4::date IS NULL OR t.effective <= $4)
+                AND ($5::text IS NULL OR ev.event->'values'->>'currency' = $5)
+            )
+            SELECT
+                values->>'entry_type' AS "entry_type!",
+                values->>'currency' AS "currency!",
+                values->'layer' AS "layer!",
+                COALESCE(SUM((values->>'units')::numeric) FILTER (WHERE values->>'direction' = 'debit'), 0) AS "dr_balance!",
+                COALESCE(SUM((values->>'units')::numeric) FILTER (WHERE values->>'direction' = 'credit'), 0) AS "cr_balance!",
+                COUNT(*) AS "entry_count!"
+            FROM entries
+            GROUP BY 1, 2, 3
+            ORDER BY 1, 2, 3"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            from,
+            until,
+            currency.map(|c| c.code()),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| EntryTypeTotal {
+                entry_type: row.entry_type,
+                currency: row.currency.parse().expect("Could not parse currency"),
+                layer: serde_json::from_value::<Layer>(row.layer).expect("Could not parse layer"),
+                dr_balance: row.dr_balance,
+                cr_balance: row.cr_balance,
+                entry_count: row.entry_count as u64,
+            })
+            .collect())
+    }
+
+    pub(super) fn stream_trial_balance_lines(
+        &self,
+        journal_id: JournalId,
+        currency: Option<Currency>,
+        as_of: Option<NaiveDate>,
+    ) -> BoxStream<'_, Result<TrialBalanceLine, BalanceError>> {
+        let currency = currency.map(|c| c.code().to_string());
+        match as_of {
+            None => sqlx::query!(
+                r#"
+                SELECT
+                    a.id AS "account_id: AccountId",
+                    a.code,
+                    a.name,
+                    a.normal_balance_type AS "normal_balance_type: DebitOrCredit",
+                    h.values
+                FROM cala_current_balances c
+                JOIN cala_balance_history h
+                    ON c.journal_id = h.journal_id
+                    AND c.account_id = h.account_id
+                    AND c.currency = h.currency
+                    AND c.latest_version = h.version
+                JOIN cala_accounts a
+                    ON c.account_id = a.id
+                WHERE c.journal_id = $1
+                AND ($2::text IS NULL OR c.currency = $2)
+                AND NOT EXISTS (SELECT 1 FROM cala_account_sets s WHERE s.id = a.id)
+                ORDER BY a.code, a.id, c.currency"#,
+                journal_id as JournalId,
+                currency,
+            )
+            .fetch(&self.pool)
+            .map(|row| {
+                let row = row?;
+                Ok(TrialBalanceLine {
+                    account_id: row.account_id,
+                    account_code: row.code,
+                    account_name: row.name,
+                    balance: AccountBalance::new(
+                        row.normal_balance_type,
+                        serde_json::from_value(row.values)
+                            .expect("Failed to deserialize balance snapshot"),
+                    ),
+                })
+            })
+            .boxed(),
+            Some(as_of) => sqlx::query!(
+                r#"
+                WITH latest AS (
+                    SELECT DISTINCT ON (b.account_id, b.currency)
+                        b.account_id, b.currency, b.values
+                    FROM cala_cumulative_effective_balances b
+                    WHERE b.journal_id = $1
+                    AND ($2::text IS NULL OR b.currency = $2)
+                    AND b.effective <= $3
+                    ORDER BY b.account_id, b.currency, b.effective DESC, b.version DESC
+                )
+                SELECT
+                    a.id AS "account_id: AccountId",
+                    a.code,
+                    a.name,
+                    a.normal_balance_type AS "normal_balance_type: DebitOrCredit",
+                    l.values
+                FROM latest l
+                JOIN cala_accounts a
+                    ON l.account_id = a.id
+                WHERE NOT EXISTS (SELECT 1 FROM cala_account_sets s WHERE s.id = a.id)
+                ORDER BY a.code, a.id, l.currency"#,
+                journal_id as JournalId,
+                currency,
+                as_of,
+            )
+            .fetch(&self.pool)
+            .map(|row| {
+                let row = row?;
+                Ok(TrialBalanceLine {
+                    account_id: row.account_id,
+                    account_code: row.code,
+                    account_name: row.name,
+                    balance: AccountBalance::new(
+                        row.normal_balance_type,
+                        serde_json::from_value(row.values)
+                            .expect("Failed to deserialize balance snapshot"),
+                    ),
+                })
+            })
+            .boxed(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn list_history(
+        &self,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+        args: es_entity::PaginatedQueryArgs<BalanceHistoryByVersionCursor>,
+        direction: es_entity::ListDirection,
+        recorded_from: Option<DateTime<Utc>>,
+        recorded_until: Option<DateTime<Utc>>,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<BalanceVersion, BalanceHistoryByVersionCursor>,
+        BalanceError,
+    > {
+        let es_entity::PaginatedQueryArgs { first, after } = args;
+        let after_version = after.map(|c| c.version as i32);
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                h.values,
+                h.recorded_at,
+                e.event->'values' AS "entry_values?: serde_json::Value",
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+            FROM cala_balance_history h
+            JOIN cala_accounts a
+            ON h.account_id = a.id
+            LEFT JOIN cala_entry_events e
+            ON e.id = h.latest_entry_id
+            AND e.sequence = 1
+            WHERE h.journal_id = $1
+            AND h.account_id = $2
+            AND h.currency = $3
+            AND ($5::int IS NULL OR ($6 AND h.version > $5) OR (NOT $6 AND h.version < $5))
+            AND ($7::timestamptz IS NULL OR h.recorded_at >= $7)
+            AND ($8::timestamptz IS NULL OR h.recorded_at < $8)
+            ORDER BY
+                CASE WHEN $6 THEN h.version END ASC,
+                CASE WHEN NOT $6 THEN h.version END DESC
+            LIMIT $4
+            "#,
+            journal_id as JournalId,
+            account_id as AccountId,
+            currency.code(),
+            (first + 1) as i64,
+            after_version,
+            matches!(direction, es_entity::ListDirection::Ascending),
+            recorded_from,
+            recorded_until,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_next_page = rows.len() > first;
+        let entities = rows
+            .into_iter()
+            .take(first)
+            .map(|row| {
+                let details: BalanceSnapshot = serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot");
+                let entry = row.entry_values.map(|v| {
+                    serde_json::from_value::<EntryValues>(v)
+                        .expect("Failed to deserialize entry values")
+                });
+                BalanceVersion {
+                    balance: AccountBalance::new(row.normal_balance_type, details),
+                    entry,
+                    recorded_at: row.recorded_at,
+                }
+            })
+            .collect::<Vec<_>>();
+        let end_cursor = entities.last().map(BalanceHistoryByVersionCursor::from);
+
+        Ok(es_entity::PaginatedQueryRet {
+            entities,
+            has_next_page,
+            end_cursor,
+        })
+    }
+
     #[instrument(
         level = "trace",
         name = "cala_ledger.balances.find_for_update",
@@ -140,6 +556,7 @@ impl BalanceRepo {
             JOIN cala_accounts a
             ON account_id = a.id
             WHERE eventually_consistent = FALSE
+            AND balance_shards IS NULL
             "#,
             journal_id as JournalId,
             &account_ids as &[AccountId],
@@ -154,7 +571,10 @@ impl BalanceRepo {
                 v.currency AS "currency!",
                 b.latest_values
             FROM UNNEST($2::uuid[], $3::text[]) AS v(account_id, currency)
-            JOIN cala_accounts a ON a.id = v.account_id AND a.eventually_consistent = FALSE
+            JOIN cala_accounts a
+                ON a.id = v.account_id
+                AND a.eventually_consistent = FALSE
+                AND a.balance_shards IS NULL
             LEFT JOIN cala_current_balances b
                 ON b.journal_id = $1
                 AND b.account_id = v.account_id
@@ -0,0 +1,228 @@
+mod repo;
+
+use chrono::{DateTime, Days, Utc};
+use sqlx::PgPool;
+
+use cala_types::primitives::JournalId;
+
+use super::error::BalanceError;
+use repo::*;
+
+/// How balance history older than the retention period is compacted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "BalanceHistoryRetentionMode", rename_all = "snake_case")]
+pub enum BalanceHistoryRetentionMode {
+    /// Only the last version of every (UTC) day is kept, intraday versions are deleted.
+    EndOfDay,
+    /// All versions are moved into the archive tables.
+    Archive,
+}
+
+/// Retention policy for the balance history of a journal.
+#[derive(Debug, Clone)]
+pub struct BalanceHistoryRetention {
+    pub journal_id: JournalId,
+    pub retain_days: u32,
+    pub mode: BalanceHistoryRetentionMode,
+    /// History recorded before this point in time is no longer complete.
+    pub pruned_before: Option<DateTime<Utc>>,
+    pub last_compacted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub modified_at: DateTime<Utc>,
+}
+
+impl BalanceHistoryRetention {
+    /// Start of the (UTC) day from which on the full history is kept.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        (now.date_naive() - Days::new(self.retain_days as u64))
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc()
+    }
+
+    fn ensure_retained(&self, as_of: DateTime<Utc>) -> Result<(), BalanceError> {
+        match self.pruned_before {
+            Some(pruned_before) if as_of < pruned_before => {
+                Err(BalanceError::HistoryPruned(self.journal_id, pruned_before))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Outcome of compacting the balance history of a journal.
+#[derive(Debug, Clone)]
+pub struct BalanceHistoryCompaction {
+    pub journal_id: JournalId,
+    pub mode: BalanceHistoryRetentionMode,
+    pub pruned_before: DateTime<Utc>,
+    pub balance_versions_removed: u64,
+    pub velocity_balance_versions_removed: u64,
+}
+
+#[derive(Clone)]
+pub(super) struct BalanceHistoryRetentions {
+    repo: BalanceHistoryRetentionRepo,
+}
+
+impl BalanceHistoryRetentions {
+    pub fn new(pool: &PgPool) -> Self {
+        Self {
+            repo: BalanceHistoryRetentionRepo::new(pool),
+        }
+    }
+
+    pub async fn set(
+        &self,
+        journal_id: JournalId,
+        retain_days: u32,
+        mode: BalanceHistoryRetentionMode,
+    ) -> Result<BalanceHistoryRetention, BalanceError> {
+        self.repo.upsert(journal_id, retain_days, mode).await
+    }
+
+    pub async fn find(
+        &self,
+        journal_id: JournalId,
+    ) -> Result<Option<BalanceHistoryRetention>, BalanceError> {
+        Ok(self.repo.find_all(&[journal_id]).await?.pop())
+    }
+
+    /// Fails if the history of any of the journals has been pruned before `as_of`.
+    pub async fn ensure_retained(
+        &self,
+        journal_ids: &[JournalId],
+        as_of: DateTime<Utc>,
+    ) -> Result<(), BalanceError> {
+        for retention in self.repo.find_all(journal_ids).await? {
+            retention.ensure_retained(as_of)?;
+        }
+        Ok(())
+    }
+
+    /// Fails if a history listing starting at `recorded_from` would have to include versions
+    /// that were thinned or archived by compaction.
+    pub async fn ensure_listable(
+        &self,
+        journal_id: JournalId,
+        recorded_from: Option<DateTime<Utc>>,
+    ) -> Result<(), BalanceError> {
+        match self.find(journal_id).await? {
+            Some(retention) => {
+                retention.ensure_retained(recorded_from.unwrap_or(DateTime::<Utc>::MIN_UTC))
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub async fn compact_all(
+        &self,
+        batch_size: usize,
+    ) -> Result<Vec<BalanceHistoryCompaction>, BalanceError> {
+        let mut ret = Vec::new();
+        for retention in self.repo.list_all().await? {
+            ret.push(self.compact(retention, batch_size).await?);
+        }
+        Ok(ret)
+    }
+
+    pub async fn compact_journal(
+        &self,
+        journal_id: JournalId,
+        batch_size: usize,
+    ) -> Result<Option<BalanceHistoryCompaction>, BalanceError> {
+        match self.find(journal_id).await? {
+            Some(retention) => Ok(Some(self.compact(retention, batch_size).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn compact(
+        &self,
+        retention: BalanceHistoryRetention,
+        batch_size: usize,
+    ) -> Result<BalanceHistoryCompaction, BalanceError> {
+        let journal_id = retention.journal_id;
+        let mode = retention.mode;
+        // The watermark is moved before anything is removed so that queries fail
+        // explicitly instead of reading partially compacted history.
+        let pruned_before = self
+            .repo
+            .advance_watermark(journal_id, retention.cutoff(Utc::now()))
+            .await?;
+
+        let mut balance_versions_removed = 0;
+        loop {
+            let n = self
+                .repo
+                .compact_balance_history(journal_id, pruned_before, mode, batch_size)
+                .await?;
+            balance_versions_removed += n;
+            if n < batch_size as u64 {
+                break;
+            }
+        }
+        let mut velocity_balance_versions_removed = 0;
+        loop {
+            let n = self
+                .repo
+                .compact_velocity_balance_history(journal_id, pruned_before, mode, batch_size)
+                .await?;
+            velocity_balance_versions_removed += n;
+            if n < batch_size as u64 {
+                break;
+            }
+        }
+        self.repo.record_compaction(journal_id).await?;
+
+        Ok(BalanceHistoryCompaction {
+            journal_id,
+            mode,
+            pruned_before,
+            balance_versions_removed,
+            velocity_balance_versions_removed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn retention(pruned_before: Option<DateTime<Utc>>) -> BalanceHistoryRetention {
+        BalanceHistoryRetention {
+            journal_id: JournalId::new(),
+            retain_days: 30,
+            mode: BalanceHistoryRetentionMode::EndOfDay,
+            pruned_before,
+            last_compacted_at: None,
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn cutoff_is_start_of_day() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 31, 17, 45, 12).unwrap();
+        assert_eq!(
+            retention(None).cutoff(now),
+            Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn queries_before_watermark_fail() {
+        let pruned_before = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let pruned = retention(Some(pruned_before));
+        assert!(pruned.ensure_retained(pruned_before).is_ok());
+        assert!(matches!(
+            pruned.ensure_retained(pruned_before - chrono::Duration::seconds(1)),
+            Err(BalanceError::HistoryPruned(_, at)) if at == pruned_before
+        ));
+        assert!(retention(None)
+            .ensure_retained(DateTime::<Utc>::MIN_UTC)
+            .is_ok());
+    }
+}
@@ -0,0 +1,278 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use cala_types::primitives::JournalId;
+
+use super::{BalanceHistoryRetention, BalanceHistoryRetentionMode};
+use crate::balance::error::BalanceError;
+
+#[derive(Debug, Clone)]
+pub(super) struct BalanceHistoryRetentionRepo {
+    pool: PgPool,
+}
+
+impl BalanceHistoryRetentionRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    pub async fn upsert(
+        &self,
+        journal_id: JournalId,
+        retain_days: u32,
+        mode: BalanceHistoryRetentionMode,
+    ) -> Result<BalanceHistoryRetention, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO cala_balance_history_retention_policies (journal_id, retain_days, mode)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (journal_id) DO UPDATE SET
+                retain_days = EXCLUDED.retain_days,
+                mode = EXCLUDED.mode,
+                modified_at = NOW()
+            RETURNING
+                journal_id AS "journal_id: JournalId",
+                retain_days,
+                mode AS "mode: BalanceHistoryRetentionMode",
+                pruned_before,
+                last_compacted_at,
+                created_at,
+                modified_at"#,
+            journal_id as JournalId,
+            retain_days as i32,
+            mode as BalanceHistoryRetentionMode,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(BalanceHistoryRetention {
+            journal_id: row.journal_id,
+            retain_days: row.retain_days as u32,
+            mode: row.mode,
+            pruned_before: row.pruned_before,
+            last_compacted_at: row.last_compacted_at,
+            created_at: row.created_at,
+            modified_at: row.modified_at,
+        })
+    }
+
+    pub async fn find_all(
+        &self,
+        journal_ids: &[JournalId],
+    ) -> Result<Vec<BalanceHistoryRetention>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                journal_id AS "journal_id: JournalId",
+                retain_days,
+                mode AS "mode: BalanceHistoryRetentionMode",
+                pruned_before,
+                last_compacted_at,
+                created_at,
+                modified_at
+            FROM cala_balance_history_retention_policies
+            WHERE journal_id = ANY($1)"#,
+            journal_ids as &[JournalId],
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| BalanceHistoryRetention {
+                journal_id: row.journal_id,
+                retain_days: row.retain_days as u32,
+                mode: row.mode,
+                pruned_before: row.pruned_before,
+                last_compacted_at: row.last_compacted_at,
+                created_at: row.created_at,
+                modified_at: row.modified_at,
+            })
+            .collect())
+    }
+
+    pub async fn list_all(&self) -> Result<Vec<BalanceHistoryRetention>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                journal_id AS "journal_id: JournalId",
+                retain_days,
+                mode AS "mode: BalanceHistoryRetentionMode",
+                pruned_before,
+                last_compacted_at,
+                created_at,
+                modified_at
+            FROM cala_balance_history_retention_policies
+            ORDER BY journal_id"#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| BalanceHistoryRetention {
+                journal_id: row.journal_id,
+                retain_days: row.retain_days as u32,
+                mode: row.mode,
+                pruned_before: row.pruned_before,
+                last_compacted_at: row.last_compacted_at,
+                created_at: row.created_at,
+                modified_at: row.modified_at,
+            })
+            .collect())
+    }
+
+    /// Moves the watermark forward (never backwards) and returns its new position.
+    pub async fn advance_watermark(
+        &self,
+        journal_id: JournalId,
+        cutoff: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE cala_balance_history_retention_policies
+            SET pruned_before = GREATEST(pruned_before, $2)
+            WHERE journal_id = $1
+            RETURNING pruned_before AS "pruned_before!""#,
+            journal_id as JournalId,
+            cutoff,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.pruned_before)
+    }
+
+    pub async fn record_compaction(&self, journal_id: JournalId) -> Result<(), BalanceError> {
+        sqlx::query!(
+            r#"
+            UPDATE cala_balance_history_retention_policies
+            SET last_compacted_at = NOW()
+            WHERE journal_id = $1"#,
+            journal_id as JournalId,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes up to `batch_size` versions recorded before `pruned_before`. The last version
+    /// before the watermark is always kept so that later points in time remain answerable,
+    /// in end of day mode so is the last version of every day. Account sets are skipped
+    /// as their entries are looked up through the history.
+    pub async fn compact_balance_history(
+        &self,
+        journal_id: JournalId,
+        pruned_before: DateTime<Utc>,
+        mode: BalanceHistoryRetentionMode,
+        batch_size: usize,
+    ) -> Result<u64, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            WITH candidates AS (
+                SELECT h.journal_id, h.account_id, h.currency, h.version
+                FROM cala_balance_history h
+                WHERE h.journal_id = $1
+                AND h.recorded_at < $2
+                AND NOT EXISTS (SELECT 1 FROM cala_account_sets s WHERE s.id = h.account_id)
+                AND EXISTS (
+                    SELECT 1 FROM cala_balance_history n
+                    WHERE n.account_id = h.account_id
+                    AND n.journal_id = h.journal_id
+                    AND n.currency = h.currency
+                    AND n.version > h.version
+                    AND n.recorded_at < $2
+                    AND (
+                        $3 = 'archive'::BalanceHistoryRetentionMode
+                        OR (n.recorded_at AT TIME ZONE 'UTC')::date = (h.recorded_at AT TIME ZONE 'UTC')::date
+                    )
+                )
+                LIMIT $4
+            ),
+            removed AS (
+                DELETE FROM cala_balance_history h
+                USING candidates c
+                WHERE h.journal_id = c.journal_id
+                AND h.account_id = c.account_id
+                AND h.currency = c.currency
+                AND h.version = c.version
+                RETURNING h.*
+            ),
+            archived AS (
+                INSERT INTO cala_balance_history_archive
+                    (journal_id, account_id, latest_entry_id, currency, version, values, recorded_at)
+                SELECT journal_id, account_id, latest_entry_id, currency, version, values, recorded_at
+                FROM removed
+                WHERE $3 = 'archive'::BalanceHistoryRetentionMode
+            )
+            SELECT COUNT(*) AS "removed!" FROM removed"#,
+            journal_id as JournalId,
+            pruned_before,
+            mode as BalanceHistoryRetentionMode,
+            batch_size as i64,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.removed as u64)
+    }
+
+    pub async fn compact_velocity_balance_history(
+        &self,
+        journal_id: JournalId,
+        pruned_before: DateTime<Utc>,
+        mode: BalanceHistoryRetentionMode,
+        batch_size: usize,
+    ) -> Result<u64, BalanceError> {
+        let row = sqlx::query!(
+            r#"
+            WITH candidates AS (
+                SELECT h.journal_id, h.account_id, h.currency, h.velocity_control_id,
+                    h.velocity_limit_id, h.partition_window, h.version
+                FROM cala_velocity_balance_history h
+                WHERE h.journal_id = $1
+                AND h.recorded_at < $2
+                AND EXISTS (
+                    SELECT 1 FROM cala_velocity_balance_history n
+                    WHERE n.account_id = h.account_id
+                    AND n.journal_id = h.journal_id
+                    AND n.currency = h.currency
+                    AND n.velocity_control_id = h.velocity_control_id
+                    AND n.velocity_limit_id = h.velocity_limit_id
+                    AND n.partition_window = h.partition_window
+                    AND n.version > h.version
+                    AND n.recorded_at < $2
+                    AND (
+                        $3 = 'archive'::BalanceHistoryRetentionMode
+                        OR (n.recorded_at AT TIME ZONE 'UTC')::date = (h.recorded_at AT TIME ZONE 'UTC')::date
+                    )
+                )
+                LIMIT $4
+            ),
+            removed AS (
+                DELETE FROM cala_velocity_balance_history h
+                USING candidates c
+                WHERE h.journal_id = c.journal_id
+                AND h.account_id = c.account_id
+                AND h.currency = c.currency
+                AND h.velocity_control_id = c.velocity_control_id
+                AND h.velocity_limit_id = c.velocity_limit_id
+                AND h.partition_window = c.partition_window
+                AND h.version = c.version
+                RETURNING h.*
+            ),
+            archived AS (
+                INSERT INTO cala_velocity_balance_history_archive
+                    (journal_id, account_id, currency, velocity_control_id, velocity_limit_id,
+                    partition_window, latest_entry_id, version, values, recorded_at)
+                SELECT journal_id, account_id, currency, velocity_control_id, velocity_limit_id,
+                    partition_window, latest_entry_id, version, values, recorded_at
+                FROM removed
+                WHERE $3 = 'archive'::BalanceHistoryRetentionMode
+            )
+            SELECT COUNT(*) AS "removed!" FROM removed"#,
+            journal_id as JournalId,
+            pruned_before,
+            mode as BalanceHistoryRetentionMode,
+            batch_size as i64,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.removed as u64)
+    }
+}
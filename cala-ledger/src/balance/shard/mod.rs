@@ -0,0 +1,252 @@
+mod repo;
+
+use chrono::{DateTime, Utc};
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap};
+
+use cala_types::{balance::BalanceSnapshot, entry::EntryValues, primitives::*};
+
+use crate::primitives::JournalId;
+
+use super::{
+    account_balance::AccountBalance, error::BalanceError, snapshot::Snapshots,
+    trial_balance::TrialBalanceLine,
+};
+use repo::*;
+
+/// Balances of accounts configured with `balance_shards` are split into independently
+/// locked shards. Each transaction updates one shard per account (picked from the
+/// transaction id) and reads merge all shards into a single balance.
+#[derive(Clone)]
+pub(super) struct BalanceShards {
+    repo: BalanceShardRepo,
+    pool: PgPool,
+}
+
+impl BalanceShards {
+    pub fn new(pool: &PgPool) -> Self {
+        Self {
+            repo: BalanceShardRepo::new(pool),
+            pool: pool.clone(),
+        }
+    }
+
+    pub async fn update_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        time: DateTime<Utc>,
+        entries: &[EntryValues],
+    ) -> Result<(), BalanceError> {
+        let account_ids: Vec<_> = entries.iter().map(|e| e.account_id).collect();
+        let shard_counts = self.repo.shard_counts(op, &account_ids).await?;
+        if shard_counts.is_empty() {
+            return Ok(());
+        }
+
+        let mut keys = BTreeSet::new();
+        let mut sharded_entries = Vec::new();
+        for entry in entries {
+            if let Some(n_shards) = shard_counts.get(&entry.account_id) {
+                let key = (
+                    entry.account_id,
+                    entry.currency,
+                    pick_shard(entry.transaction_id, *n_shards),
+                );
+                keys.insert(key);
+                sharded_entries.push((key, entry));
+            }
+        }
+
+        let mut current = self.repo.find_for_update(op, journal_id, &keys).await?;
+        for (key, entry) in sharded_entries {
+            let snapshot = match current.remove(&key) {
+                Some(snapshot) => Snapshots::update_snapshot(time, snapshot, entry),
+                None => Snapshots::new_snapshot(time, entry.account_id, entry),
+            };
+            current.insert(key, snapshot);
+        }
+        let updated: Vec<_> = keys
+            .into_iter()
+            .filter_map(|key| current.remove(&key).map(|snapshot| (key.2, snapshot)))
+            .collect();
+        self.repo.upsert(op, journal_id, &updated).await
+    }
+
+    /// Sharded balances only keep their current state, so any query that relies on
+    /// the balance history or the effective balances can't be answered for them.
+    pub async fn ensure_unsharded(&self, account_ids: &[AccountId]) -> Result<(), BalanceError> {
+        match self.repo.find_sharded(account_ids).await?.first() {
+            Some(account_id) => Err(BalanceError::BalanceSharded(*account_id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Locks and merges all shards of the account, returning one balance per currency.
+    pub async fn load_all_for_update(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+    ) -> Result<HashMap<Currency, BalanceSnapshot>, BalanceError> {
+        Ok(self
+            .repo
+            .load_all_for_update(op, journal_id, account_id)
+            .await?
+            .into_iter()
+            .map(|(currency, shards)| (currency, merge(shards)))
+            .collect())
+    }
+
+    pub async fn find_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        journal_id: JournalId,
+        account_id: AccountId,
+        currency: Currency,
+    ) -> Result<AccountBalance, BalanceError> {
+        let id = (journal_id, account_id, currency);
+        self.find_all_in_op(op, &[id])
+            .await?
+            .remove(&id)
+            .ok_or(BalanceError::NotFound(journal_id, account_id, currency))
+    }
+
+    pub async fn find_all(
+        &self,
+        ids: &[BalanceId],
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        self.find_all_in_op(&self.pool, ids).await
+    }
+
+    async fn find_all_in_op(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        ids: &[BalanceId],
+    ) -> Result<HashMap<BalanceId, AccountBalance>, BalanceError> {
+        let mut journal_ids = Vec::with_capacity(ids.len());
+        let mut account_ids = Vec::with_capacity(ids.len());
+        let mut currencies = Vec::with_capacity(ids.len());
+        for (journal_id, account_id, currency) in ids {
+            journal_ids.push(*journal_id);
+            account_ids.push(*account_id);
+            currencies.push(currency.code());
+        }
+        Ok(self
+            .repo
+            .find_all(op, &journal_ids, &account_ids, &currencies)
+            .await?
+            .into_iter()
+            .map(|(id, (normal_balance_type, shards))| {
+                (id, AccountBalance::new(normal_balance_type, merge(shards)))
+            })
+            .collect())
+    }
+
+    pub fn stream_trial_balance_lines(
+        &self,
+        journal_id: JournalId,
+        currency: Option<Currency>,
+    ) -> BoxStream<'_, Result<TrialBalanceLine, BalanceError>> {
+        self.repo
+            .stream_for_trial_balance(journal_id, currency)
+            .map_ok(|row| TrialBalanceLine {
+                account_id: row.account_id,
+                account_code: row.code,
+                account_name: row.name,
+                balance: AccountBalance::new(row.normal_balance_type, merge(row.shards)),
+            })
+            .boxed()
+    }
+}
+
+fn pick_shard(transaction_id: TransactionId, n_shards: u16) -> i32 {
+    (uuid::Uuid::from(transaction_id).as_u128() % u128::from(n_shards)) as i32
+}
+
+/// Sums the shards of a balance. The version of the merged balance is the total
+/// number of updates across all shards.
+pub(super) fn merge(shards: Vec<BalanceSnapshot>) -> BalanceSnapshot {
+    let mut shards = shards.into_iter();
+    let mut merged = shards.next().expect("at least one shard");
+    for shard in shards {
+        merged.version += shard.version;
+        merged.created_at = merged.created_at.min(shard.created_at);
+        if shard.modified_at > merged.modified_at {
+            merged.modified_at = shard.modified_at;
+            merged.entry_id = shard.entry_id;
+        }
+        for (merged, shard) in [
+            (&mut merged.settled, shard.settled),
+            (&mut merged.pending, shard.pending),
+            (&mut merged.encumbrance, shard.encumbrance),
+        ] {
+            merged.dr_balance += shard.dr_balance;
+            merged.cr_balance += shard.cr_balance;
+            if shard.modified_at > merged.modified_at {
+                merged.modified_at = shard.modified_at;
+                merged.entry_id = shard.entry_id;
+            }
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use cala_types::balance::BalanceAmount;
+
+    use super::*;
+
+    fn shard(settled_dr: Decimal, version: u32, modified_at: DateTime<Utc>) -> BalanceSnapshot {
+        let entry_id = EntryId::new();
+        let amount = |dr_balance| BalanceAmount {
+            dr_balance,
+            cr_balance: Decimal::ZERO,
+            entry_id,
+            modified_at,
+        };
+        BalanceSnapshot {
+            journal_id: JournalId::new(),
+            account_id: AccountId::new(),
+            currency: Currency::USD,
+            version,
+            created_at: modified_at,
+            modified_at,
+            entry_id,
+            settled: amount(settled_dr),
+            pending: amount(Decimal::ZERO),
+            encumbrance: amount(Decimal::ZERO),
+        }
+    }
+
+    #[test]
+    fn merge_sums_shards() {
+        let now = Utc::now();
+        let older = shard(dec!(10), 2, now - Duration::seconds(1));
+        let newer = shard(dec!(5), 3, now);
+        let latest_entry = newer.entry_id;
+
+        let merged = merge(vec![older.clone(), newer]);
+
+        assert_eq!(merged.settled.dr_balance, dec!(15));
+        assert_eq!(merged.version, 5);
+        assert_eq!(merged.created_at, older.created_at);
+        assert_eq!(merged.modified_at, now);
+        assert_eq!(merged.entry_id, latest_entry);
+        assert_eq!(merged.settled.entry_id, latest_entry);
+    }
+
+    #[test]
+    fn pick_shard_stays_in_range() {
+        for _ in 0..100 {
+            let shard = pick_shard(TransactionId::new(), 4);
+            assert!((0..4).contains(&shard));
+        }
+    }
+}
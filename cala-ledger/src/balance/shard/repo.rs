@@ -0,0 +1,272 @@
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap};
+
+use cala_types::{
+    balance::BalanceSnapshot,
+    primitives::{AccountId, Currency, DebitOrCredit, JournalId},
+};
+
+use crate::balance::error::BalanceError;
+
+pub(super) type ShardKey = (AccountId, Currency, i32);
+
+pub(super) struct ShardedTrialBalanceRow {
+    pub account_id: AccountId,
+    pub code: String,
+    pub name: String,
+    pub normal_balance_type: DebitOrCredit,
+    pub shards: Vec<BalanceSnapshot>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct BalanceShardRepo {
+    pool: PgPool,
+}
+
+impl BalanceShardRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    pub async fn shard_counts(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        account_ids: &[AccountId],
+    ) -> Result<HashMap<AccountId, u16>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id AS "id: AccountId", balance_shards AS "balance_shards!"
+            FROM cala_accounts
+            WHERE id = ANY($1) AND balance_shards IS NOT NULL"#,
+            account_ids as &[AccountId],
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.id, row.balance_shards as u16))
+            .collect())
+    }
+
+    pub async fn find_sharded(
+        &self,
+        account_ids: &[AccountId],
+    ) -> Result<Vec<AccountId>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id AS "id: AccountId"
+            FROM cala_accounts
+            WHERE id = ANY($1) AND balance_shards IS NOT NULL"#,
+            account_ids as &[AccountId],
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// Locks the given shards (in order) and returns their current snapshots.
+    pub async fn find_for_update(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        keys: &BTreeSet<ShardKey>,
+    ) -> Result<HashMap<ShardKey, BalanceSnapshot>, BalanceError> {
+        let mut account_ids = Vec::with_capacity(keys.len());
+        let mut currencies = Vec::with_capacity(keys.len());
+        let mut shards = Vec::with_capacity(keys.len());
+        for (account_id, currency, shard) in keys {
+            account_ids.push(*account_id);
+            currencies.push(currency.code());
+            shards.push(*shard);
+        }
+        sqlx::query!(
+            r#"
+            SELECT pg_advisory_xact_lock(hashtext(concat($1::text, account_id::text, currency, shard::text)))
+            FROM UNNEST($2::uuid[], $3::text[], $4::int[]) WITH ORDINALITY AS v(account_id, currency, shard, idx)
+            ORDER BY idx"#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str],
+            &shards,
+        )
+        .execute(op.as_executor())
+        .await?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT s.shard, s.values
+            FROM UNNEST($2::uuid[], $3::text[], $4::int[]) AS v(account_id, currency, shard)
+            JOIN cala_balance_shards s
+                ON s.journal_id = $1
+                AND s.account_id = v.account_id
+                AND s.currency = v.currency
+                AND s.shard = v.shard"#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str],
+            &shards,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let snapshot: BalanceSnapshot = serde_json::from_value(row.values)
+                    .expect("Failed to deserialize balance snapshot");
+                (
+                    (snapshot.account_id, snapshot.currency, row.shard),
+                    snapshot,
+                )
+            })
+            .collect())
+    }
+
+    pub async fn upsert(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        shards: &[(i32, BalanceSnapshot)],
+    ) -> Result<(), BalanceError> {
+        let mut account_ids = Vec::with_capacity(shards.len());
+        let mut currencies = Vec::with_capacity(shards.len());
+        let mut shard_ids = Vec::with_capacity(shards.len());
+        let mut versions = Vec::with_capacity(shards.len());
+        let mut values = Vec::with_capacity(shards.len());
+        for (shard, snapshot) in shards {
+            account_ids.push(snapshot.account_id);
+            currencies.push(snapshot.currency.code());
+            shard_ids.push(*shard);
+            versions.push(snapshot.version as i32);
+            values.push(
+                serde_json::to_value(snapshot).expect("Failed to serialize balance snapshot"),
+            );
+        }
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_balance_shards (journal_id, account_id, currency, shard, version, values)
+            SELECT $1, * FROM UNNEST($2::uuid[], $3::text[], $4::int[], $5::int[], $6::jsonb[])
+            ON CONFLICT (journal_id, account_id, currency, shard)
+            DO UPDATE SET
+                version = EXCLUDED.version,
+                values = EXCLUDED.values,
+                modified_at = NOW()"#,
+            journal_id as JournalId,
+            &account_ids as &[AccountId],
+            &currencies as &[&str],
+            &shard_ids,
+            &versions,
+            &values,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    /// Locks every shard of the account in the journal and returns them per currency.
+    pub async fn load_all_for_update(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        journal_id: JournalId,
+        account_id: AccountId,
+    ) -> Result<HashMap<Currency, Vec<BalanceSnapshot>>, BalanceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT values
+            FROM cala_balance_shards
+            WHERE journal_id = $1 AND account_id = $2
+            ORDER BY currency, shard
+            FOR UPDATE"#,
+            journal_id as JournalId,
+            account_id as AccountId,
+        )
+        .fetch_all(op.as_executor())
+        .await?;
+        let mut res: HashMap<_, Vec<BalanceSnapshot>> = HashMap::new();
+        for row in rows {
+            let snapshot: BalanceSnapshot =
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot");
+            res.entry(snapshot.currency).or_default().push(snapshot);
+        }
+        Ok(res)
+    }
+
+    pub async fn find_all(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        journal_ids: &[JournalId],
+        account_ids: &[AccountId],
+        currencies: &[&str],
+    ) -> Result<
+        HashMap<(JournalId, AccountId, Currency), (DebitOrCredit, Vec<BalanceSnapshot>)>,
+        BalanceError,
+    > {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+                r#"
+            SELECT
+                s.journal_id AS "journal_id: JournalId",
+                s.values,
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit"
+            FROM UNNEST($1::uuid[], $2::uuid[], $3::text[]) AS v(journal_id, account_id, currency)
+            JOIN cala_balance_shards s
+                ON s.journal_id = v.journal_id
+                AND s.account_id = v.account_id
+                AND s.currency = v.currency
+            JOIN cala_accounts a
+                ON a.id = s.account_id"#,
+                journal_ids as &[JournalId],
+                account_ids as &[AccountId],
+                currencies as &[&str],
+            ))
+            .await?;
+        let mut res: HashMap<_, (DebitOrCredit, Vec<BalanceSnapshot>)> = HashMap::new();
+        for row in rows {
+            let snapshot: BalanceSnapshot =
+                serde_json::from_value(row.values).expect("Failed to deserialize balance snapshot");
+            res.entry((row.journal_id, snapshot.account_id, snapshot.currency))
+                .or_insert_with(|| (row.normal_balance_type, Vec::new()))
+                .1
+                .push(snapshot);
+        }
+        Ok(res)
+    }
+
+    pub fn stream_for_trial_balance(
+        &self,
+        journal_id: JournalId,
+        currency: Option<Currency>,
+    ) -> BoxStream<'_, Result<ShardedTrialBalanceRow, BalanceError>> {
+        sqlx::query!(
+            r#"
+            SELECT
+                a.id AS "account_id: AccountId",
+                a.code,
+                a.name,
+                a.normal_balance_type AS "normal_balance_type!: DebitOrCredit",
+                jsonb_agg(s.values ORDER BY s.shard) AS "shards!"
+            FROM cala_balance_shards s
+            JOIN cala_accounts a
+                ON a.id = s.account_id
+            WHERE s.journal_id = $1
+            AND ($2::text IS NULL OR s.currency = $2)
+            GROUP BY a.id, s.currency
+            ORDER BY a.code, a.id, s.currency"#,
+            journal_id as JournalId,
+            currency.map(|c| c.code().to_string()),
+        )
+        .fetch(&self.pool)
+        .map(|row| {
+            let row = row?;
+            Ok(ShardedTrialBalanceRow {
+                account_id: row.account_id,
+                code: row.code,
+                name: row.name,
+                normal_balance_type: row.normal_balance_type,
+                shards: serde_json::from_value(row.shards)
+                    .expect("Failed to deserialize balance snapshot"),
+            })
+        })
+        .boxed()
+    }
+}
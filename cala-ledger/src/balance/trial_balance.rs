@@ -0,0 +1,209 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use std::collections::BTreeMap;
+
+use crate::primitives::*;
+
+use super::account_balance::AccountBalance;
+
+const LAYERS: [Layer; 3] = [Layer::Settled, Layer::Pending, Layer::Encumbrance];
+
+/// The balance of a single (non account set) account as it appears in a `TrialBalance`.
+#[derive(Debug, Clone)]
+pub struct TrialBalanceLine {
+    pub account_id: AccountId,
+    pub account_code: String,
+    pub account_name: String,
+    pub balance: AccountBalance,
+}
+
+/// Sum of all debits and credits for a currency in a given layer.
+#[derive(Debug, Clone)]
+pub struct TrialBalanceTotal {
+    pub currency: Currency,
+    pub layer: Layer,
+    pub dr_balance: Decimal,
+    pub cr_balance: Decimal,
+}
+
+impl TrialBalanceTotal {
+    pub fn difference(&self) -> Decimal {
+        self.dr_balance - self.cr_balance
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.dr_balance == self.cr_balance
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrialBalance {
+    pub journal_id: JournalId,
+    pub as_of: Option<NaiveDate>,
+    pub lines: Vec<TrialBalanceLine>,
+    pub totals: Vec<TrialBalanceTotal>,
+}
+
+impl TrialBalance {
+    pub(super) fn new(journal_id: JournalId, as_of: Option<NaiveDate>) -> Self {
+        Self {
+            journal_id,
+            as_of,
+            lines: Vec::new(),
+            totals: Vec::new(),
+        }
+    }
+
+    pub(super) fn push(&mut self, line: TrialBalanceLine) {
+        self.lines.push(line);
+    }
+
+    pub(super) fn calculate_totals(&mut self) {
+        let mut totals: BTreeMap<Currency, [(Decimal, Decimal); 3]> = BTreeMap::new();
+        for line in self.lines.iter() {
+            let details = &line.balance.details;
+            let sums = totals.entry(details.currency).or_default();
+            for (sum, amount) in
+                sums.iter_mut()
+                    .zip([&details.settled, &details.pending, &details.encumbrance])
+            {
+                sum.0 += amount.dr_balance;
+                sum.1 += amount.cr_balance;
+            }
+        }
+        self.totals = totals
+            .into_iter()
+            .flat_map(|(currency, sums)| {
+                LAYERS
+                    .into_iter()
+                    .zip(sums)
+                    .map(move |(layer, (dr_balance, cr_balance))| TrialBalanceTotal {
+                        currency,
+                        layer,
+                        dr_balance,
+                        cr_balance,
+                    })
+            })
+            .collect();
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.totals.iter().all(|t| t.is_balanced())
+    }
+
+    /// Totals (per currency and layer) where debits do not equal credits.
+    pub fn imbalances(&self) -> impl Iterator<Item = &TrialBalanceTotal> {
+        self.totals.iter().filter(|t| !t.is_balanced())
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "account_id,account_code,account_name,currency,normal_balance_type,\
+             settled_dr,settled_cr,pending_dr,pending_cr,encumbrance_dr,encumbrance_cr\n",
+        );
+        for line in self.lines.iter() {
+            let details = &line.balance.details;
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{}\n",
+                line.account_id,
+                escape_csv(&line.account_code),
+                escape_csv(&line.account_name),
+                details.currency,
+                line.balance.balance_type,
+                details.settled.dr_balance,
+                details.settled.cr_balance,
+                details.pending.dr_balance,
+                details.pending.cr_balance,
+                details.encumbrance.dr_balance,
+                details.encumbrance.cr_balance,
+            ));
+        }
+        csv
+    }
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    use cala_types::balance::{BalanceAmount, BalanceSnapshot};
+
+    use super::*;
+
+    fn line(code: &str, settled_dr: Decimal, settled_cr: Decimal) -> TrialBalanceLine {
+        let entry_id = EntryId::new();
+        let amount = |dr_balance, cr_balance| BalanceAmount {
+            dr_balance,
+            cr_balance,
+            entry_id,
+            modified_at: Utc::now(),
+        };
+        let account_id = AccountId::new();
+        TrialBalanceLine {
+            account_id,
+            account_code: code.to_string(),
+            account_name: format!("Account, {code}"),
+            balance: AccountBalance::new(
+                DebitOrCredit::Debit,
+                BalanceSnapshot {
+                    journal_id: JournalId::new(),
+                    account_id,
+                    currency: Currency::USD,
+                    version: 1,
+                    created_at: Utc::now(),
+                    modified_at: Utc::now(),
+                    entry_id,
+                    settled: amount(settled_dr, settled_cr),
+                    pending: amount(Decimal::ZERO, Decimal::ZERO),
+                    encumbrance: amount(Decimal::ZERO, Decimal::ZERO),
+                },
+            ),
+        }
+    }
+
+    #[test]
+    fn balanced_when_debits_equal_credits() {
+        let mut trial_balance = TrialBalance::new(JournalId::new(), None);
+        trial_balance.push(line("A", dec!(10), Decimal::ZERO));
+        trial_balance.push(line("B", Decimal::ZERO, dec!(10)));
+        trial_balance.calculate_totals();
+
+        assert_eq!(trial_balance.totals.len(), 3);
+        assert!(trial_balance.is_balanced());
+        assert_eq!(trial_balance.imbalances().count(), 0);
+    }
+
+    #[test]
+    fn reports_imbalance_per_layer() {
+        let mut trial_balance = TrialBalance::new(JournalId::new(), None);
+        trial_balance.push(line("A", dec!(10), Decimal::ZERO));
+        trial_balance.push(line("B", Decimal::ZERO, dec!(7)));
+        trial_balance.calculate_totals();
+
+        assert!(!trial_balance.is_balanced());
+        let imbalances: Vec<_> = trial_balance.imbalances().collect();
+        assert_eq!(imbalances.len(), 1);
+        assert_eq!(imbalances[0].layer, Layer::Settled);
+        assert_eq!(imbalances[0].difference(), dec!(3));
+    }
+
+    #[test]
+    fn csv_escapes_fields() {
+        let mut trial_balance = TrialBalance::new(JournalId::new(), None);
+        trial_balance.push(line("A", dec!(10), Decimal::ZERO));
+        let csv = trial_balance.to_csv();
+        let rows: Vec<_> = csv.lines().collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[1].contains(",A,\"Account, A\",USD,Debit,10,0,"));
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+use cala_types::primitives::*;
+
+use super::error::ChartOfAccountsError;
+
+/// Declarative description of journals, accounts, account sets and their memberships.
+/// Journals are identified by `id` or `code`, accounts by `id` or `code` and account sets
+/// by `id` or `external_id`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChartOfAccounts {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub journals: Vec<ChartJournal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<ChartAccount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartJournal {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<JournalId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub account_sets: Vec<ChartAccountSet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartAccount {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<AccountId>,
+    pub code: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub normal_balance_type: DebitOrCredit,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartAccountSet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<AccountSetId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub normal_balance_type: DebitOrCredit,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// Codes of the member accounts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<String>,
+    /// Keys (`external_id`, or `id` if there is none) of the member account sets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub account_sets: Vec<String>,
+}
+
+impl ChartAccountSet {
+    /// The key other account sets of the document use to reference this one.
+    pub fn key(&self) -> Option<String> {
+        self.external_id
+            .clone()
+            .or_else(|| self.id.map(|id| id.to_string()))
+    }
+}
+
+impl ChartOfAccounts {
+    /// Checks that every entry is identifiable and that member references of account sets
+    /// resolve to account sets of the same journal.
+    pub fn validate(&self) -> Result<(), ChartOfAccountsError> {
+        let mut journal_keys = HashSet::new();
+        let mut set_keys = HashSet::new();
+        for journal in self.journals.iter() {
+            let key = match (&journal.id, &journal.code) {
+                (_, Some(code)) => code.clone(),
+                (Some(id), None) => id.to_string(),
+                (None, None) => {
+                    return Err(ChartOfAccountsError::InvalidDocument(format!(
+                        "journal '{}' needs an id or a code",
+                        journal.name
+                    )))
+                }
+            };
+            if !journal_keys.insert(key.clone()) {
+                return Err(ChartOfAccountsError::InvalidDocument(format!(
+                    "journal '{key}' is listed more than once"
+                )));
+            }
+
+            let mut journal_set_keys = HashSet::new();
+            for set in journal.account_sets.iter() {
+                let key = set.key().ok_or_else(|| {
+                    ChartOfAccountsError::InvalidDocument(format!(
+                        "account set '{}' needs an id or an external_id",
+                        set.name
+                    ))
+                })?;
+                if !set_keys.insert(key.clone()) {
+                    return Err(ChartOfAccountsError::InvalidDocument(format!(
+                        "account set '{key}' is listed more than once"
+                    )));
+                }
+                journal_set_keys.insert(key);
+            }
+            for set in journal.account_sets.iter() {
+                if let Some(member) = set
+                    .account_sets
+                    .iter()
+                    .find(|member| !journal_set_keys.contains(*member))
+                {
+                    return Err(ChartOfAccountsError::InvalidDocument(format!(
+                        "account set '{member}' is not part of journal '{key}'"
+                    )));
+                }
+            }
+        }
+
+        let mut codes = HashSet::new();
+        for account in self.accounts.iter() {
+            if !codes.insert(&account.code) {
+                return Err(ChartOfAccountsError::InvalidDocument(format!(
+                    "account '{}' is listed more than once",
+                    account.code
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"{
+        "journals": [{
+            "code": "GL",
+            "name": "General Ledger",
+            "account_sets": [
+                { "external_id": "assets", "name": "Assets", "normal_balance_type": "debit", "account_sets": ["cash"] },
+                { "external_id": "cash", "name": "Cash", "normal_balance_type": "debit", "accounts": ["CASH-USD"] }
+            ]
+        }],
+        "accounts": [
+            { "code": "CASH-USD", "name": "Cash USD", "normal_balance_type": "debit" }
+        ]
+    }"#;
+
+    #[test]
+    fn parses_document() {
+        let chart: ChartOfAccounts = serde_json::from_str(DOCUMENT).unwrap();
+        assert!(chart.validate().is_ok());
+        assert_eq!(chart.journals[0].account_sets.len(), 2);
+        assert_eq!(chart.accounts[0].normal_balance_type, DebitOrCredit::Debit);
+        let json = serde_json::to_value(&chart).unwrap();
+        assert_eq!(
+            serde_json::from_value::<ChartOfAccounts>(json).unwrap(),
+            chart
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_member_sets() {
+        let mut chart: ChartOfAccounts = serde_json::from_str(DOCUMENT).unwrap();
+        chart.journals[0].account_sets[0]
+            .account_sets
+            .push("liabilities".to_string());
+        assert!(matches!(
+            chart.validate(),
+            Err(ChartOfAccountsError::InvalidDocument(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        let mut chart: ChartOfAccounts = serde_json::from_str(DOCUMENT).unwrap();
+        chart.accounts.push(chart.accounts[0].clone());
+        assert!(matches!(
+            chart.validate(),
+            Err(ChartOfAccountsError::InvalidDocument(_))
+        ));
+    }
+}
@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ChartOfAccountsError {
+    #[error("ChartOfAccountsError - Sqlx: {0}")]
+    Sqlx(#[from] sqlx::Error),
+    #[error("ChartOfAccountsError - InvalidDocument: {0}")]
+    InvalidDocument(String),
+    #[error("ChartOfAccountsError - AccountSetInOtherJournal: account set '{0}' belongs to a different journal")]
+    AccountSetInOtherJournal(String),
+    #[error("ChartOfAccountsError - JournalNotFound: journal '{0}' not found")]
+    JournalNotFound(cala_types::primitives::JournalId),
+    #[error("ChartOfAccountsError - AccountNotFound: code '{0}' not found")]
+    AccountNotFound(String),
+    #[error("ChartOfAccountsError - JournalError: {0}")]
+    JournalError(#[from] crate::journal::error::JournalError),
+    #[error("ChartOfAccountsError - AccountError: {0}")]
+    AccountError(#[from] crate::account::error::AccountError),
+    #[error("ChartOfAccountsError - AccountSetError: {0}")]
+    AccountSetError(#[from] crate::account_set::error::AccountSetError),
+}
@@ -0,0 +1,585 @@
+mod document;
+pub mod error;
+mod repo;
+
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use tracing::instrument;
+
+use crate::{
+    account::*,
+    account_set::*,
+    journal::*,
+    ledger_operation::*,
+    outbox::*,
+    primitives::{AccountId, AccountSetId, JournalId, TenantId},
+};
+
+pub use document::*;
+use error::*;
+use repo::*;
+
+/// Counts of what applying a [`ChartOfAccounts`] changed in the ledger.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChartOfAccountsApplied {
+    pub journals_created: usize,
+    pub journals_updated: usize,
+    pub accounts_created: usize,
+    pub accounts_updated: usize,
+    pub account_sets_created: usize,
+    pub account_sets_updated: usize,
+    pub members_added: usize,
+}
+
+impl ChartOfAccountsApplied {
+    pub fn is_unchanged(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Service for applying and exporting a declarative [`ChartOfAccounts`].
+///
+/// Applying a chart is idempotent: entries that already exist are updated in place and
+/// missing memberships are added. Nothing that is absent from the document is removed.
+/// Charts are scoped to a tenant, pass `None` for the default scope.
+#[derive(Clone)]
+pub struct Charts {
+    repo: ChartOfAccountsRepo,
+    journals: Journals,
+    accounts: Accounts,
+    account_sets: AccountSets,
+    outbox: Outbox,
+    pool: PgPool,
+}
+
+impl Charts {
+    pub(crate) fn new(
+        pool: &PgPool,
+        outbox: Outbox,
+        journals: &Journals,
+        accounts: &Accounts,
+        account_sets: &AccountSets,
+    ) -> Self {
+        Self {
+            repo: ChartOfAccountsRepo::new(pool),
+            journals: journals.clone(),
+            accounts: accounts.clone(),
+            account_sets: account_sets.clone(),
+            outbox,
+            pool: pool.clone(),
+        }
+    }
+
+    #[instrument(name = "cala_ledger.chart_of_accounts.apply", skip_all)]
+    pub async fn apply(
+        &self,
+        tenant_id: Option<TenantId>,
+        chart: &ChartOfAccounts,
+    ) -> Result<ChartOfAccountsApplied, ChartOfAccountsError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        let applied = self.apply_in_op(&mut op, tenant_id, chart).await?;
+        op.commit().await?;
+        Ok(applied)
+    }
+
+    pub async fn apply_in_op(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        chart: &ChartOfAccounts,
+    ) -> Result<ChartOfAccountsApplied, ChartOfAccountsError> {
+        chart.validate()?;
+        let mut applied = ChartOfAccountsApplied::default();
+
+        let journal_ids = self
+            .apply_journals(op, tenant_id, chart, &mut applied)
+            .await?;
+        let account_ids = self
+            .apply_accounts(op, tenant_id, chart, &mut applied)
+            .await?;
+
+        let mut set_ids = HashMap::new();
+        for (journal, journal_id) in chart.journals.iter().zip(journal_ids) {
+            self.apply_account_sets(
+                op,
+                tenant_id,
+                journal_id,
+                journal,
+                &mut set_ids,
+                &mut applied,
+            )
+            .await?;
+        }
+
+        self.apply_members(op, tenant_id, chart, &account_ids, &set_ids, &mut applied)
+            .await?;
+
+        Ok(applied)
+    }
+
+    /// Exports the given journals together with their account sets and the accounts that
+    /// are direct members of those sets. All journals must belong to the tenant.
+    #[instrument(name = "cala_ledger.chart_of_accounts.export", skip(self))]
+    pub async fn export(
+        &self,
+        tenant_id: Option<TenantId>,
+        journal_ids: &[JournalId],
+    ) -> Result<ChartOfAccounts, ChartOfAccountsError> {
+        let mut journals = self.journals.find_all::<Journal>(journal_ids).await?;
+        if let Some(journal) = journals
+            .values()
+            .find(|journal| journal.values().tenant_id != tenant_id)
+        {
+            return Err(crate::journal::error::JournalError::TenantMismatch(journal.id()).into());
+        }
+        let set_ids = self
+            .repo
+            .find_account_set_ids_for_journals(journal_ids)
+            .await?;
+        let sets = self.account_sets.find_all::<AccountSet>(&set_ids).await?;
+        let members = self.repo.find_direct_members(&self.pool, &set_ids).await?;
+        let member_account_ids: Vec<_> = members
+            .iter()
+            .filter(|(_, _, is_set)| !is_set)
+            .map(|(_, id, _)| *id)
+            .collect();
+        let accounts = self
+            .accounts
+            .find_all::<Account>(&member_account_ids)
+            .await?;
+
+        let mut set_keys = HashMap::new();
+        for set in sets.values() {
+            let values = set.values();
+            set_keys.insert(
+                AccountId::from(values.id),
+                values
+                    .external_id
+                    .clone()
+                    .unwrap_or_else(|| values.id.to_string()),
+            );
+        }
+        let mut member_accounts: HashMap<AccountSetId, Vec<String>> = HashMap::new();
+        let mut member_sets: HashMap<AccountSetId, Vec<String>> = HashMap::new();
+        for (set_id, member_id, is_set) in members {
+            if is_set {
+                if let Some(key) = set_keys.get(&member_id) {
+                    member_sets.entry(set_id).or_default().push(key.clone());
+                }
+            } else if let Some(account) = accounts.get(&member_id) {
+                member_accounts
+                    .entry(set_id)
+                    .or_default()
+                    .push(account.values().code.clone());
+            }
+        }
+
+        let mut chart = ChartOfAccounts::default();
+        for journal_id in journal_ids {
+            let journal = journals
+                .remove(journal_id)
+                .ok_or(ChartOfAccountsError::JournalNotFound(*journal_id))?;
+            let values = journal.values();
+            let mut account_sets: Vec<_> = set_ids
+                .iter()
+                .filter_map(|id| sets.get(id))
+                .filter(|set| set.values().journal_id == *journal_id)
+                .map(|set| {
+                    let values = set.values();
+                    let mut accounts = member_accounts.remove(&values.id).unwrap_or_default();
+                    accounts.sort();
+                    let mut account_sets = member_sets.remove(&values.id).unwrap_or_default();
+                    account_sets.sort();
+                    ChartAccountSet {
+                        id: Some(values.id),
+                        external_id: values.external_id.clone(),
+                        name: values.name.clone(),
+                        normal_balance_type: values.normal_balance_type,
+                        description: values.description.clone(),
+                        metadata: values.metadata.clone(),
+                        accounts,
+                        account_sets,
+                    }
+                })
+                .collect();
+            account_sets.sort_by_key(|set| set.key());
+            chart.journals.push(ChartJournal {
+                id: Some(values.id),
+                code: values.code.clone(),
+                name: values.name.clone(),
+                description: values.description.clone(),
+                account_sets,
+            });
+        }
+
+        chart.accounts = accounts
+            .values()
+            .map(|account| {
+                let values = account.values();
+                ChartAccount {
+                    id: Some(values.id),
+                    code: values.code.clone(),
+                    name: values.name.clone(),
+                    external_id: values.external_id.clone(),
+                    normal_balance_type: values.normal_balance_type,
+                    description: values.description.clone(),
+                    metadata: values.metadata.clone(),
+                }
+            })
+            .collect();
+        chart.accounts.sort_by(|a, b| a.code.cmp(&b.code));
+
+        Ok(chart)
+    }
+
+    async fn apply_journals(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        chart: &ChartOfAccounts,
+        applied: &mut ChartOfAccountsApplied,
+    ) -> Result<Vec<JournalId>, ChartOfAccountsError> {
+        let codes: Vec<_> = chart
+            .journals
+            .iter()
+            .filter_map(|journal| journal.code.clone())
+            .collect();
+        let ids_by_code = self
+            .repo
+            .find_journal_ids_by_code(&mut *op, tenant_id, &codes)
+            .await?;
+        let resolved: Vec<_> = chart
+            .journals
+            .iter()
+            .map(|journal| {
+                journal.id.or_else(|| {
+                    journal
+                        .code
+                        .as_ref()
+                        .and_then(|code| ids_by_code.get(code))
+                        .copied()
+                })
+            })
+            .collect();
+        let lookup: Vec<_> = resolved.iter().flatten().copied().collect();
+        self.journals
+            .check_tenant_in_op(op, tenant_id, &lookup)
+            .await?;
+        let mut existing = self.journals.find_all_in_op::<Journal>(op, &lookup).await?;
+
+        let mut journal_ids = Vec::with_capacity(chart.journals.len());
+        for (journal, id) in chart.journals.iter().zip(resolved) {
+            match id.and_then(|id| existing.remove(&id)) {
+                Some(mut current) => {
+                    let values = current.values();
+                    let mut builder = JournalUpdate::default();
+                    let mut changed = false;
+                    if values.name != journal.name {
+                        builder.name(journal.name.clone());
+                        changed = true;
+                    }
+                    if journal.description.is_some() && values.description != journal.description {
+                        builder.description(journal.description.clone().unwrap_or_default());
+                        changed = true;
+                    }
+                    if changed {
+                        current.update(builder);
+                        self.journals.persist_in_op(op, &mut current).await?;
+                        applied.journals_updated += 1;
+                    }
+                    journal_ids.push(current.id());
+                }
+                None => {
+                    let mut builder = NewJournal::builder();
+                    builder
+                        .id(id.unwrap_or_else(JournalId::new))
+                        .name(journal.name.clone());
+                    if let Some(tenant_id) = tenant_id {
+                        builder.tenant_id(tenant_id);
+                    }
+                    if let Some(code) = &journal.code {
+                        builder.code(code.clone());
+                    }
+                    if let Some(description) = &journal.description {
+                        builder.description(description.clone());
+                    }
+                    let new_journal = builder.build().expect("Couldn't build NewJournal");
+                    let created = self.journals.create_in_op(op, new_journal).await?;
+                    applied.journals_created += 1;
+                    journal_ids.push(created.id());
+                }
+            }
+        }
+        Ok(journal_ids)
+    }
+
+    async fn apply_accounts(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        chart: &ChartOfAccounts,
+        applied: &mut ChartOfAccountsApplied,
+    ) -> Result<HashMap<String, AccountId>, ChartOfAccountsError> {
+        let codes: Vec<_> = chart
+            .accounts
+            .iter()
+            .map(|account| account.code.clone())
+            .collect();
+        let ids_by_code = self
+            .repo
+            .find_account_ids_by_code(&mut *op, tenant_id, &codes)
+            .await?;
+        let resolved: Vec<_> = chart
+            .accounts
+            .iter()
+            .map(|account| {
+                account
+                    .id
+                    .or_else(|| ids_by_code.get(&account.code).copied())
+            })
+            .collect();
+        let lookup: Vec<_> = resolved.iter().flatten().copied().collect();
+        self.accounts
+            .check_tenant_in_op(op, tenant_id, &lookup)
+            .await?;
+        let mut existing = self.accounts.find_all_in_op::<Account>(op, &lookup).await?;
+
+        let mut account_ids = HashMap::new();
+        for (account, id) in chart.accounts.iter().zip(resolved) {
+            match id.and_then(|id| existing.remove(&id)) {
+                Some(mut current) => {
+                    let values = current.values();
+                    let mut builder = AccountUpdate::default();
+                    let mut changed = false;
+                    if values.code != account.code {
+                        builder.code(account.code.clone());
+                        changed = true;
+                    }
+                    if values.name != account.name {
+                        builder.name(account.name.clone());
+                        changed = true;
+                    }
+                    if values.normal_balance_type != account.normal_balance_type {
+                        builder.normal_balance_type(account.normal_balance_type);
+                        changed = true;
+                    }
+                    if account.external_id.is_some() && values.external_id != account.external_id {
+                        builder.external_id(account.external_id.clone().unwrap_or_default());
+                        changed = true;
+                    }
+                    if account.description.is_some() && values.description != account.description {
+                        builder.description(account.description.clone().unwrap_or_default());
+                        changed = true;
+                    }
+                    if account.metadata.is_some() && values.metadata != account.metadata {
+                        builder
+                            .metadata(account.metadata.clone())
+                            .expect("Failed to serialize metadata");
+                        changed = true;
+                    }
+                    if changed {
+                        current.update(builder);
+                        self.accounts.persist_in_op(op, &mut current).await?;
+                        applied.accounts_updated += 1;
+                    }
+                    account_ids.insert(account.code.clone(), current.id());
+                }
+                None => {
+                    let mut builder = NewAccount::builder();
+                    builder
+                        .id(id.unwrap_or_else(AccountId::new))
+                        .code(account.code.clone())
+                        .name(account.name.clone())
+                        .normal_balance_type(account.normal_balance_type);
+                    if let Some(tenant_id) = tenant_id {
+                        builder.tenant_id(tenant_id);
+                    }
+                    if let Some(external_id) = &account.external_id {
+                        builder.external_id(external_id.clone());
+                    }
+                    if let Some(description) = &account.description {
+                        builder.description(description.clone());
+                    }
+                    if let Some(metadata) = &account.metadata {
+                        builder
+                            .metadata(metadata.clone())
+                            .expect("Failed to serialize metadata");
+                    }
+                    let new_account = builder.build().expect("Couldn't build NewAccount");
+                    let created = self.accounts.create_in_op(op, new_account).await?;
+                    applied.accounts_created += 1;
+                    account_ids.insert(account.code.clone(), created.id());
+                }
+            }
+        }
+        Ok(account_ids)
+    }
+
+    async fn apply_account_sets(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        journal_id: JournalId,
+        journal: &ChartJournal,
+        set_ids: &mut HashMap<String, AccountSetId>,
+        applied: &mut ChartOfAccountsApplied,
+    ) -> Result<(), ChartOfAccountsError> {
+        let external_ids: Vec<_> = journal
+            .account_sets
+            .iter()
+            .filter_map(|set| set.external_id.clone())
+            .collect();
+        let ids_by_external_id = self
+            .repo
+            .find_account_set_ids_by_external_id(&mut *op, tenant_id, &external_ids)
+            .await?;
+        let resolved: Vec<_> = journal
+            .account_sets
+            .iter()
+            .map(|set| {
+                set.id.or_else(|| {
+                    set.external_id
+                        .as_ref()
+                        .and_then(|external_id| ids_by_external_id.get(external_id))
+                        .copied()
+                })
+            })
+            .collect();
+        let lookup: Vec<_> = resolved.iter().flatten().copied().collect();
+        let mut existing = self
+            .account_sets
+            .find_all_in_op::<AccountSet>(op, &lookup)
+            .await?;
+
+        for (set, id) in journal.account_sets.iter().zip(resolved) {
+            let key = set.key().expect("validated account set key");
+            match id.and_then(|id| existing.remove(&id)) {
+                Some(mut current) => {
+                    let values = current.values();
+                    if values.journal_id != journal_id {
+                        return Err(ChartOfAccountsError::AccountSetInOtherJournal(key));
+                    }
+                    let mut builder = AccountSetUpdate::default();
+                    let mut changed = false;
+                    if values.name != set.name {
+                        builder.name(set.name.clone());
+                        changed = true;
+                    }
+                    if values.normal_balance_type != set.normal_balance_type {
+                        builder.normal_balance_type(set.normal_balance_type);
+                        changed = true;
+                    }
+                    if set.external_id.is_some() && values.external_id != set.external_id {
+                        builder.external_id(set.external_id.clone().unwrap_or_default());
+                        changed = true;
+                    }
+                    if set.description.is_some() && values.description != set.description {
+                        builder.description(set.description.clone().unwrap_or_default());
+                        changed = true;
+                    }
+                    if set.metadata.is_some() && values.metadata != set.metadata {
+                        builder
+                            .metadata(set.metadata.clone())
+                            .expect("Failed to serialize metadata");
+                        changed = true;
+                    }
+                    if changed {
+                        current.update(builder);
+                        self.account_sets.persist_in_op(op, &mut current).await?;
+                        applied.account_sets_updated += 1;
+                    }
+                    set_ids.insert(key, current.id());
+                }
+                None => {
+                    let mut builder = NewAccountSet::builder();
+                    builder
+                        .id(id.unwrap_or_else(AccountSetId::new))
+                        .journal_id(journal_id)
+                        .name(set.name.clone())
+                        .normal_balance_type(set.normal_balance_type);
+                    if let Some(external_id) = &set.external_id {
+                        builder.external_id(external_id.clone());
+                    }
+                    if let Some(description) = &set.description {
+                        builder.description(description.clone());
+                    }
+                    if let Some(metadata) = &set.metadata {
+                        builder
+                            .metadata(metadata.clone())
+                            .expect("Failed to serialize metadata");
+                    }
+                    let new_set = builder.build().expect("Couldn't build NewAccountSet");
+                    let created = self.account_sets.create_in_op(op, new_set).await?;
+                    applied.account_sets_created += 1;
+                    set_ids.insert(key, created.id());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn apply_members(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        chart: &ChartOfAccounts,
+        account_ids: &HashMap<String, AccountId>,
+        set_ids: &HashMap<String, AccountSetId>,
+        applied: &mut ChartOfAccountsApplied,
+    ) -> Result<(), ChartOfAccountsError> {
+        let sets: Vec<_> = chart
+            .journals
+            .iter()
+            .flat_map(|journal| journal.account_sets.iter())
+            .collect();
+
+        let unknown_codes: Vec<_> = sets
+            .iter()
+            .flat_map(|set| set.accounts.iter())
+            .filter(|code| !account_ids.contains_key(*code))
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let ledger_account_ids = self
+            .repo
+            .find_account_ids_by_code(&mut *op, tenant_id, &unknown_codes)
+            .await?;
+
+        let all_set_ids: Vec<_> = set_ids.values().copied().collect();
+        let existing: HashSet<_> = self
+            .repo
+            .find_direct_members(&mut *op, &all_set_ids)
+            .await?
+            .into_iter()
+            .map(|(set_id, member_id, _)| (set_id, member_id))
+            .collect();
+
+        for set in sets {
+            let set_id = set_ids[&set.key().expect("validated account set key")];
+            for code in set.accounts.iter() {
+                let account_id = account_ids
+                    .get(code)
+                    .or_else(|| ledger_account_ids.get(code))
+                    .copied()
+                    .ok_or_else(|| ChartOfAccountsError::AccountNotFound(code.clone()))?;
+                if !existing.contains(&(set_id, account_id)) {
+                    self.account_sets
+                        .add_member_in_op(op, set_id, account_id)
+                        .await?;
+                    applied.members_added += 1;
+                }
+            }
+            for key in set.account_sets.iter() {
+                let member_id = set_ids[key];
+                if !existing.contains(&(set_id, AccountId::from(member_id))) {
+                    self.account_sets
+                        .add_member_in_op(op, set_id, member_id)
+                        .await?;
+                    applied.members_added += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
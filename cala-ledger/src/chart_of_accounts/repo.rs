@@ -0,0 +1,124 @@
+use sqlx::PgPool;
+
+use std::collections::HashMap;
+
+use cala_types::primitives::*;
+
+use super::error::ChartOfAccountsError;
+
+#[derive(Debug, Clone)]
+pub(super) struct ChartOfAccountsRepo {
+    pool: PgPool,
+}
+
+impl ChartOfAccountsRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    pub async fn find_journal_ids_by_code(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        tenant_id: Option<TenantId>,
+        codes: &[String],
+    ) -> Result<HashMap<String, JournalId>, ChartOfAccountsError> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+                r#"SELECT id AS "id: JournalId", code AS "code!" FROM cala_journals
+                WHERE code = ANY($1) AND tenant_id IS NOT DISTINCT FROM $2"#,
+                codes,
+                tenant_id as Option<TenantId>,
+            ))
+            .await?;
+        Ok(rows.into_iter().map(|row| (row.code, row.id)).collect())
+    }
+
+    pub async fn find_account_ids_by_code(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        tenant_id: Option<TenantId>,
+        codes: &[String],
+    ) -> Result<HashMap<String, AccountId>, ChartOfAccountsError> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+                r#"SELECT id AS "id: AccountId", code FROM cala_accounts
+                WHERE code = ANY($1) AND tenant_id IS NOT DISTINCT FROM $2"#,
+                codes,
+                tenant_id as Option<TenantId>,
+            ))
+            .await?;
+        Ok(rows.into_iter().map(|row| (row.code, row.id)).collect())
+    }
+
+    /// Account sets belong to the tenant of their journal.
+    pub async fn find_account_set_ids_by_external_id(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        tenant_id: Option<TenantId>,
+        external_ids: &[String],
+    ) -> Result<HashMap<String, AccountSetId>, ChartOfAccountsError> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+                r#"
+                SELECT s.id AS "id: AccountSetId", s.external_id AS "external_id!"
+                FROM cala_account_sets s
+                JOIN cala_journals j ON j.id = s.journal_id
+                WHERE s.external_id = ANY($1)
+                AND j.tenant_id IS NOT DISTINCT FROM $2"#,
+                external_ids,
+                tenant_id as Option<TenantId>,
+            ))
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.external_id, row.id))
+            .collect())
+    }
+
+    pub async fn find_account_set_ids_for_journals(
+        &self,
+        journal_ids: &[JournalId],
+    ) -> Result<Vec<AccountSetId>, ChartOfAccountsError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id AS "id: AccountSetId"
+            FROM cala_account_sets
+            WHERE journal_id = ANY($1)
+            ORDER BY created_at, id"#,
+            journal_ids as &[JournalId],
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// Returns the direct member accounts and member account sets of the given sets.
+    pub async fn find_direct_members(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        account_set_ids: &[AccountSetId],
+    ) -> Result<Vec<(AccountSetId, AccountId, bool)>, ChartOfAccountsError> {
+        let rows = op
+            .into_executor()
+            .fetch_all(sqlx::query!(
+            r#"
+            SELECT account_set_id AS "account_set_id!: AccountSetId", member_account_id AS "member_id!: AccountId", FALSE AS "is_set!"
+            FROM cala_account_set_member_accounts
+            WHERE account_set_id = ANY($1) AND transitive IS FALSE
+            UNION ALL
+            SELECT account_set_id, member_account_set_id, TRUE
+            FROM cala_account_set_member_account_sets
+            WHERE account_set_id = ANY($1)
+            ORDER BY 1, 2"#,
+            account_set_ids as &[AccountSetId],
+            ))
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.account_set_id, row.member_id, row.is_set))
+            .collect())
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::{
+    metadata_filter::add_path_eq,
+    primitives::{AccountId, Currency, DebitOrCredit, JournalId, Layer},
+};
+
+/// Criteria for [`Entries::search`](super::Entries::search). All criteria that are set
+/// must match.
+#[derive(Debug, Default, Clone)]
+pub struct EntryFilter {
+    pub(super) journal_id: Option<JournalId>,
+    pub(super) account_id: Option<AccountId>,
+    pub(super) entry_type: Option<String>,
+    pub(super) layer: Option<Layer>,
+    pub(super) direction: Option<DebitOrCredit>,
+    pub(super) currency: Option<Currency>,
+    pub(super) effective_from: Option<NaiveDate>,
+    pub(super) effective_until: Option<NaiveDate>,
+    pub(super) units_min: Option<Decimal>,
+    pub(super) units_max: Option<Decimal>,
+    pub(super) metadata: Option<Value>,
+}
+
+impl EntryFilter {
+    pub fn journal_id(mut self, journal_id: impl Into<JournalId>) -> Self {
+        self.journal_id = Some(journal_id.into());
+        self
+    }
+
+    pub fn account_id(mut self, account_id: impl Into<AccountId>) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    pub fn entry_type(mut self, entry_type: impl Into<String>) -> Self {
+        self.entry_type = Some(entry_type.into());
+        self
+    }
+
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    pub fn direction(mut self, direction: DebitOrCredit) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    /// Effective date of the transaction of the entry, both bounds inclusive.
+    pub fn effective(mut self, from: Option<NaiveDate>, until: Option<NaiveDate>) -> Self {
+        self.effective_from = from;
+        self.effective_until = until;
+        self
+    }
+
+    /// Units of the entry, both bounds inclusive.
+    pub fn units(mut self, min: Option<Decimal>, max: Option<Decimal>) -> Self {
+        self.units_min = min;
+        self.units_max = max;
+        self
+    }
+
+    /// Requires the metadata value at the dot separated `path` (eg. `invoice.id`)
+    /// to equal `value`. Can be called multiple times to match several paths.
+    pub fn metadata_eq(mut self, path: &str, value: impl Into<Value>) -> Self {
+        self.metadata = Some(add_path_eq(self.metadata.take(), path, value.into()));
+        self
+    }
+}
+
+/// Ordering of [`Entries::search`](super::Entries::search) results. Entries are ordered
+/// by creation within the same effective date.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EntriesOrderBy {
+    #[default]
+    CreatedAt,
+    Effective,
+}
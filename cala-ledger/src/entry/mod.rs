@@ -1,5 +1,6 @@
 mod entity;
 pub mod error;
+mod filter;
 mod repo;
 
 use sqlx::PgPool;
@@ -15,6 +16,7 @@ use crate::{
 
 pub use entity::*;
 use error::*;
+pub use filter::*;
 pub use repo::entry_cursor::EntriesByCreatedAtCursor;
 use repo::*;
 
@@ -76,6 +78,17 @@ impl Entries {
             .await
     }
 
+    /// Lists the entries matching the filter across journals and accounts.
+    pub async fn search(
+        &self,
+        filter: EntryFilter,
+        order_by: EntriesOrderBy,
+        query: es_entity::PaginatedQueryArgs<EntriesByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<es_entity::PaginatedQueryRet<Entry, EntriesByCreatedAtCursor>, EntryError> {
+        self.repo.search(&filter, order_by, query, direction).await
+    }
+
     pub async fn list_for_transaction_id(
         &self,
         transaction_id: TransactionId,
@@ -1,8 +1,10 @@
-use crate::primitives::{AccountId, AccountSetId, DataSourceId, EntryId, JournalId, TransactionId};
+use crate::primitives::{
+    AccountId, AccountSetId, DataSourceId, DebitOrCredit, EntryId, JournalId, Layer, TransactionId,
+};
 use es_entity::*;
 use sqlx::PgPool;
 
-use super::{entity::*, error::*};
+use super::{entity::*, error::*, filter::*};
 
 #[derive(EsRepo, Debug, Clone)]
 #[es_repo(
@@ -12,6 +14,16 @@ use super::{entity::*, error::*};
         account_id(ty = "AccountId", list_for, update(persist = false)),
         journal_id(ty = "JournalId", list_for, update(persist = false)),
         transaction_id(ty = "TransactionId", list_for, update(persist = false)),
+        entry_type(ty = "String", update(persist = false)),
+        layer(ty = "Layer", update(persist = false)),
+        direction(ty = "DebitOrCredit", update(persist = false)),
+        currency(
+            ty = "String",
+            create(accessor = "currency.code().to_string()"),
+            update(persist = false)
+        ),
+        units(ty = "rust_decimal::Decimal", update(persist = false)),
+        metadata(ty = "Option<serde_json::Value>", update(persist = false)),
         data_source_id(
             ty = "DataSourceId",
             create(accessor = "data_source().into()"),
@@ -101,6 +113,205 @@ impl EntryRepo {
         })
     }
 
+    pub(super) async fn search(
+        &self,
+        filter: &EntryFilter,
+        order_by: EntriesOrderBy,
+        query: es_entity::PaginatedQueryArgs<entry_cursor::EntriesByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<Entry, entry_cursor::EntriesByCreatedAtCursor>,
+        EntryError,
+    > {
+        let es_entity::PaginatedQueryArgs { first, after } = query;
+        let (id, created_at) = if let Some(after) = after {
+            (Some(after.id), Some(after.created_at))
+        } else {
+            (None, None)
+        };
+
+        let executor = &self.pool;
+
+        let (entities, has_next_page) = match (order_by, direction) {
+            (EntriesOrderBy::CreatedAt, es_entity::ListDirection::Ascending) => {
+                es_entity::es_query!(
+                    entity = Entry,
+                    r#"
+                    SELECT e.created_at, e.id
+                    FROM cala_entries e
+                    JOIN cala_transactions t ON t.id = e.transaction_id
+                    WHERE ($4::uuid IS NULL OR e.journal_id = $4)
+                      AND ($5::uuid IS NULL OR e.account_id = $5)
+                      AND ($6::text IS NULL OR e.entry_type = $6)
+                      AND ($7::Layer IS NULL OR e.layer = $7)
+                      AND ($8::DebitOrCredit IS NULL OR e.direction = $8)
+                      AND ($9::text IS NULL OR e.currency = $9)
+                      AND ($10::date IS NULL OR t.effective >= $10)
+                      AND ($11::date IS NULL OR t.effective <= $11)
+                      AND ($12::numeric IS NULL OR e.units >= $12)
+                      AND ($13::numeric IS NULL OR e.units <= $13)
+                      AND ($14::jsonb IS NULL OR e.metadata @> $14)
+                      AND (COALESCE((e.created_at, e.id) > ($3, $2), $2 IS NULL))
+                    ORDER BY e.created_at ASC, e.id ASC
+                    LIMIT $1"#,
+                    (first + 1) as i64,
+                    id as Option<EntryId>,
+                    created_at as Option<chrono::DateTime<chrono::Utc>>,
+                    filter.journal_id as Option<JournalId>,
+                    filter.account_id as Option<AccountId>,
+                    filter.entry_type,
+                    filter.layer as Option<Layer>,
+                    filter.direction as Option<DebitOrCredit>,
+                    filter.currency.map(|c| c.code()),
+                    filter.effective_from,
+                    filter.effective_until,
+                    filter.units_min,
+                    filter.units_max,
+                    filter.metadata,
+                )
+                .fetch_n(executor, first)
+                .await?
+            }
+            (EntriesOrderBy::CreatedAt, es_entity::ListDirection::Descending) => {
+                es_entity::es_query!(
+                    entity = Entry,
+                    r#"
+                    SELECT e.created_at, e.id
+                    FROM cala_entries e
+                    JOIN cala_transactions t ON t.id = e.transaction_id
+                    WHERE ($4::uuid IS NULL OR e.journal_id = $4)
+                      AND ($5::uuid IS NULL OR e.account_id = $5)
+                      AND ($6::text IS NULL OR e.entry_type = $6)
+                      AND ($7::Layer IS NULL OR e.layer = $7)
+                      AND ($8::DebitOrCredit IS NULL OR e.direction = $8)
+                      AND ($9::text IS NULL OR e.currency = $9)
+                      AND ($10::date IS NULL OR t.effective >= $10)
+                      AND ($11::date IS NULL OR t.effective <= $11)
+                      AND ($12::numeric IS NULL OR e.units >= $12)
+                      AND ($13::numeric IS NULL OR e.units <= $13)
+                      AND ($14::jsonb IS NULL OR e.metadata @> $14)
+                      AND (COALESCE((e.created_at, e.id) < ($3, $2), $2 IS NULL))
+                    ORDER BY e.created_at DESC, e.id DESC
+                    LIMIT $1"#,
+                    (first + 1) as i64,
+                    id as Option<EntryId>,
+                    created_at as Option<chrono::DateTime<chrono::Utc>>,
+                    filter.journal_id as Option<JournalId>,
+                    filter.account_id as Option<AccountId>,
+                    filter.entry_type,
+                    filter.layer as Option<Layer>,
+                    filter.direction as Option<DebitOrCredit>,
+                    filter.currency.map(|c| c.code()),
+                    filter.effective_from,
+                    filter.effective_until,
+                    filter.units_min,
+                    filter.units_max,
+                    filter.metadata,
+                )
+                .fetch_n(executor, first)
+                .await?
+            }
+            (EntriesOrderBy::Effective, es_entity::ListDirection::Ascending) => {
+                es_entity::es_query!(
+                    entity = Entry,
+                    r#"
+                    SELECT t.effective, e.created_at, e.id
+                    FROM cala_entries e
+                    JOIN cala_transactions t ON t.id = e.transaction_id
+                    WHERE ($4::uuid IS NULL OR e.journal_id = $4)
+                      AND ($5::uuid IS NULL OR e.account_id = $5)
+                      AND ($6::text IS NULL OR e.entry_type = $6)
+                      AND ($7::Layer IS NULL OR e.layer = $7)
+                      AND ($8::DebitOrCredit IS NULL OR e.direction = $8)
+                      AND ($9::text IS NULL OR e.currency = $9)
+                      AND ($10::date IS NULL OR t.effective >= $10)
+                      AND ($11::date IS NULL OR t.effective <= $11)
+                      AND ($12::numeric IS NULL OR e.units >= $12)
+                      AND ($13::numeric IS NULL OR e.units <= $13)
+                      AND ($14::jsonb IS NULL OR e.metadata @> $14)
+                      AND (COALESCE((t.effective, e.created_at, e.id) > (
+                        (SELECT ct.effective FROM cala_entries ce
+                         JOIN cala_transactions ct ON ct.id = ce.transaction_id
+                         WHERE ce.id = $2),
+                        $3, $2
+                      ), $2 IS NULL))
+                    ORDER BY t.effective ASC, e.created_at ASC, e.id ASC
+                    LIMIT $1"#,
+                    (first + 1) as i64,
+                    id as Option<EntryId>,
+                    created_at as Option<chrono::DateTime<chrono::Utc>>,
+                    filter.journal_id as Option<JournalId>,
+                    filter.account_id as Option<AccountId>,
+                    filter.entry_type,
+                    filter.layer as Option<Layer>,
+                    filter.direction as Option<DebitOrCredit>,
+                    filter.currency.map(|c| c.code()),
+                    filter.effective_from,
+                    filter.effective_until,
+                    filter.units_min,
+                    filter.units_max,
+                    filter.metadata,
+                )
+                .fetch_n(executor, first)
+                .await?
+            }
+            (EntriesOrderBy::Effective, es_entity::ListDirection::Descending) => {
+                es_entity::es_query!(
+                    entity = Entry,
+                    r#"
+                    SELECT t.effective, e.created_at, e.id
+                    FROM cala_entries e
+                    JOIN cala_transactions t ON t.id = e.transaction_id
+                    WHERE ($4::uuid IS NULL OR e.journal_id = $4)
+                      AND ($5::uuid IS NULL OR e.account_id = $5)
+                      AND ($6::text IS NULL OR e.entry_type = $6)
+                      AND ($7::Layer IS NULL OR e.layer = $7)
+                      AND ($8::DebitOrCredit IS NULL OR e.direction = $8)
+                      AND ($9::text IS NULL OR e.currency = $9)
+                      AND ($10::date IS NULL OR t.effective >= $10)
+                      AND ($11::date IS NULL OR t.effective <= $11)
+                      AND ($12::numeric IS NULL OR e.units >= $12)
+                      AND ($13::numeric IS NULL OR e.units <= $13)
+                      AND ($14::jsonb IS NULL OR e.metadata @> $14)
+                      AND (COALESCE((t.effective, e.created_at, e.id) < (
+                        (SELECT ct.effective FROM cala_entries ce
+                         JOIN cala_transactions ct ON ct.id = ce.transaction_id
+                         WHERE ce.id = $2),
+                        $3, $2
+                      ), $2 IS NULL))
+                    ORDER BY t.effective DESC, e.created_at DESC, e.id DESC
+                    LIMIT $1"#,
+                    (first + 1) as i64,
+                    id as Option<EntryId>,
+                    created_at as Option<chrono::DateTime<chrono::Utc>>,
+                    filter.journal_id as Option<JournalId>,
+                    filter.account_id as Option<AccountId>,
+                    filter.entry_type,
+                    filter.layer as Option<Layer>,
+                    filter.direction as Option<DebitOrCredit>,
+                    filter.currency.map(|c| c.code()),
+                    filter.effective_from,
+                    filter.effective_until,
+                    filter.units_min,
+                    filter.units_max,
+                    filter.metadata,
+                )
+                .fetch_n(executor, first)
+                .await?
+            }
+        };
+
+        let end_cursor = entities
+            .last()
+            .map(entry_cursor::EntriesByCreatedAtCursor::from);
+
+        Ok(es_entity::PaginatedQueryRet {
+            entities,
+            has_next_page,
+            end_cursor,
+        })
+    }
+
     #[cfg(feature = "import")]
     pub(super) async fn import(
         &self,
@@ -110,13 +321,19 @@ impl EntryRepo {
     ) -> Result<(), EntryError> {
         let recorded_at = op.now();
         sqlx::query!(
-            r#"INSERT INTO cala_entries (data_source_id, id, journal_id, account_id, transaction_id, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6)"#,
+            r#"INSERT INTO cala_entries (data_source_id, id, journal_id, account_id, transaction_id, entry_type, layer, direction, currency, units, metadata, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#,
             origin as DataSourceId,
             entry.values().id as EntryId,
             entry.values().journal_id as JournalId,
             entry.values().account_id as AccountId,
             entry.values().transaction_id as TransactionId,
+            entry.values().entry_type,
+            entry.values().layer as Layer,
+            entry.values().direction as DebitOrCredit,
+            entry.values().currency.code(),
+            entry.values().units,
+            entry.values().metadata,
             recorded_at,
         )
         .execute(op.as_executor())
@@ -0,0 +1,41 @@
+use chrono::NaiveDate;
+use derive_builder::Builder;
+use rust_decimal::Decimal;
+
+use crate::primitives::*;
+
+/// Representation of a ***new*** fx rate with required properties and a builder.
+/// Upserting a rate for a pair and effective date that already exists replaces it.
+#[derive(Builder, Debug, Clone)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct NewFxRate {
+    #[builder(setter(into))]
+    pub(super) base_currency: Currency,
+    #[builder(setter(into))]
+    pub(super) quote_currency: Currency,
+    pub(super) effective: NaiveDate,
+    #[builder(setter(into))]
+    pub(super) rate: Decimal,
+}
+
+impl NewFxRate {
+    pub fn builder() -> NewFxRateBuilder {
+        NewFxRateBuilder::default()
+    }
+}
+
+impl NewFxRateBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if let (Some(base), Some(quote)) = (&self.base_currency, &self.quote_currency) {
+            if base == quote {
+                return Err("base and quote currency must differ".to_string());
+            }
+        }
+        if let Some(rate) = &self.rate {
+            if *rate <= Decimal::ZERO {
+                return Err("rate must be positive".to_string());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+use chrono::NaiveDate;
+use thiserror::Error;
+
+use cala_types::primitives::Currency;
+
+#[derive(Error, Debug)]
+pub enum FxRateError {
+    #[error("FxRateError - Sqlx: {0}")]
+    Sqlx(#[from] sqlx::Error),
+    #[error("FxRateError - NotFound: there is no rate from {0} to {1} effective on {2}")]
+    NotFound(Currency, Currency, NaiveDate),
+}
@@ -0,0 +1,112 @@
+mod entity;
+pub mod error;
+mod repo;
+
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::instrument;
+
+pub use cala_types::fx_rate::FxRateValues;
+
+#[cfg(feature = "import")]
+use crate::primitives::DataSourceId;
+use crate::{
+    ledger_operation::*,
+    outbox::*,
+    primitives::{Currency, DataSource},
+};
+
+pub use entity::*;
+use error::*;
+use repo::*;
+
+/// Service for maintaining the exchange rates used to convert balances
+/// into a reporting currency.
+#[derive(Clone)]
+pub struct FxRates {
+    repo: FxRateRepo,
+    outbox: Outbox,
+    pool: PgPool,
+}
+
+impl FxRates {
+    pub(crate) fn new(pool: &PgPool, outbox: Outbox) -> Self {
+        Self {
+            repo: FxRateRepo::new(pool),
+            outbox,
+            pool: pool.clone(),
+        }
+    }
+
+    #[instrument(name = "cala_ledger.fx_rates.upsert", skip(self))]
+    pub async fn upsert(&self, new_rate: NewFxRate) -> Result<FxRateValues, FxRateError> {
+        let mut op = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        let rate = self.upsert_in_op(&mut op, new_rate).await?;
+        op.commit().await?;
+        Ok(rate)
+    }
+
+    pub async fn upsert_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        new_rate: NewFxRate,
+    ) -> Result<FxRateValues, FxRateError> {
+        let rate = self
+            .repo
+            .upsert_in_op(db, DataSource::Local.into(), new_rate)
+            .await?;
+        db.accumulate(std::iter::once(OutboxEventPayload::FxRateUpserted {
+            source: DataSource::Local,
+            fx_rate: rate.clone(),
+        }));
+        Ok(rate)
+    }
+
+    /// Returns the rate for converting `base_currency` into `quote_currency` that
+    /// applies on the given date, ie. the one with the latest effective date on or before it.
+    #[instrument(name = "cala_ledger.fx_rates.find_effective", skip(self))]
+    pub async fn find_effective(
+        &self,
+        base_currency: Currency,
+        quote_currency: Currency,
+        as_of: NaiveDate,
+    ) -> Result<FxRateValues, FxRateError> {
+        self.repo
+            .find_effective(base_currency, quote_currency, as_of)
+            .await
+    }
+
+    pub(crate) async fn find_all_effective(
+        &self,
+        base_currencies: &[Currency],
+        quote_currency: Currency,
+        as_of: NaiveDate,
+    ) -> Result<HashMap<Currency, FxRateValues>, FxRateError> {
+        self.repo
+            .find_all_effective(base_currencies, quote_currency, as_of)
+            .await
+    }
+
+    #[cfg(feature = "import")]
+    pub async fn sync_fx_rate_upsert(
+        &self,
+        mut db: es_entity::DbOpWithTime<'_>,
+        origin: DataSourceId,
+        values: FxRateValues,
+    ) -> Result<(), FxRateError> {
+        self.repo.import_in_op(&mut db, origin, &values).await?;
+        let time = db.now();
+        self.outbox
+            .persist_events_at(
+                db,
+                std::iter::once(OutboxEventPayload::FxRateUpserted {
+                    source: DataSource::Remote { id: origin },
+                    fx_rate: values,
+                }),
+                time,
+            )
+            .await?;
+        Ok(())
+    }
+}
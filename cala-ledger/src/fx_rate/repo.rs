@@ -0,0 +1,169 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use cala_types::{fx_rate::FxRateValues, primitives::Currency};
+
+use super::{entity::NewFxRate, error::FxRateError};
+use crate::primitives::DataSourceId;
+
+struct FxRateRow {
+    base_currency: String,
+    quote_currency: String,
+    effective: NaiveDate,
+    rate: Decimal,
+    version: i32,
+    created_at: DateTime<Utc>,
+    modified_at: DateTime<Utc>,
+}
+
+impl From<FxRateRow> for FxRateValues {
+    fn from(row: FxRateRow) -> Self {
+        Self {
+            base_currency: row.base_currency.parse().expect("Could not parse currency"),
+            quote_currency: row
+                .quote_currency
+                .parse()
+                .expect("Could not parse currency"),
+            effective: row.effective,
+            rate: row.rate,
+            version: row.version as u32,
+            created_at: row.created_at,
+            modified_at: row.modified_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct FxRateRepo {
+    pool: PgPool,
+}
+
+impl FxRateRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    pub async fn upsert_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        origin: DataSourceId,
+        new_rate: NewFxRate,
+    ) -> Result<FxRateValues, FxRateError> {
+        let now = op.now().unwrap_or_else(Utc::now);
+        let row = sqlx::query_as!(
+            FxRateRow,
+            r#"
+            INSERT INTO cala_fx_rates (base_currency, quote_currency, effective, rate, version, data_source_id, created_at, modified_at)
+            VALUES ($1, $2, $3, $4, 1, $5, $6, $6)
+            ON CONFLICT (base_currency, quote_currency, effective) DO UPDATE
+            SET rate = EXCLUDED.rate,
+                version = cala_fx_rates.version + 1,
+                data_source_id = EXCLUDED.data_source_id,
+                modified_at = EXCLUDED.modified_at
+            RETURNING base_currency, quote_currency, effective, rate, version, created_at, modified_at"#,
+            new_rate.base_currency.code(),
+            new_rate.quote_currency.code(),
+            new_rate.effective,
+            new_rate.rate,
+            origin as DataSourceId,
+            now,
+        )
+        .fetch_one(op.as_executor())
+        .await?;
+        Ok(FxRateValues::from(row))
+    }
+
+    /// Returns the rate of the pair with the latest effective date on or before `as_of`.
+    pub async fn find_effective(
+        &self,
+        base_currency: Currency,
+        quote_currency: Currency,
+        as_of: NaiveDate,
+    ) -> Result<FxRateValues, FxRateError> {
+        let row = sqlx::query_as!(
+            FxRateRow,
+            r#"
+            SELECT base_currency, quote_currency, effective, rate, version, created_at, modified_at
+            FROM cala_fx_rates
+            WHERE base_currency = $1
+            AND quote_currency = $2
+            AND effective <= $3
+            ORDER BY effective DESC
+            LIMIT 1"#,
+            base_currency.code(),
+            quote_currency.code(),
+            as_of,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(FxRateValues::from).ok_or(FxRateError::NotFound(
+            base_currency,
+            quote_currency,
+            as_of,
+        ))
+    }
+
+    pub async fn find_all_effective(
+        &self,
+        base_currencies: &[Currency],
+        quote_currency: Currency,
+        as_of: NaiveDate,
+    ) -> Result<HashMap<Currency, FxRateValues>, FxRateError> {
+        let base_currencies: Vec<_> = base_currencies.iter().map(|c| c.code()).collect();
+        let rows = sqlx::query_as!(
+            FxRateRow,
+            r#"
+            SELECT DISTINCT ON (base_currency)
+                base_currency, quote_currency, effective, rate, version, created_at, modified_at
+            FROM cala_fx_rates
+            WHERE base_currency = ANY($1)
+            AND quote_currency = $2
+            AND effective <= $3
+            ORDER BY base_currency, effective DESC"#,
+            &base_currencies as &[&str],
+            quote_currency.code(),
+            as_of,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let values = FxRateValues::from(row);
+                (values.base_currency, values)
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "import")]
+    pub async fn import_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        origin: DataSourceId,
+        values: &FxRateValues,
+    ) -> Result<(), FxRateError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_fx_rates (base_currency, quote_currency, effective, rate, version, data_source_id, created_at, modified_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (base_currency, quote_currency, effective) DO UPDATE
+            SET rate = EXCLUDED.rate,
+                version = EXCLUDED.version,
+                data_source_id = EXCLUDED.data_source_id,
+                modified_at = EXCLUDED.modified_at"#,
+            values.base_currency.code(),
+            values.quote_currency.code(),
+            values.effective,
+            values.rate,
+            values.version as i32,
+            origin as DataSourceId,
+            values.created_at,
+            values.modified_at,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+}
@@ -65,6 +65,7 @@ impl Journal {
             name,
             status,
             description,
+            enable_effective_balances,
         } = builder
             .into()
             .build()
@@ -87,6 +88,12 @@ impl Journal {
             self.values.description.clone_from(&description);
             updated_fields.push("description".to_string());
         }
+        if let Some(enable_effective_balances) = enable_effective_balances {
+            if enable_effective_balances != self.values.config.enable_effective_balances {
+                self.values.config.enable_effective_balances = enable_effective_balances;
+                updated_fields.push("enable_effective_balances".to_string());
+            }
+        }
 
         if !updated_fields.is_empty() {
             self.events.push(JournalEvent::Updated {
@@ -122,6 +129,10 @@ pub struct JournalUpdateValues {
     pub status: Option<Status>,
     #[builder(setter(into, strip_option))]
     pub description: Option<String>,
+    /// Turning effective balances on for a journal with existing postings requires
+    /// running `EffectiveBalances::backfill_batch` to populate the history.
+    #[builder(setter(into, strip_option))]
+    pub enable_effective_balances: Option<bool>,
 }
 
 impl From<(JournalValues, Vec<String>)> for JournalUpdate {
@@ -141,6 +152,9 @@ impl From<(JournalValues, Vec<String>)> for JournalUpdate {
                         builder.description(desc);
                     }
                 }
+                "enable_effective_balances" => {
+                    builder.enable_effective_balances(values.config.enable_effective_balances);
+                }
                 _ => unreachable!("Unknown field: {}", field),
             }
         }
@@ -179,6 +193,8 @@ pub struct NewJournal {
     pub(super) name: String,
     #[builder(setter(strip_option, into), default)]
     pub(super) code: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    pub(super) tenant_id: Option<TenantId>,
     #[builder(setter(into), default)]
     status: Status,
     #[builder(setter(strip_option, into), default)]
@@ -207,6 +223,7 @@ impl IntoEvents<JournalEvent> for NewJournal {
                     version: 1,
                     name: self.name,
                     code: self.code,
+                    tenant_id: self.tenant_id,
                     status: self.status,
                     description: self.description,
                     config: JournalConfig {
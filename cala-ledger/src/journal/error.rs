@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::primitives::JournalId;
+
 #[derive(Error, Debug)]
 pub enum JournalError {
     #[error("JournalError - Sqlx: {0}")]
@@ -8,6 +10,10 @@ pub enum JournalError {
     EsEntityError(es_entity::EsEntityError),
     #[error("JournalError - CursorDestructureError: {0}")]
     CursorDestructureError(#[from] es_entity::CursorDestructureError),
+    #[error("JournalError - NotFound: code '{0}' not found")]
+    CouldNotFindByCode(String),
+    #[error("JournalError - TenantMismatch: journal '{0}' belongs to a different tenant")]
+    TenantMismatch(JournalId),
     #[error("JournalError - code already exists")]
     CodeAlreadyExists,
 }
@@ -10,7 +10,11 @@ use std::collections::HashMap;
 
 #[cfg(feature = "import")]
 use crate::primitives::DataSourceId;
-use crate::{ledger_operation::*, outbox::*, primitives::DataSource};
+use crate::{
+    ledger_operation::*,
+    outbox::*,
+    primitives::{DataSource, TenantId},
+};
 
 pub use entity::*;
 use error::*;
@@ -59,6 +63,15 @@ impl Journals {
         self.repo.find_all(journal_ids).await
     }
 
+    #[instrument(name = "cala_ledger.journals.find_all_in_op", skip(self, op))]
+    pub async fn find_all_in_op<T: From<Journal>>(
+        &self,
+        op: &mut LedgerOperation<'_>,
+        journal_ids: &[JournalId],
+    ) -> Result<HashMap<JournalId, T>, JournalError> {
+        self.repo.find_all_in_op(op, journal_ids).await
+    }
+
     #[instrument(name = "cala_ledger.journals.find_by_id", skip(self))]
     pub async fn find(&self, journal_id: JournalId) -> Result<Journal, JournalError> {
         self.repo.find_by_id(journal_id).await
@@ -83,8 +96,29 @@ impl Journals {
     }
 
     #[instrument(name = "cala_ledger.journal.find_by_code", skip(self))]
-    pub async fn find_by_code(&self, code: String) -> Result<Journal, JournalError> {
-        self.repo.find_by_code(Some(code)).await
+    pub async fn find_by_code(
+        &self,
+        tenant_id: Option<TenantId>,
+        code: String,
+    ) -> Result<Journal, JournalError> {
+        self.repo.find_by_tenant_and_code(tenant_id, code).await
+    }
+
+    /// Rejects references to journals that don't belong to the tenant.
+    pub(crate) async fn check_tenant_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        journal_ids: &[JournalId],
+    ) -> Result<(), JournalError> {
+        match self
+            .repo
+            .find_first_outside_tenant(db, tenant_id, journal_ids)
+            .await?
+        {
+            Some(journal_id) => Err(JournalError::TenantMismatch(journal_id)),
+            None => Ok(()),
+        }
     }
 
     #[cfg(feature = "import")]
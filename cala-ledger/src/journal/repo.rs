@@ -1,7 +1,7 @@
 use es_entity::*;
 use sqlx::PgPool;
 
-use crate::primitives::DataSourceId;
+use crate::primitives::{DataSourceId, TenantId};
 
 use super::{entity::*, error::JournalError};
 
@@ -12,6 +12,7 @@ use super::{entity::*, error::JournalError};
     columns(
         name(ty = "String", update(accessor = "values().name")),
         code(ty = "Option<String>", update(accessor = "values().code")),
+        tenant_id(ty = "Option<TenantId>", update(persist = false)),
         data_source_id(
             ty = "DataSourceId",
             create(accessor = "data_source().into()"),
@@ -39,11 +40,13 @@ impl JournalRepo {
     ) -> Result<(), JournalError> {
         let recorded_at = op.now();
         sqlx::query!(
-            r#"INSERT INTO cala_journals (data_source_id, id, name, created_at)
-            VALUES ($1, $2, $3, $4)"#,
+            r#"INSERT INTO cala_journals (data_source_id, id, name, code, tenant_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)"#,
             origin as DataSourceId,
             journal.values().id as JournalId,
             journal.values().name,
+            journal.values().code,
+            journal.values().tenant_id as Option<TenantId>,
             recorded_at
         )
         .execute(op.as_executor())
@@ -51,4 +54,39 @@ impl JournalRepo {
         self.persist_events(op, journal.events_mut()).await?;
         Ok(())
     }
+
+    pub async fn find_first_outside_tenant(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        tenant_id: Option<TenantId>,
+        journal_ids: &[JournalId],
+    ) -> Result<Option<JournalId>, JournalError> {
+        let row = sqlx::query!(
+            r#"SELECT id AS "id: JournalId" FROM cala_journals
+            WHERE id = ANY($1) AND tenant_id IS DISTINCT FROM $2
+            LIMIT 1"#,
+            journal_ids as &[JournalId],
+            tenant_id as Option<TenantId>,
+        )
+        .fetch_optional(op.as_executor())
+        .await?;
+        Ok(row.map(|row| row.id))
+    }
+
+    pub async fn find_by_tenant_and_code(
+        &self,
+        tenant_id: Option<TenantId>,
+        code: String,
+    ) -> Result<Journal, JournalError> {
+        es_query!(
+            tbl_prefix = "cala",
+            r#"SELECT id FROM cala_journals
+            WHERE tenant_id IS NOT DISTINCT FROM $1 AND code = $2"#,
+            tenant_id as Option<TenantId>,
+            &code,
+        )
+        .fetch_optional(self.pool())
+        .await?
+        .ok_or(JournalError::CouldNotFindByCode(code))
+    }
 }
@@ -15,6 +15,10 @@ pub struct CalaLedgerConfig {
     pub(super) pool: Option<sqlx::PgPool>,
     #[builder(setter(strip_option), default)]
     pub(super) outbox: Option<OutboxServerConfig>,
+    /// Maximum number of account sets that may be nested inside each other.
+    /// Unlimited if not set.
+    #[builder(setter(strip_option), default)]
+    pub(super) max_account_set_depth: Option<u32>,
 }
 
 impl CalaLedgerConfig {
@@ -32,6 +36,9 @@ impl CalaLedgerConfigBuilder {
             (Some(_), Some(_)) => return Err("Only one of pg_con or pool must be set".to_string()),
             _ => (),
         }
+        if let Some(Some(0)) = self.max_account_set_depth {
+            return Err("max_account_set_depth must be at least 1".to_string());
+        }
         Ok(())
     }
 }
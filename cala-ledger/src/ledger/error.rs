@@ -3,7 +3,8 @@ use thiserror::Error;
 
 use crate::{
     account::error::AccountError, account_set::error::AccountSetError,
-    balance::error::BalanceError, entry::error::EntryError, journal::error::JournalError,
+    balance::error::BalanceError, chart_of_accounts::error::ChartOfAccountsError,
+    entry::error::EntryError, fx_rate::error::FxRateError, journal::error::JournalError,
     outbox::server::error::OutboxServerError, transaction::error::TransactionError,
     tx_template::error::TxTemplateError, velocity::error::VelocityError,
 };
@@ -34,8 +35,12 @@ pub enum LedgerError {
     EntryError(#[from] EntryError),
     #[error("LedgerError - BalanceError: {0}")]
     BalanceError(#[from] BalanceError),
+    #[error("LedgerError - FxRateError: {0}")]
+    FxRateError(#[from] FxRateError),
     #[error("LedgerError - VelocityError: {0}")]
     VelocityError(#[from] VelocityError),
+    #[error("LedgerError - ChartOfAccountsError: {0}")]
+    ChartOfAccountsError(#[from] ChartOfAccountsError),
 }
 
 impl From<sqlx::Error> for LedgerError {
@@ -2,7 +2,10 @@ pub mod config;
 pub mod error;
 
 use sqlx::PgPool;
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
 pub use tracing::instrument;
 
 pub use config::*;
@@ -12,11 +15,14 @@ use crate::{
     account::Accounts,
     account_set::AccountSets,
     balance::Balances,
+    chart_of_accounts::Charts,
     entry::Entries,
+    entry::EntryValues,
+    fx_rate::FxRates,
     journal::Journals,
     ledger_operation::*,
     outbox::{server, EventSequence, Outbox, OutboxListener},
-    primitives::TransactionId,
+    primitives::{JournalId, TenantId, TransactionId},
     transaction::{Transaction, Transactions},
     tx_template::{Params, TxTemplates},
     velocity::Velocities,
@@ -40,6 +46,8 @@ pub struct CalaLedger {
     entries: Entries,
     velocities: Velocities,
     balances: Balances,
+    fx_rates: FxRates,
+    charts: Charts,
     outbox: Outbox,
     #[allow(clippy::type_complexity)]
     outbox_handle: Arc<Mutex<Option<tokio::task::JoinHandle<Result<(), LedgerError>>>>>,
@@ -74,12 +82,21 @@ impl CalaLedger {
 
         let accounts = Accounts::new(&pool, outbox.clone());
         let journals = Journals::new(&pool, outbox.clone());
-        let tx_templates = TxTemplates::new(&pool, outbox.clone());
+        let tx_templates = TxTemplates::new(&pool, outbox.clone(), &accounts);
         let transactions = Transactions::new(&pool, outbox.clone());
         let entries = Entries::new(&pool, outbox.clone());
-        let balances = Balances::new(&pool, outbox.clone(), &journals);
+        let fx_rates = FxRates::new(&pool, outbox.clone());
+        let balances = Balances::new(&pool, outbox.clone(), &journals, &fx_rates);
         let velocities = Velocities::new(&pool, outbox.clone());
-        let account_sets = AccountSets::new(&pool, outbox.clone(), &accounts, &entries, &balances);
+        let account_sets = AccountSets::new(
+            &pool,
+            outbox.clone(),
+            &accounts,
+            &entries,
+            &balances,
+            config.max_account_set_depth,
+        );
+        let charts = Charts::new(&pool, outbox.clone(), &journals, &accounts, &account_sets);
         Ok(Self {
             accounts,
             account_sets,
@@ -89,7 +106,9 @@ impl CalaLedger {
             transactions,
             entries,
             balances,
+            fx_rates,
             velocities,
+            charts,
             outbox_handle: Arc::new(Mutex::new(outbox_handle)),
             pool,
         })
@@ -138,10 +157,18 @@ impl CalaLedger {
         &self.entries
     }
 
+    pub fn fx_rates(&self) -> &FxRates {
+        &self.fx_rates
+    }
+
     pub fn transactions(&self) -> &Transactions {
         &self.transactions
     }
 
+    pub fn chart_of_accounts(&self) -> &Charts {
+        &self.charts
+    }
+
     pub async fn post_transaction(
         &self,
         tx_id: TransactionId,
@@ -156,21 +183,57 @@ impl CalaLedger {
         Ok(transaction)
     }
 
+    pub async fn post_transaction_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        tx_id: TransactionId,
+        tx_template_code: &str,
+        params: impl Into<Params> + std::fmt::Debug,
+    ) -> Result<Transaction, LedgerError> {
+        self.post_transaction_for_tenant_in_op(db, None, tx_id, tx_template_code, params)
+            .await
+    }
+
+    pub async fn post_transaction_for_tenant(
+        &self,
+        tenant_id: TenantId,
+        tx_id: TransactionId,
+        tx_template_code: &str,
+        params: impl Into<Params> + std::fmt::Debug,
+    ) -> Result<Transaction, LedgerError> {
+        let mut db = LedgerOperation::init(&self.pool, &self.outbox).await?;
+        let transaction = self
+            .post_transaction_for_tenant_in_op(
+                &mut db,
+                Some(tenant_id),
+                tx_id,
+                tx_template_code,
+                params,
+            )
+            .await?;
+        db.commit().await?;
+        Ok(transaction)
+    }
+
+    /// Posts a transaction using the template with the given code in the tenant (or the
+    /// default scope for `None`). The journals and accounts the transaction touches must
+    /// belong to the same tenant.
     #[instrument(
         name = "cala_ledger.transaction_post",
         skip(self, db)
         fields(transaction_id, external_id)
     )]
-    pub async fn post_transaction_in_op(
+    pub async fn post_transaction_for_tenant_in_op(
         &self,
         db: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
         tx_id: TransactionId,
         tx_template_code: &str,
         params: impl Into<Params> + std::fmt::Debug,
     ) -> Result<Transaction, LedgerError> {
         let prepared_tx = self
             .tx_templates
-            .prepare_transaction_in_op(db, tx_id, tx_template_code, params.into())
+            .prepare_transaction_in_op(db, tenant_id, tx_id, tx_template_code, params.into())
             .await?;
 
         let transaction = self
@@ -187,35 +250,19 @@ impl CalaLedger {
             .create_all_in_op(db, prepared_tx.entries)
             .await?;
 
-        let account_ids = entries
-            .iter()
-            .map(|entry| entry.account_id)
-            .collect::<Vec<_>>();
-        let mappings = self
-            .account_sets
-            .fetch_mappings_in_op(db, transaction.values().journal_id, &account_ids)
+        let mut journal_ids: Vec<_> = entries.iter().map(|entry| entry.journal_id).collect();
+        journal_ids.push(transaction.journal_id());
+        self.journals
+            .check_tenant_in_op(db, prepared_tx.tenant_id, &journal_ids)
             .await?;
-
-        self.velocities
-            .update_balances_with_limit_enforcement_in_op(
-                db,
-                transaction.created_at(),
-                transaction.values(),
-                &entries,
-                &account_ids,
-                &mappings,
-            )
+        let account_ids: Vec<_> = entries.iter().map(|entry| entry.account_id).collect();
+        self.accounts
+            .check_tenant_in_op(db, prepared_tx.tenant_id, &account_ids)
             .await?;
 
-        self.balances
-            .update_balances_in_op(
-                db,
-                transaction.journal_id(),
-                entries,
-                transaction.effective(),
-                transaction.created_at(),
-                mappings,
-            )
+        self.accounts.check_postable_in_op(db, &entries).await?;
+
+        self.update_balances_in_op(db, &transaction, entries)
             .await?;
         Ok(transaction)
     }
@@ -265,39 +312,65 @@ impl CalaLedger {
 
         let entries = self.entries.create_all_in_op(db, new_entries).await?;
 
-        let account_ids = entries
-            .iter()
-            .map(|entry| entry.account_id)
-            .collect::<Vec<_>>();
-        let mappings = self
-            .account_sets
-            .fetch_mappings_in_op(db, transaction.values().journal_id, &account_ids)
-            .await?;
+        self.accounts.check_postable_in_op(db, &entries).await?;
 
-        self.velocities
-            .update_balances_with_limit_enforcement_in_op(
-                db,
-                transaction.created_at(),
-                transaction.values(),
-                &entries,
-                &account_ids,
-                &mappings,
-            )
-            .await?;
-
-        self.balances
-            .update_balances_in_op(
-                db,
-                transaction.journal_id(),
-                entries,
-                transaction.effective(),
-                transaction.created_at(),
-                mappings,
-            )
+        self.update_balances_in_op(db, &transaction, entries)
             .await?;
         Ok(transaction)
     }
 
+    /// Entries of a transaction can be posted to different journals. Account set mappings,
+    /// velocity limits and balances are resolved per journal, in a stable journal order
+    /// to keep the locking order consistent across concurrent postings.
+    async fn update_balances_in_op(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        transaction: &Transaction,
+        entries: Vec<EntryValues>,
+    ) -> Result<(), LedgerError> {
+        let mut entries_by_journal: BTreeMap<JournalId, Vec<EntryValues>> = BTreeMap::new();
+        for entry in entries {
+            entries_by_journal
+                .entry(entry.journal_id)
+                .or_default()
+                .push(entry);
+        }
+
+        for (journal_id, entries) in entries_by_journal {
+            let account_ids = entries
+                .iter()
+                .map(|entry| entry.account_id)
+                .collect::<Vec<_>>();
+            let mappings = self
+                .account_sets
+                .fetch_mappings_in_op(db, journal_id, &account_ids)
+                .await?;
+
+            self.velocities
+                .update_balances_with_limit_enforcement_in_op(
+                    db,
+                    transaction.created_at(),
+                    transaction.values(),
+                    &entries,
+                    &account_ids,
+                    &mappings,
+                )
+                .await?;
+
+            self.balances
+                .update_balances_in_op(
+                    db,
+                    journal_id,
+                    entries,
+                    transaction.effective(),
+                    transaction.created_at(),
+                    mappings,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
     pub async fn register_outbox_listener(
         &self,
         start_after: Option<EventSequence>,
@@ -331,6 +404,22 @@ impl CalaLedger {
                     .sync_account_update(op, account, fields)
                     .await?
             }
+            AccountAliasAdded {
+                account_id, alias, ..
+            } => {
+                let op = es_entity::DbOp::from(db).with_time(event.recorded_at);
+                self.accounts
+                    .sync_alias_addition(op, origin, account_id, alias)
+                    .await?
+            }
+            AccountAliasRemoved {
+                account_id, alias, ..
+            } => {
+                let op = es_entity::DbOp::from(db).with_time(event.recorded_at);
+                self.accounts
+                    .sync_alias_removal(op, origin, account_id, alias)
+                    .await?
+            }
             AccountSetCreated { account_set, .. } => {
                 let op = es_entity::DbOp::from(db).with_time(event.recorded_at);
                 self.account_sets
@@ -413,6 +502,18 @@ impl CalaLedger {
                     .sync_balance_update(db, origin, balance)
                     .await?
             }
+            FxRateUpserted { fx_rate, .. } => {
+                let op = es_entity::DbOp::from(db).with_time(event.recorded_at);
+                self.fx_rates
+                    .sync_fx_rate_upsert(op, origin, fx_rate)
+                    .await?
+            }
+            VelocityBreachRecorded { breach, .. } => {
+                let op = es_entity::DbOp::from(db).with_time(event.recorded_at);
+                self.velocities
+                    .sync_breach_recorded(op, origin, breach)
+                    .await?
+            }
         }
         Ok(())
     }
@@ -125,14 +125,18 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(clippy::all))]
 
 mod cel_context;
+mod metadata_filter;
 mod param;
 
 pub mod account;
 pub mod account_set;
 pub mod balance;
+pub mod chart_of_accounts;
 pub mod entry;
+pub mod fx_rate;
 pub mod journal;
 pub mod ledger_operation;
+pub mod metadata_schema;
 pub mod migrate;
 pub mod transaction;
 pub mod tx_template;
@@ -0,0 +1,36 @@
+//! Builds the JSON documents used to filter on metadata with `@>` containment.
+use serde_json::{Map, Value};
+
+/// Adds the requirement that the value at the dot separated `path` (eg. `region.country`)
+/// equals `value` to the containment document.
+pub(crate) fn add_path_eq(document: Option<Value>, path: &str, value: Value) -> Value {
+    let nested = path.split('.').rev().fold(value, |value, key| {
+        Value::Object(Map::from_iter([(key.to_string(), value)]))
+    });
+    match document {
+        Some(document) => merge(document, nested),
+        None => nested,
+    }
+}
+
+fn merge(existing: Value, other: Value) -> Value {
+    match (existing, other) {
+        (Value::Object(mut existing), Value::Object(other)) => {
+            for (key, value) in other {
+                let merged = match existing.remove(&key) {
+                    Some(current) => merge(current, value),
+                    None => value,
+                };
+                existing.insert(key, merged);
+            }
+            Value::Object(existing)
+        }
+        (_, other) => other,
+    }
+}
+
+pub(crate) fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
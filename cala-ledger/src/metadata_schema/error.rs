@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use super::MetadataViolation;
+
+#[derive(Error, Debug)]
+pub enum MetadataSchemaError {
+    #[error("MetadataSchemaError - InvalidSchema: {0}")]
+    InvalidSchema(String),
+    #[error("MetadataSchemaError - Violation: {}", display_violations(.0))]
+    Violation(Vec<MetadataViolation>),
+}
+
+fn display_violations(violations: &[MetadataViolation]) -> String {
+    violations
+        .iter()
+        .map(MetadataViolation::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
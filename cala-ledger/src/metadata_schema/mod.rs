@@ -0,0 +1,135 @@
+//! Validates `metadata` against JSON schemas. Schemas are registered per tenant and account type
+//! (see [`Accounts::set_metadata_schema`](crate::account::Accounts::set_metadata_schema))
+//! and declared on the transaction and entries of a tx template.
+pub mod error;
+
+use serde_json::Value;
+
+use error::*;
+
+/// A value in the metadata that doesn't conform to the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataViolation {
+    /// JSON pointer to the offending value, `/` for the metadata itself.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for MetadataViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+pub(crate) fn check_schema(schema: &Value) -> Result<(), MetadataSchemaError> {
+    compile(schema).map(|_| ())
+}
+
+/// Compiles the schema and validates the metadata, see [`MetadataValidator::validate`].
+pub(crate) fn validate(
+    schema: &Value,
+    metadata: Option<&Value>,
+) -> Result<(), MetadataSchemaError> {
+    MetadataValidator::new(schema)?.validate(metadata)
+}
+
+/// A compiled schema for validating metadata repeatedly without recompiling it.
+pub(crate) struct MetadataValidator(jsonschema::Validator);
+
+impl MetadataValidator {
+    pub fn new(schema: &Value) -> Result<Self, MetadataSchemaError> {
+        compile(schema).map(Self)
+    }
+
+    /// Validates the metadata collecting all violations. Missing metadata is validated as `null`.
+    pub fn validate(&self, metadata: Option<&Value>) -> Result<(), MetadataSchemaError> {
+        let instance = metadata.unwrap_or(&Value::Null);
+        let violations: Vec<_> = self
+            .0
+            .iter_errors(instance)
+            .map(|err| {
+                let path = err.instance_path.to_string();
+                MetadataViolation {
+                    path: if path.is_empty() {
+                        "/".to_string()
+                    } else {
+                        path
+                    },
+                    message: err.to_string(),
+                }
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(MetadataSchemaError::Violation(violations))
+        }
+    }
+}
+
+fn compile(schema: &Value) -> Result<jsonschema::Validator, MetadataSchemaError> {
+    if !schema.is_object() {
+        return Err(MetadataSchemaError::InvalidSchema(
+            "schema must be a JSON object".to_string(),
+        ));
+    }
+    jsonschema::validator_for(schema).map_err(|e| MetadataSchemaError::InvalidSchema(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "region": { "type": "string" },
+                "limits": {
+                    "type": "object",
+                    "properties": { "daily": { "type": "integer" } }
+                }
+            },
+            "required": ["region"]
+        })
+    }
+
+    #[test]
+    fn accepts_conforming_metadata() {
+        let metadata = json!({ "region": "EU", "limits": { "daily": 100 } });
+        assert!(validate(&schema(), Some(&metadata)).is_ok());
+    }
+
+    #[test]
+    fn reports_path_of_violations() {
+        let metadata = json!({ "limits": { "daily": "100" } });
+        let Err(MetadataSchemaError::Violation(violations)) = validate(&schema(), Some(&metadata))
+        else {
+            panic!("expected violations");
+        };
+        let paths: Vec<_> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(violations.len(), 2);
+        assert!(paths.contains(&"/"));
+        assert!(paths.contains(&"/limits/daily"));
+    }
+
+    #[test]
+    fn validates_missing_metadata_as_null() {
+        assert!(validate(&schema(), None).is_err());
+        assert!(validate(&json!({ "type": ["object", "null"] }), None).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_schema() {
+        assert!(matches!(
+            check_schema(&json!({ "type": "nope" })),
+            Err(MetadataSchemaError::InvalidSchema(_))
+        ));
+        assert!(matches!(
+            check_schema(&json!(true)),
+            Err(MetadataSchemaError::InvalidSchema(_))
+        ));
+    }
+}
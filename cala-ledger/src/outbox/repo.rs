@@ -48,7 +48,7 @@ impl OutboxRepo {
         let rows = query.fetch_all(&mut **db).await?;
         let events = rows
             .into_iter()
-            .zip(payloads.into_iter())
+            .zip(payloads)
             .map(|(row, payload)| OutboxEvent {
                 id: row.get::<OutboxEventId, _>("id"),
                 sequence: row.get("sequence"),
@@ -132,7 +132,7 @@ impl OutboxRepo {
                     recorded_at: row.recorded_at,
                 });
             }
-            events.sort_by(|a, b| a.sequence.cmp(&b.sequence));
+            events.sort_by_key(|e| e.sequence);
         }
 
         Ok(events)
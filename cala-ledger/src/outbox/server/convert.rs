@@ -1,6 +1,10 @@
 use rust_decimal::prelude::ToPrimitive;
 
-use cala_types::balance::{BalanceAmount, BalanceSnapshot};
+use cala_types::{
+    balance::{BalanceAmount, BalanceSnapshot},
+    fx_rate::FxRateValues,
+    velocity::{VelocityBreach, VelocityEnforcementAction},
+};
 
 use crate::primitives::*;
 
@@ -57,6 +61,32 @@ impl From<OutboxEvent> for proto::CalaLedgerEvent {
                 account_set: Some(proto::AccountSet::from(account_set)),
                 fields,
             }),
+            OutboxEventPayload::AccountAliasAdded {
+                source,
+                account_id,
+                alias,
+            } => proto::cala_ledger_event::Payload::AccountAliasAdded(proto::AccountAliasAdded {
+                data_source_id: source.to_string(),
+                alias: Some(proto::AccountAlias {
+                    account_id: account_id.to_string(),
+                    namespace: alias.namespace,
+                    value: alias.value,
+                }),
+            }),
+            OutboxEventPayload::AccountAliasRemoved {
+                source,
+                account_id,
+                alias,
+            } => {
+                proto::cala_ledger_event::Payload::AccountAliasRemoved(proto::AccountAliasRemoved {
+                    data_source_id: source.to_string(),
+                    alias: Some(proto::AccountAlias {
+                        account_id: account_id.to_string(),
+                        namespace: alias.namespace,
+                        value: alias.value,
+                    }),
+                })
+            }
             OutboxEventPayload::AccountSetMemberCreated {
                 source,
                 account_set_id,
@@ -161,6 +191,20 @@ impl From<OutboxEvent> for proto::CalaLedgerEvent {
                     balance: Some(proto::Balance::from(balance)),
                 })
             }
+            OutboxEventPayload::FxRateUpserted { source, fx_rate } => {
+                proto::cala_ledger_event::Payload::FxRateUpserted(proto::FxRateUpserted {
+                    data_source_id: source.to_string(),
+                    fx_rate: Some(proto::FxRate::from(fx_rate)),
+                })
+            }
+            OutboxEventPayload::VelocityBreachRecorded { source, breach } => {
+                proto::cala_ledger_event::Payload::VelocityBreachRecorded(
+                    proto::VelocityBreachRecorded {
+                        data_source_id: source.to_string(),
+                        breach: Some(proto::VelocityBreach::from(breach)),
+                    },
+                )
+            }
             OutboxEventPayload::Empty => proto::cala_ledger_event::Payload::Empty(true),
         };
         proto::CalaLedgerEvent {
@@ -178,7 +222,9 @@ impl From<AccountValues> for proto::Account {
             id,
             version,
             code,
+            tenant_id,
             name,
+            account_type,
             external_id,
             normal_balance_type,
             status,
@@ -188,7 +234,7 @@ impl From<AccountValues> for proto::Account {
         }: AccountValues,
     ) -> Self {
         let normal_balance_type: proto::DebitOrCredit = normal_balance_type.into();
-        let status: proto::Status = status.into();
+        let status: proto::AccountStatus = status.into();
         proto::Account {
             id: id.to_string(),
             version,
@@ -202,6 +248,8 @@ impl From<AccountValues> for proto::Account {
                 serde_json::from_value(json).expect("Could not transfer json -> struct")
             }),
             config: Some(proto::AccountConfig::from(config)),
+            tenant_id: tenant_id.map(|id| id.to_string()),
+            account_type,
         }
     }
 }
@@ -211,6 +259,7 @@ impl From<AccountConfig> for proto::AccountConfig {
         proto::AccountConfig {
             is_account_set: config.is_account_set,
             eventually_consistent: config.eventually_consistent,
+            balance_shards: config.balance_shards.map(u32::from),
         }
     }
 }
@@ -251,6 +300,7 @@ impl From<JournalValues> for proto::Journal {
             version,
             name,
             code,
+            tenant_id,
             status,
             description,
             config,
@@ -265,6 +315,7 @@ impl From<JournalValues> for proto::Journal {
             status: status as i32,
             description,
             config: Some(proto::JournalConfig::from(config)),
+            tenant_id: tenant_id.map(|id| id.to_string()),
         }
     }
 }
@@ -295,12 +346,24 @@ impl From<Status> for proto::Status {
     }
 }
 
+impl From<AccountStatus> for proto::AccountStatus {
+    fn from(status: AccountStatus) -> Self {
+        match status {
+            AccountStatus::Active => proto::AccountStatus::Active,
+            AccountStatus::Frozen => proto::AccountStatus::Frozen,
+            AccountStatus::Locked => proto::AccountStatus::Locked,
+            AccountStatus::Closed => proto::AccountStatus::Closed,
+        }
+    }
+}
+
 impl From<TxTemplateValues> for proto::TxTemplate {
     fn from(
         TxTemplateValues {
             id,
             version,
             code,
+            tenant_id,
             params,
             transaction,
             entries,
@@ -324,6 +387,7 @@ impl From<TxTemplateValues> for proto::TxTemplate {
             metadata: metadata.map(|json| {
                 serde_json::from_value(json).expect("Could not transfer json -> struct")
             }),
+            tenant_id: tenant_id.map(|id| id.to_string()),
         }
     }
 }
@@ -337,8 +401,10 @@ impl From<TxTemplateEntry> for proto::TxTemplateEntry {
             direction,
             currency,
             units,
+            journal_id,
             description,
             metadata,
+            metadata_schema,
         }: TxTemplateEntry,
     ) -> Self {
         proto::TxTemplateEntry {
@@ -348,8 +414,12 @@ impl From<TxTemplateEntry> for proto::TxTemplateEntry {
             direction: String::from(direction),
             currency: String::from(currency),
             units: String::from(units),
+            journal_id: journal_id.map(String::from),
             description: description.map(String::from),
             metadata: metadata.map(String::from),
+            metadata_schema: metadata_schema.map(|json| {
+                serde_json::from_value(json).expect("Could not transfer json -> struct")
+            }),
         }
     }
 }
@@ -397,6 +467,7 @@ impl From<TxTemplateTransaction> for proto::TxTemplateTransaction {
             external_id,
             description,
             metadata,
+            metadata_schema,
         }: TxTemplateTransaction,
     ) -> Self {
         proto::TxTemplateTransaction {
@@ -406,6 +477,9 @@ impl From<TxTemplateTransaction> for proto::TxTemplateTransaction {
             external_id: external_id.map(String::from),
             description: description.map(String::from),
             metadata: metadata.map(String::from),
+            metadata_schema: metadata_schema.map(|json| {
+                serde_json::from_value(json).expect("Could not transfer json -> struct")
+            }),
         }
     }
 }
@@ -539,6 +613,77 @@ impl From<BalanceAmount> for proto::BalanceAmount {
     }
 }
 
+impl From<FxRateValues> for proto::FxRate {
+    fn from(
+        FxRateValues {
+            base_currency,
+            quote_currency,
+            effective,
+            rate,
+            version,
+            created_at,
+            modified_at,
+        }: FxRateValues,
+    ) -> Self {
+        proto::FxRate {
+            base_currency: base_currency.to_string(),
+            quote_currency: quote_currency.to_string(),
+            effective: effective.to_string(),
+            rate: rate.to_string(),
+            version,
+            created_at: Some(created_at.into()),
+            modified_at: Some(modified_at.into()),
+        }
+    }
+}
+
+impl From<VelocityBreach> for proto::VelocityBreach {
+    fn from(
+        VelocityBreach {
+            id,
+            control_id,
+            limit_id,
+            account_id,
+            transaction_id,
+            action,
+            currency,
+            layer,
+            direction,
+            limit,
+            requested,
+            created_at,
+        }: VelocityBreach,
+    ) -> Self {
+        let action: proto::VelocityEnforcementAction = action.into();
+        let layer: proto::Layer = layer.into();
+        let direction: proto::DebitOrCredit = direction.into();
+        proto::VelocityBreach {
+            id: id.to_string(),
+            velocity_control_id: control_id.to_string(),
+            velocity_limit_id: limit_id.to_string(),
+            account_id: account_id.to_string(),
+            transaction_id: transaction_id.to_string(),
+            action: action.into(),
+            currency: currency.to_string(),
+            layer: layer.into(),
+            direction: direction.into(),
+            limit: limit.to_string(),
+            requested: requested.to_string(),
+            created_at: Some(created_at.into()),
+        }
+    }
+}
+
+impl From<VelocityEnforcementAction> for proto::VelocityEnforcementAction {
+    fn from(action: VelocityEnforcementAction) -> Self {
+        match action {
+            VelocityEnforcementAction::Reject => proto::VelocityEnforcementAction::Reject,
+            VelocityEnforcementAction::Warn => proto::VelocityEnforcementAction::Warn,
+            VelocityEnforcementAction::Record => proto::VelocityEnforcementAction::Record,
+        }
+    }
+}
+
 impl From<Layer> for proto::Layer {
     fn from(layer: Layer) -> Self {
         match layer {
@@ -27,6 +27,8 @@ impl OutboxService for OutboxServer {
         Box<dyn futures::Stream<Item = Result<CalaLedgerEvent, Status>> + Send + Sync + 'static>,
     >;
 
+    // The stream item type is fixed by tonic
+    #[allow(clippy::result_large_err)]
     #[instrument(name = "cala_ledger.subscribe", skip_all, fields(error, error.level, error.message))]
     async fn subscribe(
         &self,
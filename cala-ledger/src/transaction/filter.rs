@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+
+use crate::primitives::{JournalId, TxTemplateId};
+
+/// Criteria for [`Transactions::search`](super::Transactions::search). All criteria that
+/// are set must match.
+#[derive(Debug, Default, Clone)]
+pub struct TransactionFilter {
+    pub(super) journal_id: Option<JournalId>,
+    pub(super) tx_template_id: Option<TxTemplateId>,
+    pub(super) correlation_id: Option<String>,
+    pub(super) effective_from: Option<NaiveDate>,
+    pub(super) effective_until: Option<NaiveDate>,
+}
+
+impl TransactionFilter {
+    pub fn journal_id(mut self, journal_id: impl Into<JournalId>) -> Self {
+        self.journal_id = Some(journal_id.into());
+        self
+    }
+
+    pub fn tx_template_id(mut self, tx_template_id: impl Into<TxTemplateId>) -> Self {
+        self.tx_template_id = Some(tx_template_id.into());
+        self
+    }
+
+    pub fn correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+
+    /// Effective date of the transaction, both bounds inclusive.
+    pub fn effective(mut self, from: Option<NaiveDate>, until: Option<NaiveDate>) -> Self {
+        self.effective_from = from;
+        self.effective_until = until;
+        self
+    }
+}
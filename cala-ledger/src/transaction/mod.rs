@@ -1,6 +1,7 @@
 pub mod error;
 
 mod entity;
+mod filter;
 mod repo;
 
 use es_entity::EsEntity;
@@ -11,11 +12,12 @@ use std::collections::HashMap;
 
 #[cfg(feature = "import")]
 use crate::primitives::DataSourceId;
-use crate::primitives::{EntryId, TxTemplateId};
+use crate::primitives::{EntryId, JournalId, TxTemplateId};
 use crate::{ledger_operation::*, outbox::*, primitives::DataSource};
 
 pub use entity::*;
 use error::*;
+pub use filter::*;
 pub use repo::transaction_cursor::TransactionsByCreatedAtCursor;
 use repo::*;
 
@@ -102,6 +104,54 @@ impl Transactions {
             .await
     }
 
+    #[instrument(name = "cala_ledger.transactions.list_by_correlation_id", skip(self))]
+    pub async fn list_by_correlation_id(
+        &self,
+        correlation_id: impl Into<String> + std::fmt::Debug,
+        query: es_entity::PaginatedQueryArgs<TransactionsByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<Transaction, TransactionsByCreatedAtCursor>,
+        TransactionError,
+    > {
+        self.repo
+            .list_for_correlation_id_by_created_at(correlation_id.into(), query, direction)
+            .await
+    }
+
+    /// Lists the transactions of the journal, optionally restricted to an inclusive
+    /// range of effective dates.
+    #[instrument(name = "cala_ledger.transactions.list_for_journal", skip(self))]
+    pub async fn list_for_journal(
+        &self,
+        journal_id: JournalId,
+        effective_from: Option<chrono::NaiveDate>,
+        effective_until: Option<chrono::NaiveDate>,
+        query: es_entity::PaginatedQueryArgs<TransactionsByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<Transaction, TransactionsByCreatedAtCursor>,
+        TransactionError,
+    > {
+        let filter = TransactionFilter::default()
+            .journal_id(journal_id)
+            .effective(effective_from, effective_until);
+        self.repo.search(&filter, query, direction).await
+    }
+
+    #[instrument(name = "cala_ledger.transactions.search", skip(self))]
+    pub async fn search(
+        &self,
+        filter: TransactionFilter,
+        query: es_entity::PaginatedQueryArgs<TransactionsByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<Transaction, TransactionsByCreatedAtCursor>,
+        TransactionError,
+    > {
+        self.repo.search(&filter, query, direction).await
+    }
+
     #[instrument(name = "cala_ledger.transactions.find_all", skip(self))]
     pub async fn find_all<T: From<Transaction>>(
         &self,
@@ -3,7 +3,7 @@ use sqlx::PgPool;
 
 use crate::primitives::*;
 
-use super::{entity::*, error::TransactionError};
+use super::{entity::*, error::TransactionError, filter::TransactionFilter};
 
 #[derive(EsRepo, Clone)]
 #[es_repo(
@@ -11,7 +11,7 @@ use super::{entity::*, error::TransactionError};
     err = "TransactionError",
     columns(
         external_id(ty = "Option<String>", update(persist = false)),
-        correlation_id(ty = "String", update(persist = false)),
+        correlation_id(ty = "String", update(persist = false), list_for),
         journal_id(ty = "JournalId", update(persist = false)),
         tx_template_id(ty = "TxTemplateId", update(persist = false), list_for),
         data_source_id(
@@ -33,6 +33,91 @@ impl TransactionRepo {
         Self { pool: pool.clone() }
     }
 
+    pub async fn search(
+        &self,
+        filter: &TransactionFilter,
+        query: es_entity::PaginatedQueryArgs<transaction_cursor::TransactionsByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<
+            Transaction,
+            transaction_cursor::TransactionsByCreatedAtCursor,
+        >,
+        TransactionError,
+    > {
+        let es_entity::PaginatedQueryArgs { first, after } = query;
+        let (id, created_at) = if let Some(after) = after {
+            (Some(after.id), Some(after.created_at))
+        } else {
+            (None, None)
+        };
+
+        let (entities, has_next_page) = match direction {
+            es_entity::ListDirection::Ascending => {
+                es_entity::es_query!(
+                    entity = Transaction,
+                    r#"
+                    SELECT created_at, id
+                    FROM cala_transactions
+                    WHERE ($4::uuid IS NULL OR journal_id = $4)
+                      AND ($5::uuid IS NULL OR tx_template_id = $5)
+                      AND ($6::text IS NULL OR correlation_id = $6)
+                      AND ($7::date IS NULL OR effective >= $7)
+                      AND ($8::date IS NULL OR effective <= $8)
+                      AND (COALESCE((created_at, id) > ($3, $2), $2 IS NULL))
+                    ORDER BY created_at ASC, id ASC
+                    LIMIT $1"#,
+                    (first + 1) as i64,
+                    id as Option<TransactionId>,
+                    created_at as Option<chrono::DateTime<chrono::Utc>>,
+                    filter.journal_id as Option<JournalId>,
+                    filter.tx_template_id as Option<TxTemplateId>,
+                    filter.correlation_id,
+                    filter.effective_from,
+                    filter.effective_until,
+                )
+                .fetch_n(&self.pool, first)
+                .await?
+            }
+            es_entity::ListDirection::Descending => {
+                es_entity::es_query!(
+                    entity = Transaction,
+                    r#"
+                    SELECT created_at, id
+                    FROM cala_transactions
+                    WHERE ($4::uuid IS NULL OR journal_id = $4)
+                      AND ($5::uuid IS NULL OR tx_template_id = $5)
+                      AND ($6::text IS NULL OR correlation_id = $6)
+                      AND ($7::date IS NULL OR effective >= $7)
+                      AND ($8::date IS NULL OR effective <= $8)
+                      AND (COALESCE((created_at, id) < ($3, $2), $2 IS NULL))
+                    ORDER BY created_at DESC, id DESC
+                    LIMIT $1"#,
+                    (first + 1) as i64,
+                    id as Option<TransactionId>,
+                    created_at as Option<chrono::DateTime<chrono::Utc>>,
+                    filter.journal_id as Option<JournalId>,
+                    filter.tx_template_id as Option<TxTemplateId>,
+                    filter.correlation_id,
+                    filter.effective_from,
+                    filter.effective_until,
+                )
+                .fetch_n(&self.pool, first)
+                .await?
+            }
+        };
+
+        let end_cursor = entities
+            .last()
+            .map(transaction_cursor::TransactionsByCreatedAtCursor::from);
+
+        Ok(es_entity::PaginatedQueryRet {
+            entities,
+            has_next_page,
+            end_cursor,
+        })
+    }
+
     #[cfg(feature = "import")]
     pub async fn import_in_op(
         &self,
@@ -42,14 +127,15 @@ impl TransactionRepo {
     ) -> Result<(), TransactionError> {
         let recorded_at = op.now();
         sqlx::query!(
-            r#"INSERT INTO cala_transactions (data_source_id, id, journal_id, tx_template_id, external_id, correlation_id, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+            r#"INSERT INTO cala_transactions (data_source_id, id, journal_id, tx_template_id, external_id, correlation_id, effective, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
             origin as DataSourceId,
             transaction.values().id as TransactionId,
             transaction.values().journal_id as JournalId,
             transaction.values().tx_template_id as TxTemplateId,
             transaction.values().external_id,
             transaction.values().correlation_id,
+            transaction.values().effective,
             recorded_at
         )
         .execute(op.as_executor())
@@ -101,6 +101,10 @@ pub struct NewTxTemplate {
     pub(super) id: TxTemplateId,
     #[builder(setter(into))]
     pub(super) code: String,
+    /// Transactions posted with the template may only reference journals and accounts
+    /// of the same tenant.
+    #[builder(setter(strip_option, into), default)]
+    pub(super) tenant_id: Option<TenantId>,
     #[builder(setter(strip_option, into), default)]
     pub(super) description: Option<String>,
     #[builder(setter(strip_option), default)]
@@ -130,6 +134,7 @@ impl IntoEvents<TxTemplateEvent> for NewTxTemplate {
                     id: self.id,
                     version: 1,
                     code: self.code,
+                    tenant_id: self.tenant_id,
                     description: self.description,
                     params: self
                         .params
@@ -168,10 +173,17 @@ pub struct NewTxTemplateEntry {
     units: String,
     #[builder(setter(into))]
     currency: String,
+    /// Journal the entry is posted to. Entries without one are posted to the journal
+    /// of the transaction. Each journal involved has to balance on its own.
+    #[builder(setter(strip_option, into), default)]
+    journal_id: Option<String>,
     #[builder(setter(strip_option, into), default)]
     description: Option<String>,
     #[builder(setter(strip_option, into), default)]
     metadata: Option<String>,
+    /// JSON schema the evaluated metadata has to conform to when preparing a transaction.
+    #[builder(setter(strip_option, into), default)]
+    metadata_schema: Option<serde_json::Value>,
 }
 
 impl NewTxTemplateEntry {
@@ -211,8 +223,10 @@ impl NewTxTemplateEntryBuilder {
                 .as_ref()
                 .expect("Mandatory field 'currency' not set"),
         )?;
+        validate_optional_expression(&self.journal_id)?;
         validate_optional_expression(&self.description)?;
-        validate_optional_expression(&self.metadata)
+        validate_optional_expression(&self.metadata)?;
+        validate_optional_schema(&self.metadata_schema)
     }
 }
 
@@ -227,12 +241,16 @@ impl From<NewTxTemplateEntry> for cala_types::tx_template::TxTemplateEntry {
             direction: CelExpression::try_from(input.direction).expect("always a valid direction"),
             units: CelExpression::try_from(input.units).expect("always a valid units"),
             currency: CelExpression::try_from(input.currency).expect("always a valid currency"),
+            journal_id: input
+                .journal_id
+                .map(|j| CelExpression::try_from(j).expect("always a valid journal id")),
             description: input
                 .description
                 .map(|d| CelExpression::try_from(d).expect("always a valid description")),
             metadata: input
                 .metadata
                 .map(|m| CelExpression::try_from(m).expect("always a valid metadata")),
+            metadata_schema: input.metadata_schema,
         }
     }
 }
@@ -253,6 +271,9 @@ pub struct NewTxTemplateTransaction {
     description: Option<String>,
     #[builder(setter(strip_option, into), default)]
     metadata: Option<String>,
+    /// JSON schema the evaluated metadata has to conform to when preparing a transaction.
+    #[builder(setter(strip_option, into), default)]
+    metadata_schema: Option<serde_json::Value>,
 }
 
 impl NewTxTemplateTransaction {
@@ -276,7 +297,8 @@ impl NewTxTemplateTransactionBuilder {
         validate_optional_expression(&self.correlation_id)?;
         validate_optional_expression(&self.external_id)?;
         validate_optional_expression(&self.description)?;
-        validate_optional_expression(&self.metadata)
+        validate_optional_expression(&self.metadata)?;
+        validate_optional_schema(&self.metadata_schema)
     }
 }
 
@@ -289,6 +311,7 @@ impl From<NewTxTemplateTransaction> for cala_types::tx_template::TxTemplateTrans
             external_id,
             description,
             metadata,
+            metadata_schema,
         }: NewTxTemplateTransaction,
     ) -> Self {
         cala_types::tx_template::TxTemplateTransaction {
@@ -302,6 +325,7 @@ impl From<NewTxTemplateTransaction> for cala_types::tx_template::TxTemplateTrans
                 .map(|d| CelExpression::try_from(d).expect("always a valid description")),
             metadata: metadata
                 .map(|m| CelExpression::try_from(m).expect("always a valid metadata")),
+            metadata_schema,
         }
     }
 }
@@ -316,6 +340,12 @@ fn validate_optional_expression(expr: &Option<Option<String>>) -> Result<(), Str
     }
     Ok(())
 }
+fn validate_optional_schema(schema: &Option<Option<serde_json::Value>>) -> Result<(), String> {
+    if let Some(Some(schema)) = schema.as_ref() {
+        crate::metadata_schema::check_schema(schema).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
 
 #[cfg(test)]
 mod tests {
@@ -356,4 +386,14 @@ mod tests {
         let new_tx_template = NewTxTemplate::builder().build();
         assert!(new_tx_template.is_err());
     }
+
+    #[test]
+    fn fails_on_invalid_metadata_schema() {
+        let transaction = NewTxTemplateTransaction::builder()
+            .effective("date()")
+            .journal_id("params.journal_id")
+            .metadata_schema(serde_json::json!({ "type": "nope" }))
+            .build();
+        assert!(transaction.is_err());
+    }
 }
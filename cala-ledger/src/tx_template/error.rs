@@ -1,7 +1,7 @@
 use rust_decimal::Decimal;
 use thiserror::Error;
 
-use cala_types::primitives::{Currency, Layer};
+use cala_types::primitives::{Currency, JournalId, Layer};
 use cel_interpreter::CelError;
 
 #[derive(Error, Debug)]
@@ -20,8 +20,20 @@ pub enum TxTemplateError {
     SerdeJson(#[from] serde_json::Error),
     #[error("TxTemplateError - UnbalancedTransaction: currency {0}, layer {1:?}, amount {2}")]
     UnbalancedTransaction(Currency, Layer, Decimal),
+    #[error(
+        "TxTemplateError - UnbalancedJournal: journal {0}, currency {1}, layer {2:?}, amount {3}"
+    )]
+    UnbalancedJournal(JournalId, Currency, Layer, Decimal),
     #[error("TxTemplateError - NotFound: code '{0}' not found")]
     CouldNotFindByCode(String),
+    #[error("TxTemplateError - AccountAliasNotFound: alias '{0}:{1}' not found")]
+    AccountAliasNotFound(String, String),
+    #[error("TxTemplateError - InvalidTransactionMetadata: {0}")]
+    InvalidTransactionMetadata(crate::metadata_schema::error::MetadataSchemaError),
+    #[error("TxTemplateError - InvalidEntryMetadata: entry {0}: {1}")]
+    InvalidEntryMetadata(u32, crate::metadata_schema::error::MetadataSchemaError),
+    #[error("TxTemplateError - AccountError: {0}")]
+    AccountError(#[from] crate::account::error::AccountError),
     #[error("{0}")]
     ParamError(#[from] crate::param::error::ParamError),
     #[error("TxTemplateError - EsEntityError: {0}")]
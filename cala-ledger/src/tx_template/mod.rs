@@ -3,16 +3,21 @@ mod repo;
 
 pub mod error;
 
+use cel_interpreter::{CelContext, CelError, CelExpression, CelValue};
 use chrono::NaiveDate;
 use es_entity::EsEntity;
 use rust_decimal::Decimal;
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 use tracing::instrument;
 use uuid::Uuid;
 
 pub use crate::param::*;
 use crate::{
+    account::{AccountAlias, Accounts},
     entry::NewEntry,
     ledger_operation::*,
     outbox::*,
@@ -26,6 +31,7 @@ pub use repo::tx_template_cursor::TxTemplatesByCodeCursor;
 use repo::*;
 
 pub(crate) struct PreparedTransaction {
+    pub tenant_id: Option<TenantId>,
     pub transaction: NewTransaction,
     pub entries: Vec<NewEntry>,
 }
@@ -33,14 +39,16 @@ pub(crate) struct PreparedTransaction {
 #[derive(Clone)]
 pub struct TxTemplates {
     repo: TxTemplateRepo,
+    accounts: Accounts,
     outbox: Outbox,
     pool: PgPool,
 }
 
 impl TxTemplates {
-    pub(crate) fn new(pool: &PgPool, outbox: Outbox) -> Self {
+    pub(crate) fn new(pool: &PgPool, outbox: Outbox, accounts: &Accounts) -> Self {
         Self {
             repo: TxTemplateRepo::new(pool),
+            accounts: accounts.clone(),
             outbox,
             pool: pool.clone(),
         }
@@ -85,8 +93,14 @@ impl TxTemplates {
         self.repo.list_by_code(cursor, direction).await
     }
 
-    pub async fn find_by_code(&self, code: impl AsRef<str>) -> Result<TxTemplate, TxTemplateError> {
-        self.repo.find_by_code(code.as_ref().to_string()).await
+    pub async fn find_by_code(
+        &self,
+        tenant_id: Option<TenantId>,
+        code: impl AsRef<str>,
+    ) -> Result<TxTemplate, TxTemplateError> {
+        self.repo
+            .find_by_tenant_and_code(tenant_id, code.as_ref().to_string())
+            .await
     }
 
     #[instrument(
@@ -97,18 +111,49 @@ impl TxTemplates {
     pub(crate) async fn prepare_transaction_in_op(
         &self,
         db: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
         tx_id: TransactionId,
         code: &str,
         params: Params,
     ) -> Result<PreparedTransaction, TxTemplateError> {
         let time = db.now();
-        let tmpl = self.repo.find_latest_version_in_op(db, code).await?;
+        let VersionedTxTemplate {
+            values: tmpl,
+            validators,
+        } = self
+            .repo
+            .find_latest_version_in_op(db, tenant_id, code)
+            .await?;
 
-        let ctx = params.into_context(tmpl.params.as_ref())?;
+        let mut ctx = params.into_context(tmpl.params.as_ref())?;
+        let unresolved = self
+            .resolve_account_aliases(db, tenant_id, &tmpl, &mut ctx)
+            .await?;
 
-        let journal_id: Uuid = tmpl.transaction.journal_id.try_evaluate(&ctx)?;
+        self.prepare_from_context(tenant_id, tx_id, time, &tmpl, &validators, &ctx)
+            .map_err(
+                |e| match unresolved.lock().expect("poisoned mutex").take() {
+                    Some(alias) => {
+                        TxTemplateError::AccountAliasNotFound(alias.namespace, alias.value)
+                    }
+                    None => e,
+                },
+            )
+    }
 
-        let entries = self.prep_entries(&tmpl, tx_id, JournalId::from(journal_id), &ctx)?;
+    fn prepare_from_context(
+        &self,
+        tenant_id: Option<TenantId>,
+        tx_id: TransactionId,
+        time: chrono::DateTime<chrono::Utc>,
+        tmpl: &TxTemplateValues,
+        validators: &TxTemplateValidators,
+        ctx: &CelContext,
+    ) -> Result<PreparedTransaction, TxTemplateError> {
+        let journal_id: Uuid = tmpl.transaction.journal_id.try_evaluate(ctx)?;
+
+        let entries =
+            self.prep_entries(tmpl, validators, tx_id, JournalId::from(journal_id), ctx)?;
 
         let mut tx_builder = NewTransaction::builder();
         tx_builder
@@ -119,40 +164,94 @@ impl TxTemplates {
 
         tx_builder.journal_id(journal_id);
 
-        let effective: NaiveDate = tmpl.transaction.effective.try_evaluate(&ctx)?;
+        let effective: NaiveDate = tmpl.transaction.effective.try_evaluate(ctx)?;
         tx_builder.effective(effective);
 
         if let Some(correlation_id) = tmpl.transaction.correlation_id.as_ref() {
-            let correlation_id: String = correlation_id.try_evaluate(&ctx)?;
+            let correlation_id: String = correlation_id.try_evaluate(ctx)?;
             tx_builder.correlation_id(correlation_id);
         }
 
         if let Some(external_id) = tmpl.transaction.external_id.as_ref() {
-            let external_id: String = external_id.try_evaluate(&ctx)?;
+            let external_id: String = external_id.try_evaluate(ctx)?;
             tx_builder.external_id(external_id);
         }
 
         if let Some(description) = tmpl.transaction.description.as_ref() {
-            let description: String = description.try_evaluate(&ctx)?;
+            let description: String = description.try_evaluate(ctx)?;
             tx_builder.description(description);
         }
 
-        if let Some(metadata) = tmpl.transaction.metadata.as_ref() {
-            let metadata: serde_json::Value = metadata.try_evaluate(&ctx)?;
+        let metadata = tmpl
+            .transaction
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.try_evaluate::<serde_json::Value>(ctx))
+            .transpose()?;
+        if let Some(validator) = validators.transaction.as_ref() {
+            validator
+                .validate(metadata.as_ref())
+                .map_err(TxTemplateError::InvalidTransactionMetadata)?;
+        }
+        if let Some(metadata) = metadata {
             tx_builder.metadata(metadata);
         }
 
         let tx = tx_builder.build().expect("tx_build should succeed");
 
         Ok(PreparedTransaction {
+            tenant_id,
             transaction: tx,
             entries,
         })
     }
 
+    /// Makes `account(namespace, value)` available to the expressions of the template.
+    /// The aliases of the calls found in the expressions are resolved for the tenant with
+    /// a single lookup. An alias that is not found only fails the preparation when the
+    /// call is actually evaluated, which is recorded in the returned slot.
+    async fn resolve_account_aliases(
+        &self,
+        db: &mut LedgerOperation<'_>,
+        tenant_id: Option<TenantId>,
+        tmpl: &TxTemplateValues,
+        ctx: &mut CelContext,
+    ) -> Result<Arc<Mutex<Option<AccountAlias>>>, TxTemplateError> {
+        let requested: Vec<_> = template_expressions(tmpl)
+            .flat_map(|expr| expr.evaluate_call_args("account", ctx))
+            .filter_map(|args| alias_from_args(args).ok())
+            .collect();
+        let resolved = if requested.is_empty() {
+            HashMap::new()
+        } else {
+            self.accounts
+                .find_ids_by_aliases_in_op(db, tenant_id, &requested)
+                .await?
+        };
+
+        let unresolved = Arc::new(Mutex::new(None));
+        let slot = Arc::clone(&unresolved);
+        ctx.add_function("account", move |args| {
+            let alias = alias_from_args(args)?;
+            match resolved.get(&alias) {
+                Some(account_id) => Ok(CelValue::Uuid(Uuid::from(*account_id))),
+                None => {
+                    let err = CelError::Unexpected(format!(
+                        "account alias '{}:{}' not found",
+                        alias.namespace, alias.value
+                    ));
+                    *slot.lock().expect("poisoned mutex") = Some(alias);
+                    Err(err)
+                }
+            }
+        });
+        Ok(unresolved)
+    }
+
     fn prep_entries(
         &self,
         tmpl: &TxTemplateValues,
+        validators: &TxTemplateValidators,
         transaction_id: TransactionId,
         journal_id: JournalId,
         ctx: &cel_interpreter::CelContext,
@@ -160,11 +259,15 @@ impl TxTemplates {
         let mut new_entries = Vec::new();
         let mut totals = HashMap::new();
         for (zero_based_sequence, entry) in tmpl.entries.iter().enumerate() {
+            let entry_journal_id = match entry.journal_id.as_ref() {
+                Some(expr) => JournalId::from(expr.try_evaluate::<Uuid>(ctx)?),
+                None => journal_id,
+            };
             let mut builder = NewEntry::builder();
             builder
                 .id(EntryId::new())
                 .transaction_id(transaction_id)
-                .journal_id(journal_id)
+                .journal_id(entry_journal_id)
                 .sequence(zero_based_sequence as u32 + 1);
             let account_id: Uuid = entry.account_id.try_evaluate(ctx)?;
             builder.account_id(account_id);
@@ -179,7 +282,9 @@ impl TxTemplates {
             let currency: Currency = entry.currency.try_evaluate(ctx)?;
             let direction: DebitOrCredit = entry.direction.try_evaluate(ctx)?;
 
-            let total = totals.entry((currency, layer)).or_insert(Decimal::ZERO);
+            let total = totals
+                .entry((entry_journal_id, currency, layer))
+                .or_insert(Decimal::ZERO);
             match direction {
                 DebitOrCredit::Debit => *total -= units,
                 DebitOrCredit::Credit => *total += units,
@@ -193,18 +298,31 @@ impl TxTemplates {
                 builder.description(description);
             }
 
-            if let Some(metadata) = entry.metadata.as_ref() {
-                let metadata: serde_json::Value = metadata.try_evaluate(ctx)?;
+            let metadata = entry
+                .metadata
+                .as_ref()
+                .map(|metadata| metadata.try_evaluate::<serde_json::Value>(ctx))
+                .transpose()?;
+            if let Some(validator) = validators.entries[zero_based_sequence].as_ref() {
+                validator.validate(metadata.as_ref()).map_err(|e| {
+                    TxTemplateError::InvalidEntryMetadata(zero_based_sequence as u32 + 1, e)
+                })?;
+            }
+            if let Some(metadata) = metadata {
                 builder.metadata(metadata);
             }
 
             new_entries.push(builder.build().expect("Couldn't build entry"));
         }
 
-        for ((c, l), v) in totals {
-            if v != Decimal::ZERO {
+        for ((j, c, l), v) in totals {
+            if v == Decimal::ZERO {
+                continue;
+            }
+            if j == journal_id {
                 return Err(TxTemplateError::UnbalancedTransaction(c, l, v));
             }
+            return Err(TxTemplateError::UnbalancedJournal(j, c, l, v));
         }
 
         Ok(new_entries)
@@ -233,6 +351,45 @@ impl TxTemplates {
     }
 }
 
+fn template_expressions(tmpl: &TxTemplateValues) -> impl Iterator<Item = &CelExpression> {
+    let tx = &tmpl.transaction;
+    [
+        Some(&tx.effective),
+        Some(&tx.journal_id),
+        tx.correlation_id.as_ref(),
+        tx.external_id.as_ref(),
+        tx.description.as_ref(),
+        tx.metadata.as_ref(),
+    ]
+    .into_iter()
+    .chain(tmpl.entries.iter().flat_map(|entry| {
+        [
+            Some(&entry.entry_type),
+            Some(&entry.account_id),
+            Some(&entry.layer),
+            Some(&entry.direction),
+            Some(&entry.units),
+            Some(&entry.currency),
+            entry.journal_id.as_ref(),
+            entry.description.as_ref(),
+            entry.metadata.as_ref(),
+        ]
+    }))
+    .flatten()
+}
+
+fn alias_from_args(args: Vec<CelValue>) -> Result<AccountAlias, CelError> {
+    match args.as_slice() {
+        [CelValue::String(namespace), CelValue::String(value)] => {
+            Ok(AccountAlias::new(namespace.as_str(), value.as_str()))
+        }
+        [_, _] => Err(CelError::NoMatchingOverload(
+            "account(namespace, value) expects two strings".to_string(),
+        )),
+        _ => Err(CelError::MissingArgument),
+    }
+}
+
 impl From<&TxTemplateEvent> for OutboxEventPayload {
     fn from(event: &TxTemplateEvent) -> Self {
         match event {
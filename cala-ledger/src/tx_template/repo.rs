@@ -5,10 +5,57 @@ use sqlx::PgPool;
 
 use std::sync::Arc;
 
-use crate::primitives::DataSourceId;
+use crate::{
+    metadata_schema::MetadataValidator,
+    primitives::{DataSourceId, TenantId},
+};
 
 use super::{entity::*, error::TxTemplateError};
 
+/// A version of a template together with the validators compiled from its metadata schemas.
+#[derive(Clone)]
+pub(super) struct VersionedTxTemplate {
+    pub values: Arc<TxTemplateValues>,
+    pub validators: Arc<TxTemplateValidators>,
+}
+
+pub(super) struct TxTemplateValidators {
+    pub transaction: Option<MetadataValidator>,
+    /// One per entry of the template.
+    pub entries: Vec<Option<MetadataValidator>>,
+}
+
+impl TxTemplateValidators {
+    fn compile(values: &TxTemplateValues) -> Result<Self, TxTemplateError> {
+        let transaction = values
+            .transaction
+            .metadata_schema
+            .as_ref()
+            .map(MetadataValidator::new)
+            .transpose()
+            .map_err(TxTemplateError::InvalidTransactionMetadata)?;
+        let entries = values
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(zero_based_sequence, entry)| {
+                entry
+                    .metadata_schema
+                    .as_ref()
+                    .map(MetadataValidator::new)
+                    .transpose()
+                    .map_err(|e| {
+                        TxTemplateError::InvalidEntryMetadata(zero_based_sequence as u32 + 1, e)
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            transaction,
+            entries,
+        })
+    }
+}
+
 #[derive(EsRepo, Clone)]
 #[es_repo(
     entity = "TxTemplate",
@@ -19,6 +66,7 @@ use super::{entity::*, error::TxTemplateError};
             update(accessor = "values().code", persist = false),
             list_by
         ),
+        tenant_id(ty = "Option<TenantId>", update(persist = false)),
         data_source_id(
             ty = "DataSourceId",
             create(accessor = "data_source().into()"),
@@ -40,16 +88,18 @@ impl TxTemplateRepo {
     pub async fn find_latest_version_in_op(
         &self,
         op: &mut impl es_entity::AtomicOperation,
+        tenant_id: Option<TenantId>,
         code: &str,
-    ) -> Result<Arc<TxTemplateValues>, TxTemplateError> {
+    ) -> Result<VersionedTxTemplate, TxTemplateError> {
         let row = sqlx::query!(
             r#"
             SELECT t.id AS "id?: TxTemplateId", MAX(e.sequence) AS "version" 
             FROM cala_tx_templates t
             JOIN cala_tx_template_events e ON t.id = e.id
-            WHERE t.code = $1
+            WHERE t.code = $1 AND t.tenant_id IS NOT DISTINCT FROM $2
             GROUP BY t.id"#,
             code,
+            tenant_id as Option<TenantId>,
         )
         .fetch_optional(op.as_executor())
         .await?;
@@ -61,6 +111,23 @@ impl TxTemplateRepo {
         Err(TxTemplateError::NotFound)
     }
 
+    pub async fn find_by_tenant_and_code(
+        &self,
+        tenant_id: Option<TenantId>,
+        code: String,
+    ) -> Result<TxTemplate, TxTemplateError> {
+        es_query!(
+            tbl_prefix = "cala",
+            r#"SELECT id FROM cala_tx_templates
+            WHERE tenant_id IS NOT DISTINCT FROM $1 AND code = $2"#,
+            tenant_id as Option<TenantId>,
+            &code,
+        )
+        .fetch_optional(self.pool())
+        .await?
+        .ok_or(TxTemplateError::CouldNotFindByCode(code))
+    }
+
     #[cfg(feature = "import")]
     pub async fn import_in_op(
         &self,
@@ -70,11 +137,12 @@ impl TxTemplateRepo {
     ) -> Result<(), TxTemplateError> {
         let recorded_at = op.now();
         sqlx::query!(
-            r#"INSERT INTO cala_tx_templates (data_source_id, id, code, created_at)
-            VALUES ($1, $2, $3, $4)"#,
+            r#"INSERT INTO cala_tx_templates (data_source_id, id, code, tenant_id, created_at)
+            VALUES ($1, $2, $3, $4, $5)"#,
             origin as DataSourceId,
             tx_template.values().id as TxTemplateId,
             tx_template.values().code,
+            tx_template.values().tenant_id as Option<TenantId>,
             recorded_at
         )
         .execute(op.as_executor())
@@ -94,7 +162,7 @@ async fn find_versioned_template_cached(
     op: &mut impl es_entity::AtomicOperation,
     id: TxTemplateId,
     version: i32,
-) -> Result<Arc<TxTemplateValues>, TxTemplateError> {
+) -> Result<VersionedTxTemplate, TxTemplateError> {
     let row = sqlx::query!(
         r#"
           SELECT event
@@ -107,7 +175,12 @@ async fn find_versioned_template_cached(
     .await?;
     if let Some(row) = row {
         let event: TxTemplateEvent = serde_json::from_value(row.event)?;
-        Ok(Arc::new(event.into_values()))
+        let values = event.into_values();
+        let validators = TxTemplateValidators::compile(&values)?;
+        Ok(VersionedTxTemplate {
+            values: Arc::new(values),
+            validators: Arc::new(validators),
+        })
     } else {
         Err(TxTemplateError::NotFound)
     }
@@ -24,7 +24,7 @@ pub(super) use value::*;
 
 #[derive(Clone)]
 pub struct AccountControls {
-    _pool: PgPool,
+    pool: PgPool,
     repo: AccountControlRepo,
 }
 
@@ -32,7 +32,7 @@ impl AccountControls {
     pub fn new(pool: &PgPool) -> Self {
         Self {
             repo: AccountControlRepo::new(pool),
-            _pool: pool.clone(),
+            pool: pool.clone(),
         }
     }
 
@@ -45,6 +45,12 @@ impl AccountControls {
         limits: Vec<VelocityLimitValues>,
         params: impl Into<Params> + std::fmt::Debug,
     ) -> Result<(), VelocityError> {
+        if self.repo.is_sharded(&mut *db, account_id).await? {
+            return Err(VelocityError::AccountBalanceSharded(account_id));
+        }
+        if self.repo.find_tenant(&mut *db, account_id).await? != control.tenant_id {
+            return Err(VelocityError::TenantMismatch(control.id, account_id));
+        }
         let params = params.into();
 
         let mut velocity_limits = Vec::new();
@@ -110,4 +116,16 @@ impl AccountControls {
     > {
         self.repo.find_for_enforcement(db, account_ids).await
     }
+
+    pub async fn find_for_account(
+        &self,
+        account_id: AccountId,
+    ) -> Result<Option<(VelocityContextAccountValues, Vec<AccountVelocityControl>)>, VelocityError>
+    {
+        let mut controls = self
+            .repo
+            .find_for_enforcement(&self.pool, &[account_id])
+            .await?;
+        Ok(controls.remove(&account_id))
+    }
 }
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 
 use cala_types::velocity::VelocityContextAccountValues;
 
-use crate::primitives::{AccountId, VelocityControlId};
+use crate::primitives::{AccountId, TenantId, VelocityControlId};
 
 use super::{super::error::*, value::*};
 
@@ -37,6 +37,41 @@ impl AccountControlRepo {
         Ok(())
     }
 
+    pub async fn is_sharded(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        account_id: AccountId,
+    ) -> Result<bool, VelocityError> {
+        let row = op
+            .into_executor()
+            .fetch_optional(sqlx::query!(
+                r#"SELECT balance_shards FROM cala_accounts WHERE id = $1"#,
+                account_id as AccountId,
+            ))
+            .await?;
+        Ok(row.is_some_and(|row| row.balance_shards.is_some()))
+    }
+
+    /// Tenant of the account, or of the journal of the account set for account sets.
+    pub async fn find_tenant(
+        &self,
+        op: impl es_entity::IntoOneTimeExecutor<'_>,
+        account_id: AccountId,
+    ) -> Result<Option<TenantId>, VelocityError> {
+        let row = op
+            .into_executor()
+            .fetch_optional(sqlx::query!(
+                r#"SELECT CASE WHEN s.id IS NULL THEN a.tenant_id ELSE j.tenant_id END AS "tenant_id: TenantId"
+                FROM cala_accounts a
+                LEFT JOIN cala_account_sets s ON s.id = a.id
+                LEFT JOIN cala_journals j ON j.id = s.journal_id
+                WHERE a.id = $1"#,
+                account_id as AccountId,
+            ))
+            .await?;
+        Ok(row.and_then(|row| row.tenant_id))
+    }
+
     pub async fn find_for_enforcement(
         &self,
         op: impl es_entity::IntoOneTimeExecutor<'_>,
@@ -77,9 +77,26 @@ impl AccountVelocityLimit {
         time: DateTime<Utc>,
         snapshot: &BalanceSnapshot,
     ) -> Result<(), VelocityError> {
+        if let Some(exceeded) = self
+            .exceeded_limits(ctx, time, snapshot)?
+            .into_iter()
+            .next()
+        {
+            return Err(exceeded.into());
+        }
+        Ok(())
+    }
+
+    pub fn exceeded_limits(
+        &self,
+        ctx: &CelContext,
+        time: DateTime<Utc>,
+        snapshot: &BalanceSnapshot,
+    ) -> Result<Vec<LimitExceededError>, VelocityError> {
+        let mut exceeded = Vec::new();
         if let Some(currency) = &self.currency {
             if currency != &snapshot.currency {
-                return Ok(());
+                return Ok(exceeded);
             }
         }
         let time = if let Some(source) = &self.limit.timestamp_source {
@@ -88,20 +105,15 @@ impl AccountVelocityLimit {
             time
         };
         for limit in self.limit.balance.iter() {
-            if limit.start > time {
+            if !limit.is_active_at(time) {
                 continue;
             }
-            if let Some(end) = limit.end {
-                if end <= time {
-                    continue;
-                }
-            }
             let balance =
                 crate::balance::BalanceWithDirection::new(limit.enforcement_direction, snapshot);
             let requested = balance.available(limit.layer);
 
             if requested > limit.amount {
-                return Err(LimitExceededError {
+                exceeded.push(LimitExceededError {
                     account_id: snapshot.account_id,
                     currency: snapshot.currency,
                     direction: limit.enforcement_direction,
@@ -109,12 +121,11 @@ impl AccountVelocityLimit {
                     layer: limit.layer,
                     limit: limit.amount,
                     requested,
-                }
-                .into());
+                });
             }
         }
 
-        Ok(())
+        Ok(exceeded)
     }
 }
 
@@ -133,6 +144,12 @@ pub struct AccountBalanceLimit {
     pub end: Option<DateTime<Utc>>,
 }
 
+impl AccountBalanceLimit {
+    pub fn is_active_at(&self, time: DateTime<Utc>) -> bool {
+        self.start <= time && self.end.is_none_or(|end| time < end)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::primitives::*;
@@ -1,24 +1,54 @@
 mod repo;
 
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use sqlx::PgPool;
 
 use std::collections::HashMap;
 
 use cala_types::{
-    balance::BalanceSnapshot, entry::EntryValues, transaction::TransactionValues,
-    velocity::VelocityContextAccountValues,
+    balance::BalanceSnapshot,
+    entry::EntryValues,
+    transaction::TransactionValues,
+    velocity::{
+        VelocityBalance, VelocityBreach, VelocityContextAccountValues, VelocityEnforcementAction,
+    },
 };
 
 use crate::{
+    balance::BalanceWithDirection,
     ledger_operation::*,
-    primitives::{AccountId, AccountSetId},
+    primitives::{
+        AccountId, AccountSetId, Currency, DebitOrCredit, EntryId, JournalId, Layer, TransactionId,
+        TxTemplateId, VelocityBreachId,
+    },
 };
 
 use super::{account_control::*, error::*};
 
 use repo::*;
 
+/// The entry a lookup of velocity balances assumes is about to be posted. Conditions and
+/// windows of the limits are evaluated against it (with zero `units`) so that limits
+/// depending on entry or transaction fields resolve to the window the entry would hit.
+#[derive(Debug, Clone)]
+pub struct HypotheticalEntry {
+    pub journal_id: JournalId,
+    pub entry_type: String,
+    /// Used for limits without a fixed currency. When `None` such limits are only reported
+    /// for the currencies that were already posted against them.
+    pub currency: Option<Currency>,
+    /// Set as the metadata of both the entry and its transaction.
+    pub metadata: Option<serde_json::Value>,
+}
+
+type LimitToEnforce<'a> = (
+    VelocityEnforcementAction,
+    &'a AccountVelocityLimit,
+    &'a EntryValues,
+);
+type ExceededKey<'a> = (&'a VelocityBalanceKey, Layer, DebitOrCredit);
+
 #[derive(Clone)]
 pub(super) struct VelocityBalances {
     repo: VelocityBalanceRepo,
@@ -31,6 +61,8 @@ impl VelocityBalances {
         }
     }
 
+    /// Limits of controls that don't reject are not enforced, exceeding them is returned
+    /// as breaches instead.
     pub(crate) async fn update_balances_with_limit_enforcement_in_op(
         &self,
         db: &mut LedgerOperation<'_>,
@@ -39,7 +71,7 @@ impl VelocityBalances {
         entries: &[EntryValues],
         controls: HashMap<AccountId, (VelocityContextAccountValues, Vec<AccountVelocityControl>)>,
         account_set_mappings: &HashMap<AccountId, Vec<AccountSetId>>,
-    ) -> Result<(), VelocityError> {
+    ) -> Result<Vec<VelocityBreach>, VelocityError> {
         let mut context =
             super::context::EvalContext::new(transaction, controls.values().map(|v| &v.0));
 
@@ -47,7 +79,7 @@ impl VelocityBalances {
             Self::balances_to_check(&mut context, entries, &controls, account_set_mappings)?;
 
         if entries_to_enforce.is_empty() {
-            return Ok(());
+            return Ok(Vec::new());
         }
 
         let current_balances = self
@@ -55,7 +87,7 @@ impl VelocityBalances {
             .find_for_update(db, entries_to_enforce.keys())
             .await?;
 
-        let new_balances = Self::new_snapshots_with_limit_enforcement(
+        let (new_balances, exceeded) = Self::new_snapshots_with_limit_enforcement(
             context,
             created_at,
             current_balances,
@@ -64,7 +96,167 @@ impl VelocityBalances {
 
         self.repo.insert_new_snapshots(db, new_balances).await?;
 
-        Ok(())
+        let breaches = exceeded
+            .into_iter()
+            .map(|((key, _, _), (action, exceeded))| VelocityBreach {
+                id: VelocityBreachId::new(),
+                control_id: key.control_id,
+                limit_id: exceeded.limit_id,
+                account_id: exceeded.account_id,
+                transaction_id: transaction.id,
+                action,
+                currency: exceeded.currency,
+                layer: exceeded.layer,
+                direction: exceeded.direction,
+                limit: exceeded.limit,
+                requested: exceeded.requested,
+                created_at,
+            })
+            .collect();
+
+        Ok(breaches)
+    }
+
+    pub(super) async fn find_for_account(
+        &self,
+        account: &VelocityContextAccountValues,
+        controls: &[AccountVelocityControl],
+        hypothetical: &HypotheticalEntry,
+        at_time: DateTime<Utc>,
+    ) -> Result<Vec<VelocityBalance>, VelocityError> {
+        let transaction = Self::hypothetical_transaction(hypothetical, at_time);
+        let mut context = super::context::EvalContext::new(&transaction, std::iter::once(account));
+        let used_currencies = self.repo.find_currencies_for_account(account.id).await?;
+
+        let mut windows = Vec::new();
+        for control in controls {
+            for limit in control.velocity_limits.iter() {
+                let currencies = match limit.currency.or(hypothetical.currency) {
+                    Some(currency) => vec![currency],
+                    None => used_currencies
+                        .get(&(control.control_id, limit.limit_id))
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                for currency in currencies {
+                    for balance_limit in limit.limit.balance.iter() {
+                        if !balance_limit.is_active_at(at_time) {
+                            continue;
+                        }
+                        let entry = Self::hypothetical_entry(
+                            &transaction,
+                            hypothetical,
+                            account.id,
+                            currency,
+                            balance_limit,
+                        );
+                        let ctx = context.context_for_entry(account.id, &entry);
+                        if !control.needs_enforcement(&ctx)? {
+                            continue;
+                        }
+                        let Some(window) = limit.window_for_enforcement(&ctx, &entry)? else {
+                            continue;
+                        };
+                        let key = VelocityWindowKey {
+                            window,
+                            currency,
+                            control_id: control.control_id,
+                            limit_id: limit.limit_id,
+                        };
+                        windows.push((key, balance_limit));
+                    }
+                }
+            }
+        }
+
+        if windows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let current = self
+            .repo
+            .find_current_for_account(account.id, windows.iter().map(|(key, _)| key))
+            .await?;
+
+        let mut res = Vec::new();
+        for (key, balance_limit) in windows {
+            let snapshots = current.get(&key).cloned().unwrap_or_default();
+            let snapshots = if snapshots.is_empty() {
+                vec![None]
+            } else {
+                snapshots.into_iter().map(Some).collect()
+            };
+            for snapshot in snapshots {
+                let spent = snapshot
+                    .as_ref()
+                    .map(|s| {
+                        BalanceWithDirection::new(balance_limit.enforcement_direction, s)
+                            .available(balance_limit.layer)
+                    })
+                    .unwrap_or(Decimal::ZERO);
+                res.push(VelocityBalance {
+                    control_id: key.control_id,
+                    limit_id: key.limit_id,
+                    window: key.window.clone(),
+                    currency: key.currency,
+                    layer: balance_limit.layer,
+                    direction: balance_limit.enforcement_direction,
+                    limit: balance_limit.amount,
+                    spent,
+                    remaining: (balance_limit.amount - spent).max(Decimal::ZERO),
+                    balance: snapshot,
+                });
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn hypothetical_transaction(
+        hypothetical: &HypotheticalEntry,
+        at_time: DateTime<Utc>,
+    ) -> TransactionValues {
+        let id = TransactionId::new();
+        TransactionValues {
+            id,
+            version: 1,
+            created_at: at_time,
+            modified_at: at_time,
+            journal_id: hypothetical.journal_id,
+            tx_template_id: TxTemplateId::from(uuid::Uuid::nil()),
+            entry_ids: Vec::new(),
+            effective: at_time.date_naive(),
+            correlation_id: id.to_string(),
+            external_id: None,
+            description: None,
+            voided_by: None,
+            void_of: None,
+            metadata: hypothetical.metadata.clone(),
+        }
+    }
+
+    fn hypothetical_entry(
+        transaction: &TransactionValues,
+        hypothetical: &HypotheticalEntry,
+        account_id: AccountId,
+        currency: Currency,
+        limit: &AccountBalanceLimit,
+    ) -> EntryValues {
+        EntryValues {
+            id: EntryId::new(),
+            version: 1,
+            transaction_id: transaction.id,
+            journal_id: transaction.journal_id,
+            account_id,
+            entry_type: hypothetical.entry_type.clone(),
+            sequence: 1,
+            layer: limit.layer,
+            currency,
+            direction: limit.enforcement_direction,
+            units: Decimal::ZERO,
+            description: None,
+            metadata: hypothetical.metadata.clone(),
+        }
     }
 
     #[allow(clippy::type_complexity)]
@@ -76,14 +268,9 @@ impl VelocityBalances {
             (VelocityContextAccountValues, Vec<AccountVelocityControl>),
         >,
         account_set_mappings: &HashMap<AccountId, Vec<AccountSetId>>,
-    ) -> Result<
-        HashMap<VelocityBalanceKey, Vec<(&'a AccountVelocityLimit, &'a EntryValues)>>,
-        VelocityError,
-    > {
-        let mut balances_to_check: HashMap<
-            VelocityBalanceKey,
-            Vec<(&AccountVelocityLimit, &EntryValues)>,
-        > = HashMap::new();
+    ) -> Result<HashMap<VelocityBalanceKey, Vec<LimitToEnforce<'a>>>, VelocityError> {
+        let mut balances_to_check: HashMap<VelocityBalanceKey, Vec<LimitToEnforce>> =
+            HashMap::new();
         let empty = Vec::new();
         for entry in entries {
             for account_id in account_set_mappings
@@ -114,7 +301,7 @@ impl VelocityBalances {
                                     limit_id: limit.limit_id,
                                 })
                                 .or_default()
-                                .push((limit, entry));
+                                .push((control.enforcement.action, limit, entry));
                         }
                     }
                 }
@@ -124,13 +311,21 @@ impl VelocityBalances {
         Ok(balances_to_check)
     }
 
+    #[allow(clippy::type_complexity)]
     fn new_snapshots_with_limit_enforcement<'a>(
         mut context: super::context::EvalContext,
         time: DateTime<Utc>,
         mut current_balances: HashMap<VelocityBalanceKey, Option<BalanceSnapshot>>,
-        entries_to_add: &'a HashMap<VelocityBalanceKey, Vec<(&AccountVelocityLimit, &EntryValues)>>,
-    ) -> Result<HashMap<&'a VelocityBalanceKey, Vec<BalanceSnapshot>>, VelocityError> {
+        entries_to_add: &'a HashMap<VelocityBalanceKey, Vec<LimitToEnforce>>,
+    ) -> Result<
+        (
+            HashMap<&'a VelocityBalanceKey, Vec<BalanceSnapshot>>,
+            HashMap<ExceededKey<'a>, (VelocityEnforcementAction, LimitExceededError)>,
+        ),
+        VelocityError,
+    > {
         let mut res = HashMap::new();
+        let mut exceeded_limits = HashMap::new();
 
         for (key, entries) in entries_to_add.iter() {
             let mut latest_balance = current_balances
@@ -139,7 +334,7 @@ impl VelocityBalances {
 
             let mut new_balances = Vec::new();
 
-            for (limit, entry) in entries {
+            for (action, limit, entry) in entries {
                 let new_balance = match latest_balance.take() {
                     Some(balance) => {
                         crate::balance::Snapshots::update_snapshot(time, balance, entry)
@@ -148,7 +343,17 @@ impl VelocityBalances {
                 };
 
                 let ctx = context.context_for_entry(key.account_id, entry);
-                limit.enforce(&ctx, time, &new_balance)?;
+                if *action == VelocityEnforcementAction::Reject {
+                    limit.enforce(&ctx, time, &new_balance)?;
+                } else {
+                    // Later entries replace the breach so it reflects the final amount
+                    for exceeded in limit.exceeded_limits(&ctx, time, &new_balance)? {
+                        exceeded_limits.insert(
+                            (key, exceeded.layer, exceeded.direction),
+                            (*action, exceeded),
+                        );
+                    }
+                }
 
                 new_balances.push(new_balance.clone());
                 latest_balance = Some(new_balance);
@@ -156,7 +361,7 @@ impl VelocityBalances {
 
             res.insert(key, new_balances);
         }
-        Ok(res)
+        Ok((res, exceeded_limits))
     }
 }
 
@@ -315,7 +520,10 @@ mod tests {
             entry.account_id = key.account_id;
 
             let mut entries_to_add = HashMap::new();
-            entries_to_add.insert(key.clone(), vec![(&limit, &entry)]);
+            entries_to_add.insert(
+                key.clone(),
+                vec![(VelocityEnforcementAction::Reject, &limit, &entry)],
+            );
 
             let version = 5;
             let existing_balance =
@@ -323,13 +531,14 @@ mod tests {
             let mut current_balances = HashMap::new();
             current_balances.insert(key.clone(), Some(existing_balance));
 
-            let result = VelocityBalances::new_snapshots_with_limit_enforcement(
+            let (result, exceeded) = VelocityBalances::new_snapshots_with_limit_enforcement(
                 context,
                 Utc::now(),
                 current_balances,
                 &entries_to_add,
             )
             .unwrap();
+            assert!(exceeded.is_empty());
 
             assert_eq!(result.len(), 1);
             let snapshots = result.get(&key).unwrap();
@@ -356,18 +565,22 @@ mod tests {
             entry.account_id = key.account_id;
 
             let mut entries_to_add = HashMap::new();
-            entries_to_add.insert(key.clone(), vec![(&limit, &entry)]);
+            entries_to_add.insert(
+                key.clone(),
+                vec![(VelocityEnforcementAction::Reject, &limit, &entry)],
+            );
 
             let mut current_balances = HashMap::new();
             current_balances.insert(key.clone(), None);
 
-            let result = VelocityBalances::new_snapshots_with_limit_enforcement(
+            let (result, exceeded) = VelocityBalances::new_snapshots_with_limit_enforcement(
                 context,
                 Utc::now(),
                 current_balances,
                 &entries_to_add,
             )
             .unwrap();
+            assert!(exceeded.is_empty());
 
             assert_eq!(result.len(), 1);
             let snapshots = result.get(&key).unwrap();
@@ -409,15 +622,22 @@ mod tests {
             entry2.account_id = key.account_id;
 
             let mut entries_to_add = HashMap::new();
-            entries_to_add.insert(key.clone(), vec![(&limit, &entry1), (&limit, &entry2)]);
+            entries_to_add.insert(
+                key.clone(),
+                vec![
+                    (VelocityEnforcementAction::Reject, &limit, &entry1),
+                    (VelocityEnforcementAction::Reject, &limit, &entry2),
+                ],
+            );
 
-            let result = VelocityBalances::new_snapshots_with_limit_enforcement(
+            let (result, exceeded) = VelocityBalances::new_snapshots_with_limit_enforcement(
                 context,
                 Utc::now(),
                 current_balances,
                 &entries_to_add,
             )
             .unwrap();
+            assert!(exceeded.is_empty());
 
             assert_eq!(result.len(), 1);
             let snapshots = result.get(&key).unwrap();
@@ -455,7 +675,10 @@ mod tests {
             entry.account_id = key.account_id;
 
             let mut entries_to_add = HashMap::new();
-            entries_to_add.insert(key.clone(), vec![(&limit, &entry)]);
+            entries_to_add.insert(
+                key.clone(),
+                vec![(VelocityEnforcementAction::Reject, &limit, &entry)],
+            );
 
             let current_balances = HashMap::new();
 
@@ -501,7 +724,10 @@ mod tests {
             entry.account_id = key.account_id;
 
             let mut entries_to_add = HashMap::new();
-            entries_to_add.insert(key.clone(), vec![(&limit, &entry)]);
+            entries_to_add.insert(
+                key.clone(),
+                vec![(VelocityEnforcementAction::Reject, &limit, &entry)],
+            );
 
             let mut current_balances = HashMap::new();
             current_balances.insert(key.clone(), None);
@@ -514,5 +740,74 @@ mod tests {
             );
             assert!(matches!(result, Err(VelocityError::Enforcement(_))));
         }
+
+        #[test]
+        fn new_snapshots_returns_exceeded_limits_when_not_rejecting() {
+            let key = create_test_key();
+
+            let transaction = create_test_transaction();
+            let account = create_test_account_values(key.account_id);
+            let context = EvalContext::new(&transaction, [&account].into_iter());
+
+            let limit = AccountVelocityLimit {
+                limit_id: key.limit_id,
+                window: Default::default(),
+                condition: None,
+                currency: None,
+                limit: AccountLimit {
+                    timestamp_source: None,
+                    balance: vec![AccountBalanceLimit {
+                        layer: Layer::Settled,
+                        amount: Decimal::from(100),
+                        enforcement_direction: DebitOrCredit::Debit,
+                        start: Utc::now() - chrono::Duration::seconds(1),
+                        end: None,
+                    }],
+                },
+            };
+
+            let mut entry1 = create_test_entry(
+                Decimal::from(60),
+                DebitOrCredit::Debit,
+                Layer::Settled,
+                "USD",
+            );
+            entry1.account_id = key.account_id;
+            let mut entry2 = create_test_entry(
+                Decimal::from(70),
+                DebitOrCredit::Debit,
+                Layer::Settled,
+                "USD",
+            );
+            entry2.account_id = key.account_id;
+
+            let mut entries_to_add = HashMap::new();
+            entries_to_add.insert(
+                key.clone(),
+                vec![
+                    (VelocityEnforcementAction::Record, &limit, &entry1),
+                    (VelocityEnforcementAction::Record, &limit, &entry2),
+                ],
+            );
+
+            let mut current_balances = HashMap::new();
+            current_balances.insert(key.clone(), None);
+
+            let (result, exceeded) = VelocityBalances::new_snapshots_with_limit_enforcement(
+                context,
+                Utc::now(),
+                current_balances,
+                &entries_to_add,
+            )
+            .unwrap();
+
+            assert_eq!(result.get(&key).unwrap().len(), 2);
+            assert_eq!(exceeded.len(), 1);
+            let (action, exceeded) = exceeded
+                .get(&(&key, Layer::Settled, DebitOrCredit::Debit))
+                .unwrap();
+            assert_eq!(*action, VelocityEnforcementAction::Record);
+            assert_eq!(exceeded.requested, Decimal::from(130));
+        }
     }
 }
@@ -16,16 +16,113 @@ pub(super) struct VelocityBalanceKey {
     pub(super) limit_id: VelocityLimitId,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct VelocityWindowKey {
+    pub(super) window: Window,
+    pub(super) currency: Currency,
+    pub(super) control_id: VelocityControlId,
+    pub(super) limit_id: VelocityLimitId,
+}
+
 #[derive(Clone)]
 pub(super) struct VelocityBalanceRepo {
-    _pool: PgPool,
+    pool: PgPool,
 }
 
 impl VelocityBalanceRepo {
     pub fn new(pool: &PgPool) -> Self {
-        Self {
-            _pool: pool.clone(),
+        Self { pool: pool.clone() }
+    }
+
+    pub async fn find_currencies_for_account(
+        &self,
+        account_id: AccountId,
+    ) -> Result<HashMap<(VelocityControlId, VelocityLimitId), Vec<Currency>>, VelocityError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT
+                velocity_control_id AS "velocity_control_id: VelocityControlId",
+                velocity_limit_id AS "velocity_limit_id: VelocityLimitId",
+                currency
+            FROM cala_velocity_current_balances
+            WHERE account_id = $1
+            "#,
+            account_id as AccountId,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut ret: HashMap<_, Vec<Currency>> = HashMap::new();
+        for row in rows {
+            ret.entry((row.velocity_control_id, row.velocity_limit_id))
+                .or_default()
+                .push(row.currency.parse().expect("Could not parse currency"));
         }
+        Ok(ret)
+    }
+
+    pub async fn find_current_for_account(
+        &self,
+        account_id: AccountId,
+        keys: impl Iterator<Item = &VelocityWindowKey>,
+    ) -> Result<HashMap<VelocityWindowKey, Vec<BalanceSnapshot>>, VelocityError> {
+        let mut windows = Vec::new();
+        let mut currencies = Vec::new();
+        let mut control_ids = Vec::new();
+        let mut limit_ids = Vec::new();
+        for key in keys {
+            windows.push(key.window.inner().clone());
+            currencies.push(key.currency.code());
+            control_ids.push(key.control_id);
+            limit_ids.push(key.limit_id);
+        }
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                b.partition_window,
+                b.currency,
+                b.velocity_control_id AS "velocity_control_id: VelocityControlId",
+                b.velocity_limit_id AS "velocity_limit_id: VelocityLimitId",
+                b.latest_values
+            FROM UNNEST(
+              $2::jsonb[],
+              $3::text[],
+              $4::uuid[],
+              $5::uuid[]
+            )
+            AS i(partition_window, currency, velocity_control_id, velocity_limit_id)
+            JOIN cala_velocity_current_balances b
+              ON i.partition_window = b.partition_window
+              AND i.currency = b.currency
+              AND i.velocity_control_id = b.velocity_control_id
+              AND i.velocity_limit_id = b.velocity_limit_id
+            WHERE b.account_id = $1
+            ORDER BY b.journal_id
+            "#,
+            account_id as AccountId,
+            &windows[..],
+            &currencies as &[&str],
+            &control_ids as &[VelocityControlId],
+            &limit_ids as &[VelocityLimitId],
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut ret: HashMap<_, Vec<BalanceSnapshot>> = HashMap::new();
+        for row in rows {
+            let snapshot = serde_json::from_value::<BalanceSnapshot>(row.latest_values)
+                .expect("Failed to deserialize balance snapshot");
+            ret.entry(VelocityWindowKey {
+                window: Window::from(row.partition_window),
+                currency: row.currency.parse().expect("Could not parse currency"),
+                control_id: row.velocity_control_id,
+                limit_id: row.velocity_limit_id,
+            })
+            .or_default()
+            .push(snapshot);
+        }
+        Ok(ret)
     }
     pub async fn find_for_update(
         &self,
@@ -0,0 +1,33 @@
+use crate::primitives::{AccountId, TransactionId, VelocityControlId, VelocityLimitId};
+
+/// Criteria for [`Velocities::list_breaches`](crate::velocity::Velocities::list_breaches).
+/// All criteria that are set must match.
+#[derive(Debug, Default, Clone)]
+pub struct VelocityBreachFilter {
+    pub(super) account_id: Option<AccountId>,
+    pub(super) control_id: Option<VelocityControlId>,
+    pub(super) limit_id: Option<VelocityLimitId>,
+    pub(super) transaction_id: Option<TransactionId>,
+}
+
+impl VelocityBreachFilter {
+    pub fn account_id(mut self, account_id: impl Into<AccountId>) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    pub fn control_id(mut self, control_id: impl Into<VelocityControlId>) -> Self {
+        self.control_id = Some(control_id.into());
+        self
+    }
+
+    pub fn limit_id(mut self, limit_id: impl Into<VelocityLimitId>) -> Self {
+        self.limit_id = Some(limit_id.into());
+        self
+    }
+
+    pub fn transaction_id(mut self, transaction_id: impl Into<TransactionId>) -> Self {
+        self.transaction_id = Some(transaction_id.into());
+        self
+    }
+}
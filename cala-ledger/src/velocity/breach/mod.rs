@@ -0,0 +1,6 @@
+mod filter;
+mod repo;
+
+pub use filter::*;
+pub use repo::velocity_breach_cursor::*;
+pub(super) use repo::*;
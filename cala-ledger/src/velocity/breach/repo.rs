@@ -0,0 +1,179 @@
+use sqlx::PgPool;
+
+use cala_types::velocity::VelocityBreach;
+
+use super::filter::VelocityBreachFilter;
+use crate::{primitives::*, velocity::error::VelocityError};
+
+pub mod velocity_breach_cursor {
+    use serde::{Deserialize, Serialize};
+
+    use cala_types::velocity::VelocityBreach;
+
+    use crate::primitives::VelocityBreachId;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct VelocityBreachesByCreatedAtCursor {
+        pub id: VelocityBreachId,
+        pub created_at: chrono::DateTime<chrono::Utc>,
+    }
+
+    impl From<&VelocityBreach> for VelocityBreachesByCreatedAtCursor {
+        fn from(breach: &VelocityBreach) -> Self {
+            Self {
+                id: breach.id,
+                created_at: breach.created_at,
+            }
+        }
+    }
+
+    #[cfg(feature = "graphql")]
+    impl async_graphql::connection::CursorType for VelocityBreachesByCreatedAtCursor {
+        type Error = String;
+
+        fn encode_cursor(&self) -> String {
+            use base64::{engine::general_purpose, Engine as _};
+            let json = serde_json::to_string(&self).expect("could not serialize token");
+            general_purpose::STANDARD_NO_PAD.encode(json.as_bytes())
+        }
+
+        fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+            use base64::{engine::general_purpose, Engine as _};
+            let bytes = general_purpose::STANDARD_NO_PAD
+                .decode(s.as_bytes())
+                .map_err(|e| e.to_string())?;
+            let json = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+            serde_json::from_str(&json).map_err(|e| e.to_string())
+        }
+    }
+}
+
+use velocity_breach_cursor::*;
+
+#[derive(Clone)]
+pub(in crate::velocity) struct VelocityBreachRepo {
+    pool: PgPool,
+}
+
+impl VelocityBreachRepo {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    pub async fn insert_all_in_op(
+        &self,
+        op: &mut impl es_entity::AtomicOperation,
+        origin: DataSourceId,
+        breaches: &[VelocityBreach],
+    ) -> Result<(), VelocityError> {
+        let mut ids = Vec::new();
+        let mut control_ids = Vec::new();
+        let mut limit_ids = Vec::new();
+        let mut account_ids = Vec::new();
+        let mut transaction_ids = Vec::new();
+        let mut values = Vec::new();
+        let mut created_ats = Vec::new();
+        for breach in breaches {
+            ids.push(breach.id);
+            control_ids.push(breach.control_id);
+            limit_ids.push(breach.limit_id);
+            account_ids.push(breach.account_id);
+            transaction_ids.push(breach.transaction_id);
+            values.push(serde_json::to_value(breach).expect("Failed to serialize breach"));
+            created_ats.push(breach.created_at);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO cala_velocity_breaches (
+                data_source_id, id, velocity_control_id, velocity_limit_id, account_id,
+                transaction_id, values, created_at
+            )
+            SELECT $1, * FROM UNNEST(
+                $2::uuid[],
+                $3::uuid[],
+                $4::uuid[],
+                $5::uuid[],
+                $6::uuid[],
+                $7::jsonb[],
+                $8::timestamptz[]
+            )
+            "#,
+            origin as DataSourceId,
+            &ids as &[VelocityBreachId],
+            &control_ids as &[VelocityControlId],
+            &limit_ids as &[VelocityLimitId],
+            &account_ids as &[AccountId],
+            &transaction_ids as &[TransactionId],
+            &values,
+            &created_ats,
+        )
+        .execute(op.as_executor())
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list(
+        &self,
+        filter: &VelocityBreachFilter,
+        query: es_entity::PaginatedQueryArgs<VelocityBreachesByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<VelocityBreach, VelocityBreachesByCreatedAtCursor>,
+        VelocityError,
+    > {
+        let es_entity::PaginatedQueryArgs { first, after } = query;
+        let (id, created_at) = if let Some(after) = after {
+            (Some(after.id), Some(after.created_at))
+        } else {
+            (None, None)
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT values
+            FROM cala_velocity_breaches
+            WHERE ($4::uuid IS NULL OR account_id = $4)
+              AND ($5::uuid IS NULL OR velocity_control_id = $5)
+              AND ($6::uuid IS NULL OR velocity_limit_id = $6)
+              AND ($7::uuid IS NULL OR transaction_id = $7)
+              AND ($2::uuid IS NULL
+                OR ($8 AND (created_at, id) > ($3, $2))
+                OR (NOT $8 AND (created_at, id) < ($3, $2)))
+            ORDER BY
+                CASE WHEN $8 THEN created_at END ASC,
+                CASE WHEN $8 THEN id END ASC,
+                CASE WHEN NOT $8 THEN created_at END DESC,
+                CASE WHEN NOT $8 THEN id END DESC
+            LIMIT $1
+            "#,
+            (first + 1) as i64,
+            id as Option<VelocityBreachId>,
+            created_at,
+            filter.account_id as Option<AccountId>,
+            filter.control_id as Option<VelocityControlId>,
+            filter.limit_id as Option<VelocityLimitId>,
+            filter.transaction_id as Option<TransactionId>,
+            matches!(direction, es_entity::ListDirection::Ascending),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_next_page = rows.len() > first;
+        let entities = rows
+            .into_iter()
+            .take(first)
+            .map(|row| {
+                serde_json::from_value::<VelocityBreach>(row.values)
+                    .expect("Failed to deserialize breach")
+            })
+            .collect::<Vec<_>>();
+        let end_cursor = entities.last().map(VelocityBreachesByCreatedAtCursor::from);
+
+        Ok(es_entity::PaginatedQueryRet {
+            entities,
+            has_next_page,
+            end_cursor,
+        })
+    }
+}
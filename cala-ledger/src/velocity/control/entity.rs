@@ -65,6 +65,9 @@ pub struct NewVelocityControl {
     pub(super) name: String,
     #[builder(setter(into))]
     description: String,
+    /// Restricts the control to accounts and account sets of the tenant.
+    #[builder(setter(strip_option, into), default)]
+    pub(super) tenant_id: Option<TenantId>,
     #[builder(setter(into), default)]
     enforcement: NewVelocityEnforcement,
     #[builder(setter(strip_option, into), default)]
@@ -90,6 +93,7 @@ impl IntoEvents<VelocityControlEvent> for NewVelocityControl {
                     id: self.id,
                     name: self.name,
                     description: self.description,
+                    tenant_id: self.tenant_id,
                     enforcement: self.enforcement.action.into(),
                     condition: self
                         .condition
@@ -1,7 +1,10 @@
 use es_entity::*;
 use sqlx::PgPool;
 
-use crate::{primitives::DataSourceId, velocity::error::VelocityError};
+use crate::{
+    primitives::{DataSourceId, TenantId},
+    velocity::error::VelocityError,
+};
 
 use super::entity::*;
 
@@ -11,6 +14,7 @@ use super::entity::*;
     err = "VelocityError",
     columns(
         name(ty = "String", update(persist = false)),
+        tenant_id(ty = "Option<TenantId>", update(persist = false)),
         data_source_id(
             ty = "DataSourceId",
             create(accessor = "data_source().into()"),
@@ -27,6 +27,12 @@ pub enum VelocityError {
     LimitIdAlreadyExists,
     #[error("VelocityError - Limit already added to Control")]
     LimitAlreadyAddedToControl,
+    #[error(
+        "VelocityError - TenantMismatch: control {0} and account {1} belong to different tenants"
+    )]
+    TenantMismatch(VelocityControlId, AccountId),
+    #[error("VelocityError - Controls can't be attached to account {0} as its balance is sharded")]
+    AccountBalanceSharded(AccountId),
 }
 
 impl From<sqlx::Error> for VelocityError {
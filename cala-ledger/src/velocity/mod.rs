@@ -1,5 +1,6 @@
 mod account_control;
 mod balance;
+mod breach;
 mod context;
 mod control;
 pub mod error;
@@ -15,7 +16,9 @@ pub use crate::param::Params;
 use crate::{ledger_operation::*, outbox::*};
 
 use account_control::*;
+pub use balance::HypotheticalEntry;
 use balance::*;
+pub use breach::*;
 pub use control::*;
 use error::*;
 pub use limit::*;
@@ -28,6 +31,7 @@ pub struct Velocities {
     controls: VelocityControlRepo,
     account_controls: AccountControls,
     balances: VelocityBalances,
+    breaches: VelocityBreachRepo,
 }
 
 impl Velocities {
@@ -37,6 +41,7 @@ impl Velocities {
             controls: VelocityControlRepo::new(pool),
             account_controls: AccountControls::new(pool),
             balances: VelocityBalances::new(pool),
+            breaches: VelocityBreachRepo::new(pool),
             pool: pool.clone(),
             outbox,
         }
@@ -211,7 +216,8 @@ impl Velocities {
             .find_for_enforcement(db, &all_account_ids)
             .await?;
 
-        self.balances
+        let breaches = self
+            .balances
             .update_balances_with_limit_enforcement_in_op(
                 db,
                 created_at,
@@ -220,6 +226,90 @@ impl Velocities {
                 controls,
                 account_set_mappings,
             )
+            .await?;
+        if breaches.is_empty() {
+            return Ok(());
+        }
+
+        self.breaches
+            .insert_all_in_op(db, DataSource::Local.into(), &breaches)
+            .await?;
+        for breach in breaches.iter() {
+            if breach.action == VelocityEnforcementAction::Warn {
+                tracing::warn!(
+                    transaction_id = %breach.transaction_id,
+                    account_id = %breach.account_id,
+                    velocity_control_id = %breach.control_id,
+                    velocity_limit_id = %breach.limit_id,
+                    limit = %breach.limit,
+                    requested = %breach.requested,
+                    "velocity limit exceeded"
+                );
+            }
+        }
+        db.accumulate(breaches.into_iter().map(|breach| {
+            OutboxEventPayload::VelocityBreachRecorded {
+                source: DataSource::Local,
+                breach,
+            }
+        }));
+        Ok(())
+    }
+
+    /// Lists the breaches of limits whose controls warn or record instead of rejecting.
+    pub async fn list_breaches(
+        &self,
+        filter: VelocityBreachFilter,
+        query: es_entity::PaginatedQueryArgs<VelocityBreachesByCreatedAtCursor>,
+        direction: es_entity::ListDirection,
+    ) -> Result<
+        es_entity::PaginatedQueryRet<VelocityBreach, VelocityBreachesByCreatedAtCursor>,
+        VelocityError,
+    > {
+        self.breaches.list(&filter, query, direction).await
+    }
+
+    #[cfg(feature = "import")]
+    pub async fn sync_breach_recorded(
+        &self,
+        mut db: es_entity::DbOpWithTime<'_>,
+        origin: DataSourceId,
+        breach: VelocityBreach,
+    ) -> Result<(), VelocityError> {
+        self.breaches
+            .insert_all_in_op(&mut db, origin, std::slice::from_ref(&breach))
+            .await?;
+        let time = db.now();
+        self.outbox
+            .persist_events_at(
+                db,
+                std::iter::once(OutboxEventPayload::VelocityBreachRecorded {
+                    source: DataSource::Remote { id: origin },
+                    breach,
+                }),
+                time,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Returns how much of each limit attached to the account has been spent within the
+    /// window the hypothetical entry would fall into if it was posted at `at_time`.
+    pub async fn find_balances_for_account(
+        &self,
+        account_id: impl Into<AccountId>,
+        entry: &HypotheticalEntry,
+        at_time: DateTime<Utc>,
+    ) -> Result<Vec<VelocityBalance>, VelocityError> {
+        let Some((account, controls)) = self
+            .account_controls
+            .find_for_account(account_id.into())
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        self.balances
+            .find_for_account(&account, &controls, entry, at_time)
             .await
     }
 
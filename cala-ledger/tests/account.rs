@@ -0,0 +1,485 @@
+mod helpers;
+
+use rand::distr::{Alphanumeric, SampleString};
+
+use cala_ledger::{account::*, tx_template::*, *};
+
+#[tokio::test]
+async fn search_accounts() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let prefix = Alphanumeric.sample_string(&mut rand::rng(), 16);
+    let cash = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .code(format!("{prefix}-1000"))
+        .name("Cash 100%")
+        .normal_balance_type(DebitOrCredit::Debit)
+        .metadata(serde_json::json!({ "region": { "country": "US" }, "tier": 1 }))?
+        .build()?;
+    let cash = cala.accounts().create(cash).await?;
+    let revenue = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .code(format!("{prefix}-4000"))
+        .name("Revenue")
+        .normal_balance_type(DebitOrCredit::Credit)
+        .metadata(serde_json::json!({ "region": { "country": "DE" }, "tier": 1 }))?
+        .build()?;
+    let revenue = cala.accounts().create(revenue).await?;
+    let other_cash = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .code(format!("{prefix}_2000"))
+        .name("Petty cash")
+        .normal_balance_type(DebitOrCredit::Debit)
+        .build()?;
+    let other_cash = cala.accounts().create(other_cash).await?;
+
+    let search = |filter: AccountFilter| {
+        let accounts = cala.accounts().clone();
+        let query = es_entity::PaginatedQueryArgs {
+            first: 10,
+            after: None,
+        };
+        async move {
+            accounts
+                .search(filter, query)
+                .await
+                .map(|ret| ret.entities.iter().map(|a| a.id()).collect::<Vec<_>>())
+        }
+    };
+
+    let found = search(AccountFilter::default().code_prefix(format!("{prefix}-"))).await?;
+    assert_eq!(found, vec![cash.id(), revenue.id()]);
+
+    let found = search(AccountFilter::default().code_prefix(format!("{prefix}_"))).await?;
+    assert_eq!(found, vec![other_cash.id()]);
+
+    let found = search(
+        AccountFilter::default()
+            .code_prefix(&prefix)
+            .name_contains("CASH"),
+    )
+    .await?;
+    assert_eq!(found, vec![cash.id(), other_cash.id()]);
+
+    let found = search(
+        AccountFilter::default()
+            .code_prefix(&prefix)
+            .name_contains("100%"),
+    )
+    .await?;
+    assert_eq!(found, vec![cash.id()]);
+
+    let found = search(
+        AccountFilter::default()
+            .code_prefix(&prefix)
+            .normal_balance_type(DebitOrCredit::Credit),
+    )
+    .await?;
+    assert_eq!(found, vec![revenue.id()]);
+
+    let found = search(
+        AccountFilter::default()
+            .code_prefix(&prefix)
+            .metadata_eq("tier", 1)
+            .metadata_eq("region.country", "US"),
+    )
+    .await?;
+    assert_eq!(found, vec![cash.id()]);
+
+    cala.accounts()
+        .update_status(revenue.id(), AccountStatus::Frozen, "audit")
+        .await?;
+    let found = search(
+        AccountFilter::default()
+            .code_prefix(&prefix)
+            .status(AccountStatus::Frozen),
+    )
+    .await?;
+    assert_eq!(found, vec![revenue.id()]);
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (parent, child) = helpers::test_account_sets(journal.id().into());
+    let parent = cala.account_sets().create(parent).await?;
+    let child = cala.account_sets().create(child).await?;
+    cala.account_sets()
+        .add_member(parent.id(), child.id())
+        .await?;
+    cala.account_sets()
+        .add_member(child.id(), other_cash.id())
+        .await?;
+    let found = search(AccountFilter::default().member_of(parent.id())).await?;
+    assert_eq!(found, vec![other_cash.id()]);
+
+    let tenant_id = TenantId::new();
+    let tenant_cash = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .code(format!("{prefix}-1000"))
+        .name("Cash")
+        .tenant_id(tenant_id)
+        .build()?;
+    let tenant_cash = cala.accounts().create(tenant_cash).await?;
+    let found = search(AccountFilter::default().code_prefix(format!("{prefix}-"))).await?;
+    assert_eq!(found, vec![cash.id(), revenue.id()]);
+    let found = search(
+        AccountFilter::default()
+            .tenant_id(tenant_id)
+            .code_prefix(format!("{prefix}-")),
+    )
+    .await?;
+    assert_eq!(found, vec![tenant_cash.id()]);
+
+    let page = cala
+        .accounts()
+        .search(
+            AccountFilter::default().code_prefix(&prefix),
+            es_entity::PaginatedQueryArgs {
+                first: 2,
+                after: None,
+            },
+        )
+        .await?;
+    assert!(page.has_next_page);
+    let page = cala
+        .accounts()
+        .search(
+            AccountFilter::default().code_prefix(&prefix),
+            es_entity::PaginatedQueryArgs {
+                first: 2,
+                after: page.end_cursor,
+            },
+        )
+        .await?;
+    assert!(!page.has_next_page);
+    assert_eq!(page.entities.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn account_aliases() -> anyhow::Result<()> {
+    use cala_ledger::{
+        account::error::AccountError, error::LedgerError, tx_template::error::TxTemplateError,
+    };
+
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+
+    let iban = Alphanumeric.sample_string(&mut rand::rng(), 22);
+    let recipient_alias = AccountAlias::new("iban", &iban);
+    cala.accounts()
+        .add_alias(recipient.id(), recipient_alias.clone())
+        .await?;
+    cala.accounts()
+        .add_alias(recipient.id(), AccountAlias::new("card", &iban))
+        .await?;
+    let res = cala
+        .accounts()
+        .add_alias(sender.id(), recipient_alias.clone())
+        .await;
+    assert!(matches!(res, Err(AccountError::AliasAlreadyExists)));
+
+    let found = cala.accounts().find_by_alias(None, "iban", &iban).await?;
+    assert_eq!(found.id(), recipient.id());
+    let aliases = cala.accounts().list_aliases(recipient.id()).await?;
+    assert_eq!(
+        aliases,
+        vec![AccountAlias::new("card", &iban), recipient_alias.clone()]
+    );
+
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("sender")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("iban")
+            .r#type(ParamDataType::String)
+            .build()?,
+    ];
+    let entries = vec![
+        NewTxTemplateEntry::builder()
+            .entry_type("'TRANSFER_DR'")
+            .account_id("params.sender")
+            .layer("SETTLED")
+            .direction("DEBIT")
+            .units("decimal('100')")
+            .currency("'USD'")
+            .build()?,
+        NewTxTemplateEntry::builder()
+            .entry_type("'TRANSFER_CR'")
+            .account_id("account('iban', params.iban)")
+            .layer("SETTLED")
+            .direction("CREDIT")
+            .units("decimal('100')")
+            .currency("'USD'")
+            .build()?,
+    ];
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(
+            NewTxTemplate::builder()
+                .id(uuid::Uuid::now_v7())
+                .code(&tx_code)
+                .params(params)
+                .transaction(
+                    NewTxTemplateTransaction::builder()
+                        .effective("date()")
+                        .journal_id("params.journal_id")
+                        .build()?,
+                )
+                .entries(entries)
+                .build()?,
+        )
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", sender.id());
+    params.insert("iban", iban.clone());
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+    let balance = cala
+        .balances()
+        .find(journal.id(), recipient.id(), "USD".parse()?)
+        .await?;
+    assert_eq!(balance.settled(), rust_decimal::Decimal::from(100));
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", sender.id());
+    params.insert("iban", "unknown");
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, params)
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::TxTemplateError(
+            TxTemplateError::AccountAliasNotFound(_, _)
+        ))
+    ));
+
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("sender")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("iban")
+            .r#type(ParamDataType::String)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("by_alias")
+            .r#type(ParamDataType::Boolean)
+            .build()?,
+    ];
+    let entries = vec![
+        NewTxTemplateEntry::builder()
+            .entry_type("'TRANSFER_DR'")
+            .account_id("params.sender")
+            .layer("SETTLED")
+            .direction("DEBIT")
+            .units("decimal('10')")
+            .currency("'USD'")
+            .build()?,
+        NewTxTemplateEntry::builder()
+            .entry_type("'TRANSFER_CR'")
+            .account_id("params.by_alias ? account('iban', 'unknown') : params.sender")
+            .layer("SETTLED")
+            .direction("CREDIT")
+            .units("decimal('10')")
+            .currency("'USD'")
+            .build()?,
+    ];
+    let fallback_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(
+            NewTxTemplate::builder()
+                .id(uuid::Uuid::now_v7())
+                .code(&fallback_code)
+                .params(params)
+                .transaction(
+                    NewTxTemplateTransaction::builder()
+                        .effective("date()")
+                        .journal_id("params.journal_id")
+                        .metadata("{'card': account('card', params.iban)}")
+                        .build()?,
+                )
+                .entries(entries)
+                .build()?,
+        )
+        .await?;
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", sender.id());
+    params.insert("iban", iban.clone());
+    params.insert("by_alias", false);
+    let tx = cala
+        .post_transaction(TransactionId::new(), &fallback_code, params)
+        .await?;
+    assert_eq!(
+        tx.metadata::<serde_json::Value>()?,
+        Some(serde_json::json!({ "card": recipient.id() }))
+    );
+
+    cala.accounts()
+        .remove_alias(recipient.id(), recipient_alias)
+        .await?;
+    let res = cala.accounts().find_by_alias(None, "iban", &iban).await;
+    assert!(matches!(res, Err(AccountError::CouldNotFindByAlias(_, _))));
+
+    let tenant_id = TenantId::new();
+    let card = AccountAlias::new("card", &iban);
+    let (tenant_account, _) = helpers::test_accounts();
+    let mut builder = NewAccount::builder();
+    builder
+        .id(tenant_account.id)
+        .code(format!("tenant-{iban}"))
+        .name("Tenant Account")
+        .tenant_id(tenant_id);
+    let tenant_account = cala.accounts().create(builder.build()?).await?;
+    cala.accounts().add_alias(tenant_account.id(), card).await?;
+    let found = cala
+        .accounts()
+        .find_by_alias(Some(tenant_id), "card", &iban)
+        .await?;
+    assert_eq!(found.id(), tenant_account.id());
+    let found = cala.accounts().find_by_alias(None, "card", &iban).await?;
+    assert_eq!(found.id(), recipient.id());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn account_metadata_schema() -> anyhow::Result<()> {
+    use cala_ledger::{account::error::AccountError, metadata_schema::error::MetadataSchemaError};
+    use serde_json::json;
+
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let account_type = Alphanumeric.sample_string(&mut rand::rng(), 16);
+    let new_account = |metadata: serde_json::Value| {
+        let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+        NewAccount::builder()
+            .id(AccountId::new())
+            .code(&code)
+            .name(code)
+            .account_type(&account_type)
+            .metadata(metadata)
+            .unwrap()
+            .build()
+            .unwrap()
+    };
+
+    let res = cala.accounts().create(new_account(json!({}))).await;
+    assert!(matches!(res, Err(AccountError::MetadataSchemaNotFound(_))));
+
+    cala.accounts()
+        .set_metadata_schema(
+            None,
+            &account_type,
+            json!({
+                "type": "object",
+                "properties": {
+                    "region": { "type": "string", "enum": ["EU", "US"] },
+                    "limits": {
+                        "type": "object",
+                        "properties": { "daily": { "type": "integer" } }
+                    }
+                },
+                "required": ["region"]
+            }),
+        )
+        .await?;
+    assert!(cala
+        .accounts()
+        .find_metadata_schema(None, &account_type)
+        .await?
+        .is_some());
+
+    let mut account = cala
+        .accounts()
+        .create(new_account(json!({ "region": "EU" })))
+        .await?;
+
+    let res = cala
+        .accounts()
+        .create(new_account(
+            json!({ "region": "APAC", "limits": { "daily": "100" } }),
+        ))
+        .await;
+    let Err(AccountError::MetadataSchemaError(MetadataSchemaError::Violation(violations))) = res
+    else {
+        panic!("expected metadata violations");
+    };
+    let mut paths: Vec<_> = violations.into_iter().map(|v| v.path).collect();
+    paths.sort();
+    assert_eq!(paths, vec!["/limits/daily", "/region"]);
+
+    let mut update = AccountUpdate::default();
+    update.metadata(json!({ "regoin": "EU" }))?;
+    account.update(update);
+    let res = cala.accounts().persist(&mut account).await;
+    assert!(matches!(
+        res,
+        Err(AccountError::MetadataSchemaError(
+            MetadataSchemaError::Violation(_)
+        ))
+    ));
+
+    let res = cala
+        .accounts()
+        .set_metadata_schema(None, &account_type, json!({ "type": 1 }))
+        .await;
+    assert!(matches!(
+        res,
+        Err(AccountError::MetadataSchemaError(
+            MetadataSchemaError::InvalidSchema(_)
+        ))
+    ));
+
+    let tenant_id = TenantId::new();
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let mut builder = NewAccount::builder();
+    builder
+        .id(AccountId::new())
+        .code(&code)
+        .name(code)
+        .tenant_id(tenant_id)
+        .account_type(&account_type)
+        .metadata(json!({ "region": "APAC" }))?;
+    let res = cala.accounts().create(builder.build()?).await;
+    assert!(matches!(res, Err(AccountError::MetadataSchemaNotFound(_))));
+    cala.accounts()
+        .set_metadata_schema(Some(tenant_id), &account_type, json!({ "type": "object" }))
+        .await?;
+    cala.accounts().create(builder.build()?).await?;
+
+    Ok(())
+}
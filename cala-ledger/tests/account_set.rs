@@ -490,3 +490,218 @@ async fn list_members_by_external_id() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn nesting_limits() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .max_account_set_depth(2)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let mut sets = Vec::new();
+    for name in ["a", "b", "c", "d"] {
+        let new_set = NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal.id())
+            .build()
+            .unwrap();
+        sets.push(cala.account_sets().create(new_set).await.unwrap());
+    }
+    let [a, b, c, d] = [sets[0].id(), sets[1].id(), sets[2].id(), sets[3].id()];
+
+    // A set cannot contain itself
+    let res = cala.account_sets().add_member(a, a).await;
+    assert!(matches!(
+        res,
+        Err(account_set::error::AccountSetError::CycleDetected(_, _))
+    ));
+
+    // a -> b -> c
+    cala.account_sets().add_member(a, b).await?;
+    cala.account_sets().add_member(b, c).await?;
+
+    // c -> a would close the loop
+    let res = cala.account_sets().add_member(c, a).await;
+    assert!(matches!(
+        res,
+        Err(account_set::error::AccountSetError::CycleDetected(set, member)) if set == c && member == a
+    ));
+    let res = cala.account_sets().add_member(b, a).await;
+    assert!(matches!(
+        res,
+        Err(account_set::error::AccountSetError::CycleDetected(_, _))
+    ));
+
+    // a -> b -> c -> d would be 3 levels deep
+    let res = cala.account_sets().add_member(c, d).await;
+    assert!(matches!(
+        res,
+        Err(account_set::error::AccountSetError::MaxDepthExceeded(2))
+    ));
+    // d -> a -> b -> c as well
+    let res = cala.account_sets().add_member(d, a).await;
+    assert!(matches!(
+        res,
+        Err(account_set::error::AccountSetError::MaxDepthExceeded(2))
+    ));
+    // Siblings don't add depth
+    cala.account_sets().add_member(a, d).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn hierarchy_tree() -> anyhow::Result<()> {
+    let btc: Currency = "BTC".parse().unwrap();
+
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    let root = NewAccountSet::builder()
+        .id(AccountSetId::new())
+        .name("Root")
+        .journal_id(journal.id())
+        .build()
+        .unwrap();
+    let root = cala.account_sets().create(root).await.unwrap();
+    let child = NewAccountSet::builder()
+        .id(AccountSetId::new())
+        .name("Child")
+        .journal_id(journal.id())
+        .build()
+        .unwrap();
+    let child = cala.account_sets().create(child).await.unwrap();
+
+    // root -> child -> recipient, root -> sender
+    cala.account_sets()
+        .add_member(child.id(), recipient_account.id())
+        .await?;
+    cala.account_sets()
+        .add_member(root.id(), child.id())
+        .await?;
+    cala.account_sets()
+        .add_member(root.id(), sender_account.id())
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await
+        .unwrap();
+
+    let tree = cala.account_sets().tree(root.id(), None, Some(btc)).await?;
+    assert_eq!(tree.journal_id, journal.id());
+    assert_eq!(tree.root.iter().count(), 4);
+    assert_eq!(tree.root.children.len(), 2);
+    let child_node = tree.root.find(child.id()).unwrap();
+    assert_eq!(child_node.depth, 1);
+    assert_eq!(child_node.name, "Child");
+    assert!(child_node.code.is_none());
+    assert_eq!(child_node.balance.as_ref().unwrap().settled(), 1290.into());
+    let recipient_node = tree.root.find(recipient_account.id()).unwrap();
+    assert_eq!(recipient_node.depth, 2);
+    assert_eq!(
+        recipient_node.code.as_deref(),
+        Some(recipient_account.values().code.as_str())
+    );
+    assert_eq!(
+        recipient_node.balance.as_ref().unwrap().settled(),
+        1290.into()
+    );
+    assert!(tree.root.balance.is_some());
+
+    // Depth limited and without balances
+    let tree = cala.account_sets().tree(root.id(), Some(1), None).await?;
+    assert_eq!(tree.root.iter().count(), 3);
+    assert!(tree.root.iter().all(|node| node.balance.is_none()));
+    assert!(tree.root.find(recipient_account.id()).is_none());
+
+    let ancestors = cala
+        .account_sets()
+        .find_ancestors(recipient_account.id())
+        .await?;
+    assert_eq!(
+        ancestors,
+        vec![
+            AccountSetAncestor {
+                account_set_id: child.id(),
+                depth: 1
+            },
+            AccountSetAncestor {
+                account_set_id: root.id(),
+                depth: 2
+            },
+        ]
+    );
+    let ancestors = cala.account_sets().find_ancestors(root.id()).await?;
+    assert!(ancestors.is_empty());
+
+    // Diamond: root -> child -> shared and root -> other -> shared -> nested,
+    // so shared (and its members) show up under both of its parents
+    let mut sets = Vec::new();
+    for name in ["Other", "Shared", "Nested"] {
+        let set = NewAccountSet::builder()
+            .id(AccountSetId::new())
+            .name(name)
+            .journal_id(journal.id())
+            .build()
+            .unwrap();
+        sets.push(cala.account_sets().create(set).await.unwrap());
+    }
+    let (other, shared, nested) = (&sets[0], &sets[1], &sets[2]);
+    cala.account_sets()
+        .add_member(shared.id(), nested.id())
+        .await?;
+    cala.account_sets()
+        .add_member(child.id(), shared.id())
+        .await?;
+    cala.account_sets()
+        .add_member(other.id(), shared.id())
+        .await?;
+    cala.account_sets()
+        .add_member(root.id(), other.id())
+        .await?;
+    let tree = cala.account_sets().tree(root.id(), None, Some(btc)).await?;
+    assert_eq!(tree.root.iter().count(), 9);
+    let shared_nodes: Vec<_> = tree
+        .root
+        .iter()
+        .filter(|node| node.id == AccountSetMemberId::AccountSet(shared.id()))
+        .collect();
+    assert_eq!(shared_nodes.len(), 2);
+    for node in shared_nodes {
+        assert_eq!(node.children.len(), 1);
+        assert_eq!(
+            node.children[0].id,
+            AccountSetMemberId::AccountSet(nested.id())
+        );
+    }
+    assert_eq!(tree.root.find(other.id()).unwrap().children.len(), 1);
+    assert_eq!(tree.root.find(child.id()).unwrap().children.len(), 2);
+
+    Ok(())
+}
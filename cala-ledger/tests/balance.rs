@@ -0,0 +1,1193 @@
+mod helpers;
+
+use rand::distr::{Alphanumeric, SampleString};
+use rust_decimal_macros::dec;
+
+use cala_ledger::{account::NewAccount, balance::BalanceHistoryByVersionCursor, tx_template::*, *};
+
+#[tokio::test]
+async fn balance_history() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    for _ in 0..2 {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+    }
+
+    let first_page = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            es_entity::PaginatedQueryArgs {
+                first: 3,
+                after: None,
+            },
+            es_entity::ListDirection::Ascending,
+            None,
+            None,
+        )
+        .await?;
+    assert!(first_page.has_next_page);
+    assert_eq!(first_page.entities.len(), 3);
+    let versions: Vec<_> = first_page
+        .entities
+        .iter()
+        .map(|v| v.balance.details.version)
+        .collect();
+    assert_eq!(versions, vec![1, 2, 3]);
+    for version in first_page.entities.iter() {
+        let entry = version.entry.as_ref().expect("entry should be joined");
+        assert_eq!(entry.id, version.balance.details.entry_id);
+        assert_eq!(entry.account_id, recipient_account.id());
+    }
+    assert_eq!(first_page.entities[0].balance.settled(), dec!(100));
+
+    let second_page = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            es_entity::PaginatedQueryArgs {
+                first: 3,
+                after: first_page.end_cursor,
+            },
+            es_entity::ListDirection::Ascending,
+            None,
+            None,
+        )
+        .await?;
+    assert!(!second_page.has_next_page);
+    assert_eq!(second_page.entities.len(), 1);
+    assert_eq!(second_page.entities[0].balance.details.version, 4);
+    assert_eq!(second_page.entities[0].balance.settled(), dec!(200));
+
+    let latest = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            es_entity::PaginatedQueryArgs {
+                first: 1,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+            None,
+            None,
+        )
+        .await?;
+    assert_eq!(latest.entities[0].balance.details.version, 4);
+    let before_latest = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            es_entity::PaginatedQueryArgs {
+                first: 1,
+                after: Some(BalanceHistoryByVersionCursor { version: 4 }),
+            },
+            es_entity::ListDirection::Descending,
+            None,
+            None,
+        )
+        .await?;
+    assert_eq!(before_latest.entities[0].balance.details.version, 3);
+
+    let future = chrono::Utc::now() + chrono::Duration::hours(1);
+    let none_recorded = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Ascending,
+            Some(future),
+            None,
+        )
+        .await?;
+    assert!(none_recorded.entities.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn balance_as_of() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    let before_first = chrono::Utc::now();
+    let mut after_first = before_first;
+    for _ in 0..2 {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+        if after_first == before_first {
+            after_first = chrono::Utc::now();
+        }
+    }
+
+    let res = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            before_first,
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::NotFound(..))
+    ));
+
+    let recipient_balance = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            after_first,
+        )
+        .await?;
+    assert_eq!(recipient_balance.settled(), dec!(1290));
+
+    let balances = cala
+        .balances()
+        .find_all_as_of(
+            &[
+                (journal.id(), recipient_account.id(), Currency::USD),
+                (journal.id(), sender_account.id(), Currency::USD),
+            ],
+            after_first,
+        )
+        .await?;
+    let recipient_balance = balances
+        .get(&(journal.id(), recipient_account.id(), Currency::USD))
+        .unwrap();
+    assert_eq!(recipient_balance.settled(), dec!(100));
+    assert_eq!(recipient_balance.pending(), dec!(100));
+    let sender_balance = balances
+        .get(&(journal.id(), sender_account.id(), Currency::USD))
+        .unwrap();
+    assert_eq!(sender_balance.settled(), dec!(-100));
+
+    let balances = cala
+        .balances()
+        .find_all_as_of(
+            &[(journal.id(), recipient_account.id(), Currency::USD)],
+            chrono::Utc::now(),
+        )
+        .await?;
+    let recipient_balance = balances
+        .get(&(journal.id(), recipient_account.id(), Currency::USD))
+        .unwrap();
+    assert_eq!(recipient_balance.settled(), dec!(200));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trial_balance() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal_with_effective_balances();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let (sender_set, _) = helpers::test_account_sets(journal.id().into());
+    let sender_set = cala.account_sets().create(sender_set).await.unwrap();
+    cala.account_sets()
+        .add_member(sender_set.id(), sender_account.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    let date1 = chrono::NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+    let date2 = chrono::NaiveDate::from_ymd_opt(2025, 5, 6).unwrap();
+    for effective in [date1, date2] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("effective", effective);
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+    }
+
+    let trial_balance = cala
+        .balances()
+        .trial_balance(journal.id(), None, None)
+        .await?;
+    assert!(trial_balance.is_balanced());
+    // The account set's balance must not be counted twice
+    assert_eq!(trial_balance.lines.len(), 4);
+    assert!(trial_balance
+        .lines
+        .iter()
+        .all(|l| l.account_id != sender_set.id().into()));
+    let btc_settled = trial_balance
+        .totals
+        .iter()
+        .find(|t| t.currency == Currency::BTC && t.layer == Layer::Settled)
+        .unwrap();
+    assert_eq!(btc_settled.dr_balance, dec!(2580));
+    assert_eq!(btc_settled.cr_balance, dec!(2580));
+
+    let trial_balance = cala
+        .balances()
+        .trial_balance(journal.id(), Some(Currency::USD), Some(date1))
+        .await?;
+    assert!(trial_balance.is_balanced());
+    assert_eq!(trial_balance.lines.len(), 2);
+    let usd_pending = trial_balance
+        .totals
+        .iter()
+        .find(|t| t.layer == Layer::Pending)
+        .unwrap();
+    assert_eq!(usd_pending.dr_balance, dec!(100));
+    assert_eq!(trial_balance.to_csv().lines().count(), 3);
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+    let res = cala
+        .balances()
+        .trial_balance(journal.id(), None, Some(date1))
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::EffectiveBalancesNotEnabled(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_integrity() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal_with_effective_balances();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let (sender_set, _) = helpers::test_account_sets(journal.id().into());
+    let sender_set = cala.account_sets().create(sender_set).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    for (i, effective) in [
+        chrono::NaiveDate::from_ymd_opt(2025, 5, 5).unwrap(),
+        chrono::NaiveDate::from_ymd_opt(2025, 5, 6).unwrap(),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("effective", effective);
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+        if i == 0 {
+            cala.account_sets()
+                .add_member(sender_set.id(), sender_account.id())
+                .await?;
+        }
+    }
+
+    let report = cala
+        .balances()
+        .verify_integrity(Some(journal.id()), false)
+        .await?;
+    assert!(report.is_consistent());
+    assert_eq!(report.journals_checked, 1);
+    assert_eq!(report.balances_checked, 6);
+
+    sqlx::query(
+        r#"UPDATE cala_balance_history
+           SET values = jsonb_set(values, '{settled,cr_balance}', '"1"')
+           WHERE journal_id = $1 AND account_id = $2 AND currency = 'BTC'
+           AND version = (
+             SELECT latest_version FROM cala_current_balances
+             WHERE journal_id = $1 AND account_id = $2 AND currency = 'BTC'
+           )"#,
+    )
+    .bind(uuid::Uuid::from(journal.id()))
+    .bind(uuid::Uuid::from(recipient_account.id()))
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        r#"UPDATE cala_balance_history
+           SET values = jsonb_set(values, '{pending,dr_balance}', '"0"')
+           WHERE journal_id = $1 AND account_id = $2 AND currency = 'USD'
+           AND version = (
+             SELECT latest_version FROM cala_current_balances
+             WHERE journal_id = $1 AND account_id = $2 AND currency = 'USD'
+           )"#,
+    )
+    .bind(uuid::Uuid::from(journal.id()))
+    .bind(uuid::Uuid::from(sender_set.id()))
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        r#"UPDATE cala_cumulative_effective_balances
+           SET values = jsonb_set(values, '{settled,dr_balance}', '"7"')
+           WHERE journal_id = $1 AND account_id = $2 AND currency = 'BTC'
+           AND effective = '2025-05-05'"#,
+    )
+    .bind(uuid::Uuid::from(journal.id()))
+    .bind(uuid::Uuid::from(recipient_account.id()))
+    .execute(&pool)
+    .await?;
+
+    let report = cala
+        .balances()
+        .verify_integrity(Some(journal.id()), false)
+        .await?;
+    assert!(!report.is_consistent());
+    assert_eq!(report.repaired_balances, 0);
+    let current: Vec<_> = report
+        .discrepancies
+        .iter()
+        .filter(|d| d.source == balance::BalanceDiscrepancySource::Current)
+        .collect();
+    assert_eq!(current.len(), 2);
+    let recipient = current
+        .iter()
+        .find(|d| d.account_id == recipient_account.id())
+        .unwrap();
+    assert_eq!(recipient.layer, Layer::Settled);
+    assert_eq!(recipient.expected.cr_balance, dec!(2580));
+    assert_eq!(recipient.stored.unwrap().cr_balance, dec!(1));
+    let set = current
+        .iter()
+        .find(|d| d.account_id == sender_set.id().into())
+        .unwrap();
+    assert!(set.is_account_set);
+    assert_eq!(set.layer, Layer::Pending);
+    assert!(report.discrepancies.iter().any(|d| d.source
+        == balance::BalanceDiscrepancySource::Effective(
+            chrono::NaiveDate::from_ymd_opt(2025, 5, 5).unwrap()
+        )));
+
+    let report = cala
+        .balances()
+        .verify_integrity(Some(journal.id()), true)
+        .await?;
+    assert!(!report.is_consistent());
+    assert_eq!(report.repaired_balances, 2);
+    assert!(report.repaired_effective_balances > 0);
+
+    let report = cala
+        .balances()
+        .verify_integrity(Some(journal.id()), false)
+        .await?;
+    assert!(report.is_consistent());
+
+    let recipient_balance = cala
+        .balances()
+        .find(journal.id(), recipient_account.id(), Currency::BTC)
+        .await?;
+    assert_eq!(recipient_balance.settled(), dec!(2580));
+    let set_balance = cala
+        .balances()
+        .find(journal.id(), sender_set.id(), Currency::USD)
+        .await?;
+    assert_eq!(set_balance.pending(), dec!(-200));
+    let effective_balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            chrono::NaiveDate::from_ymd_opt(2025, 5, 5).unwrap(),
+        )
+        .await?;
+    assert_eq!(effective_balance.settled(), dec!(1290));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn eventually_consistent_balances() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal_with_effective_balances();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, _) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let hot_account = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .name(format!("Hot Account {code}"))
+        .code(code)
+        .eventually_consistent(true)
+        .build()
+        .unwrap();
+    let hot_account = cala.accounts().create(hot_account).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    for _ in 0..2 {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", hot_account.id());
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+    }
+
+    let res = cala
+        .balances()
+        .find(journal.id(), hot_account.id(), Currency::BTC)
+        .await;
+    assert!(res.is_err());
+    let lag = cala
+        .balances()
+        .find_lag(journal.id(), hot_account.id(), Currency::BTC)
+        .await?;
+    assert!(!lag.is_caught_up());
+    assert_eq!(lag.last_folded_entry_id, None);
+    let res = cala
+        .accounts()
+        .update_status(hot_account.id(), AccountStatus::Closed, "offboarding")
+        .await;
+    assert!(matches!(
+        res,
+        Err(account::error::AccountError::NonZeroBalance(_))
+    ));
+
+    let report = cala
+        .balances()
+        .verify_integrity(Some(journal.id()), false)
+        .await?;
+    assert!(report.is_consistent());
+
+    let lag = loop {
+        cala.balances().fold_queued_entries(100).await?;
+        let lag = cala
+            .balances()
+            .find_lag(journal.id(), hot_account.id(), Currency::BTC)
+            .await?;
+        if lag.is_caught_up() {
+            break lag;
+        }
+    };
+    assert!(lag.last_folded_entry_id.is_some());
+    assert!(lag.last_folded_sequence.is_some());
+
+    let balance = cala
+        .balances()
+        .find(journal.id(), hot_account.id(), Currency::BTC)
+        .await?;
+    assert_eq!(balance.settled(), dec!(2580));
+    assert_eq!(balance.details.version, 2);
+
+    let sender_balance = cala
+        .balances()
+        .find(journal.id(), sender_account.id(), Currency::BTC)
+        .await?;
+    assert_eq!(sender_balance.settled(), dec!(-2580));
+
+    let report = cala
+        .balances()
+        .verify_integrity(Some(journal.id()), false)
+        .await?;
+    assert!(report.is_consistent());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sharded_balances() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, _) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let omnibus = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .name(format!("Omnibus Account {code}"))
+        .code(code)
+        .balance_shards(4)
+        .build()
+        .unwrap();
+    let omnibus = cala.accounts().create(omnibus).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    let postings = (0..8).map(|_| {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", omnibus.id());
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+    });
+    for res in futures::future::join_all(postings).await {
+        res?;
+    }
+
+    let balance = cala
+        .balances()
+        .find(journal.id(), omnibus.id(), Currency::BTC)
+        .await?;
+    assert_eq!(balance.settled(), dec!(10320));
+    assert_eq!(balance.details.version, 8);
+
+    let balances = cala
+        .balances()
+        .find_all(&[
+            (journal.id(), omnibus.id(), Currency::BTC),
+            (journal.id(), sender_account.id(), Currency::BTC),
+        ])
+        .await?;
+    assert_eq!(balances.len(), 2);
+    assert_eq!(
+        balances[&(journal.id(), omnibus.id(), Currency::BTC)].settled(),
+        dec!(10320)
+    );
+
+    let trial_balance = cala
+        .balances()
+        .trial_balance(journal.id(), Some(Currency::BTC), None)
+        .await?;
+    assert_eq!(trial_balance.lines.len(), 2);
+    assert!(trial_balance.is_balanced());
+
+    let report = cala
+        .balances()
+        .verify_integrity(Some(journal.id()), false)
+        .await?;
+    assert!(report.is_consistent());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sharded_account_in_account_set() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, _) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let omnibus = NewAccount::builder()
+        .id(uuid::Uuid::now_v7())
+        .name(format!("Omnibus Account {code}"))
+        .code(code)
+        .balance_shards(4)
+        .build()
+        .unwrap();
+    let omnibus = cala.accounts().create(omnibus).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+    for _ in 0..4 {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", omnibus.id());
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await?;
+    }
+
+    let set = account_set::NewAccountSet::builder()
+        .id(AccountSetId::new())
+        .name("Omnibus Set")
+        .journal_id(journal.id())
+        .build()
+        .unwrap();
+    let set = cala.account_sets().create(set).await?;
+    cala.account_sets()
+        .add_member(set.id(), omnibus.id())
+        .await?;
+
+    let balance = cala
+        .balances()
+        .find(journal.id(), set.id(), Currency::BTC)
+        .await?;
+    assert_eq!(balance.settled(), dec!(5160));
+    let history = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            set.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Ascending,
+            None,
+            None,
+        )
+        .await?;
+    assert_eq!(history.entities.len(), 1);
+    assert_eq!(history.entities[0].balance.settled(), dec!(5160));
+
+    let res = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            omnibus.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Ascending,
+            None,
+            None,
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::BalanceSharded(_))
+    ));
+    let res = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            omnibus.id(),
+            Currency::BTC,
+            chrono::Utc::now(),
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::BalanceSharded(_))
+    ));
+    let res = cala
+        .balances()
+        .find_all_as_of(
+            &[
+                (journal.id(), sender_account.id(), Currency::BTC),
+                (journal.id(), omnibus.id(), Currency::BTC),
+            ],
+            chrono::Utc::now(),
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::BalanceSharded(_))
+    ));
+
+    cala.account_sets()
+        .remove_member(set.id(), omnibus.id())
+        .await?;
+    let balance = cala
+        .balances()
+        .find(journal.id(), set.id(), Currency::BTC)
+        .await?;
+    assert_eq!(balance.settled(), dec!(0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn converted_balance() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender_account.id());
+    params.insert("recipient", recipient_account.id());
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await
+        .unwrap();
+
+    let eur: Currency = "EUR".parse()?;
+    let jpy: Currency = "JPY".parse()?;
+    let today = chrono::Utc::now().date_naive();
+    let yesterday = today.pred_opt().unwrap();
+    let tomorrow = today.succ_opt().unwrap();
+    for (base, rate, effective) in [
+        (Currency::USD, dec!(0.9), yesterday),
+        (Currency::BTC, dec!(2), yesterday),
+        (Currency::BTC, dec!(4), tomorrow),
+    ] {
+        cala.fx_rates()
+            .upsert(
+                fx_rate::NewFxRate::builder()
+                    .base_currency(base)
+                    .quote_currency(eur)
+                    .effective(effective)
+                    .rate(rate)
+                    .build()?,
+            )
+            .await?;
+    }
+    let rate = cala
+        .fx_rates()
+        .find_effective(Currency::BTC, eur, today)
+        .await?;
+    assert_eq!(rate.rate, dec!(2));
+    assert_eq!(rate.effective, yesterday);
+
+    let converted = cala
+        .balances()
+        .find_converted(
+            journal.id(),
+            recipient_account.id(),
+            eur,
+            chrono::Utc::now(),
+        )
+        .await?;
+    assert_eq!(converted.currency, eur);
+    assert_eq!(converted.settled(), dec!(2670));
+    assert_eq!(converted.pending(), dec!(90));
+    assert_eq!(converted.fx_rates().count(), 2);
+
+    let res = cala
+        .balances()
+        .find_converted(
+            journal.id(),
+            recipient_account.id(),
+            jpy,
+            chrono::Utc::now(),
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::FxRateError(
+            fx_rate::error::FxRateError::NotFound(..)
+        ))
+    ));
+
+    let invalid = fx_rate::NewFxRate::builder()
+        .base_currency(eur)
+        .quote_currency(eur)
+        .effective(today)
+        .rate(dec!(1))
+        .build();
+    assert!(invalid.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn entry_type_breakdown() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let (sender_set, _) = helpers::test_account_sets(journal.id().into());
+    let sender_set = cala.account_sets().create(sender_set).await.unwrap();
+    cala.account_sets()
+        .add_member(sender_set.id(), sender_account.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    let date1 = chrono::NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+    let date2 = chrono::NaiveDate::from_ymd_opt(2025, 5, 6).unwrap();
+    let date3 = chrono::NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    for effective in [date1, date2, date3] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("effective", effective);
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+    }
+
+    let breakdown = cala
+        .balances()
+        .entry_type_breakdown(journal.id(), recipient_account.id(), date2, None, None)
+        .await?;
+    assert_eq!(breakdown.totals.len(), 3);
+    let btc = breakdown
+        .find("TEST_BTC_CR", Currency::BTC, Layer::Settled)
+        .unwrap();
+    assert_eq!(btc.cr_balance, dec!(2580));
+    assert_eq!(btc.dr_balance, dec!(0));
+    assert_eq!(btc.entry_count, 2);
+    assert_eq!(btc.net(DebitOrCredit::Credit), dec!(2580));
+
+    let breakdown = cala
+        .balances()
+        .entry_type_breakdown(
+            journal.id(),
+            recipient_account.id(),
+            date1,
+            Some(date2),
+            Some(Currency::USD),
+        )
+        .await?;
+    assert_eq!(breakdown.totals.len(), 2);
+    let pending = breakdown
+        .find("TEST_USD_PENDING_CR", Currency::USD, Layer::Pending)
+        .unwrap();
+    assert_eq!(pending.cr_balance, dec!(200));
+
+    // Account sets aggregate the entries of their members
+    let breakdown = cala
+        .balances()
+        .entry_type_breakdown(journal.id(), sender_set.id(), date1, None, None)
+        .await?;
+    assert_eq!(breakdown.totals.len(), 3);
+    let btc = breakdown
+        .find("TEST_BTC_DR", Currency::BTC, Layer::Settled)
+        .unwrap();
+    assert_eq!(btc.dr_balance, dec!(3870));
+    assert_eq!(btc.entry_count, 3);
+
+    let res = cala
+        .balances()
+        .entry_type_breakdown(journal.id(), sender_set.id(), date2, Some(date1), None)
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::InvalidDateRange(_, _))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn history_retention() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool.clone())
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    for _ in 0..3 {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+    }
+
+    // Backdate the recorded versions: two on the same day 10 days ago, one 3 days ago
+    let today = chrono::Utc::now().date_naive();
+    let ten_days_ago = today - chrono::Days::new(10);
+    for (version, recorded_at) in [
+        (1, ten_days_ago.and_hms_opt(8, 0, 0).unwrap().and_utc()),
+        (2, ten_days_ago.and_hms_opt(18, 0, 0).unwrap().and_utc()),
+        (
+            3,
+            (today - chrono::Days::new(3))
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+                .and_utc(),
+        ),
+    ] {
+        sqlx::query(
+            r#"UPDATE cala_balance_history SET recorded_at = $3
+               WHERE journal_id = $1 AND account_id = $2 AND currency = 'BTC' AND version = $4"#,
+        )
+        .bind(uuid::Uuid::from(journal.id()))
+        .bind(uuid::Uuid::from(recipient_account.id()))
+        .bind(recorded_at)
+        .bind(version)
+        .execute(&pool)
+        .await?;
+    }
+    let intraday = ten_days_ago.and_hms_opt(12, 0, 0).unwrap().and_utc();
+    let balance = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            intraday,
+        )
+        .await?;
+    assert_eq!(balance.settled(), dec!(1290));
+
+    let retention = cala
+        .balances()
+        .set_history_retention(
+            journal.id(),
+            5,
+            balance::BalanceHistoryRetentionMode::EndOfDay,
+        )
+        .await?;
+    assert!(retention.pruned_before.is_none());
+    let compaction = cala
+        .balances()
+        .compact_journal_history(journal.id(), 100)
+        .await?
+        .unwrap();
+    assert_eq!(compaction.balance_versions_removed, 1);
+    assert_eq!(
+        compaction.pruned_before,
+        (today - chrono::Days::new(5))
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    );
+
+    let res = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            intraday,
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::HistoryPruned(_, _))
+    ));
+    let balance = cala
+        .balances()
+        .find_as_of(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            chrono::Utc::now(),
+        )
+        .await?;
+    assert_eq!(balance.settled(), dec!(3870));
+
+    // Listing across the thinned range is rejected
+    let res = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+            None,
+            None,
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::HistoryPruned(_, _))
+    ));
+    let history = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+            Some(compaction.pruned_before),
+            None,
+        )
+        .await?;
+    assert_eq!(
+        history
+            .entities
+            .iter()
+            .map(|v| v.balance.details.version)
+            .collect::<Vec<_>>(),
+        vec![3]
+    );
+
+    cala.balances()
+        .set_history_retention(
+            journal.id(),
+            1,
+            balance::BalanceHistoryRetentionMode::Archive,
+        )
+        .await?;
+    let compaction = cala
+        .balances()
+        .compact_journal_history(journal.id(), 100)
+        .await?
+        .unwrap();
+    assert_eq!(compaction.balance_versions_removed, 1);
+    let archived: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM cala_balance_history_archive
+           WHERE journal_id = $1 AND account_id = $2 AND currency = 'BTC'"#,
+    )
+    .bind(uuid::Uuid::from(journal.id()))
+    .bind(uuid::Uuid::from(recipient_account.id()))
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(archived, 1);
+
+    let res = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+            None,
+            None,
+        )
+        .await;
+    assert!(matches!(
+        res,
+        Err(balance::error::BalanceError::HistoryPruned(_, _))
+    ));
+    let history = cala
+        .balances()
+        .list_history(
+            journal.id(),
+            recipient_account.id(),
+            Currency::BTC,
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+            Some(compaction.pruned_before),
+            None,
+        )
+        .await?;
+    assert!(history.entities.is_empty());
+
+    Ok(())
+}
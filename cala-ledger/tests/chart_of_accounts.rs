@@ -0,0 +1,154 @@
+mod helpers;
+
+use rand::distr::{Alphanumeric, SampleString};
+
+use cala_ledger::{chart_of_accounts::*, *};
+
+#[tokio::test]
+async fn apply_and_export() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let suffix = Alphanumeric.sample_string(&mut rand::rng(), 16);
+    let document = serde_json::json!({
+        "journals": [{
+            "code": format!("GL-{suffix}"),
+            "name": "General Ledger",
+            "account_sets": [
+                {
+                    "external_id": format!("assets-{suffix}"),
+                    "name": "Assets",
+                    "account_sets": [format!("cash-{suffix}")]
+                },
+                {
+                    "external_id": format!("cash-{suffix}"),
+                    "name": "Cash",
+                    "accounts": [format!("cash-usd-{suffix}"), format!("cash-btc-{suffix}")]
+                }
+            ]
+        }],
+        "accounts": [
+            { "code": format!("cash-usd-{suffix}"), "name": "Cash USD" },
+            { "code": format!("cash-btc-{suffix}"), "name": "Cash BTC", "normal_balance_type": "credit" }
+        ]
+    });
+    let chart: ChartOfAccounts = serde_json::from_value(document)?;
+
+    let applied = cala.chart_of_accounts().apply(None, &chart).await?;
+    assert_eq!(applied.journals_created, 1);
+    assert_eq!(applied.accounts_created, 2);
+    assert_eq!(applied.account_sets_created, 2);
+    assert_eq!(applied.members_added, 3);
+
+    let applied = cala.chart_of_accounts().apply(None, &chart).await?;
+    assert!(applied.is_unchanged());
+
+    let journal = cala
+        .journals()
+        .find_by_code(None, format!("GL-{suffix}"))
+        .await?;
+    let cash = cala
+        .account_sets()
+        .find_by_external_id(format!("cash-{suffix}"))
+        .await?;
+    let members = cala
+        .account_sets()
+        .list_members_by_created_at(
+            cash.id(),
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+        )
+        .await?;
+    assert_eq!(members.entities.len(), 2);
+
+    let exported = cala
+        .chart_of_accounts()
+        .export(None, &[journal.id()])
+        .await?;
+    assert_eq!(exported.journals.len(), 1);
+    assert_eq!(exported.accounts.len(), 2);
+    let assets = exported.journals[0]
+        .account_sets
+        .iter()
+        .find(|set| set.external_id == Some(format!("assets-{suffix}")))
+        .unwrap();
+    assert_eq!(assets.account_sets, vec![format!("cash-{suffix}")]);
+
+    let applied = cala.chart_of_accounts().apply(None, &exported).await?;
+    assert!(applied.is_unchanged());
+
+    let mut renamed = exported.clone();
+    renamed.accounts[0].name = "Renamed".to_string();
+    let applied = cala.chart_of_accounts().apply(None, &renamed).await?;
+    assert_eq!(applied.accounts_updated, 1);
+    assert_eq!(applied.accounts_created, 0);
+
+    let mut unknown = chart.clone();
+    unknown.journals[0].account_sets[1]
+        .accounts
+        .push(format!("missing-{suffix}"));
+    let res = cala.chart_of_accounts().apply(None, &unknown).await;
+    assert!(matches!(
+        res,
+        Err(chart_of_accounts::error::ChartOfAccountsError::AccountNotFound(_))
+    ));
+
+    let other_journal = cala.journals().create(helpers::test_journal()).await?;
+    let mut moved = exported.clone();
+    moved.journals[0].id = Some(other_journal.id());
+    moved.journals[0].code = None;
+    let res = cala.chart_of_accounts().apply(None, &moved).await;
+    assert!(matches!(
+        res,
+        Err(chart_of_accounts::error::ChartOfAccountsError::AccountSetInOtherJournal(_))
+    ));
+
+    let tenant_id = TenantId::new();
+    let mut tenant_chart = chart.clone();
+    for set in tenant_chart.journals[0].account_sets.iter_mut() {
+        set.external_id = set.external_id.as_ref().map(|id| format!("tenant-{id}"));
+        set.account_sets = set
+            .account_sets
+            .iter()
+            .map(|id| format!("tenant-{id}"))
+            .collect();
+    }
+    let applied = cala
+        .chart_of_accounts()
+        .apply(Some(tenant_id), &tenant_chart)
+        .await?;
+    assert_eq!(applied.journals_created, 1);
+    assert_eq!(applied.accounts_created, 2);
+    assert_eq!(applied.members_added, 3);
+
+    let tenant_journal = cala
+        .journals()
+        .find_by_code(Some(tenant_id), format!("GL-{suffix}"))
+        .await?;
+    assert_ne!(tenant_journal.id(), journal.id());
+    let res = cala
+        .chart_of_accounts()
+        .export(None, &[tenant_journal.id()])
+        .await;
+    assert!(matches!(
+        res,
+        Err(
+            chart_of_accounts::error::ChartOfAccountsError::JournalError(
+                journal::error::JournalError::TenantMismatch(_)
+            )
+        )
+    ));
+    let exported = cala
+        .chart_of_accounts()
+        .export(Some(tenant_id), &[tenant_journal.id()])
+        .await?;
+    assert_eq!(exported.accounts.len(), 2);
+
+    Ok(())
+}
@@ -4,7 +4,7 @@ use chrono::NaiveDate;
 use rand::distr::{Alphanumeric, SampleString};
 use rust_decimal_macros::dec;
 
-use cala_ledger::{tx_template::*, *};
+use cala_ledger::{balance::error::BalanceError, journal::JournalUpdate, tx_template::*, *};
 
 #[tokio::test]
 async fn transaction_post_with_effective_balances() -> anyhow::Result<()> {
@@ -125,3 +125,509 @@ async fn transaction_post_with_effective_balances() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn backfill_effective_balances() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal();
+    let mut journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let (sender_set, _) = helpers::test_account_sets(journal.id().into());
+    let sender_set = cala.account_sets().create(sender_set).await.unwrap();
+    cala.account_sets()
+        .add_member(sender_set.id(), sender_account.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    let date1 = NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+    let date2 = NaiveDate::from_ymd_opt(2025, 5, 4).unwrap();
+    for effective in [date1, date2] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("effective", effective);
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+    }
+
+    let res = cala
+        .balances()
+        .effective()
+        .backfill_batch(journal.id(), 2)
+        .await;
+    assert!(matches!(
+        res,
+        Err(BalanceError::EffectiveBalancesNotEnabled(_))
+    ));
+
+    let mut update = JournalUpdate::default();
+    update.enable_effective_balances(true);
+    journal.update(update);
+    cala.journals().persist(&mut journal).await?;
+
+    let backfill = loop {
+        let backfill = cala
+            .balances()
+            .effective()
+            .backfill_batch(journal.id(), 2)
+            .await?;
+        if backfill.is_completed() {
+            break backfill;
+        }
+    };
+    assert_eq!(backfill.balances_processed, backfill.balances_total);
+    assert_eq!(backfill.balances_total, 6);
+
+    let recipient_balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), recipient_account.id(), Currency::BTC, date2)
+        .await?;
+    assert_eq!(recipient_balance.settled(), dec!(1290));
+    let recipient_balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), recipient_account.id(), Currency::USD, date1)
+        .await?;
+    assert_eq!(recipient_balance.settled(), dec!(200));
+    assert_eq!(recipient_balance.pending(), dec!(200));
+
+    let sender_balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), sender_account.id(), Currency::USD, date1)
+        .await?;
+    let sender_set_balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), sender_set.id(), Currency::USD, date1)
+        .await?;
+    assert_eq!(sender_set_balance.settled(), sender_balance.settled());
+
+    let backfill = cala
+        .balances()
+        .effective()
+        .backfill_batch(journal.id(), 2)
+        .await?;
+    assert!(backfill.is_completed());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn backfill_after_member_removal() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let mut journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+    let (set, _) = helpers::test_account_sets(journal.id().into());
+    let set = cala.account_sets().create(set).await?;
+    cala.account_sets()
+        .add_member(set.id(), recipient.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let effective = NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id());
+    params.insert("sender", sender.id());
+    params.insert("recipient", recipient.id());
+    params.insert("effective", effective);
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+    cala.account_sets()
+        .remove_member(set.id(), recipient.id())
+        .await?;
+
+    let mut update = JournalUpdate::default();
+    update.enable_effective_balances(true);
+    journal.update(update);
+    cala.journals().persist(&mut journal).await?;
+    while !cala
+        .balances()
+        .effective()
+        .backfill_batch(journal.id(), 10)
+        .await?
+        .is_completed()
+    {}
+
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), set.id(), Currency::BTC, effective)
+        .await?;
+    assert_eq!(balance.settled(), dec!(1290));
+    let today = chrono::Utc::now().date_naive();
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), set.id(), Currency::BTC, today)
+        .await?;
+    assert_eq!(balance.settled(), dec!(0));
+    let current = cala
+        .balances()
+        .find(journal.id(), set.id(), Currency::BTC)
+        .await?;
+    assert_eq!(current.settled(), balance.settled());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn effective_balance_statement() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let new_journal = helpers::test_journal_with_effective_balances();
+    let journal = cala.journals().create(new_journal).await.unwrap();
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let new_template = helpers::currency_conversion_template(&tx_code);
+    cala.tx_templates().create(new_template).await.unwrap();
+
+    for (m, d) in [(1, 10), (2, 15), (2, 20)] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params.insert("effective", NaiveDate::from_ymd_opt(2025, m, d).unwrap());
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await
+            .unwrap();
+    }
+
+    let statement = cala
+        .balances()
+        .effective()
+        .statement(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 31).unwrap(),
+            Some(balance::StatementGrouping::Monthly),
+        )
+        .await?;
+    assert_eq!(statement.periods.len(), 3);
+    let expected = [
+        (dec!(0), dec!(100), dec!(100)),
+        (dec!(100), dec!(200), dec!(300)),
+        (dec!(300), dec!(0), dec!(300)),
+    ];
+    for (period, (opening, credits, closing)) in statement.periods.iter().zip(expected) {
+        for layer in [Layer::Settled, Layer::Pending] {
+            let activity = period.activity(layer);
+            assert_eq!(activity.opening, opening);
+            assert_eq!(activity.debits, dec!(0));
+            assert_eq!(activity.credits, credits);
+            assert_eq!(activity.closing, closing);
+        }
+    }
+    assert_eq!(
+        statement.periods[1].until,
+        NaiveDate::from_ymd_opt(2025, 2, 28).unwrap()
+    );
+
+    let statement = cala
+        .balances()
+        .effective()
+        .statement(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 31).unwrap(),
+            None,
+        )
+        .await?;
+    assert_eq!(statement.periods.len(), 1);
+    let activity = statement.periods[0].activity(Layer::Settled);
+    assert_eq!(activity.opening, dec!(100));
+    assert_eq!(activity.credits, dec!(200));
+    assert_eq!(activity.closing, dec!(300));
+
+    let statement = cala
+        .balances()
+        .effective()
+        .statement(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            Some(balance::StatementGrouping::Quarterly),
+        )
+        .await?;
+    assert!(statement.periods.is_empty());
+
+    let res = cala
+        .balances()
+        .effective()
+        .statement(
+            journal.id(),
+            recipient_account.id(),
+            Currency::USD,
+            NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            None,
+        )
+        .await;
+    assert!(matches!(res, Err(BalanceError::InvalidDateRange(..))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn move_account_set_member() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+    let (one, two) = helpers::test_account_sets(journal.id().into());
+    let one = cala.account_sets().create(one).await?;
+    let two = cala.account_sets().create(two).await?;
+    cala.account_sets()
+        .add_member(one.id(), recipient.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let mut params = Params::new();
+    params.insert("journal_id", journal.id().to_string());
+    params.insert("sender", sender.id());
+    params.insert("recipient", recipient.id());
+    let effective = NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+    params.insert("effective", effective);
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+
+    let res = cala
+        .account_sets()
+        .move_member(two.id(), one.id(), recipient.id(), Default::default())
+        .await;
+    assert!(matches!(
+        res,
+        Err(account_set::error::AccountSetError::MemberNotFound(_))
+    ));
+    let res = cala
+        .account_sets()
+        .move_member(one.id(), one.id(), recipient.id(), Default::default())
+        .await;
+    assert!(matches!(
+        res,
+        Err(account_set::error::AccountSetError::MoveToSameAccountSet(_))
+    ));
+
+    // As of now: the new parent only holds the balance from today onwards
+    cala.account_sets()
+        .move_member(
+            one.id(),
+            two.id(),
+            recipient.id(),
+            account_set::MemberMoveMode::AsOfNow,
+        )
+        .await?;
+    let today = chrono::Utc::now().date_naive();
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), one.id(), Currency::BTC, effective)
+        .await?;
+    assert_eq!(balance.details.settled.cr_balance, dec!(1290));
+    assert!(cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), two.id(), Currency::BTC, effective)
+        .await
+        .is_err());
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), two.id(), Currency::BTC, today)
+        .await?;
+    assert_eq!(balance.details.settled.cr_balance, dec!(1290));
+
+    // Retroactive: the history is restated as if the member had always been in set three
+    let three = account_set::NewAccountSet::builder()
+        .id(AccountSetId::new())
+        .name("three")
+        .journal_id(journal.id())
+        .build()
+        .unwrap();
+    let three = cala.account_sets().create(three).await?;
+    cala.account_sets()
+        .move_member(
+            two.id(),
+            three.id(),
+            recipient.id(),
+            account_set::MemberMoveMode::Retroactive,
+        )
+        .await?;
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), three.id(), Currency::BTC, effective)
+        .await?;
+    assert_eq!(balance.settled(), dec!(1290));
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), three.id(), Currency::BTC, today)
+        .await?;
+    assert_eq!(balance.settled(), dec!(1290));
+    let current = cala
+        .balances()
+        .find(journal.id(), three.id(), Currency::BTC)
+        .await?;
+    assert_eq!(current.settled(), balance.settled());
+
+    // Set two only held the member since today so nothing is removed before that
+    assert!(cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), two.id(), Currency::BTC, effective)
+        .await
+        .is_err());
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), two.id(), Currency::BTC, today)
+        .await?;
+    assert_eq!(balance.settled(), dec!(0));
+    let balance = cala
+        .balances()
+        .effective()
+        .find_cumulative(journal.id(), one.id(), Currency::BTC, effective)
+        .await?;
+    assert_eq!(balance.settled(), dec!(1290));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn move_account_set_member_retroactively() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let journal = cala
+        .journals()
+        .create(helpers::test_journal_with_effective_balances())
+        .await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+    let (one, two) = helpers::test_account_sets(journal.id().into());
+    let one = cala.account_sets().create(one).await?;
+    let two = cala.account_sets().create(two).await?;
+    let parent = account_set::NewAccountSet::builder()
+        .id(AccountSetId::new())
+        .name("parent")
+        .journal_id(journal.id())
+        .build()
+        .unwrap();
+    let parent = cala.account_sets().create(parent).await?;
+    cala.account_sets()
+        .add_member(parent.id(), one.id())
+        .await?;
+    cala.account_sets()
+        .add_member(parent.id(), two.id())
+        .await?;
+    cala.account_sets()
+        .add_member(one.id(), recipient.id())
+        .await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let date1 = NaiveDate::from_ymd_opt(2025, 5, 4).unwrap();
+    let date2 = NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+    for effective in [date1, date2] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender.id());
+        params.insert("recipient", recipient.id());
+        params.insert("effective", effective);
+        cala.post_transaction(TransactionId::new(), &tx_code, params)
+            .await?;
+    }
+
+    cala.account_sets()
+        .move_member(
+            one.id(),
+            two.id(),
+            recipient.id(),
+            account_set::MemberMoveMode::Retroactive,
+        )
+        .await?;
+
+    let today = chrono::Utc::now().date_naive();
+    for (set_id, date, settled) in [
+        (one.id(), date1, dec!(0)),
+        (one.id(), date2, dec!(0)),
+        (one.id(), today, dec!(0)),
+        (two.id(), date1, dec!(1290)),
+        (two.id(), date2, dec!(2580)),
+        (two.id(), today, dec!(2580)),
+        (parent.id(), date1, dec!(1290)),
+        (parent.id(), today, dec!(2580)),
+    ] {
+        let balance = cala
+            .balances()
+            .effective()
+            .find_cumulative(journal.id(), set_id, Currency::BTC, date)
+            .await?;
+        assert_eq!(balance.settled(), settled);
+    }
+
+    Ok(())
+}
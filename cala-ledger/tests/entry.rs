@@ -0,0 +1,127 @@
+mod helpers;
+
+use chrono::NaiveDate;
+use rand::distr::{Alphanumeric, SampleString};
+use rust_decimal::Decimal;
+
+use cala_ledger::{entry::*, tx_template::*, *};
+
+#[tokio::test]
+async fn search_entries() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+
+    let later = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+    let earlier = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+    let mut transaction_ids = Vec::new();
+    for effective in [later, earlier] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender.id());
+        params.insert("recipient", recipient.id());
+        params.insert("effective", effective);
+        let tx = cala
+            .post_transaction(TransactionId::new(), &tx_code, params)
+            .await?;
+        transaction_ids.push(tx.id());
+    }
+
+    let search = |filter: EntryFilter, order_by: EntriesOrderBy| {
+        let entries = cala.entries().clone();
+        let query = es_entity::PaginatedQueryArgs {
+            first: 20,
+            after: None,
+        };
+        async move {
+            entries
+                .search(filter, order_by, query, es_entity::ListDirection::Ascending)
+                .await
+                .map(|ret| ret.entities)
+        }
+    };
+    let in_journal = || EntryFilter::default().journal_id(journal.id());
+
+    let entries = search(in_journal().entry_type("TEST_BTC_DR"), Default::default()).await?;
+    assert_eq!(entries.len(), 2);
+
+    let entries = search(in_journal().layer(Layer::Pending), Default::default()).await?;
+    assert_eq!(entries.len(), 4);
+
+    let entries = search(
+        in_journal()
+            .account_id(recipient.id())
+            .direction(DebitOrCredit::Credit)
+            .currency("USD".parse()?),
+        Default::default(),
+    )
+    .await?;
+    assert_eq!(entries.len(), 4);
+
+    let entries = search(
+        in_journal().effective(Some(earlier), Some(earlier)),
+        Default::default(),
+    )
+    .await?;
+    assert_eq!(entries.len(), 6);
+    assert!(entries
+        .iter()
+        .all(|entry| entry.values().transaction_id == transaction_ids[1]));
+
+    let entries = search(
+        in_journal().units(Some(Decimal::from(1000)), None),
+        Default::default(),
+    )
+    .await?;
+    assert_eq!(entries.len(), 4);
+
+    let entries = search(
+        in_journal().metadata_eq("sender", sender.id().to_string()),
+        Default::default(),
+    )
+    .await?;
+    assert_eq!(entries.len(), 2);
+
+    let entries = search(in_journal(), EntriesOrderBy::CreatedAt).await?;
+    assert_eq!(entries[0].values().transaction_id, transaction_ids[0]);
+    let entries = search(in_journal(), EntriesOrderBy::Effective).await?;
+    assert_eq!(entries[0].values().transaction_id, transaction_ids[1]);
+
+    let mut after = None;
+    let mut seen = Vec::new();
+    loop {
+        let page = cala
+            .entries()
+            .search(
+                in_journal(),
+                EntriesOrderBy::Effective,
+                es_entity::PaginatedQueryArgs { first: 5, after },
+                es_entity::ListDirection::Descending,
+            )
+            .await?;
+        seen.extend(page.entities.iter().map(|entry| entry.id()));
+        if !page.has_next_page {
+            break;
+        }
+        after = page.end_cursor;
+    }
+    assert_eq!(seen.len(), 12);
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 12);
+
+    Ok(())
+}
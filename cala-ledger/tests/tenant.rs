@@ -0,0 +1,238 @@
+mod helpers;
+
+use rand::distr::{Alphanumeric, SampleString};
+
+use cala_ledger::{
+    account::{error::AccountError, *},
+    account_set::{error::AccountSetError, *},
+    error::LedgerError,
+    journal::{error::JournalError, *},
+    tx_template::{error::TxTemplateError, *},
+    *,
+};
+
+fn tenant_journal(tenant_id: TenantId) -> NewJournal {
+    let name = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    NewJournal::builder()
+        .id(JournalId::new())
+        .name(name)
+        .tenant_id(tenant_id)
+        .build()
+        .unwrap()
+}
+
+fn tenant_account(tenant_id: TenantId, code: &str) -> NewAccount {
+    NewAccount::builder()
+        .id(AccountId::new())
+        .name(format!("Tenant Account {code}"))
+        .code(code)
+        .tenant_id(tenant_id)
+        .build()
+        .unwrap()
+}
+
+fn tenant_template(tenant_id: TenantId, code: &str) -> NewTxTemplate {
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("sender")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("recipient")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+        NewParamDefinition::builder()
+            .name("journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()
+            .unwrap(),
+    ];
+    let entries = vec![
+        NewTxTemplateEntry::builder()
+            .entry_type("'TEST_DR'")
+            .account_id("params.sender")
+            .layer("SETTLED")
+            .direction("DEBIT")
+            .units("decimal('100')")
+            .currency("'USD'")
+            .build()
+            .unwrap(),
+        NewTxTemplateEntry::builder()
+            .entry_type("'TEST_CR'")
+            .account_id("params.recipient")
+            .layer("SETTLED")
+            .direction("CREDIT")
+            .units("decimal('100')")
+            .currency("'USD'")
+            .build()
+            .unwrap(),
+    ];
+    NewTxTemplate::builder()
+        .id(TxTemplateId::new())
+        .code(code)
+        .tenant_id(tenant_id)
+        .params(params)
+        .transaction(
+            NewTxTemplateTransaction::builder()
+                .effective("date()")
+                .journal_id("params.journal_id")
+                .build()
+                .unwrap(),
+        )
+        .entries(entries)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn codes_are_unique_per_tenant() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let tenant_a = TenantId::new();
+    let tenant_b = TenantId::new();
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+
+    let account_a = cala
+        .accounts()
+        .create(tenant_account(tenant_a, &code))
+        .await?;
+    let account_b = cala
+        .accounts()
+        .create(tenant_account(tenant_b, &code))
+        .await?;
+
+    let found = cala
+        .accounts()
+        .find_by_code(Some(tenant_a), code.clone())
+        .await?;
+    assert_eq!(found.id(), account_a.id());
+    let found = cala
+        .accounts()
+        .find_by_code(Some(tenant_b), code.clone())
+        .await?;
+    assert_eq!(found.id(), account_b.id());
+    let res = cala.accounts().find_by_code(None, code.clone()).await;
+    assert!(matches!(res, Err(AccountError::CouldNotFindByCode(_))));
+
+    let res = cala
+        .accounts()
+        .create(tenant_account(tenant_a, &code))
+        .await;
+    assert!(matches!(res, Err(AccountError::CodeAlreadyExists)));
+
+    cala.tx_templates()
+        .create(tenant_template(tenant_a, &code))
+        .await?;
+    cala.tx_templates()
+        .create(tenant_template(tenant_b, &code))
+        .await?;
+    let template = cala
+        .tx_templates()
+        .find_by_code(Some(tenant_b), code.clone())
+        .await?;
+    assert_eq!(template.values().tenant_id, Some(tenant_b));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cross_tenant_references_are_rejected() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let tenant_a = TenantId::new();
+    let tenant_b = TenantId::new();
+
+    let journal_a = cala.journals().create(tenant_journal(tenant_a)).await?;
+    let journal_b = cala.journals().create(tenant_journal(tenant_b)).await?;
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let sender_a = cala
+        .accounts()
+        .create(tenant_account(tenant_a, &format!("{code}-sender")))
+        .await?;
+    let recipient_a = cala
+        .accounts()
+        .create(tenant_account(tenant_a, &format!("{code}-recipient")))
+        .await?;
+    let recipient_b = cala
+        .accounts()
+        .create(tenant_account(tenant_b, &format!("{code}-recipient")))
+        .await?;
+
+    cala.tx_templates()
+        .create(tenant_template(tenant_a, &code))
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal_a.id());
+    params.insert("sender", sender_a.id());
+    params.insert("recipient", recipient_a.id());
+    cala.post_transaction_for_tenant(tenant_a, TransactionId::new(), &code, params)
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal_a.id());
+    params.insert("sender", sender_a.id());
+    params.insert("recipient", recipient_b.id());
+    let res = cala
+        .post_transaction_for_tenant(tenant_a, TransactionId::new(), &code, params)
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::AccountError(AccountError::TenantMismatch(id))) if id == recipient_b.id()
+    ));
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal_b.id());
+    params.insert("sender", sender_a.id());
+    params.insert("recipient", recipient_a.id());
+    let res = cala
+        .post_transaction_for_tenant(tenant_a, TransactionId::new(), &code, params)
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::JournalError(JournalError::TenantMismatch(id))) if id == journal_b.id()
+    ));
+
+    let mut params = Params::new();
+    params.insert("journal_id", journal_a.id());
+    params.insert("sender", sender_a.id());
+    params.insert("recipient", recipient_a.id());
+    let res = cala
+        .post_transaction(TransactionId::new(), &code, params)
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::TxTemplateError(TxTemplateError::NotFound))
+    ));
+
+    let new_set = NewAccountSet::builder()
+        .id(AccountSetId::new())
+        .name(format!("Tenant Set {code}"))
+        .journal_id(journal_a.id())
+        .build()
+        .unwrap();
+    let set = cala.account_sets().create(new_set).await?;
+    cala.account_sets()
+        .add_member(set.id(), recipient_a.id())
+        .await?;
+    let res = cala
+        .account_sets()
+        .add_member(set.id(), recipient_b.id())
+        .await;
+    assert!(matches!(res, Err(AccountSetError::TenantMismatch(_, _))));
+
+    Ok(())
+}
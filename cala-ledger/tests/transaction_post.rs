@@ -81,3 +81,474 @@ async fn transaction_post() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn transaction_post_enforces_account_status() -> anyhow::Result<()> {
+    use cala_ledger::{
+        account::{error::AccountError, AccountUpdate},
+        error::LedgerError,
+    };
+
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+    let params = || {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender_account.id());
+        params.insert("recipient", recipient_account.id());
+        params
+    };
+
+    // The sender is only debited, the recipient only credited
+    cala.accounts()
+        .update_status(sender_account.id(), AccountStatus::Frozen, "investigation")
+        .await?;
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, params())
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::AccountError(AccountError::AccountFrozen(id))) if id == sender_account.id()
+    ));
+
+    cala.accounts()
+        .update_status(sender_account.id(), AccountStatus::Active, "cleared")
+        .await?;
+    cala.accounts()
+        .update_status(
+            recipient_account.id(),
+            AccountStatus::Frozen,
+            "investigation",
+        )
+        .await?;
+    cala.post_transaction(TransactionId::new(), &tx_code, params())
+        .await?;
+
+    cala.accounts()
+        .update_status(recipient_account.id(), AccountStatus::Locked, "dispute")
+        .await?;
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, params())
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::AccountError(AccountError::AccountLocked(_)))
+    ));
+
+    let res = cala
+        .accounts()
+        .update_status(recipient_account.id(), AccountStatus::Closed, "offboarding")
+        .await;
+    assert!(matches!(res, Err(AccountError::NonZeroBalance(_))));
+    let mut recipient = cala.accounts().find(recipient_account.id()).await?;
+    let mut update = AccountUpdate::default();
+    #[allow(deprecated)]
+    update.status(AccountStatus::Closed);
+    recipient.update(update);
+    let res = cala.accounts().persist(&mut recipient).await;
+    assert!(matches!(res, Err(AccountError::NonZeroBalance(_))));
+
+    let (idle, _) = helpers::test_accounts();
+    let idle = cala.accounts().create(idle).await?;
+    let idle = cala
+        .accounts()
+        .update_status(idle.id(), AccountStatus::Closed, "never used")
+        .await?;
+    assert_eq!(idle.status(), AccountStatus::Closed);
+    let res = cala
+        .accounts()
+        .update_status(idle.id(), AccountStatus::Active, "reopen")
+        .await;
+    assert!(matches!(res, Err(AccountError::AccountClosed(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transaction_search() -> anyhow::Result<()> {
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(helpers::currency_conversion_template(&tx_code))
+        .await?;
+
+    let first = chrono::NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+    let second = chrono::NaiveDate::from_ymd_opt(2025, 7, 2).unwrap();
+    let mut transactions = Vec::new();
+    for effective in [first, second] {
+        let mut params = Params::new();
+        params.insert("journal_id", journal.id().to_string());
+        params.insert("sender", sender.id());
+        params.insert("recipient", recipient.id());
+        params.insert("effective", effective);
+        transactions.push(
+            cala.post_transaction(TransactionId::new(), &tx_code, params)
+                .await?,
+        );
+    }
+    let voided = cala
+        .void_transaction(TransactionId::new(), transactions[0].id())
+        .await?;
+
+    let query = || es_entity::PaginatedQueryArgs {
+        first: 10,
+        after: None,
+    };
+
+    let correlated = cala
+        .transactions()
+        .list_by_correlation_id(
+            transactions[0].values().correlation_id.clone(),
+            query(),
+            es_entity::ListDirection::Ascending,
+        )
+        .await?;
+    let ids: Vec<_> = correlated.entities.iter().map(|tx| tx.id()).collect();
+    assert_eq!(ids, vec![transactions[0].id(), voided.id()]);
+
+    let in_journal = cala
+        .transactions()
+        .list_for_journal(
+            journal.id(),
+            None,
+            None,
+            query(),
+            es_entity::ListDirection::Descending,
+        )
+        .await?;
+    let ids: Vec<_> = in_journal.entities.iter().map(|tx| tx.id()).collect();
+    assert_eq!(
+        ids,
+        vec![voided.id(), transactions[1].id(), transactions[0].id()]
+    );
+
+    let on_second = cala
+        .transactions()
+        .list_for_journal(
+            journal.id(),
+            Some(second),
+            Some(second),
+            query(),
+            es_entity::ListDirection::Ascending,
+        )
+        .await?;
+    assert!(on_second
+        .entities
+        .iter()
+        .any(|tx| tx.id() == transactions[1].id()));
+    assert!(on_second
+        .entities
+        .iter()
+        .all(|tx| tx.values().effective == second));
+
+    let page = cala
+        .transactions()
+        .search(
+            transaction::TransactionFilter::default()
+                .journal_id(journal.id())
+                .effective(Some(first), None),
+            es_entity::PaginatedQueryArgs {
+                first: 2,
+                after: None,
+            },
+            es_entity::ListDirection::Ascending,
+        )
+        .await?;
+    assert!(page.has_next_page);
+    let page = cala
+        .transactions()
+        .search(
+            transaction::TransactionFilter::default()
+                .journal_id(journal.id())
+                .effective(Some(first), None),
+            es_entity::PaginatedQueryArgs {
+                first: 2,
+                after: page.end_cursor,
+            },
+            es_entity::ListDirection::Ascending,
+        )
+        .await?;
+    assert!(!page.has_next_page);
+    assert_eq!(page.entities[0].id(), voided.id());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transaction_post_across_journals() -> anyhow::Result<()> {
+    use cala_ledger::{error::LedgerError, tx_template::error::TxTemplateError};
+
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let customers = cala.journals().create(helpers::test_journal()).await?;
+    let treasury = cala.journals().create(helpers::test_journal()).await?;
+    let (customer, customer_clearing) = helpers::test_accounts();
+    let customer = cala.accounts().create(customer).await?;
+    let customer_clearing = cala.accounts().create(customer_clearing).await?;
+    let (treasury_clearing, reserve) = helpers::test_accounts();
+    let treasury_clearing = cala.accounts().create(treasury_clearing).await?;
+    let reserve = cala.accounts().create(reserve).await?;
+    let (reserves, _) = helpers::test_account_sets(treasury.id().into());
+    let reserves = cala.account_sets().create(reserves).await?;
+    cala.account_sets()
+        .add_member(reserves.id(), reserve.id())
+        .await?;
+
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("customer_journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("treasury_journal_id")
+            .r#type(ParamDataType::Uuid)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("amount")
+            .r#type(ParamDataType::Decimal)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("treasury_amount")
+            .r#type(ParamDataType::Decimal)
+            .build()?,
+    ];
+    let entry = |account_id: AccountId, direction: &str, journal: Option<&str>, amount: &str| {
+        let mut builder = NewTxTemplateEntry::builder();
+        builder
+            .entry_type("'TRANSFER'")
+            .account_id(format!("uuid('{account_id}')"))
+            .layer("SETTLED")
+            .direction(direction)
+            .units(amount)
+            .currency("'USD'");
+        if let Some(journal) = journal {
+            builder.journal_id(journal);
+        }
+        builder.build().unwrap()
+    };
+    let entries = vec![
+        entry(customer.id(), "DEBIT", None, "params.amount"),
+        entry(customer_clearing.id(), "CREDIT", None, "params.amount"),
+        entry(
+            treasury_clearing.id(),
+            "DEBIT",
+            Some("params.treasury_journal_id"),
+            "params.amount",
+        ),
+        entry(
+            reserve.id(),
+            "CREDIT",
+            Some("params.treasury_journal_id"),
+            "params.treasury_amount",
+        ),
+    ];
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(
+            NewTxTemplate::builder()
+                .id(uuid::Uuid::now_v7())
+                .code(&tx_code)
+                .params(params)
+                .transaction(
+                    NewTxTemplateTransaction::builder()
+                        .effective("date()")
+                        .journal_id("params.customer_journal_id")
+                        .build()?,
+                )
+                .entries(entries)
+                .build()?,
+        )
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("customer_journal_id", customers.id().to_string());
+    params.insert("treasury_journal_id", treasury.id().to_string());
+    params.insert("amount", Decimal::from(100));
+    params.insert("treasury_amount", Decimal::from(100));
+    let transaction = cala
+        .post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+    assert_eq!(transaction.journal_id(), customers.id());
+
+    let usd = "USD".parse().unwrap();
+    let balance = cala
+        .balances()
+        .find(customers.id(), customer_clearing.id(), usd)
+        .await?;
+    assert_eq!(balance.settled(), Decimal::from(100));
+    let balance = cala
+        .balances()
+        .find(treasury.id(), reserve.id(), usd)
+        .await?;
+    assert_eq!(balance.settled(), Decimal::from(100));
+    let balance = cala
+        .balances()
+        .find(treasury.id(), reserves.id(), usd)
+        .await?;
+    assert_eq!(balance.settled(), Decimal::from(100));
+    let res = cala
+        .balances()
+        .find(customers.id(), reserve.id(), usd)
+        .await;
+    assert!(res.is_err());
+
+    let mut params = Params::new();
+    params.insert("customer_journal_id", customers.id().to_string());
+    params.insert("treasury_journal_id", treasury.id().to_string());
+    params.insert("amount", Decimal::from(100));
+    params.insert("treasury_amount", Decimal::from(90));
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, params)
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::TxTemplateError(
+            TxTemplateError::UnbalancedJournal(journal_id, _, _, _)
+        )) if journal_id == treasury.id()
+    ));
+    let balance = cala
+        .balances()
+        .find(customers.id(), customer_clearing.id(), usd)
+        .await?;
+    assert_eq!(balance.settled(), Decimal::from(100));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transaction_post_validates_metadata_schema() -> anyhow::Result<()> {
+    use cala_ledger::{
+        error::LedgerError, metadata_schema::error::MetadataSchemaError,
+        tx_template::error::TxTemplateError,
+    };
+    use serde_json::json;
+
+    let pool = helpers::init_pool().await?;
+    let cala_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(false)
+        .build()?;
+    let cala = CalaLedger::init(cala_config).await?;
+
+    let journal = cala.journals().create(helpers::test_journal()).await?;
+    let (sender, recipient) = helpers::test_accounts();
+    let sender = cala.accounts().create(sender).await?;
+    let recipient = cala.accounts().create(recipient).await?;
+
+    let params = vec![
+        NewParamDefinition::builder()
+            .name("reference")
+            .r#type(ParamDataType::String)
+            .build()?,
+        NewParamDefinition::builder()
+            .name("channel")
+            .r#type(ParamDataType::String)
+            .build()?,
+    ];
+    let entry = |account_id: AccountId, direction: &str| {
+        NewTxTemplateEntry::builder()
+            .entry_type("'TRANSFER'")
+            .account_id(format!("uuid('{account_id}')"))
+            .layer("SETTLED")
+            .direction(direction)
+            .units("decimal('10')")
+            .currency("'USD'")
+            .metadata(r#"{"channel": params.channel}"#)
+            .metadata_schema(json!({
+                "type": "object",
+                "properties": { "channel": { "enum": ["web", "mobile"] } }
+            }))
+            .build()
+            .unwrap()
+    };
+    let tx_code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    cala.tx_templates()
+        .create(
+            NewTxTemplate::builder()
+                .id(uuid::Uuid::now_v7())
+                .code(&tx_code)
+                .params(params)
+                .transaction(
+                    NewTxTemplateTransaction::builder()
+                        .effective("date()")
+                        .journal_id(format!("uuid('{}')", journal.id()))
+                        .metadata(r#"{"reference": params.reference}"#)
+                        .metadata_schema(json!({
+                            "type": "object",
+                            "properties": { "reference": { "type": "string", "minLength": 8 } },
+                            "required": ["reference"]
+                        }))
+                        .build()?,
+                )
+                .entries(vec![
+                    entry(sender.id(), "DEBIT"),
+                    entry(recipient.id(), "CREDIT"),
+                ])
+                .build()?,
+        )
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("reference", "INV-00001");
+    params.insert("channel", "web");
+    cala.post_transaction(TransactionId::new(), &tx_code, params)
+        .await?;
+
+    let mut params = Params::new();
+    params.insert("reference", "INV-1");
+    params.insert("channel", "web");
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, params)
+        .await;
+    let Err(LedgerError::TxTemplateError(TxTemplateError::InvalidTransactionMetadata(
+        MetadataSchemaError::Violation(violations),
+    ))) = res
+    else {
+        panic!("expected transaction metadata violation");
+    };
+    assert_eq!(violations[0].path, "/reference");
+
+    let mut params = Params::new();
+    params.insert("reference", "INV-00002");
+    params.insert("channel", "fax");
+    let res = cala
+        .post_transaction(TransactionId::new(), &tx_code, params)
+        .await;
+    assert!(matches!(
+        res,
+        Err(LedgerError::TxTemplateError(
+            TxTemplateError::InvalidEntryMetadata(1, _)
+        ))
+    ));
+
+    Ok(())
+}
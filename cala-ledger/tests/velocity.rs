@@ -169,6 +169,236 @@ async fn create_control_on_account() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn find_balances_for_account() -> anyhow::Result<()> {
+    let (cala, journal_id, tx_code) = init_test().await?;
+    let velocity = cala.velocities();
+
+    let limit = Decimal::ONE_HUNDRED;
+    let (control_id, control_params) = control_and_limits(velocity, limit).await?;
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+    velocity
+        .attach_control_to_account(control_id, sender_account.id(), control_params)
+        .await?;
+
+    let mut entry = HypotheticalEntry {
+        journal_id,
+        entry_type: "TEST_DR".to_string(),
+        currency: None,
+        metadata: None,
+    };
+    let balances = velocity
+        .find_balances_for_account(sender_account.id(), &entry, chrono::Utc::now())
+        .await?;
+    assert!(balances.is_empty());
+
+    entry.currency = Some("USD".parse().unwrap());
+    let balances = velocity
+        .find_balances_for_account(sender_account.id(), &entry, chrono::Utc::now())
+        .await?;
+    assert_eq!(balances.len(), 2);
+    assert!(balances
+        .iter()
+        .all(|balance| balance.spent == Decimal::ZERO && balance.balance.is_none()));
+    entry.currency = None;
+
+    let mut tx_params = Params::new();
+    tx_params.insert("journal_id", journal_id.to_string());
+    tx_params.insert("sender", sender_account.id());
+    tx_params.insert("recipient", recipient_account.id());
+    tx_params.insert("amount", Decimal::from(40));
+    cala.post_transaction(TransactionId::new(), &tx_code, tx_params)
+        .await?;
+
+    let balances = velocity
+        .find_balances_for_account(sender_account.id(), &entry, chrono::Utc::now())
+        .await?;
+    assert_eq!(balances.len(), 2);
+    for balance in balances {
+        assert_eq!(balance.control_id, control_id);
+        assert_eq!(balance.currency, "USD".parse().unwrap());
+        assert_eq!(balance.limit, limit);
+        assert_eq!(balance.spent, Decimal::from(40));
+        assert_eq!(balance.remaining, Decimal::from(60));
+        assert_eq!(balance.balance.unwrap().journal_id, journal_id);
+    }
+
+    let balances = velocity
+        .find_balances_for_account(recipient_account.id(), &entry, chrono::Utc::now())
+        .await?;
+    assert!(balances.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn find_balances_for_entry_dependent_window() -> anyhow::Result<()> {
+    let (cala, journal_id, tx_code) = init_test().await?;
+    let velocity = cala.velocities();
+
+    let limit = NewVelocityLimit::builder()
+        .id(VelocityLimitId::new())
+        .name("Per entry type")
+        .description("test")
+        .window(vec![NewPartitionKey::builder()
+            .alias("entry_type")
+            .value("context.vars.entry.entryType")
+            .build()
+            .expect("partition key")])
+        .limit(
+            NewLimit::builder()
+                .balance(vec![NewBalanceLimit::builder()
+                    .layer("SETTLED")
+                    .amount("decimal('100')")
+                    .enforcement_direction("DEBIT")
+                    .build()
+                    .expect("limit")])
+                .build()
+                .expect("limit"),
+        )
+        .build()
+        .expect("build limit");
+    let limit = velocity.create_limit(limit).await?;
+    let control = NewVelocityControl::builder()
+        .id(VelocityControlId::new())
+        .name("per entry type")
+        .description("test")
+        .build()
+        .expect("build control");
+    let control = velocity.create_control(control).await?;
+    velocity
+        .add_limit_to_control(control.id(), limit.id())
+        .await?;
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await?;
+    let recipient_account = cala.accounts().create(receiver).await?;
+    velocity
+        .attach_control_to_account(control.id(), sender_account.id(), Params::new())
+        .await?;
+
+    let mut tx_params = Params::new();
+    tx_params.insert("journal_id", journal_id.to_string());
+    tx_params.insert("sender", sender_account.id());
+    tx_params.insert("recipient", recipient_account.id());
+    tx_params.insert("amount", Decimal::from(40));
+    cala.post_transaction(TransactionId::new(), &tx_code, tx_params)
+        .await?;
+
+    let mut entry = HypotheticalEntry {
+        journal_id,
+        entry_type: "TEST_DR".to_string(),
+        currency: None,
+        metadata: None,
+    };
+    let balances = velocity
+        .find_balances_for_account(sender_account.id(), &entry, chrono::Utc::now())
+        .await?;
+    assert_eq!(balances.len(), 1);
+    assert_eq!(balances[0].spent, Decimal::from(40));
+
+    entry.entry_type = "OTHER".to_string();
+    let balances = velocity
+        .find_balances_for_account(sender_account.id(), &entry, chrono::Utc::now())
+        .await?;
+    assert_eq!(balances.len(), 1);
+    assert_eq!(balances[0].spent, Decimal::ZERO);
+    assert!(balances[0].balance.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn record_breaches_without_rejecting() -> anyhow::Result<()> {
+    let (cala, journal_id, tx_code) = init_test().await?;
+    let velocity = cala.velocities();
+
+    let limit = account_closing_limit(velocity, "DEBIT").await?;
+    let control = NewVelocityControl::builder()
+        .id(VelocityControlId::new())
+        .name("shadow")
+        .description("test")
+        .enforcement(
+            NewVelocityEnforcement::builder()
+                .action(VelocityEnforcementAction::Record)
+                .build()
+                .expect("build enforcement"),
+        )
+        .build()
+        .expect("build control");
+    let control = velocity.create_control(control).await?;
+    velocity
+        .add_limit_to_control(control.id(), limit.id())
+        .await?;
+
+    let (sender, receiver) = helpers::test_accounts();
+    let sender_account = cala.accounts().create(sender).await.unwrap();
+    let recipient_account = cala.accounts().create(receiver).await.unwrap();
+    velocity
+        .attach_control_to_account(control.id(), sender_account.id(), Params::default())
+        .await?;
+
+    let mut tx_params = Params::new();
+    tx_params.insert("journal_id", journal_id.to_string());
+    tx_params.insert("sender", sender_account.id());
+    tx_params.insert("recipient", recipient_account.id());
+    tx_params.insert("amount", Decimal::from(40));
+    cala.post_transaction(TransactionId::new(), &tx_code, tx_params.clone())
+        .await?;
+    tx_params.insert("amount", Decimal::from(10));
+    let transaction = cala
+        .post_transaction(TransactionId::new(), &tx_code, tx_params)
+        .await?;
+
+    let breaches = velocity
+        .list_breaches(
+            VelocityBreachFilter::default().account_id(sender_account.id()),
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+        )
+        .await?;
+    assert_eq!(breaches.entities.len(), 2);
+    let latest = &breaches.entities[0];
+    assert_eq!(latest.transaction_id, transaction.id());
+    assert_eq!(latest.control_id, control.id());
+    assert_eq!(latest.limit_id, limit.id());
+    assert_eq!(latest.action, VelocityEnforcementAction::Record);
+    assert_eq!(latest.limit, Decimal::ZERO);
+    assert_eq!(latest.requested, Decimal::from(50));
+
+    let breaches = velocity
+        .list_breaches(
+            VelocityBreachFilter::default().transaction_id(transaction.id()),
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+        )
+        .await?;
+    assert_eq!(breaches.entities.len(), 1);
+
+    let breaches = velocity
+        .list_breaches(
+            VelocityBreachFilter::default().account_id(recipient_account.id()),
+            es_entity::PaginatedQueryArgs {
+                first: 10,
+                after: None,
+            },
+            es_entity::ListDirection::Descending,
+        )
+        .await?;
+    assert!(breaches.entities.is_empty());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn create_control_on_account_set() -> anyhow::Result<()> {
     let (cala, journal_id, tx_code) = init_test().await?;
@@ -686,3 +916,28 @@ mod limit_via_account_sets {
         Ok(())
     }
 }
+
+#[tokio::test]
+async fn cannot_attach_control_to_sharded_account() -> anyhow::Result<()> {
+    let (cala, _, _) = init_test().await?;
+    let velocity = cala.velocities();
+
+    let (control_id, control_params) = control_and_limits(velocity, Decimal::ONE_HUNDRED).await?;
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let omnibus = NewAccount::builder()
+        .id(AccountId::new())
+        .name(format!("Omnibus {code}"))
+        .code(code)
+        .balance_shards(4)
+        .build()
+        .unwrap();
+    let omnibus = cala.accounts().create(omnibus).await?;
+
+    let res = velocity
+        .attach_control_to_account(control_id, omnibus.id(), control_params)
+        .await;
+    assert!(matches!(res, Err(VelocityError::AccountBalanceSharded(_))));
+
+    Ok(())
+}
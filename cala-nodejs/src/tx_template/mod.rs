@@ -39,7 +39,7 @@ impl CalaTxTemplates {
   pub async fn find_by_code(&self, code: String) -> napi::Result<CalaTxTemplate> {
     let template = self
       .inner
-      .find_by_code(code)
+      .find_by_code(None, code)
       .await
       .map_err(crate::generic_napi_error)?;
 
@@ -2,8 +2,16 @@ use serde::{Deserialize, Serialize};
 
 use job::JobPollerConfig;
 
+use crate::extension::{
+    balance_history_compaction::BalanceHistoryCompactionConfig, balance_queue::BalanceQueueConfig,
+};
+
 #[derive(Clone, Default, Debug, Deserialize, Serialize)]
 pub struct AppConfig {
     #[serde(default)]
     pub jobs: JobPollerConfig,
+    #[serde(default)]
+    pub balance_queue: BalanceQueueConfig,
+    #[serde(default)]
+    pub balance_history_compaction: BalanceHistoryCompactionConfig,
 }
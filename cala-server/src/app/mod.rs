@@ -35,6 +35,27 @@ impl CalaApp {
                 ledger.clone(),
             ),
         );
+        jobs.add_initializer(
+            crate::extension::effective_balances_backfill::EffectiveBalancesBackfillJobInitializer::new(
+                ledger.clone(),
+            ),
+        );
+        jobs.add_initializer_and_spawn_unique(
+            crate::extension::balance_queue::BalanceQueueJobInitializer::new(
+                ledger.clone(),
+                config.balance_queue,
+            ),
+            crate::extension::balance_queue::BalanceQueueJobConfig,
+        )
+        .await?;
+        jobs.add_initializer_and_spawn_unique(
+            crate::extension::balance_history_compaction::BalanceHistoryCompactionJobInitializer::new(
+                ledger.clone(),
+                config.balance_history_compaction,
+            ),
+            crate::extension::balance_history_compaction::BalanceHistoryCompactionJobConfig,
+        )
+        .await?;
         jobs.start_poll().await?;
         Ok(Self {
             _pool: pool,
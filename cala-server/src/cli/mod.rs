@@ -2,7 +2,7 @@ pub mod config;
 mod db;
 
 use anyhow::Context;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::{fs, path::PathBuf};
 
 use self::config::{Config, EnvOverride};
@@ -22,6 +22,21 @@ struct Cli {
     cala_home: String,
     #[clap(env = "PG_CON")]
     pg_con: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Recomputes all balances from the recorded entries and reports discrepancies
+    VerifyBalances {
+        /// Only verify the balances of this journal
+        #[clap(long)]
+        journal_id: Option<uuid::Uuid>,
+        /// Write corrective balance versions for every discrepancy found
+        #[clap(long)]
+        repair: bool,
+    },
 }
 
 pub async fn run<Q: QueryExtensionMarker, M: MutationExtensionMarker>() -> anyhow::Result<()> {
@@ -29,7 +44,12 @@ pub async fn run<Q: QueryExtensionMarker, M: MutationExtensionMarker>() -> anyho
 
     let config = Config::load_config(cli.config, EnvOverride { db_con: cli.pg_con })?;
 
-    run_cmd::<Q, M>(&cli.cala_home, config).await?;
+    match cli.command {
+        Some(Command::VerifyBalances { journal_id, repair }) => {
+            verify_balances(config, journal_id, repair).await?
+        }
+        None => run_cmd::<Q, M>(&cli.cala_home, config).await?,
+    }
 
     Ok(())
 }
@@ -52,6 +72,62 @@ async fn run_cmd<Q: QueryExtensionMarker, M: MutationExtensionMarker>(
     Ok(())
 }
 
+async fn verify_balances(
+    config: Config,
+    journal_id: Option<uuid::Uuid>,
+    repair: bool,
+) -> anyhow::Result<()> {
+    use cala_ledger::{balance::BalanceDiscrepancySource, CalaLedger, CalaLedgerConfig};
+    let pool = db::init_pool(&config.db).await?;
+    let ledger_config = CalaLedgerConfig::builder()
+        .pool(pool)
+        .exec_migrations(true)
+        .build()?;
+    let ledger = CalaLedger::init(ledger_config).await?;
+    let report = ledger
+        .balances()
+        .verify_integrity(journal_id.map(Into::into), repair)
+        .await?;
+
+    for d in report.discrepancies.iter() {
+        let source = match d.source {
+            BalanceDiscrepancySource::Current => "current".to_string(),
+            BalanceDiscrepancySource::Effective(date) => format!("effective {date}"),
+        };
+        let stored = d
+            .stored
+            .map(|s| format!("dr {} cr {}", s.dr_balance, s.cr_balance))
+            .unwrap_or_else(|| "missing".to_string());
+        println!(
+            "journal {} account {}{} {} {:?} ({source}): expected dr {} cr {}, stored {stored}",
+            d.journal_id,
+            d.account_id,
+            if d.is_account_set { " (set)" } else { "" },
+            d.currency,
+            d.layer,
+            d.expected.dr_balance,
+            d.expected.cr_balance,
+        );
+    }
+    println!("journals checked: {}", report.journals_checked);
+    println!("balances checked: {}", report.balances_checked);
+    println!(
+        "effective balances checked: {}",
+        report.effective_balances_checked
+    );
+    println!("discrepancies: {}", report.discrepancies.len());
+    if repair {
+        println!("repaired balances: {}", report.repaired_balances);
+        println!(
+            "repaired effective balances: {}",
+            report.repaired_effective_balances
+        );
+    } else if !report.is_consistent() {
+        anyhow::bail!("balance discrepancies found");
+    }
+    Ok(())
+}
+
 pub fn store_server_pid(cala_home: &str, pid: u32) -> anyhow::Result<()> {
     create_cala_dir(cala_home)?;
     let _ = fs::remove_file(format!("{cala_home}/server-pid"));
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+#[serde_with::serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BalanceHistoryCompactionConfig {
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    #[serde(default = "default_interval")]
+    pub interval: Duration,
+}
+
+impl Default for BalanceHistoryCompactionConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: default_batch_size(),
+            interval: default_interval(),
+        }
+    }
+}
+
+fn default_batch_size() -> usize {
+    10000
+}
+
+fn default_interval() -> Duration {
+    Duration::from_secs(60 * 60)
+}
@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use cala_ledger::CalaLedger;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use job::*;
+
+use super::config::BalanceHistoryCompactionConfig;
+
+pub const BALANCE_HISTORY_COMPACTION_JOB_TYPE: JobType =
+    JobType::new("balance-history-compaction-job");
+
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct BalanceHistoryCompactionJobConfig;
+
+impl JobConfig for BalanceHistoryCompactionJobConfig {
+    type Initializer = BalanceHistoryCompactionJobInitializer;
+}
+
+pub(crate) struct BalanceHistoryCompactionJobInitializer {
+    ledger: CalaLedger,
+    config: BalanceHistoryCompactionConfig,
+}
+impl BalanceHistoryCompactionJobInitializer {
+    pub fn new(ledger: CalaLedger, config: BalanceHistoryCompactionConfig) -> Self {
+        Self { ledger, config }
+    }
+}
+
+impl JobInitializer for BalanceHistoryCompactionJobInitializer {
+    fn job_type() -> JobType {
+        BALANCE_HISTORY_COMPACTION_JOB_TYPE
+    }
+
+    fn init(&self, _job: &Job) -> Result<Box<dyn JobRunner>, Box<dyn std::error::Error>> {
+        Ok(Box::new(BalanceHistoryCompactionJob {
+            ledger: self.ledger.clone(),
+            config: self.config.clone(),
+        }))
+    }
+}
+
+/// Applies the balance history retention policies of all journals.
+pub struct BalanceHistoryCompactionJob {
+    ledger: CalaLedger,
+    config: BalanceHistoryCompactionConfig,
+}
+
+#[async_trait]
+impl JobRunner for BalanceHistoryCompactionJob {
+    #[instrument(name = "job.balance_history_compaction.run", skip(self, _current_job))]
+    async fn run(
+        &self,
+        _current_job: CurrentJob,
+    ) -> Result<JobCompletion, Box<dyn std::error::Error>> {
+        for compaction in self
+            .ledger
+            .balances()
+            .compact_history(self.config.batch_size)
+            .await?
+        {
+            tracing::info!(
+                journal_id = %compaction.journal_id,
+                pruned_before = %compaction.pruned_before,
+                balance_versions_removed = compaction.balance_versions_removed,
+                velocity_balance_versions_removed = compaction.velocity_balance_versions_removed,
+                "balance history compacted"
+            );
+        }
+        Ok(JobCompletion::RescheduleIn(self.config.interval))
+    }
+}
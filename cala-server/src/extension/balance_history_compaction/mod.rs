@@ -0,0 +1,5 @@
+mod config;
+mod job;
+
+pub use config::*;
+pub use job::*;
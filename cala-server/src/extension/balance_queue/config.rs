@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+#[serde_with::serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BalanceQueueConfig {
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde_as(as = "serde_with::DurationMilliSeconds<u64>")]
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: Duration,
+}
+
+impl Default for BalanceQueueConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: default_batch_size(),
+            poll_interval: default_poll_interval(),
+        }
+    }
+}
+
+fn default_batch_size() -> usize {
+    1000
+}
+
+fn default_poll_interval() -> Duration {
+    Duration::from_millis(500)
+}
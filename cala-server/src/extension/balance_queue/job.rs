@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use cala_ledger::CalaLedger;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use job::*;
+
+use super::config::BalanceQueueConfig;
+
+pub const BALANCE_QUEUE_JOB_TYPE: JobType = JobType::new("balance-queue-job");
+
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct BalanceQueueJobConfig;
+
+impl JobConfig for BalanceQueueJobConfig {
+    type Initializer = BalanceQueueJobInitializer;
+}
+
+pub(crate) struct BalanceQueueJobInitializer {
+    ledger: CalaLedger,
+    config: BalanceQueueConfig,
+}
+impl BalanceQueueJobInitializer {
+    pub fn new(ledger: CalaLedger, config: BalanceQueueConfig) -> Self {
+        Self { ledger, config }
+    }
+}
+
+impl JobInitializer for BalanceQueueJobInitializer {
+    fn job_type() -> JobType {
+        BALANCE_QUEUE_JOB_TYPE
+    }
+
+    fn init(&self, _job: &Job) -> Result<Box<dyn JobRunner>, Box<dyn std::error::Error>> {
+        Ok(Box::new(BalanceQueueJob {
+            ledger: self.ledger.clone(),
+            config: self.config.clone(),
+        }))
+    }
+}
+
+/// Folds the entries posted to eventually consistent accounts into their balances.
+pub struct BalanceQueueJob {
+    ledger: CalaLedger,
+    config: BalanceQueueConfig,
+}
+
+#[async_trait]
+impl JobRunner for BalanceQueueJob {
+    #[instrument(name = "job.balance_queue.run", skip(self, _current_job))]
+    async fn run(
+        &self,
+        _current_job: CurrentJob,
+    ) -> Result<JobCompletion, Box<dyn std::error::Error>> {
+        let n_folded = self
+            .ledger
+            .balances()
+            .fold_queued_entries(self.config.batch_size)
+            .await?;
+        if n_folded >= self.config.batch_size {
+            return Ok(JobCompletion::RescheduleNow);
+        }
+        Ok(JobCompletion::RescheduleIn(self.config.poll_interval))
+    }
+}
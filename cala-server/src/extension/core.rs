@@ -2,6 +2,8 @@
 pub struct CoreMutationExtension {
     #[graphql(flatten)]
     cala_outbox_import: super::cala_outbox_import::Mutation,
+    #[graphql(flatten)]
+    effective_balances_backfill: super::effective_balances_backfill::Mutation,
 }
 
 #[derive(async_graphql::SimpleObject, Default)]
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use cala_ledger::{primitives::JournalId, CalaLedger};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use job::*;
+
+pub const EFFECTIVE_BALANCES_BACKFILL_JOB_TYPE: JobType =
+    JobType::new("effective-balances-backfill-job");
+
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct EffectiveBalancesBackfillJobConfig {
+    journal_id: JournalId,
+    batch_size: usize,
+}
+impl EffectiveBalancesBackfillJobConfig {
+    pub fn new(journal_id: JournalId, batch_size: Option<usize>) -> Self {
+        Self {
+            journal_id,
+            batch_size: batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+        }
+    }
+}
+
+impl JobConfig for EffectiveBalancesBackfillJobConfig {
+    type Initializer = EffectiveBalancesBackfillJobInitializer;
+}
+
+pub(crate) struct EffectiveBalancesBackfillJobInitializer {
+    ledger: CalaLedger,
+}
+impl EffectiveBalancesBackfillJobInitializer {
+    pub fn new(ledger: CalaLedger) -> Self {
+        Self { ledger }
+    }
+}
+
+impl JobInitializer for EffectiveBalancesBackfillJobInitializer {
+    fn job_type() -> JobType {
+        EFFECTIVE_BALANCES_BACKFILL_JOB_TYPE
+    }
+
+    fn init(&self, job: &Job) -> Result<Box<dyn JobRunner>, Box<dyn std::error::Error>> {
+        Ok(Box::new(EffectiveBalancesBackfillJob {
+            ledger: self.ledger.clone(),
+            config: job.config()?,
+        }))
+    }
+}
+
+pub struct EffectiveBalancesBackfillJob {
+    ledger: CalaLedger,
+    config: EffectiveBalancesBackfillJobConfig,
+}
+
+#[async_trait]
+impl JobRunner for EffectiveBalancesBackfillJob {
+    #[instrument(name = "job.effective_balances_backfill.run", skip(self, _current_job), fields(journal_id = %self.config.journal_id))]
+    async fn run(
+        &self,
+        _current_job: CurrentJob,
+    ) -> Result<JobCompletion, Box<dyn std::error::Error>> {
+        // Progress is persisted by the ledger so a restarted job resumes where it left off.
+        loop {
+            let backfill = self
+                .ledger
+                .balances()
+                .effective()
+                .backfill_batch(self.config.journal_id, self.config.batch_size)
+                .await?;
+            tracing::info!(
+                balances_processed = backfill.balances_processed,
+                balances_total = backfill.balances_total,
+                "effective balances backfill progress"
+            );
+            if backfill.is_completed() {
+                break;
+            }
+        }
+        Ok(JobCompletion::Complete)
+    }
+}
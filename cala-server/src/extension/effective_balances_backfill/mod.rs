@@ -0,0 +1,5 @@
+mod job;
+mod mutation;
+
+pub use job::*;
+pub use mutation::*;
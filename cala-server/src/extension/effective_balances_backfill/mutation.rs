@@ -0,0 +1,54 @@
+use async_graphql::*;
+
+use super::job::*;
+use crate::{
+    app::CalaApp,
+    graphql::{
+        primitives::{DbOp, UUID},
+        Job,
+    },
+};
+
+#[derive(InputObject)]
+pub struct EffectiveBalancesBackfillJobCreateInput {
+    pub job_id: UUID,
+    pub journal_id: UUID,
+    pub batch_size: Option<u32>,
+}
+
+#[derive(SimpleObject)]
+pub struct EffectiveBalancesBackfillJobCreatePayload {
+    pub job: Job,
+}
+
+#[derive(Default)]
+pub struct Mutation;
+
+#[Object(name = "EffectiveBalancesBackfillMutation")]
+impl Mutation {
+    async fn effective_balances_backfill_job_create(
+        &self,
+        ctx: &Context<'_>,
+        input: EffectiveBalancesBackfillJobCreateInput,
+    ) -> async_graphql::Result<EffectiveBalancesBackfillJobCreatePayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+        let job = app
+            .jobs()
+            .create_and_spawn_in_op(
+                &mut *op,
+                input.job_id,
+                EffectiveBalancesBackfillJobConfig::new(
+                    input.journal_id.into(),
+                    input.batch_size.map(|size| size as usize),
+                ),
+            )
+            .await?;
+        Ok(EffectiveBalancesBackfillJobCreatePayload {
+            job: Job::from(job),
+        })
+    }
+}
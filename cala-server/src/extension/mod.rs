@@ -1,7 +1,10 @@
 use async_graphql::*;
 
+pub(crate) mod balance_history_compaction;
+pub(crate) mod balance_queue;
 pub(crate) mod cala_outbox_import;
 pub mod core;
+pub(crate) mod effective_balances_backfill;
 
 pub trait MutationExtensionMarker: Default + OutputType + ContainerType + 'static {}
 pub trait QueryExtensionMarker: Default + OutputType + ContainerType + 'static {}
@@ -12,7 +12,7 @@ use crate::app::CalaApp;
 
 use super::{
     account_set::*, balance::Balance, convert::ToGlobalId, entry::Entry, loader::LedgerDataLoader,
-    primitives::*,
+    primitives::*, velocity::VelocityBalance,
 };
 
 #[derive(Clone, SimpleObject)]
@@ -23,8 +23,10 @@ pub struct Account {
     version: u32,
     code: String,
     name: String,
+    tenant_id: Option<UUID>,
+    account_type: Option<String>,
     normal_balance_type: DebitOrCredit,
-    status: Status,
+    status: AccountStatus,
     external_id: Option<String>,
     description: Option<String>,
     metadata: Option<JSON>,
@@ -108,6 +110,44 @@ impl Account {
         .await
     }
 
+    /// The velocity balances an entry with the given fields would be checked against.
+    async fn velocity_balances(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        entry_type: String,
+        currency: Option<CurrencyCode>,
+        metadata: Option<JSON>,
+        at_time: Option<Timestamp>,
+    ) -> async_graphql::Result<Vec<VelocityBalance>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let at_time = at_time
+            .map(|t| t.into_inner())
+            .unwrap_or_else(chrono::Utc::now);
+        let entry = cala_ledger::velocity::HypotheticalEntry {
+            journal_id: JournalId::from(journal_id),
+            entry_type,
+            currency: currency.map(Currency::from),
+            metadata: metadata.map(|json| json.into_inner()),
+        };
+        let balances = app
+            .ledger()
+            .velocities()
+            .find_balances_for_account(AccountId::from(self.account_id), &entry, at_time)
+            .await?;
+        Ok(balances.into_iter().map(VelocityBalance::from).collect())
+    }
+
+    async fn aliases(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<AccountAlias>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let aliases = app
+            .ledger()
+            .accounts()
+            .list_aliases(AccountId::from(self.account_id))
+            .await?;
+        Ok(aliases.into_iter().map(AccountAlias::from).collect())
+    }
+
     async fn entries(
         &self,
         ctx: &Context<'_>,
@@ -152,13 +192,18 @@ pub(super) struct AccountCreateInput {
     pub external_id: Option<String>,
     pub code: String,
     pub name: String,
+    pub tenant_id: Option<UUID>,
+    pub account_type: Option<String>,
     #[graphql(default)]
     pub normal_balance_type: DebitOrCredit,
     pub description: Option<String>,
     #[graphql(default)]
-    pub status: Status,
+    pub status: AccountStatus,
     pub metadata: Option<JSON>,
     pub account_set_ids: Option<Vec<UUID>>,
+    #[graphql(default)]
+    pub eventually_consistent: bool,
+    pub balance_shards: Option<u16>,
 }
 
 #[derive(SimpleObject)]
@@ -173,8 +218,9 @@ pub(super) struct AccountUpdateInput {
     pub name: Option<String>,
     pub normal_balance_type: Option<DebitOrCredit>,
     pub description: Option<String>,
-    pub status: Option<Status>,
     pub metadata: Option<JSON>,
+    #[graphql(deprecation = "Use accountStatusUpdate, which records the reason for the change")]
+    pub status: Option<AccountStatus>,
 }
 
 #[derive(SimpleObject)]
@@ -182,6 +228,93 @@ pub(super) struct AccountUpdatePayload {
     pub account: Account,
 }
 
+#[derive(InputObject)]
+pub(super) struct AccountStatusUpdateInput {
+    pub account_id: UUID,
+    pub status: AccountStatus,
+    pub reason: String,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct AccountStatusUpdatePayload {
+    pub account: Account,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct AccountAlias {
+    namespace: String,
+    value: String,
+}
+
+#[derive(InputObject)]
+pub(super) struct AccountAliasInput {
+    pub account_id: UUID,
+    pub namespace: String,
+    pub value: String,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct AccountAliasAddPayload {
+    pub account: Account,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct AccountAliasRemovePayload {
+    pub account: Account,
+}
+
+#[derive(InputObject)]
+pub(super) struct AccountMetadataSchemaSetInput {
+    pub tenant_id: Option<UUID>,
+    pub account_type: String,
+    pub schema: JSON,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct AccountMetadataSchemaSetPayload {
+    pub account_type: String,
+    pub schema: JSON,
+}
+
+#[derive(InputObject, Default)]
+pub(super) struct AccountFilter {
+    pub tenant_id: Option<UUID>,
+    pub code_prefix: Option<String>,
+    pub name_contains: Option<String>,
+    pub normal_balance_type: Option<DebitOrCredit>,
+    pub status: Option<AccountStatus>,
+    pub metadata: Option<Vec<MetadataFilter>>,
+    pub account_set_id: Option<UUID>,
+}
+
+impl From<AccountFilter> for cala_ledger::account::AccountFilter {
+    fn from(input: AccountFilter) -> Self {
+        let mut filter = Self::default();
+        if let Some(tenant_id) = input.tenant_id {
+            filter = filter.tenant_id(tenant_id);
+        }
+        if let Some(code_prefix) = input.code_prefix {
+            filter = filter.code_prefix(code_prefix);
+        }
+        if let Some(name_contains) = input.name_contains {
+            filter = filter.name_contains(name_contains);
+        }
+        if let Some(normal_balance_type) = input.normal_balance_type {
+            filter = filter.normal_balance_type(normal_balance_type);
+        }
+        if let Some(status) = input.status {
+            filter = filter.status(status);
+        }
+        for metadata in input.metadata.unwrap_or_default() {
+            filter = filter.metadata_eq(&metadata.path, metadata.value.into_inner());
+        }
+        if let Some(account_set_id) = input.account_set_id {
+            filter = filter.member_of(account_set_id);
+        }
+        filter
+    }
+}
+
 impl ToGlobalId for cala_ledger::AccountId {
     fn to_global_id(&self) -> async_graphql::types::ID {
         async_graphql::types::ID::from(format!("account:{self}"))
@@ -199,6 +332,8 @@ impl From<cala_ledger::account::Account> for Account {
             version: values.version,
             code: values.code,
             name: values.name,
+            tenant_id: values.tenant_id.map(UUID::from),
+            account_type: values.account_type,
             normal_balance_type: values.normal_balance_type,
             status: values.status,
             external_id: values.external_id,
@@ -225,3 +360,42 @@ impl From<cala_ledger::account::Account> for AccountUpdatePayload {
         }
     }
 }
+
+impl From<cala_ledger::account::Account> for AccountStatusUpdatePayload {
+    fn from(value: cala_ledger::account::Account) -> Self {
+        Self {
+            account: Account::from(value),
+        }
+    }
+}
+
+impl From<cala_ledger::account::Account> for AccountAliasAddPayload {
+    fn from(value: cala_ledger::account::Account) -> Self {
+        Self {
+            account: Account::from(value),
+        }
+    }
+}
+
+impl From<cala_ledger::account::Account> for AccountAliasRemovePayload {
+    fn from(value: cala_ledger::account::Account) -> Self {
+        Self {
+            account: Account::from(value),
+        }
+    }
+}
+
+impl From<cala_ledger::account::AccountAlias> for AccountAlias {
+    fn from(alias: cala_ledger::account::AccountAlias) -> Self {
+        Self {
+            namespace: alias.namespace,
+            value: alias.value,
+        }
+    }
+}
+
+impl From<AccountAliasInput> for cala_ledger::account::AccountAlias {
+    fn from(input: AccountAliasInput) -> Self {
+        Self::new(input.namespace, input.value)
+    }
+}
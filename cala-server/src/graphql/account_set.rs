@@ -308,6 +308,118 @@ pub(super) struct RemoveFromAccountSetPayload {
     pub account_set: AccountSet,
 }
 
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(remote = "cala_ledger::account_set::MemberMoveMode")]
+pub(super) enum MemberMoveMode {
+    AsOfNow,
+    Retroactive,
+}
+
+#[derive(InputObject)]
+pub(super) struct MoveAccountSetMemberInput {
+    pub from_account_set_id: UUID,
+    pub to_account_set_id: UUID,
+    pub member_id: UUID,
+    pub member_type: AccountSetMemberType,
+    pub mode: Option<MemberMoveMode>,
+}
+
+impl From<&MoveAccountSetMemberInput> for AccountSetMemberId {
+    fn from(input: &MoveAccountSetMemberInput) -> Self {
+        match input.member_type {
+            AccountSetMemberType::Account => {
+                AccountSetMemberId::Account(AccountId::from(input.member_id))
+            }
+            AccountSetMemberType::AccountSet => {
+                AccountSetMemberId::AccountSet(AccountSetId::from(input.member_id))
+            }
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub(super) struct MoveAccountSetMemberPayload {
+    pub account_set: AccountSet,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct AccountSetTreeNode {
+    member_id: UUID,
+    member_type: AccountSetMemberType,
+    name: String,
+    code: Option<String>,
+    depth: u32,
+    balance: Option<Balance>,
+    children: Vec<AccountSetTreeNode>,
+}
+
+impl From<cala_ledger::account_set::AccountSetTreeNode> for AccountSetTreeNode {
+    fn from(node: cala_ledger::account_set::AccountSetTreeNode) -> Self {
+        let (member_id, member_type) = match node.id {
+            AccountSetMemberId::Account(id) => (UUID::from(id), AccountSetMemberType::Account),
+            AccountSetMemberId::AccountSet(id) => {
+                (UUID::from(id), AccountSetMemberType::AccountSet)
+            }
+        };
+        Self {
+            member_id,
+            member_type,
+            name: node.name,
+            code: node.code,
+            depth: node.depth,
+            balance: node.balance.map(Balance::from),
+            children: node
+                .children
+                .into_iter()
+                .map(AccountSetTreeNode::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub(super) struct AccountSetTree {
+    journal_id: UUID,
+    currency: Option<CurrencyCode>,
+    root: AccountSetTreeNode,
+}
+
+impl From<cala_ledger::account_set::AccountSetTree> for AccountSetTree {
+    fn from(tree: cala_ledger::account_set::AccountSetTree) -> Self {
+        Self {
+            journal_id: tree.journal_id.into(),
+            currency: tree.currency.map(CurrencyCode::from),
+            root: tree.root.into(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub(super) struct AccountSetAncestor {
+    account_set_id: UUID,
+    depth: u32,
+}
+
+#[ComplexObject]
+impl AccountSetAncestor {
+    async fn account_set(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<AccountSet>> {
+        let loader = ctx.data_unchecked::<DataLoader<LedgerDataLoader>>();
+        Ok(loader
+            .load_one(AccountSetId::from(self.account_set_id))
+            .await?)
+    }
+}
+
+impl From<cala_ledger::account_set::AccountSetAncestor> for AccountSetAncestor {
+    fn from(ancestor: cala_ledger::account_set::AccountSetAncestor) -> Self {
+        Self {
+            account_set_id: ancestor.account_set_id.into(),
+            depth: ancestor.depth,
+        }
+    }
+}
+
 impl ToGlobalId for cala_ledger::AccountSetId {
     fn to_global_id(&self) -> async_graphql::types::ID {
         async_graphql::types::ID::from(format!("account_set:{self}"))
@@ -358,6 +470,14 @@ impl From<cala_ledger::account_set::AccountSet> for RemoveFromAccountSetPayload
     }
 }
 
+impl From<cala_ledger::account_set::AccountSet> for MoveAccountSetMemberPayload {
+    fn from(value: cala_ledger::account_set::AccountSet) -> Self {
+        Self {
+            account_set: AccountSet::from(value),
+        }
+    }
+}
+
 #[derive(InputObject)]
 pub(super) struct AccountSetUpdateInput {
     pub name: Option<String>,
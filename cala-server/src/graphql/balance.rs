@@ -1,7 +1,13 @@
-use async_graphql::*;
+use async_graphql::{dataloader::*, types::connection::*, *};
 
-use super::{convert::ToGlobalId, primitives::*};
-use cala_ledger::primitives::{AccountId, Currency, JournalId};
+use super::{
+    convert::ToGlobalId, entry::Entry, fx_rate::FxRate, loader::LedgerDataLoader, primitives::*,
+};
+use crate::app::CalaApp;
+use cala_ledger::{
+    balance::BalanceHistoryByVersionCursor,
+    primitives::{AccountId, BalanceId, Currency, EntryId, JournalId, Layer},
+};
 
 #[derive(SimpleObject)]
 pub(super) struct Money {
@@ -54,6 +60,234 @@ impl Balance {
             entry_id: amount.entry_id.into(),
         }
     }
+
+    async fn history(
+        &self,
+        ctx: &Context<'_>,
+        first: i32,
+        after: Option<String>,
+        recorded_from: Option<Timestamp>,
+        recorded_until: Option<Timestamp>,
+    ) -> Result<Connection<BalanceHistoryByVersionCursor, BalanceVersion, EmptyFields, EmptyFields>>
+    {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let details = &self.balance.details;
+        let (journal_id, account_id, currency) =
+            (details.journal_id, details.account_id, details.currency);
+        query(
+            after,
+            None,
+            Some(first),
+            None,
+            |after, _, first, _| async move {
+                let first = first.expect("First always exists");
+                let result = app
+                    .ledger()
+                    .balances()
+                    .list_history(
+                        journal_id,
+                        account_id,
+                        currency,
+                        cala_ledger::es_entity::PaginatedQueryArgs { first, after },
+                        cala_ledger::es_entity::ListDirection::Descending,
+                        recorded_from.map(|t| t.into_inner()),
+                        recorded_until.map(|t| t.into_inner()),
+                    )
+                    .await?;
+                let mut connection = Connection::new(false, result.has_next_page);
+                connection
+                    .edges
+                    .extend(result.entities.into_iter().map(|version| {
+                        let cursor = BalanceHistoryByVersionCursor::from(&version);
+                        Edge::new(cursor, BalanceVersion::from(version))
+                    }));
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+}
+
+#[derive(InputObject)]
+pub(super) struct BalanceIdInput {
+    pub journal_id: UUID,
+    pub account_id: UUID,
+    pub currency: CurrencyCode,
+}
+
+impl From<BalanceIdInput> for BalanceId {
+    fn from(input: BalanceIdInput) -> Self {
+        (
+            JournalId::from(input.journal_id),
+            AccountId::from(input.account_id),
+            Currency::from(input.currency),
+        )
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub(super) struct BalanceVersion {
+    pub balance: Balance,
+    pub entry_id: Option<UUID>,
+    pub recorded_at: Timestamp,
+}
+
+#[ComplexObject]
+impl BalanceVersion {
+    async fn entry(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Entry>> {
+        let Some(entry_id) = self.entry_id else {
+            return Ok(None);
+        };
+        let loader = ctx.data_unchecked::<DataLoader<LedgerDataLoader>>();
+        Ok(loader.load_one(EntryId::from(entry_id)).await?)
+    }
+}
+
+impl From<cala_ledger::balance::BalanceVersion> for BalanceVersion {
+    fn from(version: cala_ledger::balance::BalanceVersion) -> Self {
+        Self {
+            entry_id: version.entry.as_ref().map(|e| UUID::from(e.id)),
+            recorded_at: Timestamp::from(version.recorded_at),
+            balance: Balance::from(version.balance),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub(super) struct TrialBalanceLine {
+    pub account_id: UUID,
+    pub account_code: String,
+    pub account_name: String,
+    pub balance: Balance,
+}
+
+impl From<cala_ledger::balance::TrialBalanceLine> for TrialBalanceLine {
+    fn from(line: cala_ledger::balance::TrialBalanceLine) -> Self {
+        Self {
+            account_id: line.account_id.into(),
+            account_code: line.account_code,
+            account_name: line.account_name,
+            balance: Balance::from(line.balance),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub(super) struct TrialBalanceTotal {
+    pub currency: CurrencyCode,
+    pub layer: Layer,
+    pub dr_balance: Money,
+    pub cr_balance: Money,
+    pub difference: Money,
+}
+
+impl From<&cala_ledger::balance::TrialBalanceTotal> for TrialBalanceTotal {
+    fn from(total: &cala_ledger::balance::TrialBalanceTotal) -> Self {
+        Self {
+            currency: total.currency.into(),
+            layer: total.layer,
+            dr_balance: (total.dr_balance, total.currency).into(),
+            cr_balance: (total.cr_balance, total.currency).into(),
+            difference: (total.difference(), total.currency).into(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub(super) struct TrialBalance {
+    pub journal_id: UUID,
+    pub as_of: Option<Date>,
+    pub is_balanced: bool,
+    pub totals: Vec<TrialBalanceTotal>,
+    pub imbalances: Vec<TrialBalanceTotal>,
+    #[graphql(skip)]
+    pub(super) trial_balance: cala_ledger::balance::TrialBalance,
+}
+
+#[ComplexObject]
+impl TrialBalance {
+    async fn lines(&self) -> Vec<TrialBalanceLine> {
+        self.trial_balance
+            .lines
+            .iter()
+            .cloned()
+            .map(TrialBalanceLine::from)
+            .collect()
+    }
+
+    async fn csv(&self) -> String {
+        self.trial_balance.to_csv()
+    }
+}
+
+impl From<cala_ledger::balance::TrialBalance> for TrialBalance {
+    fn from(trial_balance: cala_ledger::balance::TrialBalance) -> Self {
+        Self {
+            journal_id: trial_balance.journal_id.into(),
+            as_of: trial_balance.as_of.map(Date::from),
+            is_balanced: trial_balance.is_balanced(),
+            totals: trial_balance
+                .totals
+                .iter()
+                .map(TrialBalanceTotal::from)
+                .collect(),
+            imbalances: trial_balance
+                .imbalances()
+                .map(TrialBalanceTotal::from)
+                .collect(),
+            trial_balance,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub(super) struct EntryTypeTotal {
+    pub entry_type: String,
+    pub currency: CurrencyCode,
+    pub layer: Layer,
+    pub dr_balance: Money,
+    pub cr_balance: Money,
+    pub entry_count: u64,
+}
+
+impl From<cala_ledger::balance::EntryTypeTotal> for EntryTypeTotal {
+    fn from(total: cala_ledger::balance::EntryTypeTotal) -> Self {
+        Self {
+            currency: total.currency.into(),
+            layer: total.layer,
+            dr_balance: (total.dr_balance, total.currency).into(),
+            cr_balance: (total.cr_balance, total.currency).into(),
+            entry_count: total.entry_count,
+            entry_type: total.entry_type,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub(super) struct EntryTypeBreakdown {
+    pub journal_id: UUID,
+    pub account_id: UUID,
+    pub from: Date,
+    pub until: Option<Date>,
+    pub totals: Vec<EntryTypeTotal>,
+}
+
+impl From<cala_ledger::balance::EntryTypeBreakdown> for EntryTypeBreakdown {
+    fn from(breakdown: cala_ledger::balance::EntryTypeBreakdown) -> Self {
+        Self {
+            journal_id: breakdown.journal_id.into(),
+            account_id: breakdown.account_id.into(),
+            from: breakdown.from.into(),
+            until: breakdown.until.map(Date::from),
+            totals: breakdown
+                .totals
+                .into_iter()
+                .map(EntryTypeTotal::from)
+                .collect(),
+        }
+    }
 }
 
 impl ToGlobalId for (JournalId, AccountId, Currency) {
@@ -99,3 +333,218 @@ impl From<cala_ledger::balance::AccountBalance> for Balance {
         }
     }
 }
+
+#[derive(SimpleObject)]
+pub(super) struct ConvertedBalanceLine {
+    pub balance: Balance,
+    pub fx_rate: Option<FxRate>,
+    pub settled: Money,
+    pub pending: Money,
+    pub encumbrance: Money,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct ConvertedBalance {
+    pub journal_id: UUID,
+    pub account_id: UUID,
+    pub currency: CurrencyCode,
+    pub as_of: Timestamp,
+    pub settled: Money,
+    pub pending: Money,
+    pub encumbrance: Money,
+    pub lines: Vec<ConvertedBalanceLine>,
+}
+
+impl From<cala_ledger::balance::ConvertedBalance> for ConvertedBalance {
+    fn from(converted: cala_ledger::balance::ConvertedBalance) -> Self {
+        let currency = converted.currency;
+        Self {
+            journal_id: converted.journal_id.into(),
+            account_id: converted.account_id.into(),
+            currency: currency.into(),
+            as_of: converted.as_of.into(),
+            settled: (converted.settled(), currency).into(),
+            pending: (converted.pending(), currency).into(),
+            encumbrance: (converted.encumbrance(), currency).into(),
+            lines: converted
+                .lines
+                .into_iter()
+                .map(|line| ConvertedBalanceLine {
+                    settled: (line.settled(), currency).into(),
+                    pending: (line.pending(), currency).into(),
+                    encumbrance: (line.encumbrance(), currency).into(),
+                    fx_rate: line.fx_rate.map(FxRate::from),
+                    balance: Balance::from(line.balance),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "cala_ledger::balance::StatementGrouping")]
+pub(super) enum StatementGrouping {
+    Daily,
+    Monthly,
+    Quarterly,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct LayerActivity {
+    pub opening: Money,
+    pub debits: Money,
+    pub credits: Money,
+    pub closing: Money,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct StatementPeriod {
+    pub from: Date,
+    pub until: Date,
+    pub settled: LayerActivity,
+    pub pending: LayerActivity,
+    pub encumbrance: LayerActivity,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct BalanceStatement {
+    pub journal_id: UUID,
+    pub account_id: UUID,
+    pub currency: CurrencyCode,
+    pub from: Date,
+    pub until: Date,
+    pub grouping: Option<StatementGrouping>,
+    pub periods: Vec<StatementPeriod>,
+}
+
+impl From<cala_ledger::balance::BalanceStatement> for BalanceStatement {
+    fn from(statement: cala_ledger::balance::BalanceStatement) -> Self {
+        let currency = statement.currency;
+        let activity = |period: &cala_ledger::balance::StatementPeriod, layer| {
+            let activity = period.activity(layer);
+            LayerActivity {
+                opening: (activity.opening, currency).into(),
+                debits: (activity.debits, currency).into(),
+                credits: (activity.credits, currency).into(),
+                closing: (activity.closing, currency).into(),
+            }
+        };
+        Self {
+            journal_id: statement.journal_id.into(),
+            account_id: statement.account_id.into(),
+            currency: currency.into(),
+            from: statement.from.into(),
+            until: statement.until.into(),
+            grouping: statement.grouping.map(StatementGrouping::from),
+            periods: statement
+                .periods
+                .iter()
+                .map(|period| StatementPeriod {
+                    from: period.from.into(),
+                    until: period.until.into(),
+                    settled: activity(period, Layer::Settled),
+                    pending: activity(period, Layer::Pending),
+                    encumbrance: activity(period, Layer::Encumbrance),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(remote = "cala_ledger::balance::BalanceHistoryRetentionMode")]
+pub(super) enum BalanceHistoryRetentionMode {
+    EndOfDay,
+    Archive,
+}
+
+#[derive(InputObject)]
+pub(super) struct BalanceHistoryRetentionSetInput {
+    pub journal_id: UUID,
+    pub retain_days: u32,
+    pub mode: BalanceHistoryRetentionMode,
+}
+
+#[derive(SimpleObject)]
+pub(super) struct BalanceHistoryRetention {
+    journal_id: UUID,
+    retain_days: u32,
+    mode: BalanceHistoryRetentionMode,
+    pruned_before: Option<Timestamp>,
+    last_compacted_at: Option<Timestamp>,
+    created_at: Timestamp,
+    modified_at: Timestamp,
+}
+
+impl From<cala_ledger::balance::BalanceHistoryRetention> for BalanceHistoryRetention {
+    fn from(retention: cala_ledger::balance::BalanceHistoryRetention) -> Self {
+        Self {
+            journal_id: retention.journal_id.into(),
+            retain_days: retention.retain_days,
+            mode: retention.mode.into(),
+            pruned_before: retention.pruned_before.map(Timestamp::from),
+            last_compacted_at: retention.last_compacted_at.map(Timestamp::from),
+            created_at: retention.created_at.into(),
+            modified_at: retention.modified_at.into(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub(super) struct BalanceHistoryRetentionSetPayload {
+    pub retention: BalanceHistoryRetention,
+}
+
+#[derive(SimpleObject)]
+pub struct EffectiveBalancesBackfill {
+    journal_id: UUID,
+    balances_processed: u32,
+    balances_total: u32,
+    is_completed: bool,
+    started_at: Timestamp,
+    updated_at: Timestamp,
+    completed_at: Option<Timestamp>,
+}
+
+impl From<cala_ledger::balance::EffectiveBalancesBackfill> for EffectiveBalancesBackfill {
+    fn from(backfill: cala_ledger::balance::EffectiveBalancesBackfill) -> Self {
+        Self {
+            journal_id: backfill.journal_id.into(),
+            balances_processed: backfill.balances_processed,
+            balances_total: backfill.balances_total,
+            is_completed: backfill.is_completed(),
+            started_at: backfill.started_at.into(),
+            updated_at: backfill.updated_at.into(),
+            completed_at: backfill.completed_at.map(Timestamp::from),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct BalanceLag {
+    journal_id: UUID,
+    account_id: UUID,
+    currency: CurrencyCode,
+    is_caught_up: bool,
+    last_folded_sequence: Option<u64>,
+    last_folded_entry_id: Option<UUID>,
+    last_folded_at: Option<Timestamp>,
+    pending_entries: u64,
+    oldest_pending_at: Option<Timestamp>,
+}
+
+impl From<cala_ledger::balance::BalanceLag> for BalanceLag {
+    fn from(lag: cala_ledger::balance::BalanceLag) -> Self {
+        Self {
+            journal_id: lag.journal_id.into(),
+            account_id: lag.account_id.into(),
+            currency: lag.currency.into(),
+            is_caught_up: lag.is_caught_up(),
+            last_folded_sequence: lag.last_folded_sequence,
+            last_folded_entry_id: lag.last_folded_entry_id.map(UUID::from),
+            last_folded_at: lag.last_folded_at.map(Timestamp::from),
+            pending_entries: lag.pending_entries,
+            oldest_pending_at: lag.oldest_pending_at.map(Timestamp::from),
+        }
+    }
+}
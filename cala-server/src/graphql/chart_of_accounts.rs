@@ -0,0 +1,53 @@
+use async_graphql::*;
+
+use super::primitives::*;
+
+#[derive(InputObject)]
+pub struct ChartOfAccountsApplyInput {
+    pub(super) tenant_id: Option<UUID>,
+    /// The chart of accounts as a YAML or JSON document.
+    pub(super) document: String,
+}
+
+#[derive(SimpleObject)]
+pub struct ChartOfAccountsApplyPayload {
+    journals_created: usize,
+    journals_updated: usize,
+    accounts_created: usize,
+    accounts_updated: usize,
+    account_sets_created: usize,
+    account_sets_updated: usize,
+    members_added: usize,
+}
+
+#[derive(SimpleObject)]
+pub struct ChartOfAccountsExport {
+    /// The exported chart of accounts as a YAML document.
+    document: String,
+    json: JSON,
+}
+
+impl From<cala_ledger::chart_of_accounts::ChartOfAccountsApplied> for ChartOfAccountsApplyPayload {
+    fn from(applied: cala_ledger::chart_of_accounts::ChartOfAccountsApplied) -> Self {
+        Self {
+            journals_created: applied.journals_created,
+            journals_updated: applied.journals_updated,
+            accounts_created: applied.accounts_created,
+            accounts_updated: applied.accounts_updated,
+            account_sets_created: applied.account_sets_created,
+            account_sets_updated: applied.account_sets_updated,
+            members_added: applied.members_added,
+        }
+    }
+}
+
+impl TryFrom<cala_ledger::chart_of_accounts::ChartOfAccounts> for ChartOfAccountsExport {
+    type Error = Error;
+
+    fn try_from(chart: cala_ledger::chart_of_accounts::ChartOfAccounts) -> Result<Self> {
+        Ok(Self {
+            document: serde_yaml::to_string(&chart)?,
+            json: serde_json::to_value(&chart)?.into(),
+        })
+    }
+}
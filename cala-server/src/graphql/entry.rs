@@ -78,3 +78,62 @@ impl From<cala_ledger::entry::Entry> for Entry {
         }
     }
 }
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Default)]
+#[graphql(remote = "cala_ledger::entry::EntriesOrderBy")]
+pub(super) enum EntriesOrderBy {
+    #[default]
+    CreatedAt,
+    Effective,
+}
+
+#[derive(InputObject, Default)]
+pub(super) struct EntryFilter {
+    pub journal_id: Option<UUID>,
+    pub account_id: Option<UUID>,
+    pub entry_type: Option<String>,
+    pub layer: Option<Layer>,
+    pub direction: Option<DebitOrCredit>,
+    pub currency: Option<CurrencyCode>,
+    pub effective_from: Option<Date>,
+    pub effective_until: Option<Date>,
+    pub units_min: Option<Decimal>,
+    pub units_max: Option<Decimal>,
+    pub metadata: Option<Vec<MetadataFilter>>,
+}
+
+impl From<EntryFilter> for cala_ledger::entry::EntryFilter {
+    fn from(input: EntryFilter) -> Self {
+        let mut filter = Self::default()
+            .effective(
+                input.effective_from.map(Into::into),
+                input.effective_until.map(Into::into),
+            )
+            .units(
+                input.units_min.map(Into::into),
+                input.units_max.map(Into::into),
+            );
+        if let Some(journal_id) = input.journal_id {
+            filter = filter.journal_id(journal_id);
+        }
+        if let Some(account_id) = input.account_id {
+            filter = filter.account_id(account_id);
+        }
+        if let Some(entry_type) = input.entry_type {
+            filter = filter.entry_type(entry_type);
+        }
+        if let Some(layer) = input.layer {
+            filter = filter.layer(layer);
+        }
+        if let Some(direction) = input.direction {
+            filter = filter.direction(direction);
+        }
+        if let Some(currency) = input.currency {
+            filter = filter.currency(currency.into());
+        }
+        for metadata in input.metadata.unwrap_or_default() {
+            filter = filter.metadata_eq(&metadata.path, metadata.value.into_inner());
+        }
+        filter
+    }
+}
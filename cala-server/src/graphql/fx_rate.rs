@@ -0,0 +1,49 @@
+use async_graphql::*;
+
+use super::primitives::*;
+
+#[derive(InputObject)]
+pub struct FxRateUpsertInput {
+    pub(super) base_currency: CurrencyCode,
+    pub(super) quote_currency: CurrencyCode,
+    pub(super) effective: Date,
+    pub(super) rate: Decimal,
+}
+
+#[derive(Clone, SimpleObject)]
+pub struct FxRate {
+    base_currency: CurrencyCode,
+    quote_currency: CurrencyCode,
+    effective: Date,
+    rate: Decimal,
+    version: u32,
+    created_at: Timestamp,
+    modified_at: Timestamp,
+}
+
+#[derive(SimpleObject)]
+pub struct FxRateUpsertPayload {
+    pub fx_rate: FxRate,
+}
+
+impl From<cala_ledger::fx_rate::FxRateValues> for FxRate {
+    fn from(values: cala_ledger::fx_rate::FxRateValues) -> Self {
+        Self {
+            base_currency: values.base_currency.into(),
+            quote_currency: values.quote_currency.into(),
+            effective: values.effective.into(),
+            rate: values.rate.into(),
+            version: values.version,
+            created_at: values.created_at.into(),
+            modified_at: values.modified_at.into(),
+        }
+    }
+}
+
+impl From<cala_ledger::fx_rate::FxRateValues> for FxRateUpsertPayload {
+    fn from(values: cala_ledger::fx_rate::FxRateValues) -> Self {
+        Self {
+            fx_rate: FxRate::from(values),
+        }
+    }
+}
@@ -6,6 +6,7 @@ use super::{convert::ToGlobalId, primitives::*};
 pub struct JournalCreateInput {
     pub(super) journal_id: UUID,
     pub(super) name: String,
+    pub(super) tenant_id: Option<UUID>,
     #[graphql(default)]
     pub(super) status: Status,
     pub(super) description: Option<String>,
@@ -17,6 +18,7 @@ pub struct Journal {
     journal_id: UUID,
     version: u32,
     name: String,
+    tenant_id: Option<UUID>,
     status: Status,
     description: Option<String>,
     created_at: Timestamp,
@@ -44,6 +46,7 @@ impl From<cala_ledger::journal::Journal> for Journal {
             journal_id: UUID::from(values.id),
             version: values.version,
             name: values.name,
+            tenant_id: values.tenant_id.map(UUID::from),
             status: values.status,
             description: values.description,
             created_at: Timestamp::from(created_at),
@@ -65,6 +68,7 @@ pub struct JournalUpdateInput {
     pub(super) name: Option<String>,
     pub(super) status: Option<Status>,
     pub(super) description: Option<String>,
+    pub(super) enable_effective_balances: Option<bool>,
 }
 
 #[derive(SimpleObject)]
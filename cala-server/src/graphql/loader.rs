@@ -5,6 +5,7 @@ use std::{collections::HashMap, sync::Arc};
 use super::{
     account::Account,
     account_set::AccountSet,
+    entry::Entry,
     journal::Journal,
     transaction::Transaction,
     tx_template::TxTemplate,
@@ -14,6 +15,7 @@ use cala_ledger::{
     account::{error::AccountError, *},
     account_set::{error::AccountSetError, *},
     balance::{error::BalanceError, *},
+    entry::error::EntryError,
     journal::{error::JournalError, *},
     primitives::*,
     transaction::error::TransactionError,
@@ -84,6 +86,24 @@ impl Loader<JournalId> for LedgerDataLoader {
     }
 }
 
+impl Loader<EntryId> for LedgerDataLoader {
+    type Value = Entry;
+    type Error = Arc<EntryError>;
+
+    async fn load(&self, keys: &[EntryId]) -> Result<HashMap<EntryId, Entry>, Self::Error> {
+        let entries = self
+            .ledger
+            .entries()
+            .find_all(keys)
+            .await
+            .map_err(Arc::new)?;
+        Ok(entries
+            .into_iter()
+            .map(|(id, entry)| (id, Entry::from(entry)))
+            .collect())
+    }
+}
+
 impl Loader<TransactionId> for LedgerDataLoader {
     type Value = Transaction;
     type Error = Arc<TransactionError>;
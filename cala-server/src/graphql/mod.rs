@@ -1,8 +1,10 @@
 pub mod account;
 pub mod account_set;
 pub mod balance;
+pub mod chart_of_accounts;
 mod convert;
 pub mod entry;
+pub mod fx_rate;
 mod job;
 pub mod journal;
 pub mod loader;
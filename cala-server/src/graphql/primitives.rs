@@ -3,7 +3,7 @@ use async_graphql::*;
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-pub use cala_ledger::primitives::{DebitOrCredit, Layer, Status};
+pub use cala_ledger::primitives::{AccountStatus, DebitOrCredit, Layer, Status};
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -116,3 +116,10 @@ impl From<Decimal> for rust_decimal::Decimal {
         value.0
     }
 }
+
+/// Matches metadata whose value at the dot separated `path` equals `value`.
+#[derive(InputObject)]
+pub struct MetadataFilter {
+    pub path: String,
+    pub value: JSON,
+}
@@ -1,11 +1,15 @@
 use async_graphql::{dataloader::*, types::connection::*, *};
-use cala_ledger::{balance::AccountBalance, primitives::*, tx_template::NewParamDefinition};
+use cala_ledger::{
+    balance::AccountBalance, entry::EntriesByCreatedAtCursor, primitives::*,
+    transaction::TransactionsByCreatedAtCursor, tx_template::NewParamDefinition,
+    velocity::VelocityBreachesByCreatedAtCursor,
+};
 
 use crate::{app::CalaApp, extension::*};
 
 use super::{
-    account::*, account_set::*, balance::*, journal::*, loader::*, primitives::*, transaction::*,
-    tx_template::*, velocity::*,
+    account::*, account_set::*, balance::*, chart_of_accounts::*, entry::*, fx_rate::*, journal::*,
+    loader::*, primitives::*, transaction::*, tx_template::*, velocity::*,
 };
 
 #[derive(Default)]
@@ -47,21 +51,97 @@ impl<E: QueryExtensionMarker> CoreQuery<E> {
         &self,
         ctx: &Context<'_>,
         code: String,
+        tenant_id: Option<UUID>,
     ) -> async_graphql::Result<Option<Account>> {
         let app = ctx.data_unchecked::<CalaApp>();
-        match app.ledger().accounts().find_by_code(code).await {
+        match app
+            .ledger()
+            .accounts()
+            .find_by_code(tenant_id.map(TenantId::from), code)
+            .await
+        {
             Ok(account) => Ok(Some(account.into())),
             Err(cala_ledger::account::error::AccountError::CouldNotFindByCode(_)) => Ok(None),
             Err(err) => Err(err.into()),
         }
     }
 
+    async fn account_by_alias(
+        &self,
+        ctx: &Context<'_>,
+        tenant_id: Option<UUID>,
+        namespace: String,
+        value: String,
+    ) -> async_graphql::Result<Option<Account>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        match app
+            .ledger()
+            .accounts()
+            .find_by_alias(tenant_id.map(TenantId::from), namespace, value)
+            .await
+        {
+            Ok(account) => Ok(Some(account.into())),
+            Err(cala_ledger::account::error::AccountError::CouldNotFindByAlias(..)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn account_metadata_schema(
+        &self,
+        ctx: &Context<'_>,
+        tenant_id: Option<UUID>,
+        account_type: String,
+    ) -> async_graphql::Result<Option<JSON>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let schema = app
+            .ledger()
+            .accounts()
+            .find_metadata_schema(tenant_id.map(TenantId::from), account_type)
+            .await?;
+        Ok(schema.map(JSON::from))
+    }
+
     async fn accounts(
         &self,
         ctx: &Context<'_>,
         first: i32,
         after: Option<String>,
+        filter: Option<AccountFilter>,
     ) -> Result<Connection<AccountsByNameCursor, Account, EmptyFields, EmptyFields>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        query(
+            after,
+            None,
+            Some(first),
+            None,
+            |after, _, first, _| async move {
+                let first = first.expect("First always exists");
+                let query = cala_ledger::es_entity::PaginatedQueryArgs { first, after };
+                let result = match filter {
+                    Some(filter) => app.ledger().accounts().search(filter.into(), query).await?,
+                    None => app.ledger().accounts().list(query).await?,
+                };
+                let mut connection = Connection::new(false, result.has_next_page);
+                connection
+                    .edges
+                    .extend(result.entities.into_iter().map(|entity| {
+                        let cursor = AccountsByNameCursor::from(&entity);
+                        Edge::new(cursor, Account::from(entity))
+                    }));
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    async fn entries(
+        &self,
+        ctx: &Context<'_>,
+        first: i32,
+        after: Option<String>,
+        filter: Option<EntryFilter>,
+        #[graphql(default)] order_by: EntriesOrderBy,
+    ) -> Result<Connection<EntriesByCreatedAtCursor, Entry, EmptyFields, EmptyFields>> {
         let app = ctx.data_unchecked::<CalaApp>();
         query(
             after,
@@ -72,15 +152,20 @@ impl<E: QueryExtensionMarker> CoreQuery<E> {
                 let first = first.expect("First always exists");
                 let result = app
                     .ledger()
-                    .accounts()
-                    .list(cala_ledger::es_entity::PaginatedQueryArgs { first, after })
+                    .entries()
+                    .search(
+                        filter.unwrap_or_default().into(),
+                        order_by.into(),
+                        cala_ledger::es_entity::PaginatedQueryArgs { first, after },
+                        cala_ledger::es_entity::ListDirection::Descending,
+                    )
                     .await?;
                 let mut connection = Connection::new(false, result.has_next_page);
                 connection
                     .edges
                     .extend(result.entities.into_iter().map(|entity| {
-                        let cursor = AccountsByNameCursor::from(&entity);
-                        Edge::new(cursor, Account::from(entity))
+                        let cursor = EntriesByCreatedAtCursor::from(&entity);
+                        Edge::new(cursor, Entry::from(entity))
                     }));
                 Ok::<_, async_graphql::Error>(connection)
             },
@@ -97,6 +182,51 @@ impl<E: QueryExtensionMarker> CoreQuery<E> {
         Ok(loader.load_one(AccountSetId::from(id)).await?)
     }
 
+    async fn account_set_tree(
+        &self,
+        ctx: &Context<'_>,
+        account_set_id: UUID,
+        max_depth: Option<u32>,
+        currency: Option<CurrencyCode>,
+    ) -> async_graphql::Result<AccountSetTree> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let tree = app
+            .ledger()
+            .account_sets()
+            .tree(
+                AccountSetId::from(account_set_id),
+                max_depth,
+                currency.map(Currency::from),
+            )
+            .await?;
+        Ok(tree.into())
+    }
+
+    async fn account_set_ancestors(
+        &self,
+        ctx: &Context<'_>,
+        member_id: UUID,
+        member_type: AccountSetMemberType,
+    ) -> async_graphql::Result<Vec<AccountSetAncestor>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let member = match member_type {
+            AccountSetMemberType::Account => {
+                cala_ledger::account_set::AccountSetMemberId::from(AccountId::from(member_id))
+            }
+            AccountSetMemberType::AccountSet => {
+                cala_ledger::account_set::AccountSetMemberId::from(AccountSetId::from(member_id))
+            }
+        };
+        Ok(app
+            .ledger()
+            .account_sets()
+            .find_ancestors(member)
+            .await?
+            .into_iter()
+            .map(AccountSetAncestor::from)
+            .collect())
+    }
+
     async fn journal(&self, ctx: &Context<'_>, id: UUID) -> async_graphql::Result<Option<Journal>> {
         let loader = ctx.data_unchecked::<DataLoader<LedgerDataLoader>>();
         Ok(loader.load_one(JournalId::from(id)).await?)
@@ -118,6 +248,213 @@ impl<E: QueryExtensionMarker> CoreQuery<E> {
         Ok(balance.map(Balance::from))
     }
 
+    async fn balance_lag(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        account_id: UUID,
+        currency: CurrencyCode,
+    ) -> async_graphql::Result<BalanceLag> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let lag = app
+            .ledger()
+            .balances()
+            .find_lag(
+                JournalId::from(journal_id),
+                AccountId::from(account_id),
+                Currency::from(currency),
+            )
+            .await?;
+        Ok(lag.into())
+    }
+
+    async fn balance_as_of(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        account_id: UUID,
+        currency: CurrencyCode,
+        as_of: Timestamp,
+    ) -> async_graphql::Result<Option<Balance>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        match app
+            .ledger()
+            .balances()
+            .find_as_of(
+                JournalId::from(journal_id),
+                AccountId::from(account_id),
+                Currency::from(currency),
+                as_of.into_inner(),
+            )
+            .await
+        {
+            Ok(balance) => Ok(Some(balance.into())),
+            Err(cala_ledger::balance::error::BalanceError::NotFound(..)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn balances_as_of(
+        &self,
+        ctx: &Context<'_>,
+        ids: Vec<BalanceIdInput>,
+        as_of: Timestamp,
+    ) -> async_graphql::Result<Vec<Balance>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let ids: Vec<BalanceId> = ids.into_iter().map(BalanceId::from).collect();
+        let mut balances = app
+            .ledger()
+            .balances()
+            .find_all_as_of(&ids, as_of.into_inner())
+            .await?;
+        Ok(ids
+            .iter()
+            .filter_map(|id| balances.remove(id))
+            .map(Balance::from)
+            .collect())
+    }
+
+    async fn converted_balance(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        account_id: UUID,
+        currency: CurrencyCode,
+        as_of: Option<Timestamp>,
+    ) -> async_graphql::Result<ConvertedBalance> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let converted = app
+            .ledger()
+            .balances()
+            .find_converted(
+                JournalId::from(journal_id),
+                AccountId::from(account_id),
+                Currency::from(currency),
+                as_of
+                    .map(|t| t.into_inner())
+                    .unwrap_or_else(chrono::Utc::now),
+            )
+            .await?;
+        Ok(converted.into())
+    }
+
+    async fn trial_balance(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        currency: Option<CurrencyCode>,
+        as_of: Option<Date>,
+    ) -> async_graphql::Result<TrialBalance> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let trial_balance = app
+            .ledger()
+            .balances()
+            .trial_balance(
+                JournalId::from(journal_id),
+                currency.map(Currency::from),
+                as_of.map(chrono::NaiveDate::from),
+            )
+            .await?;
+        Ok(trial_balance.into())
+    }
+
+    async fn entry_type_breakdown(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        account_id: UUID,
+        from: Date,
+        until: Option<Date>,
+        currency: Option<CurrencyCode>,
+    ) -> async_graphql::Result<EntryTypeBreakdown> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let breakdown = app
+            .ledger()
+            .balances()
+            .entry_type_breakdown(
+                JournalId::from(journal_id),
+                AccountId::from(account_id),
+                chrono::NaiveDate::from(from),
+                until.map(chrono::NaiveDate::from),
+                currency.map(Currency::from),
+            )
+            .await?;
+        Ok(breakdown.into())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn balance_statement(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        account_id: UUID,
+        currency: CurrencyCode,
+        from: Date,
+        until: Date,
+        grouping: Option<StatementGrouping>,
+    ) -> async_graphql::Result<BalanceStatement> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let statement = app
+            .ledger()
+            .balances()
+            .effective()
+            .statement(
+                JournalId::from(journal_id),
+                AccountId::from(account_id),
+                Currency::from(currency),
+                chrono::NaiveDate::from(from),
+                chrono::NaiveDate::from(until),
+                grouping.map(cala_ledger::balance::StatementGrouping::from),
+            )
+            .await?;
+        Ok(statement.into())
+    }
+
+    async fn balance_history_retention(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+    ) -> async_graphql::Result<Option<BalanceHistoryRetention>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        Ok(app
+            .ledger()
+            .balances()
+            .find_history_retention(JournalId::from(journal_id))
+            .await?
+            .map(BalanceHistoryRetention::from))
+    }
+
+    async fn chart_of_accounts_export(
+        &self,
+        ctx: &Context<'_>,
+        tenant_id: Option<UUID>,
+        journal_ids: Vec<UUID>,
+    ) -> async_graphql::Result<ChartOfAccountsExport> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let journal_ids: Vec<_> = journal_ids.into_iter().map(JournalId::from).collect();
+        let chart = app
+            .ledger()
+            .chart_of_accounts()
+            .export(tenant_id.map(TenantId::from), &journal_ids)
+            .await?;
+        chart.try_into()
+    }
+
+    async fn effective_balances_backfill(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+    ) -> async_graphql::Result<Option<EffectiveBalancesBackfill>> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        Ok(app
+            .ledger()
+            .balances()
+            .effective()
+            .find_backfill(JournalId::from(journal_id))
+            .await?
+            .map(EffectiveBalancesBackfill::from))
+    }
+
     async fn transaction(
         &self,
         ctx: &Context<'_>,
@@ -147,6 +484,124 @@ impl<E: QueryExtensionMarker> CoreQuery<E> {
         }
     }
 
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        first: i32,
+        after: Option<String>,
+        filter: Option<TransactionFilter>,
+    ) -> Result<Connection<TransactionsByCreatedAtCursor, Transaction, EmptyFields, EmptyFields>>
+    {
+        let app = ctx.data_unchecked::<CalaApp>();
+        query(
+            after,
+            None,
+            Some(first),
+            None,
+            |after, _, first, _| async move {
+                let first = first.expect("First always exists");
+                let result = app
+                    .ledger()
+                    .transactions()
+                    .search(
+                        filter.unwrap_or_default().into(),
+                        cala_ledger::es_entity::PaginatedQueryArgs { first, after },
+                        cala_ledger::es_entity::ListDirection::Descending,
+                    )
+                    .await?;
+                let mut connection = Connection::new(false, result.has_next_page);
+                connection
+                    .edges
+                    .extend(result.entities.into_iter().map(|entity| {
+                        let cursor = TransactionsByCreatedAtCursor::from(&entity);
+                        Edge::new(cursor, Transaction::from(entity))
+                    }));
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    async fn transactions_by_correlation_id(
+        &self,
+        ctx: &Context<'_>,
+        correlation_id: String,
+        first: i32,
+        after: Option<String>,
+    ) -> Result<Connection<TransactionsByCreatedAtCursor, Transaction, EmptyFields, EmptyFields>>
+    {
+        let app = ctx.data_unchecked::<CalaApp>();
+        query(
+            after,
+            None,
+            Some(first),
+            None,
+            |after, _, first, _| async move {
+                let first = first.expect("First always exists");
+                let result = app
+                    .ledger()
+                    .transactions()
+                    .list_by_correlation_id(
+                        correlation_id,
+                        cala_ledger::es_entity::PaginatedQueryArgs { first, after },
+                        cala_ledger::es_entity::ListDirection::Descending,
+                    )
+                    .await?;
+                let mut connection = Connection::new(false, result.has_next_page);
+                connection
+                    .edges
+                    .extend(result.entities.into_iter().map(|entity| {
+                        let cursor = TransactionsByCreatedAtCursor::from(&entity);
+                        Edge::new(cursor, Transaction::from(entity))
+                    }));
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    async fn transactions_for_journal(
+        &self,
+        ctx: &Context<'_>,
+        journal_id: UUID,
+        effective_from: Option<Date>,
+        effective_until: Option<Date>,
+        first: i32,
+        after: Option<String>,
+    ) -> Result<Connection<TransactionsByCreatedAtCursor, Transaction, EmptyFields, EmptyFields>>
+    {
+        let app = ctx.data_unchecked::<CalaApp>();
+        query(
+            after,
+            None,
+            Some(first),
+            None,
+            |after, _, first, _| async move {
+                let first = first.expect("First always exists");
+                let result = app
+                    .ledger()
+                    .transactions()
+                    .list_for_journal(
+                        JournalId::from(journal_id),
+                        effective_from.map(Into::into),
+                        effective_until.map(Into::into),
+                        cala_ledger::es_entity::PaginatedQueryArgs { first, after },
+                        cala_ledger::es_entity::ListDirection::Descending,
+                    )
+                    .await?;
+                let mut connection = Connection::new(false, result.has_next_page);
+                connection
+                    .edges
+                    .extend(result.entities.into_iter().map(|entity| {
+                        let cursor = TransactionsByCreatedAtCursor::from(&entity);
+                        Edge::new(cursor, Transaction::from(entity))
+                    }));
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
     async fn tx_template(
         &self,
         ctx: &Context<'_>,
@@ -160,9 +615,15 @@ impl<E: QueryExtensionMarker> CoreQuery<E> {
         &self,
         ctx: &Context<'_>,
         code: String,
+        tenant_id: Option<UUID>,
     ) -> async_graphql::Result<Option<TxTemplate>> {
         let app = ctx.data_unchecked::<CalaApp>();
-        match app.ledger().tx_templates().find_by_code(code).await {
+        match app
+            .ledger()
+            .tx_templates()
+            .find_by_code(tenant_id.map(TenantId::from), code)
+            .await
+        {
             Ok(tx_template) => Ok(Some(tx_template.into())),
             Err(cala_ledger::tx_template::error::TxTemplateError::CouldNotFindByCode(_)) => {
                 Ok(None)
@@ -188,6 +649,45 @@ impl<E: QueryExtensionMarker> CoreQuery<E> {
         let loader = ctx.data_unchecked::<DataLoader<LedgerDataLoader>>();
         Ok(loader.load_one(VelocityControlId::from(id)).await?)
     }
+
+    async fn velocity_breaches(
+        &self,
+        ctx: &Context<'_>,
+        first: i32,
+        after: Option<String>,
+        filter: Option<VelocityBreachFilter>,
+    ) -> Result<
+        Connection<VelocityBreachesByCreatedAtCursor, VelocityBreach, EmptyFields, EmptyFields>,
+    > {
+        let app = ctx.data_unchecked::<CalaApp>();
+        query(
+            after,
+            None,
+            Some(first),
+            None,
+            |after, _, first, _| async move {
+                let first = first.expect("First always exists");
+                let result = app
+                    .ledger()
+                    .velocities()
+                    .list_breaches(
+                        filter.unwrap_or_default().into(),
+                        cala_ledger::es_entity::PaginatedQueryArgs { first, after },
+                        cala_ledger::es_entity::ListDirection::Descending,
+                    )
+                    .await?;
+                let mut connection = Connection::new(false, result.has_next_page);
+                connection
+                    .edges
+                    .extend(result.entities.into_iter().map(|breach| {
+                        let cursor = VelocityBreachesByCreatedAtCursor::from(&breach);
+                        Edge::new(cursor, VelocityBreach::from(breach))
+                    }));
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
 }
 
 #[derive(Default)]
@@ -218,8 +718,15 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
             .name(input.name)
             .code(input.code)
             .normal_balance_type(input.normal_balance_type)
-            .status(input.status);
+            .status(input.status)
+            .eventually_consistent(input.eventually_consistent);
 
+        if let Some(tenant_id) = input.tenant_id {
+            builder.tenant_id(tenant_id);
+        }
+        if let Some(account_type) = input.account_type {
+            builder.account_type(account_type);
+        }
         if let Some(external_id) = input.external_id {
             builder.external_id(external_id);
         }
@@ -229,6 +736,9 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
         if let Some(metadata) = input.metadata {
             builder.metadata(metadata)?;
         }
+        if let Some(balance_shards) = input.balance_shards {
+            builder.balance_shards(balance_shards);
+        }
         let account = app
             .ledger()
             .accounts()
@@ -269,9 +779,6 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
         if let Some(normal_balance_type) = input.normal_balance_type {
             builder.normal_balance_type(normal_balance_type);
         }
-        if let Some(status) = input.status {
-            builder.status(status);
-        }
         if let Some(external_id) = input.external_id {
             builder.external_id(external_id);
         }
@@ -282,16 +789,116 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
             builder.metadata(metadata)?;
         }
 
-        let mut account = app.ledger().accounts().find(AccountId::from(id)).await?;
+        let account_id = AccountId::from(id);
+        let mut account = app.ledger().accounts().find(account_id).await?;
         account.update(builder);
         app.ledger()
             .accounts()
             .persist_in_op(&mut op, &mut account)
             .await?;
+        if let Some(status) = input.status {
+            account = app
+                .ledger()
+                .accounts()
+                .update_status_in_op(&mut op, account_id, status, "accountUpdate")
+                .await?;
+        }
+
+        Ok(account.into())
+    }
+
+    async fn account_status_update(
+        &self,
+        ctx: &Context<'_>,
+        input: AccountStatusUpdateInput,
+    ) -> Result<AccountStatusUpdatePayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+
+        let account = app
+            .ledger()
+            .accounts()
+            .update_status_in_op(
+                &mut op,
+                AccountId::from(input.account_id),
+                input.status,
+                input.reason,
+            )
+            .await?;
+
+        Ok(account.into())
+    }
+
+    async fn account_alias_add(
+        &self,
+        ctx: &Context<'_>,
+        input: AccountAliasInput,
+    ) -> Result<AccountAliasAddPayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+
+        let account = app
+            .ledger()
+            .accounts()
+            .add_alias_in_op(&mut op, AccountId::from(input.account_id), input.into())
+            .await?;
+
+        Ok(account.into())
+    }
+
+    async fn account_alias_remove(
+        &self,
+        ctx: &Context<'_>,
+        input: AccountAliasInput,
+    ) -> Result<AccountAliasRemovePayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+
+        let account = app
+            .ledger()
+            .accounts()
+            .remove_alias_in_op(&mut op, AccountId::from(input.account_id), input.into())
+            .await?;
 
         Ok(account.into())
     }
 
+    async fn account_metadata_schema_set(
+        &self,
+        ctx: &Context<'_>,
+        input: AccountMetadataSchemaSetInput,
+    ) -> Result<AccountMetadataSchemaSetPayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+
+        app.ledger()
+            .accounts()
+            .set_metadata_schema_in_op(
+                &mut op,
+                input.tenant_id.map(TenantId::from),
+                input.account_type.clone(),
+                input.schema.clone().into_inner(),
+            )
+            .await?;
+
+        Ok(AccountMetadataSchemaSetPayload {
+            account_type: input.account_type,
+            schema: input.schema,
+        })
+    }
+
     async fn account_set_create(
         &self,
         ctx: &Context<'_>,
@@ -405,6 +1012,32 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
         Ok(account_set.into())
     }
 
+    async fn move_account_set_member(
+        &self,
+        ctx: &Context<'_>,
+        input: MoveAccountSetMemberInput,
+    ) -> Result<MoveAccountSetMemberPayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+
+        let account_set = app
+            .ledger()
+            .account_sets()
+            .move_member_in_op(
+                &mut op,
+                AccountSetId::from(input.from_account_set_id),
+                AccountSetId::from(input.to_account_set_id),
+                &input,
+                input.mode.map(Into::into).unwrap_or_default(),
+            )
+            .await?;
+
+        Ok(account_set.into())
+    }
+
     async fn journal_create(
         &self,
         ctx: &Context<'_>,
@@ -420,6 +1053,9 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
             .id(input.journal_id)
             .name(input.name)
             .status(input.status);
+        if let Some(tenant_id) = input.tenant_id {
+            builder.tenant_id(tenant_id);
+        }
         if let Some(description) = input.description {
             builder.description(description);
         }
@@ -454,6 +1090,9 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
         if let Some(description) = input.description {
             builder.description(description);
         }
+        if let Some(enable_effective_balances) = input.enable_effective_balances {
+            builder.enable_effective_balances(enable_effective_balances);
+        }
 
         let mut journal = app.ledger().journals().find(JournalId::from(id)).await?;
         journal.update(builder);
@@ -466,6 +1105,72 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
         Ok(journal.into())
     }
 
+    async fn balance_history_retention_set(
+        &self,
+        ctx: &Context<'_>,
+        input: BalanceHistoryRetentionSetInput,
+    ) -> Result<BalanceHistoryRetentionSetPayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let retention = app
+            .ledger()
+            .balances()
+            .set_history_retention(
+                JournalId::from(input.journal_id),
+                input.retain_days,
+                input.mode.into(),
+            )
+            .await?;
+        Ok(BalanceHistoryRetentionSetPayload {
+            retention: retention.into(),
+        })
+    }
+
+    async fn chart_of_accounts_apply(
+        &self,
+        ctx: &Context<'_>,
+        input: ChartOfAccountsApplyInput,
+    ) -> Result<ChartOfAccountsApplyPayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+        let chart: cala_ledger::chart_of_accounts::ChartOfAccounts =
+            serde_yaml::from_str(&input.document)?;
+        let applied = app
+            .ledger()
+            .chart_of_accounts()
+            .apply_in_op(&mut op, input.tenant_id.map(TenantId::from), &chart)
+            .await?;
+
+        Ok(applied.into())
+    }
+
+    async fn fx_rate_upsert(
+        &self,
+        ctx: &Context<'_>,
+        input: FxRateUpsertInput,
+    ) -> Result<FxRateUpsertPayload> {
+        let app = ctx.data_unchecked::<CalaApp>();
+        let mut op = ctx
+            .data_unchecked::<DbOp>()
+            .try_lock()
+            .expect("Lock held concurrently");
+        let new_rate = cala_ledger::fx_rate::NewFxRate::builder()
+            .base_currency(input.base_currency)
+            .quote_currency(input.quote_currency)
+            .effective(chrono::NaiveDate::from(input.effective))
+            .rate(input.rate)
+            .build()?;
+        let rate = app
+            .ledger()
+            .fx_rates()
+            .upsert_in_op(&mut op, new_rate)
+            .await?;
+
+        Ok(rate.into())
+    }
+
     async fn tx_template_create(
         &self,
         ctx: &Context<'_>,
@@ -485,6 +1190,7 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
             external_id,
             description,
             metadata,
+            metadata_schema,
         } = input.transaction;
         new_tx_template_transaction_builder
             .effective(effective)
@@ -501,6 +1207,9 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
         if let Some(metadata) = metadata {
             new_tx_template_transaction_builder.metadata(metadata);
         }
+        if let Some(metadata_schema) = metadata_schema {
+            new_tx_template_transaction_builder.metadata_schema(metadata_schema.into_inner());
+        }
         let new_transaction = new_tx_template_transaction_builder.build()?;
 
         let mut new_params = Vec::new();
@@ -528,7 +1237,10 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
                 direction,
                 units,
                 currency,
+                journal_id,
                 description,
+                metadata,
+                metadata_schema,
             } = entry;
             let mut new_entry_input_builder =
                 cala_ledger::tx_template::NewTxTemplateEntry::builder();
@@ -539,9 +1251,18 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
                 .direction(direction)
                 .units(units)
                 .currency(currency);
+            if let Some(journal_id) = journal_id {
+                new_entry_input_builder.journal_id(journal_id);
+            }
             if let Some(desc) = description {
                 new_entry_input_builder.description(desc);
             }
+            if let Some(metadata) = metadata {
+                new_entry_input_builder.metadata(metadata);
+            }
+            if let Some(metadata_schema) = metadata_schema {
+                new_entry_input_builder.metadata_schema(metadata_schema.into_inner());
+            }
             let new_entry_input = new_entry_input_builder.build()?;
             new_entries.push(new_entry_input);
         }
@@ -553,6 +1274,9 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
             .transaction(new_transaction)
             .params(new_params)
             .entries(new_entries);
+        if let Some(tenant_id) = input.tenant_id {
+            new_tx_template_builder.tenant_id(tenant_id);
+        }
         if let Some(desc) = input.description {
             new_tx_template_builder.description(desc);
         }
@@ -583,8 +1307,9 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
         let params = input.params.map(cala_ledger::tx_template::Params::from);
         let transaction = app
             .ledger()
-            .post_transaction_in_op(
+            .post_transaction_for_tenant_in_op(
                 &mut op,
+                input.tenant_id.map(TenantId::from),
                 input.transaction_id.into(),
                 &input.tx_template_code,
                 params.unwrap_or_default(),
@@ -706,6 +1431,9 @@ impl<E: MutationExtensionMarker> CoreMutation<E> {
             .name(input.name)
             .description(input.description);
 
+        if let Some(tenant_id) = input.tenant_id {
+            new_velocity_control_builder.tenant_id(tenant_id);
+        }
         if let Some(condition) = input.condition {
             new_velocity_control_builder.condition(condition);
         }
@@ -2,10 +2,39 @@ use async_graphql::*;
 
 use super::{convert::ToGlobalId, primitives::*};
 
+#[derive(InputObject, Default)]
+pub struct TransactionFilter {
+    pub journal_id: Option<UUID>,
+    pub tx_template_id: Option<UUID>,
+    pub correlation_id: Option<String>,
+    pub effective_from: Option<Date>,
+    pub effective_until: Option<Date>,
+}
+
+impl From<TransactionFilter> for cala_ledger::transaction::TransactionFilter {
+    fn from(input: TransactionFilter) -> Self {
+        let mut filter = Self::default().effective(
+            input.effective_from.map(Into::into),
+            input.effective_until.map(Into::into),
+        );
+        if let Some(journal_id) = input.journal_id {
+            filter = filter.journal_id(journal_id);
+        }
+        if let Some(tx_template_id) = input.tx_template_id {
+            filter = filter.tx_template_id(tx_template_id);
+        }
+        if let Some(correlation_id) = input.correlation_id {
+            filter = filter.correlation_id(correlation_id);
+        }
+        filter
+    }
+}
+
 #[derive(InputObject)]
 pub struct TransactionInput {
     pub transaction_id: UUID,
     pub tx_template_code: String,
+    pub tenant_id: Option<UUID>,
     pub params: Option<JSON>,
 }
 
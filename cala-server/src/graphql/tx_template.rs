@@ -8,6 +8,7 @@ pub struct TxTemplate {
     tx_template_id: UUID,
     version: u32,
     code: String,
+    tenant_id: Option<UUID>,
     params: Option<Vec<ParamDefinition>>,
     transaction: TxTemplateTransaction,
     entries: Vec<TxTemplateEntry>,
@@ -33,8 +34,10 @@ pub(super) struct TxTemplateEntry {
     direction: Expression,
     units: Expression,
     currency: Expression,
+    journal_id: Option<Expression>,
     description: Option<Expression>,
     metadata: Option<Expression>,
+    metadata_schema: Option<JSON>,
 }
 
 #[derive(Clone, SimpleObject)]
@@ -45,12 +48,14 @@ pub(super) struct TxTemplateTransaction {
     external_id: Option<Expression>,
     description: Option<Expression>,
     metadata: Option<Expression>,
+    metadata_schema: Option<JSON>,
 }
 
 #[derive(InputObject)]
 pub(super) struct TxTemplateCreateInput {
     pub tx_template_id: UUID,
     pub code: String,
+    pub tenant_id: Option<UUID>,
     pub params: Option<Vec<ParamDefinitionInput>>,
     pub transaction: TxTemplateTransactionInput,
     pub entries: Vec<TxTemplateEntryInput>,
@@ -66,6 +71,7 @@ pub(super) struct TxTemplateTransactionInput {
     pub external_id: Option<Expression>,
     pub description: Option<Expression>,
     pub metadata: Option<Expression>,
+    pub metadata_schema: Option<JSON>,
 }
 
 #[derive(InputObject)]
@@ -76,7 +82,10 @@ pub(super) struct TxTemplateEntryInput {
     pub direction: Expression,
     pub units: Expression,
     pub currency: Expression,
+    pub journal_id: Option<Expression>,
     pub description: Option<Expression>,
+    pub metadata: Option<Expression>,
+    pub metadata_schema: Option<JSON>,
 }
 
 #[derive(InputObject)]
@@ -117,6 +126,7 @@ impl From<cala_ledger::tx_template::TxTemplate> for TxTemplate {
             version: values.version,
             tx_template_id: UUID::from(values.id),
             code: values.code,
+            tenant_id: values.tenant_id.map(UUID::from),
             transaction,
             entries,
             params,
@@ -137,6 +147,7 @@ impl From<cala_ledger::tx_template::TxTemplateTransaction> for TxTemplateTransac
             external_id,
             description,
             metadata,
+            metadata_schema,
         }: cala_ledger::tx_template::TxTemplateTransaction,
     ) -> Self {
         Self {
@@ -146,6 +157,7 @@ impl From<cala_ledger::tx_template::TxTemplateTransaction> for TxTemplateTransac
             external_id: external_id.map(Expression::from),
             description: description.map(Expression::from),
             metadata: metadata.map(Expression::from),
+            metadata_schema: metadata_schema.map(JSON::from),
         }
     }
 }
@@ -159,8 +171,10 @@ impl From<cala_ledger::tx_template::TxTemplateEntry> for TxTemplateEntry {
             direction,
             units,
             currency,
+            journal_id,
             description,
             metadata,
+            metadata_schema,
         }: cala_ledger::tx_template::TxTemplateEntry,
     ) -> Self {
         Self {
@@ -170,8 +184,10 @@ impl From<cala_ledger::tx_template::TxTemplateEntry> for TxTemplateEntry {
             direction: Expression::from(direction),
             units: Expression::from(units),
             currency: Expression::from(currency),
+            journal_id: journal_id.map(Expression::from),
             description: description.map(Expression::from),
             metadata: metadata.map(Expression::from),
+            metadata_schema: metadata_schema.map(JSON::from),
         }
     }
 }
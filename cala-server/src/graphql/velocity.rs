@@ -98,6 +98,7 @@ pub struct VelocityControl {
     velocity_control_id: UUID,
     name: String,
     description: String,
+    tenant_id: Option<UUID>,
     enforcement: VelocityEnforcement,
     condition: Option<Expression>,
 }
@@ -129,6 +130,44 @@ impl VelocityControl {
     }
 }
 
+#[derive(SimpleObject, Clone)]
+pub struct VelocityBalance {
+    velocity_control_id: UUID,
+    velocity_limit_id: UUID,
+    window: JSON,
+    currency: CurrencyCode,
+    journal_id: Option<UUID>,
+    layer: Layer,
+    direction: DebitOrCredit,
+    limit: Decimal,
+    spent: Decimal,
+    remaining: Decimal,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct VelocityBreach {
+    velocity_breach_id: UUID,
+    velocity_control_id: UUID,
+    velocity_limit_id: UUID,
+    account_id: UUID,
+    transaction_id: UUID,
+    action: VelocityEnforcementAction,
+    currency: CurrencyCode,
+    layer: Layer,
+    direction: DebitOrCredit,
+    limit: Decimal,
+    requested: Decimal,
+    created_at: Timestamp,
+}
+
+#[derive(InputObject, Default)]
+pub struct VelocityBreachFilter {
+    pub account_id: Option<UUID>,
+    pub velocity_control_id: Option<UUID>,
+    pub velocity_limit_id: Option<UUID>,
+    pub transaction_id: Option<UUID>,
+}
+
 #[derive(SimpleObject, Clone)]
 struct VelocityEnforcement {
     velocity_enforcement_action: VelocityEnforcementAction,
@@ -139,6 +178,7 @@ pub(super) struct VelocityControlCreateInput {
     pub velocity_control_id: UUID,
     pub name: String,
     pub description: String,
+    pub tenant_id: Option<UUID>,
     pub enforcement: VelocityEnforcementInput,
     pub condition: Option<Expression>,
 }
@@ -154,6 +194,8 @@ pub(super) struct VelocityEnforcementInput {
 pub(super) enum VelocityEnforcementAction {
     #[default]
     Reject,
+    Warn,
+    Record,
 }
 
 #[derive(SimpleObject)]
@@ -193,6 +235,7 @@ impl From<cala_ledger::velocity::VelocityControl> for VelocityControl {
             id,
             name,
             description,
+            tenant_id,
             enforcement,
             condition,
         } = velocity_control.into_values();
@@ -204,6 +247,7 @@ impl From<cala_ledger::velocity::VelocityControl> for VelocityControl {
             velocity_control_id: UUID::from(id),
             name,
             description,
+            tenant_id: tenant_id.map(UUID::from),
             enforcement,
             condition: condition.map(Expression::from),
         }
@@ -313,6 +357,61 @@ impl From<cala_ledger::velocity::PartitionKey> for PartitionKey {
     }
 }
 
+impl From<cala_ledger::velocity::VelocityBalance> for VelocityBalance {
+    fn from(balance: cala_ledger::velocity::VelocityBalance) -> Self {
+        Self {
+            velocity_control_id: UUID::from(balance.control_id),
+            velocity_limit_id: UUID::from(balance.limit_id),
+            window: JSON::from(balance.window.inner().clone()),
+            currency: CurrencyCode::from(balance.currency),
+            journal_id: balance.balance.map(|b| UUID::from(b.journal_id)),
+            layer: balance.layer,
+            direction: balance.direction,
+            limit: Decimal::from(balance.limit),
+            spent: Decimal::from(balance.spent),
+            remaining: Decimal::from(balance.remaining),
+        }
+    }
+}
+
+impl From<cala_ledger::velocity::VelocityBreach> for VelocityBreach {
+    fn from(breach: cala_ledger::velocity::VelocityBreach) -> Self {
+        Self {
+            velocity_breach_id: UUID::from(breach.id),
+            velocity_control_id: UUID::from(breach.control_id),
+            velocity_limit_id: UUID::from(breach.limit_id),
+            account_id: UUID::from(breach.account_id),
+            transaction_id: UUID::from(breach.transaction_id),
+            action: VelocityEnforcementAction::from(breach.action),
+            currency: CurrencyCode::from(breach.currency),
+            layer: breach.layer,
+            direction: breach.direction,
+            limit: Decimal::from(breach.limit),
+            requested: Decimal::from(breach.requested),
+            created_at: Timestamp::from(breach.created_at),
+        }
+    }
+}
+
+impl From<VelocityBreachFilter> for cala_ledger::velocity::VelocityBreachFilter {
+    fn from(input: VelocityBreachFilter) -> Self {
+        let mut filter = Self::default();
+        if let Some(account_id) = input.account_id {
+            filter = filter.account_id(account_id);
+        }
+        if let Some(control_id) = input.velocity_control_id {
+            filter = filter.control_id(control_id);
+        }
+        if let Some(limit_id) = input.velocity_limit_id {
+            filter = filter.limit_id(limit_id);
+        }
+        if let Some(transaction_id) = input.transaction_id {
+            filter = filter.transaction_id(transaction_id);
+        }
+        filter
+    }
+}
+
 impl From<cala_ledger::velocity::VelocityLimit> for VelocityLimitCreatePayload {
     fn from(entity: cala_ledger::velocity::VelocityLimit) -> Self {
         Self {